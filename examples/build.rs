@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use pilota_build::plugin::SerdePlugin;
+use pilota_build::plugin::{PbJsonPlugin, SerdePlugin};
 
 fn main() {
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
@@ -30,6 +30,31 @@ fn main() {
             pilota_build::Output::File(out_dir.join("fieldmask.rs")),
         );
 
+    // For thrift unknown fields
+    let unknown_fields_idl = idl_dir.join("unknown_fields.thrift");
+    pilota_build::Builder::thrift()
+        .ignore_unused(false)
+        .include_dirs(vec![unknown_fields_idl.parent().unwrap().to_path_buf()])
+        .keep_unknown_fields([unknown_fields_idl.clone()])
+        .with_descriptor(true)
+        .with_field_mask(true)
+        .compile_with_config(
+            vec![pilota_build::IdlService::from_path(unknown_fields_idl)],
+            pilota_build::Output::File(out_dir.join("unknown_fields.rs")),
+        );
+
+    // For protobuf fieldmask
+    let pb_fieldmask_idl = idl_dir.join("pb_fieldmask.proto");
+    pilota_build::Builder::pb()
+        .ignore_unused(false)
+        .include_dirs(vec![pb_fieldmask_idl.parent().unwrap().to_path_buf()])
+        .with_descriptor(true)
+        .with_field_mask(true)
+        .compile_with_config(
+            vec![pilota_build::IdlService::from_path(pb_fieldmask_idl)],
+            pilota_build::Output::File(out_dir.join("pb_fieldmask.rs")),
+        );
+
     // for pilota serde plugin
     let serde_idl = idl_dir.join("serde_pb.proto");
     pilota_build::Builder::pb()
@@ -41,6 +66,17 @@ fn main() {
             pilota_build::Output::File(out_dir.join("serde_pb.rs")),
         );
 
+    // for pilota pb json plugin
+    let pb_json_idl = idl_dir.join("pb_json.proto");
+    pilota_build::Builder::pb()
+        .ignore_unused(false)
+        .include_dirs(vec![pb_json_idl.parent().unwrap().to_path_buf()])
+        .plugin(PbJsonPlugin)
+        .compile_with_config(
+            vec![pilota_build::IdlService::from_path(pb_json_idl)],
+            pilota_build::Output::File(out_dir.join("pb_json.rs")),
+        );
+
     // For protobuf_options_reference
     let protobuf_options_reference_idl = idl_dir.join("custom_options.proto");
     pilota_build::Builder::pb()
@@ -0,0 +1,11 @@
+namespace rs unknown_fields
+
+struct Item {
+    1: required string name,
+}
+
+struct ItemV2 {
+    1: required string name,
+    2: optional i64 id,
+    3: optional list<string> tags,
+}
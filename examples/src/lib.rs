@@ -6,6 +6,18 @@ pub mod fieldmask {
     include!(concat!(env!("OUT_DIR"), "/fieldmask.rs"));
 }
 
+pub mod unknown_fields {
+    include!(concat!(env!("OUT_DIR"), "/unknown_fields.rs"));
+}
+
+pub mod pb_fieldmask {
+    include!(concat!(env!("OUT_DIR"), "/pb_fieldmask.rs"));
+}
+
+pub mod pb_json {
+    include!(concat!(env!("OUT_DIR"), "/pb_json.rs"));
+}
+
 pub mod custom_options {
     include!(concat!(env!("OUT_DIR"), "/custom_options.rs"));
 }
@@ -18,9 +30,11 @@ fn test_pb_size_diff_codegen() {
         LinkedBytes,
     };
 
-    let mut b = zero_value::zero_value::B::default();
-    b.s3 = "".into();
-    b.int_vec = Some((1..=1000).collect());
+    let b = zero_value::zero_value::B {
+        s3: "".into(),
+        int_vec: Some((1..=1000).collect()),
+        ..Default::default()
+    };
 
     let mut before = LinkedBytes::new();
     encoding::string::encode(1, &b.s3, &mut before);
@@ -78,7 +92,9 @@ fn test_pb_size_diff_manual() {
     );
 }
 
+// `A::c` and `TestService` are deprecated in the idl on purpose.
 #[test]
+#[allow(deprecated)]
 fn test_pb_encode_zero_value() {
     use std::sync::Arc;
 
@@ -189,10 +205,11 @@ fn test_pb_encode_zero_value() {
     assert_eq!(decoded_c, a.c.unwrap());
 
     // test deprecated
+    #[allow(unused_imports)]
     use zero_value::zero_value::TestService;
 
     // test f32 and f64
-    let _ = zero_value::zero_value::file_descriptor_zero_value()
+    zero_value::zero_value::file_descriptor_zero_value()
         .messages()
         .for_each(|m| {
             // the name is same with the idl definition
@@ -345,10 +362,7 @@ fn test_thrift_fieldmask() {
 
 #[test]
 fn test_pb_options() {
-    use pilota::pb::{
-        descriptor_getter::{FieldDescriptorGetter, ItemDescriptorGetter},
-        Message as _,
-    };
+    use pilota::pb::descriptor_getter::FieldDescriptorGetter;
 
     // file options
     let fd_proto =
@@ -369,7 +383,7 @@ fn test_pb_options() {
         }
         if let Ok(v) = exts_custom_options::internal_api.get(file_opts) {
             println!("file_option internal_api: {}", v);
-            assert_eq!(v, false);
+            assert!(!v);
         }
         if let Ok(v) = exts_custom_options::file_kv.get(file_opts) {
             println!("file_option file_kv: {:?}", v);
@@ -441,7 +455,7 @@ fn test_pb_options() {
             let all = v.all_fields_required.unwrap_or(false);
             let depth = v.max_nesting_depth.unwrap_or_default();
             let msg = v.validation_message.clone().unwrap_or_default();
-            assert_eq!(all, true);
+            assert!(all);
             assert_eq!(depth, 3);
             assert_eq!(msg, "User validation failed");
             println!(
@@ -531,7 +545,7 @@ fn test_pb_options() {
                 println!("enum_option enum_description: {}", v);
             }
             if let Ok(v) = exts_custom_options::is_internal.get(enum_opts) {
-                assert_eq!(v, false);
+                assert!(!v);
                 println!("enum_option is_internal: {}", v);
             }
             if let Some(aa) = enum_opts.allow_alias {
@@ -572,3 +586,881 @@ fn test_pb_options() {
         }
     }
 }
+
+#[test]
+fn test_thrift_json_protocol() {
+    use fieldmask::fieldmask::{base::r#loop as base, fieldmask::*};
+    use pilota::thrift::{
+        json::{TJSONInputProtocol, TJSONOutputProtocol},
+        Message as _,
+    };
+
+    let request = Request {
+        f1: Some(true),
+        f2: Some(-1),
+        f3: Some(2),
+        f4: Some(3),
+        f5: Some(i64::MAX),
+        f6: Some(0.5),
+        f7: Some("\"quoted\"\n".into()),
+        f8: Some(pilota::Bytes::from_static(b"\x00\x01binary")),
+        f9: vec![1, 2, 3],
+        f10: Some(pilota::AHashSet::from_iter(vec!["1".into()])),
+        f11: Some(A {
+            a: Some(1),
+            b: Some("2".into()),
+            ..Default::default()
+        }),
+        f12: Some(vec![vec![1, 2, 3], vec![]]),
+        f14: Some(pilota::AHashMap::from_iter(vec![(1, "1".into())])),
+        f17: Some(vec![pilota::AHashMap::from_iter(vec![("key1".into(), 1)])]),
+        base: Some(base::Base {
+            addr: "127.0.0.1:8080".into(),
+            r#enum: Some(base::Ex::B),
+            extra: Some(base::ExtraInfo {
+                f5: Some(pilota::AHashMap::from_iter(vec![(
+                    pilota::OrderedFloat(1.5),
+                    base::Val::default(),
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let mut buf = pilota::BytesMut::new();
+    let mut protocol = TJSONOutputProtocol::new(&mut buf);
+    let size = request.size(&mut protocol);
+    request.encode(&mut protocol).unwrap();
+    assert_eq!(size, buf.len());
+    assert_eq!(
+        std::str::from_utf8(&buf).unwrap(),
+        concat!(
+            r#"{"1":{"tf":1},"2":{"i8":-1},"3":{"i16":2},"4":{"i32":3},"#,
+            r#""5":{"i64":9223372036854775807},"6":{"dbl":0.5},"#,
+            r#""7":{"str":"\"quoted\"\n"},"8":{"str":"AAFiaW5hcnk"},"#,
+            r#""9":{"lst":["i32",3,1,2,3]},"10":{"set":["str",1,"1"]},"#,
+            r#""11":{"rec":{"1":{"i32":1},"2":{"str":"2"}}},"#,
+            r#""12":{"lst":["lst",2,["i32",3,1,2,3],["i32",0]]},"#,
+            r#""14":{"map":["i32","str",1,{"1":"1"}]},"#,
+            r#""17":{"lst":["map",1,["str","i32",1,{"key1":1}]]},"#,
+            r#""255":{"rec":{"0":{"str":"127.0.0.1:8080"},"1":{"str":""},"2":{"str":""},"#,
+            r#""9":{"i32":2},"255":{"rec":{"5":{"map":["dbl","rec",1,{"1.5":{}}]}}}}}}"#,
+        )
+    );
+
+    let encoded = buf.freeze();
+    let mut bytes = encoded.clone();
+    let mut protocol = TJSONInputProtocol::new(&mut bytes);
+    let decoded = Request::decode(&mut protocol).unwrap();
+    assert_eq!(decoded, request);
+
+    // fields with a mismatched type are skipped
+    let mut bytes = encoded;
+    let mut protocol = TJSONInputProtocol::new(&mut bytes);
+    A::decode(&mut protocol).unwrap();
+    assert!(bytes.is_empty());
+}
+
+#[test]
+fn test_thrift_json_protocol_unknown_fields() {
+    use pilota::thrift::{
+        binary::TBinaryProtocol,
+        json::{TJSONInputProtocol, TJSONOutputProtocol},
+        Message as _,
+    };
+    use unknown_fields::unknown_fields::unknown_fields::{Item, ItemV2};
+
+    let v2 = ItemV2 {
+        name: "item".into(),
+        id: Some(1),
+        tags: Some(vec!["a".into()]),
+        ..Default::default()
+    };
+    let mut buf = pilota::BytesMut::new();
+    v2.encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let mut bytes = buf.freeze();
+    let item = Item::decode(&mut TBinaryProtocol::new(&mut bytes, true)).unwrap();
+    assert!(item._unknown_fields.size() > 0);
+
+    // the unknown fields are in the binary encoding and are left out
+    let mut buf = pilota::BytesMut::new();
+    item.encode(&mut TJSONOutputProtocol::new(&mut buf))
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&buf).unwrap(),
+        r#"{"1":{"str":"item"}}"#
+    );
+
+    let mut bytes = buf.freeze();
+    let decoded = Item::decode(&mut TJSONInputProtocol::new(&mut bytes)).unwrap();
+    assert_eq!(decoded.name, "item");
+    assert_eq!(decoded._unknown_fields.size(), 0);
+}
+
+#[test]
+fn test_thrift_decode_limits() {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use fieldmask::fieldmask::fieldmask::*;
+    use pilota::thrift::{
+        binary::TBinaryProtocol, DecodeLimits, Message as _, ProtocolExceptionKind,
+        TAsyncBinaryProtocol, ThriftException,
+    };
+
+    // the async protocol reads from a slice, so it never has to wait
+    fn block_on<F: Future>(f: F) -> F::Output {
+        match pin!(f).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => unreachable!(),
+        }
+    }
+
+    fn assert_limit_exceeded<T: std::fmt::Debug>(res: Result<T, ThriftException>) {
+        match res {
+            Err(ThriftException::Protocol(e)) => {
+                assert_eq!(e.kind(), ProtocolExceptionKind::LimitExceeded, "{e}")
+            }
+            res => panic!("expected a limit error, got {res:?}"),
+        }
+    }
+
+    let request = Request {
+        f7: Some("hello world".into()),
+        f9: vec![1, 2, 3],
+        f11: Some(A {
+            a: Some(1),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let mut buf = pilota::BytesMut::new();
+    request
+        .encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let encoded = buf.freeze();
+
+    let decode = |limits: DecodeLimits| {
+        let mut bytes = encoded.clone();
+        Request::decode(&mut TBinaryProtocol::new(&mut bytes, true).with_limits(limits))
+    };
+    let decode_async = |limits: DecodeLimits| {
+        block_on(Request::decode_async(
+            &mut TAsyncBinaryProtocol::new(&encoded[..]).with_limits(limits),
+        ))
+    };
+
+    for limits in [
+        DecodeLimits {
+            max_container_len: 2,
+            ..Default::default()
+        },
+        DecodeLimits {
+            max_bytes_len: 4,
+            ..Default::default()
+        },
+        DecodeLimits {
+            max_depth: 1,
+            ..Default::default()
+        },
+        DecodeLimits {
+            max_message_len: encoded.len() - 1,
+            ..Default::default()
+        },
+    ] {
+        assert_limit_exceeded(decode(limits));
+        assert_limit_exceeded(decode_async(limits));
+    }
+
+    let limits = DecodeLimits {
+        max_container_len: 3,
+        max_bytes_len: 11,
+        max_depth: 2,
+        max_message_len: encoded.len(),
+    };
+    assert_eq!(decode(limits).unwrap(), request);
+    assert_eq!(decode_async(limits).unwrap(), request);
+
+    // each struct decoded on its own gets the whole message limit
+    let twice = [&encoded[..], &encoded[..]].concat();
+    let mut protocol = TAsyncBinaryProtocol::new(&twice[..]).with_limits(limits);
+    for _ in 0..2 {
+        assert_eq!(
+            block_on(Request::decode_async(&mut protocol)).unwrap(),
+            request
+        );
+    }
+}
+
+#[test]
+fn test_pb_json_mapping() {
+    use pb_json::pb_json::{
+        google::protobuf::{self as wkt, value::Kind},
+        pb_json::{profile::Contact, *},
+    };
+    use pilota::pb::{
+        json::{register_any, JsonMessage, Value},
+        Message as _,
+    };
+
+    register_any::<Item>();
+    register_any::<wkt::Duration>();
+
+    let profile = Profile {
+        user_id: 7,
+        created_at_ms: -1,
+        view_count: u64::MAX,
+        delta: -3,
+        ratio: 0.25,
+        score: f64::INFINITY,
+        verified: true,
+        display_name: "pilota".into(),
+        avatar: pilota::Bytes::from_static(b"\xfb\xff"),
+        status: Status::STATUS_ACTIVE,
+        age: Some(0),
+        tags: vec!["a".into(), "b".into()],
+        history: vec![Status::STATUS_DELETED, Status::from(9)],
+        items_by_id: pilota::AHashMap::from_iter([(
+            1,
+            Item {
+                item_name: "one".into(),
+                ..Default::default()
+            },
+        )]),
+        flags: pilota::AHashMap::from_iter([(true, Status::STATUS_ACTIVE)]),
+        parent: Some(Box::new(Profile {
+            user_id: 1,
+            ..Default::default()
+        })),
+        contact: Some(Contact::Email("a@b.c".into())),
+        updated_at: Some(wkt::Timestamp {
+            seconds: 1,
+            nanos: 20_000_000,
+        }),
+        ttl: Some(wkt::Duration {
+            seconds: -2,
+            nanos: -500_000_000,
+        }),
+        mask: Some(wkt::FieldMask {
+            paths: vec!["display_name".into(), "items.item_name".into()],
+        }),
+        big: Some(wkt::Int64Value { value: 5 }),
+        extra: Some(wkt::Struct {
+            fields: pilota::AHashMap::from_iter([(
+                "k".into(),
+                wkt::Value {
+                    kind: Some(Kind::ListValue(wkt::ListValue {
+                        values: vec![
+                            wkt::Value {
+                                kind: Some(Kind::NullValue(wkt::NullValue::NULL_VALUE)),
+                            },
+                            wkt::Value {
+                                kind: Some(Kind::BoolValue(false)),
+                            },
+                        ],
+                    })),
+                },
+            )]),
+        }),
+        dynamic: Some(wkt::Value {
+            kind: Some(Kind::NullValue(wkt::NullValue::NULL_VALUE)),
+        }),
+        any: Some(wkt::Any {
+            type_url: "type.googleapis.com/pb_json.Item".into(),
+            value: pilota::Bytes::from_static(b"\x0a\x01x"),
+        }),
+        empty: Some(Default::default()),
+        legacy_code: "c".into(),
+        ..Default::default()
+    };
+
+    let expected: Value = r#"{
+        "userId": 7,
+        "createdAtMs": "-1",
+        "viewCount": "18446744073709551615",
+        "delta": -3,
+        "ratio": 0.25,
+        "score": "Infinity",
+        "verified": true,
+        "displayName": "pilota",
+        "avatar": "+/8=",
+        "status": "STATUS_ACTIVE",
+        "age": 0,
+        "tags": ["a", "b"],
+        "history": ["STATUS_DELETED", 9],
+        "itemsById": {"1": {"itemName": "one"}},
+        "flags": {"true": "STATUS_ACTIVE"},
+        "parent": {"userId": 1},
+        "email": "a@b.c",
+        "updatedAt": "1970-01-01T00:00:01.020Z",
+        "ttl": "-2.500s",
+        "mask": "displayName,items.itemName",
+        "big": "5",
+        "extra": {"k": [null, false]},
+        "dynamic": null,
+        "any": {"@type": "type.googleapis.com/pb_json.Item", "itemName": "x"},
+        "empty": {},
+        "code": "c"
+    }"#
+    .parse()
+    .unwrap();
+    assert_eq!(profile.to_json().unwrap(), expected);
+    assert_eq!(Profile::from_json(&expected).unwrap(), profile);
+    assert_eq!(
+        Profile::from_json_str(&profile.to_json_string().unwrap()).unwrap(),
+        profile
+    );
+
+    // the custom json_name, the lowerCamelCase and the proto names are all accepted
+    for key in ["code", "legacyCode", "legacy_code"] {
+        let decoded = Profile::from_json_str(&format!(r#"{{"{key}": "c"}}"#)).unwrap();
+        assert_eq!(decoded.legacy_code, "c");
+    }
+
+    // a packed well-known type keeps its special form under `value`
+    let ttl = wkt::Duration {
+        seconds: 3,
+        nanos: 0,
+    };
+    let any = wkt::Any {
+        type_url: "type.googleapis.com/google.protobuf.Duration".into(),
+        value: ttl.encode_to_vec(&mut Default::default()).into(),
+    };
+    let json = any.to_json().unwrap();
+    assert_eq!(
+        json,
+        r#"{"@type": "type.googleapis.com/google.protobuf.Duration", "value": "3s"}"#
+            .parse::<Value>()
+            .unwrap()
+    );
+    assert_eq!(wkt::Any::from_json(&json).unwrap(), any);
+
+    // types that aren't registered can't be converted
+    let unknown = wkt::Any {
+        type_url: "type.googleapis.com/pb_json.Unknown".into(),
+        value: Default::default(),
+    };
+    assert!(unknown.to_json().is_err());
+    assert!(
+        wkt::Any::from_json_str(r#"{"@type": "type.googleapis.com/pb_json.Unknown"}"#).is_err()
+    );
+
+    // unknown fields are rejected
+    assert!(Profile::from_json_str(r#"{"itemName": "x"}"#).is_err());
+
+    // proto field names, numeric enums and quoted numbers are accepted too
+    let decoded = Profile::from_json_str(
+        r#"{"user_id": "7", "status": 1, "contact_item": {"item_name": "x"}, "ratio": null}"#,
+    )
+    .unwrap();
+    assert_eq!(decoded.user_id, 7);
+    assert_eq!(decoded.status, Status::STATUS_ACTIVE);
+    assert_eq!(
+        decoded.contact,
+        Some(Contact::ContactItem(Item {
+            item_name: "x".into(),
+            ..Default::default()
+        }))
+    );
+
+    let err = Profile::from_json_str(r#"{"parent": {"createdAtMs": "x"}}"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to decode Protobuf JSON: Profile.created_at_ms: Profile.parent: expected an integer, found `\"x\"`"
+    );
+}
+
+#[test]
+fn test_thrift_dynamic_value() {
+    use fieldmask::fieldmask::{base::r#loop as base, fieldmask::*};
+    use pilota::thrift::{binary::TBinaryProtocol, Message as _};
+    use pilota_thrift_reflect::{decode_dynamic, encode_dynamic, DynamicValue};
+
+    let request = Request {
+        f1: Some(true),
+        f7: Some("seven".into()),
+        f9: vec![1, 2, 3],
+        f13: Some(vec![A {
+            a: Some(1),
+            ..Default::default()
+        }]),
+        f15: Some(pilota::AHashMap::from_iter(vec![(
+            "key".into(),
+            A::default(),
+        )])),
+        base: Some(base::Base {
+            addr: "127.0.0.1:8080".into(),
+            r#enum: Some(base::Ex::B),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let desc = Request::get_descriptor().unwrap();
+
+    let mut buf = pilota::BytesMut::new();
+    request
+        .encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let mut bytes = buf.freeze();
+    let mut value = decode_dynamic(desc, &mut TBinaryProtocol::new(&mut bytes, true)).unwrap();
+    assert_eq!(value.get(7), Some(&DynamicValue::String("seven".into())));
+    let Some(DynamicValue::Struct(base)) = value.get(255) else {
+        panic!("base is not decoded");
+    };
+    assert_eq!(base.get(9), Some(&DynamicValue::I32(base::Ex::B.inner())));
+
+    // rewrite a field and decode it back with the generated type
+    value.insert(7, DynamicValue::String("rewritten".into()));
+    let mut buf = pilota::BytesMut::new();
+    encode_dynamic(desc, &value, &mut TBinaryProtocol::new(&mut buf, true)).unwrap();
+    let mut bytes = buf.freeze();
+    let decoded = Request::decode(&mut TBinaryProtocol::new(&mut bytes, true)).unwrap();
+    assert_eq!(
+        decoded,
+        Request {
+            f7: Some("rewritten".into()),
+            ..request
+        }
+    );
+}
+
+#[test]
+fn test_pb_dynamic_message() {
+    use std::sync::Arc;
+
+    use ::pilota::pb::{
+        dynamic::{DynamicMessage, MapKey, Value},
+        Message as _,
+    };
+    use zero_value::zero_value::*;
+
+    let a = A {
+        str_map: ::pilota::AHashMap::from_iter(vec![("key".into(), "value".into())]),
+        s1: "s1".into(),
+        s2: Some("s2".into()),
+        b: Some(Arc::new(B {
+            s3: "s3".into(),
+            int_vec: Some(vec![1, -2, 3]),
+            ..Default::default()
+        })),
+        test: Some(a::Test::Y(8)),
+        ..Default::default()
+    };
+    let desc = file_descriptor_zero_value()
+        .message_by_package_relative_name("A")
+        .unwrap();
+
+    let mut buf = ::pilota::LinkedBytes::new();
+    a.encode(&mut buf).unwrap();
+    let mut value = DynamicMessage::decode(desc, buf.concat().freeze()).unwrap();
+    assert_eq!(value.get_by_name("s1"), Some(&Value::String("s1".into())));
+    assert_eq!(value.get_by_name("s2"), Some(&Value::String("s2".into())));
+    assert_eq!(value.get_by_name("y"), Some(&Value::I32(8)));
+    let Some(Value::Map(str_map)) = value.get_by_name("str_map") else {
+        panic!("str_map is not decoded");
+    };
+    assert_eq!(
+        str_map.get(&MapKey::String("key".into())),
+        Some(&Value::String("value".into()))
+    );
+    let Some(Value::Message(b)) = value.get_by_name("b") else {
+        panic!("b is not decoded");
+    };
+    assert_eq!(
+        b.get_by_name("int_vec"),
+        Some(&Value::List(vec![
+            Value::I32(1),
+            Value::I32(-2),
+            Value::I32(3)
+        ]))
+    );
+
+    // rewrite fields and decode them back with the generated type
+    value.set_by_name("s1", Value::String("rewritten".into()));
+    value.set_by_name("x", Value::String("x".into()));
+    assert!(value.get_by_name("y").is_none());
+    let mut buf = ::pilota::LinkedBytes::new();
+    value.encode(&mut buf).unwrap();
+    let decoded = A::decode(buf.concat().freeze()).unwrap();
+    assert_eq!(
+        decoded,
+        A {
+            s1: "rewritten".into(),
+            test: Some(a::Test::X("x".into())),
+            ..a
+        }
+    );
+}
+
+#[test]
+fn test_thrift_decode_with_field_mask() {
+    use fieldmask::fieldmask::fieldmask::*;
+    use pilota::thrift::{binary::TBinaryProtocol, Message as _};
+
+    let a = |a: i32| A {
+        a: Some(a),
+        b: Some(a.to_string().into()),
+        ..Default::default()
+    };
+    let request = Request {
+        f1: Some(true),
+        f7: Some("seven".into()),
+        f9: vec![1, 2, 3],
+        f11: Some(a(11)),
+        f13: Some(vec![a(0), a(1), a(2)]),
+        f15: Some(pilota::AHashMap::from_iter(vec![
+            ("key1".into(), a(1)),
+            ("key2".into(), a(2)),
+        ])),
+        f17: Some(vec![pilota::AHashMap::from_iter(vec![
+            ("key1".into(), 1),
+            ("key2".into(), 2),
+        ])]),
+        ..Default::default()
+    };
+    let desc = Request::get_descriptor().unwrap().type_descriptor();
+    let mask = pilota_thrift_fieldmask::FieldMaskBuilder::new(
+        &desc,
+        &[
+            "$.f1",
+            "$.f11.b",
+            "$.f13[1]",
+            "$.f15{\"key2\"}.a",
+            "$.f17[*]{\"key1\"}",
+        ],
+    )
+    .build()
+    .unwrap();
+
+    let mut buf = pilota::BytesMut::new();
+    request
+        .encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let mut bytes = buf.freeze();
+    let decoded =
+        Request::decode_with_field_mask(&mut TBinaryProtocol::new(&mut bytes, true), &mask)
+            .unwrap();
+    assert!(bytes.is_empty());
+
+    assert_eq!(
+        decoded,
+        Request {
+            f1: Some(true),
+            // required but excluded, so left as default
+            f9: vec![],
+            f11: Some(A {
+                b: Some("11".into()),
+                ..Default::default()
+            }),
+            f13: Some(vec![a(1)]),
+            f15: Some(pilota::AHashMap::from_iter(vec![(
+                "key2".into(),
+                A {
+                    a: Some(2),
+                    ..Default::default()
+                },
+            )])),
+            f17: Some(vec![pilota::AHashMap::from_iter(vec![("key1".into(), 1)])]),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_thrift_decode_with_field_mask_unknown_fields() {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use pilota::thrift::{binary::TBinaryProtocol, Message as _, TAsyncBinaryProtocol};
+    use unknown_fields::unknown_fields::unknown_fields::ItemV2;
+
+    // the async protocol reads from a slice, so it never has to wait
+    fn block_on<F: Future>(f: F) -> F::Output {
+        match pin!(f).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => unreachable!(),
+        }
+    }
+
+    let item = ItemV2 {
+        name: "item".into(),
+        id: Some(1),
+        tags: Some(vec!["a".into()]),
+        ..Default::default()
+    };
+    let desc = ItemV2::get_descriptor().unwrap().type_descriptor();
+    let mask = pilota_thrift_fieldmask::FieldMaskBuilder::new(&desc, &["$.name"])
+        .build()
+        .unwrap();
+    let mut buf = pilota::BytesMut::new();
+    item.encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let bytes = buf.freeze();
+
+    // the excluded fields are kept as unknown fields and encoded again
+    let decoded =
+        ItemV2::decode_with_field_mask(&mut TBinaryProtocol::new(&mut bytes.clone(), true), &mask)
+            .unwrap();
+    assert_eq!((decoded.id, decoded.tags.as_ref()), (None, None));
+    assert!(decoded._unknown_fields.size() > 0);
+    let mut buf = pilota::BytesMut::new();
+    decoded
+        .encode(&mut TBinaryProtocol::new(&mut buf, true))
+        .unwrap();
+    let reencoded = ItemV2::decode(&mut TBinaryProtocol::new(&mut buf.freeze(), true)).unwrap();
+    assert_eq!(
+        (reencoded.name, reencoded.id, reencoded.tags),
+        (item.name.clone(), item.id, item.tags.clone())
+    );
+
+    // the async decoder never keeps unknown fields
+    let decoded = block_on(ItemV2::decode_with_field_mask_async(
+        &mut TAsyncBinaryProtocol::new(&bytes[..]),
+        &mask,
+    ))
+    .unwrap();
+    assert_eq!(
+        decoded,
+        ItemV2 {
+            name: "item".into(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_thrift_prune_with_field_mask() {
+    use fieldmask::fieldmask::fieldmask::*;
+
+    let a = |a: i32| A {
+        a: Some(a),
+        b: Some(a.to_string().into()),
+        ..Default::default()
+    };
+    let mut request = Request {
+        f1: Some(true),
+        f7: Some("seven".into()),
+        f9: vec![1, 2, 3],
+        f11: Some(a(11)),
+        f13: Some(vec![a(0), a(1), a(2)]),
+        f15: Some(pilota::AHashMap::from_iter(vec![
+            ("key1".into(), a(1)),
+            ("key2".into(), a(2)),
+        ])),
+        f17: Some(vec![pilota::AHashMap::from_iter(vec![
+            ("key1".into(), 1),
+            ("key2".into(), 2),
+        ])]),
+        ..Default::default()
+    };
+    let desc = Request::get_descriptor().unwrap().type_descriptor();
+    let mask = pilota_thrift_fieldmask::FieldMaskBuilder::new(
+        &desc,
+        &[
+            "$.f1",
+            "$.f11.b",
+            "$.f13[1]",
+            "$.f15{\"key2\"}.a",
+            "$.f17[*]{\"key1\"}",
+        ],
+    )
+    .build()
+    .unwrap();
+
+    request.prune(&mask);
+    assert_eq!(
+        request,
+        Request {
+            f1: Some(true),
+            // required but excluded, so reset to default
+            f9: vec![],
+            f11: Some(A {
+                b: Some("11".into()),
+                ..Default::default()
+            }),
+            f13: Some(vec![a(1)]),
+            f15: Some(pilota::AHashMap::from_iter(vec![(
+                "key2".into(),
+                A {
+                    a: Some(2),
+                    ..Default::default()
+                },
+            )])),
+            f17: Some(vec![pilota::AHashMap::from_iter(vec![("key1".into(), 1)])]),
+            ..Default::default()
+        }
+    );
+
+    // a black list removes the selected paths instead
+    let mask = pilota_thrift_fieldmask::FieldMaskBuilder::new(&desc, &["$.f11", "$.f13[0]"])
+        .with_options(pilota_thrift_fieldmask::Options::new().with_black_list_mode(true))
+        .build()
+        .unwrap();
+    request.prune(&mask);
+    assert_eq!(request.f11, None);
+    assert_eq!(request.f13, Some(vec![]));
+    assert_eq!(request.f1, Some(true));
+}
+
+#[test]
+fn test_thrift_typed_field_mask() {
+    use fieldmask::fieldmask::fieldmask::*;
+
+    let desc = Request::get_descriptor().unwrap().type_descriptor();
+    let built = |paths: &[&str]| {
+        pilota_thrift_fieldmask::FieldMaskBuilder::new(&desc, paths)
+            .build()
+            .unwrap()
+    };
+
+    let mask = Request::mask()
+        .f1()
+        .f11_with(|a| a.b())
+        .f13_with(|list| list.index(1))
+        .f15_with(|map| map.key_with("key2", |a| a.a()))
+        .f17_with(|list| list.each_with(|map| map.key("key1")))
+        .build();
+    assert_eq!(
+        mask,
+        built(&[
+            "$.f1",
+            "$.f11.b",
+            "$.f13[1]",
+            "$.f15{\"key2\"}.a",
+            "$.f17[*]{\"key1\"}",
+        ])
+    );
+
+    let mask = Request::mask()
+        .f9()
+        .f13_with(|list| list.each_with(|a| a.a()))
+        .f16_with(|map| map.each())
+        .base()
+        .build();
+    assert_eq!(mask, built(&["$.f9", "$.f13[*].a", "$.f16{*}", "$.base"]));
+    assert_eq!(Request::mask().all().build(), built(&["$.*"]));
+
+    let mask = Request::mask().f11().build_black_list();
+    assert!(!mask.field(11).1);
+    assert!(mask.field(1).1);
+}
+
+#[test]
+fn test_pb_field_mask() {
+    use ::pilota::pb::{
+        field_mask::{FieldMask, FieldMaskError},
+        Message as _,
+    };
+    use pb_fieldmask::pb_fieldmask::pb_fieldmask::*;
+
+    let address = |city: &str| Address {
+        city: city.to_string().into(),
+        street: "street".into(),
+        ..Default::default()
+    };
+    let user = User {
+        user_id: 1,
+        name: "name".into(),
+        address: Some(address("city")),
+        tags: vec!["a".into(), "b".into()],
+        scores: ::pilota::AHashMap::from_iter(vec![("math".into(), 100)]),
+        profile: Some(user::Profile {
+            bio: "bio".into(),
+            home: Some(address("home")),
+            ..Default::default()
+        }),
+        contact: Some(user::Contact::Email("email".into())),
+        ..Default::default()
+    };
+
+    let desc = User::get_message_descriptor().unwrap();
+    let mask = FieldMask::from_paths(
+        &desc,
+        &["userId", "address.city", "profile.home", "tags", "phone"],
+    )
+    .unwrap();
+
+    let mut masked = user.clone();
+    masked.set_field_mask(mask);
+    let bytes = masked.encode_to_vec(&mut Default::default());
+    assert_eq!(bytes.len(), masked.encoded_len(&mut Default::default()));
+
+    let decoded = User::decode(::pilota::Bytes::from(bytes)).unwrap();
+    assert_eq!(
+        decoded,
+        User {
+            user_id: 1,
+            address: Some(Address {
+                city: "city".into(),
+                ..Default::default()
+            }),
+            tags: vec!["a".into(), "b".into()],
+            profile: Some(user::Profile {
+                home: Some(address("home")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    );
+
+    // the mask is checked against the variant a oneof currently holds
+    masked.contact = Some(user::Contact::Phone("phone".into()));
+    let decoded = User::decode(::pilota::Bytes::from(
+        masked.encode_to_vec(&mut Default::default()),
+    ))
+    .unwrap();
+    assert_eq!(decoded.contact, Some(user::Contact::Phone("phone".into())));
+
+    assert!(matches!(
+        FieldMask::from_paths(&desc, &["tags.len"]),
+        Err(FieldMaskError::NotAMessage { .. })
+    ));
+    assert!(matches!(
+        FieldMask::from_paths(&desc, &["profile.unknown"]),
+        Err(FieldMaskError::FieldNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_pb_field_mask_recursive() {
+    use ::pilota::pb::{field_mask::FieldMask, Message as _};
+    use pb_fieldmask::pb_fieldmask::pb_fieldmask::*;
+
+    let comment = |text: &str, reply: Option<Comment>| Comment {
+        text: text.to_string().into(),
+        author: "author".into(),
+        reply: reply.map(Box::new),
+        ..Default::default()
+    };
+    let mut masked = comment("a", Some(comment("b", Some(comment("c", None)))));
+
+    let desc = Comment::get_message_descriptor().unwrap();
+    masked.set_field_mask(FieldMask::from_paths(&desc, &["text", "reply.reply.author"]).unwrap());
+    let bytes = masked.encode_to_vec(&mut Default::default());
+    assert_eq!(bytes.len(), masked.encoded_len(&mut Default::default()));
+
+    let decoded = Comment::decode(::pilota::Bytes::from(bytes)).unwrap();
+    assert_eq!(
+        decoded,
+        Comment {
+            text: "a".into(),
+            reply: Some(Box::new(Comment {
+                reply: Some(Box::new(Comment {
+                    author: "author".into(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    );
+}
@@ -21,6 +21,9 @@ impl FileId {
     }
 }
 
+// The target of the `FileId` transmute below isn't nameable outside
+// pilota-build, so it can't be annotated.
+#[allow(clippy::missing_transmute_annotations)]
 fn main() {
     // Create a database
     let mut db = RootDatabase::default();
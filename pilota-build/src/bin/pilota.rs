@@ -0,0 +1,183 @@
+//! Generates rust code from thrift or protobuf files, with the options of
+//! [`Builder`], without a build script.
+
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use faststr::FastStr;
+use pilota_build::{Builder, MakeBackend, Output, parser::Parser};
+
+const USAGE: &str = "\
+Generates rust code from thrift or protobuf files.
+
+Usage: pilota [OPTIONS] (--out <FILE> | --workspace <DIR>) <IDL>...
+
+Options:
+  -o, --out <FILE>                 Write the code to a single file
+      --workspace <DIR>            Write the code to a cargo workspace
+  -I, --include <DIR>              Search includes and imports in DIR
+      --thrift                     Read thrift files, the default for `.thrift` files
+      --protobuf                   Read protobuf files, the default for `.proto` files
+      --split                      Split the code into one file per item
+      --with-descriptor            Generate the descriptors of the items
+      --with-field-mask            Generate the field masks of the items
+      --with-comments              Keep the comments of the idl
+      --keep-unknown-fields <IDL>  Keep the unknown fields of the items of IDL
+      --touch <IDL>=<ITEM>[,...]   Generate the given items of IDL even if unused
+      --all                        Generate the unused items too
+      --dedup <NAME>               Deduplicate the items named NAME
+      --special-naming <NAME>      Keep the case of NAME
+      --no-change-case             Keep the case of all names
+      --common-crate-name <NAME>   Name the crate of the deduplicated items in a workspace
+  -h, --help                       Print this help
+  -V, --version                    Print the version";
+
+#[derive(Default)]
+struct Options {
+    idls: Vec<PathBuf>,
+    out: Option<Output>,
+    include_dirs: Vec<PathBuf>,
+    protobuf: Option<bool>,
+    split: bool,
+    with_descriptor: bool,
+    with_field_mask: bool,
+    with_comments: bool,
+    keep_unknown_fields: Vec<PathBuf>,
+    touches: Vec<(PathBuf, Vec<String>)>,
+    all: bool,
+    dedups: Vec<FastStr>,
+    special_namings: Vec<FastStr>,
+    no_change_case: bool,
+    common_crate_name: Option<FastStr>,
+}
+
+enum Command {
+    Generate(Box<Options>),
+    Help,
+    Version,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("`{name}` expects a value"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-o" | "--out" => options.out = Some(Output::File(value(&arg)?.into())),
+            "--workspace" => options.out = Some(Output::Workspace(value(&arg)?.into())),
+            "-I" | "--include" => options.include_dirs.push(value(&arg)?.into()),
+            "--thrift" => options.protobuf = Some(false),
+            "--protobuf" => options.protobuf = Some(true),
+            "--split" => options.split = true,
+            "--with-descriptor" => options.with_descriptor = true,
+            "--with-field-mask" => options.with_field_mask = true,
+            "--with-comments" => options.with_comments = true,
+            "--keep-unknown-fields" => options.keep_unknown_fields.push(value(&arg)?.into()),
+            "--touch" => {
+                let touch = value(&arg)?;
+                let (path, items) = touch
+                    .split_once('=')
+                    .ok_or_else(|| format!("`--touch {touch}` expects `<IDL>=<ITEM>[,...]`"))?;
+                options.touches.push((
+                    path.into(),
+                    items.split(',').map(|s| s.trim().to_string()).collect(),
+                ));
+            }
+            "--all" => options.all = true,
+            "--dedup" => options.dedups.push(value(&arg)?.into()),
+            "--special-naming" => options.special_namings.push(value(&arg)?.into()),
+            "--no-change-case" => options.no_change_case = true,
+            "--common-crate-name" => options.common_crate_name = Some(value(&arg)?.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`")),
+            _ => options.idls.push(arg.into()),
+        }
+    }
+
+    if options.idls.is_empty() {
+        return Err("no idl file is given".into());
+    }
+    if options.out.is_none() {
+        return Err("one of `--out` and `--workspace` is required".into());
+    }
+    Ok(Command::Generate(Box::new(options)))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Generate(options)) => *options,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("pilota {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let protobuf = options.protobuf.unwrap_or_else(|| {
+        options
+            .idls
+            .iter()
+            .all(|idl| idl.extension().is_some_and(|ext| ext == "proto"))
+    });
+
+    // the errors in the idls are reported before the generation unwinds
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        if protobuf {
+            compile(Builder::pb(), options)
+        } else {
+            compile(Builder::thrift(), options)
+        }
+    }));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+fn compile<MkB, P>(builder: Builder<MkB, P>, options: Options)
+where
+    MkB: MakeBackend + Send,
+    MkB::Target: Send,
+    P: Parser,
+{
+    let mut builder = builder
+        .include_dirs(options.include_dirs)
+        .split_generated_files(options.split)
+        .with_descriptor(options.with_descriptor)
+        .with_field_mask(options.with_field_mask)
+        .with_comments(options.with_comments)
+        .keep_unknown_fields(options.keep_unknown_fields)
+        .touch(options.touches)
+        .ignore_unused(!options.all)
+        .dedup(options.dedups)
+        .special_namings(options.special_namings)
+        .change_case(!options.no_change_case);
+    if let Some(name) = options.common_crate_name {
+        builder = builder.common_crate_name(name);
+    }
+
+    let out = options.out.unwrap();
+    if let Output::Workspace(dir) = &out {
+        // the members are merged into the manifest of the workspace
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.exists() {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(manifest, "").unwrap();
+        }
+    }
+    builder.compile(options.idls, out);
+}
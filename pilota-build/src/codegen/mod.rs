@@ -148,9 +148,14 @@ where
         }
 
         if !s.is_wrapper && self.config.with_field_mask {
-            fields.push_str(
-                "\npub _field_mask: ::std::option::Option<::pilota_thrift_fieldmask::FieldMask>,\n",
-            );
+            let field_mask_ty = if B::PROTOCOL == "protobuf" {
+                "::pilota::pb::field_mask::FieldMask"
+            } else {
+                "::pilota_thrift_fieldmask::FieldMask"
+            };
+            fields.push_str(&format!(
+                "\npub _field_mask: ::std::option::Option<{field_mask_ty}>,\n"
+            ));
         }
 
         let deprecated_attr = if self.is_deprecated(def_id) {
@@ -22,6 +22,22 @@ use crate::{
     ty::Ty,
 };
 
+pub(crate) fn is_proto3(cx: &Context, file_id: crate::symbol::FileId) -> bool {
+    if let Some(f) = cx.files().get(&file_id) {
+        // The descriptor bytes are produced by `protobuf` crate's `write_to_bytes`.
+        // Parse them back to check syntax.
+        if let Ok(fd) =
+            <::protobuf::descriptor::FileDescriptorProto as ::protobuf::Message>::parse_from_bytes(
+                f.descriptor.as_ref(),
+            )
+        {
+            return fd.syntax() == "proto3";
+        }
+    }
+    // Default to proto3 if unknown to prefer smaller encoding behavior.
+    true
+}
+
 #[derive(Clone)]
 pub struct ProtobufBackend {
     cx: Context,
@@ -59,17 +75,7 @@ impl ProtobufBackend {
 
     #[inline]
     fn is_proto3(&self, file_id: crate::symbol::FileId) -> bool {
-        if let Some(f) = self.cx.files().get(&file_id) {
-            // The descriptor bytes are produced by `protobuf` crate's `write_to_bytes`.
-            // Parse them back to check syntax.
-            if let Ok(fd) = <::protobuf::descriptor::FileDescriptorProto as ::protobuf::Message>::parse_from_bytes(
-                f.descriptor.as_ref(),
-            ) {
-                return fd.syntax() == "proto3";
-            }
-        }
-        // Default to proto3 if unknown to prefer smaller encoding behavior.
-        true
+        is_proto3(&self.cx, file_id)
     }
 
     fn codegen_encoded_len(
@@ -462,6 +468,111 @@ impl ProtobufBackend {
         Some(field.id as u32).into_iter().chain(vec![])
     }
 
+    /// Returns the expression telling whether `field` is selected by the
+    /// message's field mask. Oneof fields are checked against the tag of the
+    /// variant they hold.
+    fn codegen_field_selected(&self, field: &Field) -> String {
+        let field_name = self.cx.rust_name(field.did);
+        if self.is_one_of(&field.ty) {
+            format!(
+                "self._field_mask.as_ref().map_or(true, |fm| self.{field_name}.as_ref().map_or(true, |v| fm.field(v.tag()).1))"
+            )
+        } else {
+            let tag = field.id as u32;
+            format!("self._field_mask.as_ref().map_or(true, |fm| fm.field({tag}).1)")
+        }
+    }
+
+    fn codegen_struct_field_mask(&self, def_id: DefId, stream: &mut String, s: &rir::Message) {
+        let name = self.cx.rust_name(def_id);
+
+        let set_inner_field_mask = s
+            .fields
+            .iter()
+            .filter(|f| {
+                !self.is_one_of(&f.ty) && matches!(self.ty_category(&f.ty), Category::Message)
+            })
+            .filter_map(|f| {
+                let field_name = self.cx.rust_name(f.did);
+                let tag = f.id as u32;
+                let (value, set) = match &f.ty.kind {
+                    // recursive messages are boxed by adjusting the field, so they
+                    // are still paths and `set_field_mask` is reached through the box
+                    ty::TyKind::Path(_) => ("value", "value"),
+                    ty::TyKind::Arc(_) => ("value", "::std::sync::Arc::make_mut(value)"),
+                    _ => return None,
+                };
+                let set_value =
+                    format!("{set}.set_field_mask(sub_mask.cloned().unwrap_or_default());");
+                Some(if f.is_optional() {
+                    format! {
+                        r#"if let (sub_mask, true) = field_mask.field({tag}) {{
+                            if let Some({value}) = &mut self.{field_name} {{
+                                {set_value}
+                            }}
+                        }}"#
+                    }
+                } else {
+                    format! {
+                        r#"if let (sub_mask, true) = field_mask.field({tag}) {{
+                            let {value} = &mut self.{field_name};
+                            {set_value}
+                        }}"#
+                    }
+                })
+            })
+            .join("");
+
+        let mut descriptor_getter = String::new();
+        if self.cx.config.with_descriptor {
+            let file_id = self.cx.node(def_id).unwrap().file_id;
+            let idl_name = s.name.raw_str();
+            if let ItemExts::Pb(pb) = &s.item_exts {
+                if let Some(p) = &pb.parent {
+                    if self.cx.file_paths().get(&file_id).is_some() {
+                        let path = self.cx.related_item_path(def_id, p.did);
+                        descriptor_getter = format!(
+                            r#"
+                            pub fn get_message_descriptor() -> Option<::pilota::pb::reflect::MessageDescriptor> {{
+                                {path}::get_message_descriptor()?
+                                    .nested_messages()
+                                    .find(|m| m.name() == "{idl_name}")
+                            }}
+                            "#
+                        );
+                    }
+                } else if self.cx.file_paths().get(&file_id).is_some() {
+                    let filename = self.file_name(file_id).unwrap().replace(".", "_");
+                    let filename_lower = filename.to_lowercase();
+
+                    let file = &self.cx.files().get(&file_id).unwrap().package;
+                    let path = self.cx.item_path(def_id);
+                    let super_mods = "super::".repeat(path.len() - file.len() - 1);
+
+                    descriptor_getter = format!(
+                        r#"
+                        pub fn get_message_descriptor() -> Option<::pilota::pb::reflect::MessageDescriptor> {{
+                            {super_mods}file_descriptor_{filename_lower}().message_by_package_relative_name("{idl_name}")
+                        }}
+                        "#
+                    );
+                }
+            }
+        }
+
+        stream.push_str(&format! {
+            r#"impl {name} {{
+                {descriptor_getter}
+                /// Sets the field mask used when encoding the message, and the
+                /// sub-masks of its singular message fields.
+                pub fn set_field_mask(&mut self, field_mask: ::pilota::pb::field_mask::FieldMask) {{
+                    {set_inner_field_mask}
+                    self._field_mask = Some(field_mask);
+                }}
+            }}"#
+        });
+    }
+
     fn codegen_merge_field(&self, ident: FastStr, ty: &Ty, kind: FieldKind) -> FastStr {
         match self.ty_category(ty) {
             Category::Scalar | Category::Message => {
@@ -516,6 +627,7 @@ impl CodegenBackend for ProtobufBackend {
     fn codegen_struct_impl(&self, def_id: DefId, stream: &mut String, s: &rir::Message) {
         let idl_name = s.name.raw_str();
         let name = self.cx.rust_name(def_id);
+        let with_field_mask = self.cx.config.with_field_mask && !s.is_wrapper;
         let mut encoded_len = s
             .fields
             .iter()
@@ -531,7 +643,12 @@ impl CodegenBackend for ProtobufBackend {
                         self.is_proto3(file_id)
                     },
                 );
-                FastStr::from(format!("+ {len}"))
+                if with_field_mask {
+                    let selected = self.codegen_field_selected(field);
+                    FastStr::from(format!("+ if {selected} {{ {len} }} else {{ 0 }}"))
+                } else {
+                    FastStr::from(format!("+ {len}"))
+                }
             })
             .join("");
 
@@ -545,7 +662,7 @@ impl CodegenBackend for ProtobufBackend {
                 if matches!(field.ty.kind, ty::TyKind::Vec(_)) {
                     has_repeated = true;
                 }
-                self.codegen_encode(
+                let encode = self.codegen_encode(
                     format!("self.{field_name}").into(),
                     &field.ty,
                     field.id as u32,
@@ -554,7 +671,13 @@ impl CodegenBackend for ProtobufBackend {
                         let file_id = self.cx.node(def_id).unwrap().file_id;
                         self.is_proto3(file_id)
                     },
-                )
+                );
+                if with_field_mask {
+                    let selected = self.codegen_field_selected(field);
+                    format!("if {selected} {{ {encode} }}").into()
+                } else {
+                    encode
+                }
             })
             .join("");
 
@@ -687,6 +810,10 @@ impl CodegenBackend for ProtobufBackend {
             stream.push_str(&getter_impl);
         }
 
+        if with_field_mask {
+            self.codegen_struct_field_mask(def_id, stream, s);
+        }
+
         stream.push_str(&format!(
             r#"
             impl ::pilota::pb::Message for {name} {{
@@ -902,6 +1029,30 @@ impl CodegenBackend for ProtobufBackend {
             }
         }
 
+        let tag_fn = if self.cx.config.with_field_mask {
+            let tags = e
+                .variants
+                .iter()
+                .map(|variant| {
+                    let tag = variant.id.unwrap() as u32;
+                    let variant_name = self.cx.rust_name(variant.did);
+                    format!("{name}::{variant_name}(_) => {tag}")
+                })
+                .join(",");
+            format! {
+                r#"
+                #[inline]
+                pub fn tag(&self) -> u32 {{
+                    match self {{
+                        {tags}
+                    }}
+                }}
+                "#
+            }
+        } else {
+            String::new()
+        };
+
         let merge_param_type = if is_cycled {
             "::core::option::Option<::std::boxed::Box<Self>>"
         } else {
@@ -914,6 +1065,7 @@ impl CodegenBackend for ProtobufBackend {
             {getter_impl}
 
             impl {name} {{
+                {tag_fn}
                 pub fn encode(&self, buf: &mut ::pilota::LinkedBytes) {{
                     match self {{
                         {encode}
@@ -3,11 +3,24 @@ use paste::paste;
 
 pub struct DecodeHelper {
     pub is_async: bool,
+    /// Whether the generated code decodes against a `__pilota_field_mask` in
+    /// scope, skipping the fields it excludes.
+    pub with_field_mask: bool,
 }
 
 impl DecodeHelper {
     pub fn new(is_async: bool) -> Self {
-        Self { is_async }
+        Self {
+            is_async,
+            with_field_mask: false,
+        }
+    }
+
+    pub fn new_with_field_mask(is_async: bool) -> Self {
+        Self {
+            is_async,
+            with_field_mask: true,
+        }
     }
 }
 
@@ -184,6 +184,145 @@ impl ThriftBackend {
         })
     }
 
+    fn codegen_prune_fields<'a>(
+        &'a self,
+        fields: &'a [Arc<rir::Field>],
+    ) -> impl Iterator<Item = FastStr> + 'a {
+        fields.iter().map(|f| {
+            let field_name = self.rust_name(f.did);
+            let field_id = f.id as i16;
+            if f.is_optional() {
+                let prune = self.codegen_prune_ty(&f.ty, "value".into(), "field_fm");
+                let (field_fm, prune) = if prune.is_empty() {
+                    ("_", "".into())
+                } else {
+                    (
+                        "field_fm",
+                        format! {
+                            r#"else if let Some(value) = &mut self.{field_name} {{
+                                {prune}
+                            }}"#
+                        },
+                    )
+                };
+                format! {
+                    r#"let ({field_fm}, exist) = __pilota_field_mask.field({field_id});
+                    if !exist {{
+                        self.{field_name} = None;
+                    }} {prune}"#
+                }
+                .into()
+            } else {
+                let prune = self.codegen_prune_ty(
+                    &f.ty,
+                    format!("(&mut self.{field_name})").into(),
+                    "field_fm",
+                );
+                let (field_fm, prune) = if prune.is_empty() {
+                    ("_", "".into())
+                } else {
+                    ("field_fm", format!("else {{ {prune} }}"))
+                };
+                format! {
+                    r#"let ({field_fm}, exist) = __pilota_field_mask.field({field_id});
+                    if !exist {{
+                        self.{field_name} = ::std::default::Default::default();
+                    }} {prune}"#
+                }
+                .into()
+            }
+        })
+    }
+
+    fn codegen_struct_mask_builder(&self, stream: &mut String, s: &Message, name: &Symbol) {
+        const TYPED: &str = "::pilota_thrift_fieldmask::typed";
+        let builder = format!("{name}MaskBuilder");
+        let select_fields = s
+            .fields
+            .iter()
+            .map(|f| {
+                let field_name = self.rust_name(f.did).to_string();
+                let raw_name = field_name.trim_start_matches("r#");
+                // keep clear of the methods every builder has
+                let method = if matches!(raw_name, "all" | "build" | "build_black_list") {
+                    format!("{raw_name}_field")
+                } else {
+                    field_name.clone()
+                };
+                let method_with = format!("{}_with", method.trim_start_matches("r#"));
+                let field_id = f.id;
+                let field_builder = self.codegen_mask_builder_ty(&f.ty);
+                let whole = field_builder
+                    .clone()
+                    .unwrap_or_else(|| format!("{TYPED}::ScalarMask").into());
+                let mut select = format! {
+                    r#"/// Selects `{raw_name}` as a whole.
+                    pub fn {method}(mut self) -> Self {{
+                        self.0.field({field_id}, <{whole} as {TYPED}::MaskBuilder>::whole());
+                        self
+                    }}"#
+                };
+                if let Some(field_builder) = field_builder {
+                    select.push_str(&format! {
+                        r#"/// Selects the part of `{raw_name}` given by `f`.
+                        pub fn {method_with}(mut self, f: impl FnOnce({field_builder}) -> {field_builder}) -> Self {{
+                            self.0.field({field_id}, {TYPED}::MaskBuilder::build(f(::std::default::Default::default())));
+                            self
+                        }}"#
+                    });
+                }
+                select
+            })
+            .join("");
+
+        stream.push_str(&format! {
+            r#"/// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of [`{name}`] field by field.
+            #[derive(Debug, Clone, Default)]
+            pub struct {builder}({TYPED}::StructMask);
+
+            impl {builder} {{
+                {select_fields}
+
+                /// Selects every field.
+                pub fn all(mut self) -> Self {{
+                    self.0.all();
+                    self
+                }}
+
+                /// Returns the white list mask built so far.
+                pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {{
+                    {TYPED}::MaskBuilder::build(self.0)
+                }}
+
+                /// Returns the black list mask built so far, which excludes what was selected.
+                pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {{
+                    {TYPED}::MaskBuilder::build_black_list(self.0)
+                }}
+            }}
+
+            impl {TYPED}::MaskBuilder for {builder} {{
+                fn whole() -> ::pilota_thrift_fieldmask::FieldMask {{
+                    <{TYPED}::StructMask as {TYPED}::MaskBuilder>::whole()
+                }}
+
+                fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {{
+                    {TYPED}::MaskBuilder::build(self.0)
+                }}
+            }}
+
+            impl {TYPED}::Masked for {name} {{
+                type Builder = {builder};
+            }}
+
+            impl {name} {{
+                /// Returns a builder of the field masks of this struct.
+                pub fn mask() -> {builder} {{
+                    ::std::default::Default::default()
+                }}
+            }}"#
+        });
+    }
+
     fn codegen_impl_message(
         &self,
         _def_id: DefId,
@@ -320,8 +459,8 @@ impl ThriftBackend {
             .iter()
             .filter_map(|f| {
                 let field_name = f.local_var_name();
-                match self.cx.default_val(f) { Some((default, is_const)) => {
-                    if !is_const {
+                match self.cx.default_val(f) {
+                    Some((default, false)) => {
                         if f.is_optional() {
                             Some(format! {
                                 r#"if {field_name}.is_none() {{
@@ -333,12 +472,9 @@ impl ThriftBackend {
                                 r#"let {field_name} = {field_name}.unwrap_or_else(|| {default});"#
                             ))
                         }
-                    } else {
-                        None
                     }
-                } _ => {
-                    None
-                }}
+                    _ => None,
+                }
             })
             .join("\n");
 
@@ -350,12 +486,27 @@ impl ThriftBackend {
             .fields
             .iter()
             .filter(|f| !f.is_optional() && self.default_val(f).is_none())
-            .map(|f| (self.rust_name(f.did), f.local_var_name()))
+            .map(|f| (self.rust_name(f.did), f.local_var_name(), f.id as i16))
             .collect_vec();
 
         let verify_required_fields = required_without_default_fields
             .iter()
-            .map(|(s, v)| {
+            .map(|(s, v, id)| {
+                if helper.with_field_mask {
+                    // a required field excluded by the mask is never read
+                    return format!(
+                        r#"let {v} = match {v} {{
+                            Some({v}) => {v},
+                            None if !__pilota_field_mask.field({id}).1 => ::std::default::Default::default(),
+                            None => return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field {s} is required".to_string()
+                                )
+                            ),
+                        }};"#
+                    );
+                }
                 format!(
                     r#"let Some({v}) = {v} else {{
                 return ::std::result::Result::Err(
@@ -471,7 +622,11 @@ impl ThriftBackend {
             .map(|f| {
                 let field_ident = f.local_var_name();
                 let ttype = self.ttype(&f.ty);
-                let mut read_field = self.codegen_decode_ty(helper, &f.ty);
+                let mut read_field = if helper.with_field_mask {
+                    self.codegen_decode_ty_with_field_mask(helper, &f.ty, "field_fm")
+                } else {
+                    self.codegen_decode_ty(helper, &f.ty)
+                };
                 let field_id = f.id as i16;
                 if self.field_is_box(f) {
                     read_field = format!("::std::boxed::Box::new({read_field})").into();
@@ -492,6 +647,33 @@ impl ThriftBackend {
                     ""
                 };
 
+                if helper.with_field_mask {
+                    // fields excluded by the mask are kept like unknown ones
+                    let skip_field = if keep && !helper.is_async {
+                        format!(
+                            "__pilota_offset += {};
+                            _unknown_fields.push_back(__protocol.get_bytes(Some(__pilota_begin_ptr), __pilota_offset)?);",
+                            helper.codegen_skip_ttype("field_ident.field_type".into())
+                        )
+                    } else {
+                        format!(
+                            "{};",
+                            helper.codegen_skip_ttype("field_ident.field_type".into())
+                        )
+                    };
+                    return format!(
+                        r#"Some({field_id}) if field_ident.field_type == {ttype}  => {{
+                        let (field_fm, exist) = __pilota_field_mask.field({field_id});
+                        if exist {{
+                            {field_ident} = {read_field};
+                            {fields_num}
+                        }} else {{
+                            {skip_field}
+                        }}
+                    }},"#
+                    );
+                }
+
                 format!(
                     r#"Some({field_id}) if field_ident.field_type == {ttype}  => {{
                     {field_ident} = {read_field};
@@ -718,6 +900,69 @@ impl CodegenBackend for ThriftBackend {
             })
             .join("");
 
+        let decode_with_field_mask = self.codegen_decode(
+            &DecodeHelper::new_with_field_mask(false),
+            s,
+            name.clone(),
+            keep,
+            self.is_arg(def_id),
+        );
+        let decode_with_field_mask_async = self.codegen_decode(
+            &DecodeHelper::new_with_field_mask(true),
+            s,
+            name.clone(),
+            keep,
+            self.is_arg(def_id),
+        );
+        stream.push_str(&format! {
+            r#"impl {name} {{
+                /// Decodes the struct, skipping the fields excluded by the field mask.
+                ///
+                /// Required fields that are excluded are set to their default value. With
+                /// `keep_unknown_fields`, excluded fields are kept in `_unknown_fields`.
+                pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                    __protocol: &mut T,
+                    __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+                ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {{
+                    #[allow(unused_imports)]
+                    use ::pilota::{{thrift::TLengthProtocolExt, Buf}};
+                    {decode_with_field_mask}
+                }}
+
+                /// The async version of [`Self::decode_with_field_mask`]. Like
+                /// `decode_async`, it never keeps unknown fields.
+                pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                    __protocol: &'a mut T,
+                    __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+                ) -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>> + Send + 'a>> {{
+                    ::std::boxed::Box::pin(async move {{
+                        {decode_with_field_mask_async}
+                    }})
+                }}
+            }}"#
+        });
+
+        let prune_fields = self.codegen_prune_fields(&s.fields).join("");
+        let prune_arg = if prune_fields.is_empty() {
+            "_"
+        } else {
+            "__pilota_field_mask"
+        };
+        stream.push_str(&format! {
+            r#"impl {name} {{
+                /// Removes the fields, list elements and map entries excluded by the
+                /// field mask in place.
+                ///
+                /// Required fields that are excluded are reset to their default value. List
+                /// elements are matched by their index before pruning.
+                pub fn prune(&mut self, {prune_arg}: &::pilota_thrift_fieldmask::FieldMask) {{
+                    {prune_fields}
+                }}
+            }}"#
+        });
+
+        self.codegen_struct_mask_builder(stream, s, &name);
+
         let idl_name = s.name.raw_str();
         if self.config.with_descriptor {
             stream.push_str(&format! {
@@ -1386,6 +1386,231 @@ impl ThriftBackend {
         .into()
     }
 
+    /// Decodes `ty` against `fm`, an `Option<&FieldMask>` in scope, skipping
+    /// the list items and map entries it excludes. `None` decodes everything.
+    pub(crate) fn codegen_decode_ty_with_field_mask(
+        &self,
+        helper: &DecodeHelper,
+        ty: &Ty,
+        fm: &str,
+    ) -> FastStr {
+        match &ty.kind {
+            ty::Vec(el) => {
+                let read_list_begin = helper.codegen_read_list_begin();
+                let read_list_end = helper.codegen_read_list_end();
+                let read_el = self.codegen_decode_ty_with_field_mask(helper, el, "item_fm");
+                let skip_el = helper.codegen_skip_ttype(self.ttype(el));
+                format! {
+                    r#"{{
+                        let list_ident = {read_list_begin};
+                        let mut val = ::std::vec::Vec::with_capacity(list_ident.size);
+                        for idx in 0..list_ident.size {{
+                            let (item_fm, exist) = match {fm} {{
+                                Some(list_fm) => list_fm.int(idx as i32),
+                                None => (None, true),
+                            }};
+                            if exist {{
+                                val.push({read_el});
+                            }} else {{
+                                {skip_el};
+                            }}
+                        }};
+                        {read_list_end};
+                        val
+                    }}"#
+                }
+                .into()
+            }
+            ty::Map(key_ty, val_ty) => self.decode_map_with_field_mask(
+                key_ty,
+                val_ty,
+                helper,
+                "::pilota::AHashMap::with_capacity(map_ident.size)",
+                fm,
+            ),
+            ty::BTreeMap(key_ty, val_ty) => self.decode_map_with_field_mask(
+                key_ty,
+                val_ty,
+                helper,
+                "::std::collections::BTreeMap::new()",
+                fm,
+            ),
+            ty::Path(p) if self.is_masked_message(p.did) => {
+                let name = self.cx.db.codegen_item_ty(ty.kind.clone());
+                if helper.is_async {
+                    format! {
+                        r#"match {fm} {{
+                            Some(fm) => {name}::decode_with_field_mask_async(__protocol, fm).await?,
+                            None => <{name} as ::pilota::thrift::Message>::decode_async(__protocol).await?,
+                        }}"#
+                    }
+                } else {
+                    format! {
+                        r#"match {fm} {{
+                            Some(fm) => {name}::decode_with_field_mask(__protocol, fm)?,
+                            None => ::pilota::thrift::Message::decode(__protocol)?,
+                        }}"#
+                    }
+                }
+                .into()
+            }
+            ty::Arc(ty) => {
+                let inner = self.codegen_decode_ty_with_field_mask(helper, ty, fm);
+                format!("::std::sync::Arc::new({inner})").into()
+            }
+            _ => self.codegen_decode_ty(helper, ty),
+        }
+    }
+
+    fn decode_map_with_field_mask(
+        &self,
+        key_ty: &Ty,
+        val_ty: &Ty,
+        helper: &DecodeHelper,
+        new: &str,
+        fm: &str,
+    ) -> FastStr {
+        let key_fm = match &key_ty.kind {
+            ty::String | ty::FastStr => "map_fm.str(key.as_str())",
+            ty::I8 | ty::I16 | ty::I32 | ty::I64 | ty::U8 => "map_fm.int(key as i32)",
+            ty::Path(p) if self.is_enum(p.did) => "map_fm.int(key.inner() as i32)",
+            _ => return self.decode_map(key_ty, val_ty, helper, new),
+        };
+        let key_ty_name = self.cx.db.codegen_item_ty(key_ty.kind.clone());
+        let read_el_key = self.codegen_decode_ty(helper, key_ty);
+        let read_el_val = self.codegen_decode_ty_with_field_mask(helper, val_ty, "item_fm");
+        let skip_el_val = helper.codegen_skip_ttype(self.ttype(val_ty));
+        let read_map_begin = helper.codegen_read_map_begin();
+        let read_map_end = helper.codegen_read_map_end();
+        format! {
+            r#"{{
+                let map_ident = {read_map_begin};
+                let mut val = {new};
+                for _ in 0..map_ident.size {{
+                    let key: {key_ty_name} = {read_el_key};
+                    let (item_fm, exist) = match {fm} {{
+                        Some(map_fm) => {key_fm},
+                        None => (None, true),
+                    }};
+                    if exist {{
+                        val.insert(key, {read_el_val});
+                    }} else {{
+                        {skip_el_val};
+                    }}
+                }}
+                {read_map_end};
+                val
+            }}"#
+        }
+        .into()
+    }
+
+    /// Whether the item is a struct with a generated `decode_with_field_mask`.
+    fn is_masked_message(&self, def_id: DefId) -> bool {
+        matches!(&*self.expect_item(def_id), rir::Item::Message(m) if !m.is_wrapper)
+    }
+
+    /// Returns the typed mask builder of values of type `ty`, or `None` if
+    /// they can only be selected as a whole.
+    pub(crate) fn codegen_mask_builder_ty(&self, ty: &Ty) -> Option<FastStr> {
+        const TYPED: &str = "::pilota_thrift_fieldmask::typed";
+        let builder = |ty: &Ty| {
+            self.codegen_mask_builder_ty(ty)
+                .unwrap_or_else(|| format!("{TYPED}::ScalarMask").into())
+        };
+        match &ty.kind {
+            ty::Vec(el) => Some(format!("{TYPED}::ListMask<{}>", builder(el)).into()),
+            ty::Map(key_ty, val_ty) | ty::BTreeMap(key_ty, val_ty) => {
+                let map = match &key_ty.kind {
+                    ty::String | ty::FastStr => "StrMapMask",
+                    ty::I8 | ty::I16 | ty::I32 | ty::I64 | ty::U8 => "IntMapMask",
+                    ty::Path(p) if self.is_enum(p.did) => "IntMapMask",
+                    _ => return None,
+                };
+                Some(format!("{TYPED}::{map}<{}>", builder(val_ty)).into())
+            }
+            ty::Path(p) if self.is_masked_message(p.did) => {
+                let name = self.cx.db.codegen_item_ty(ty.kind.clone());
+                Some(format!("<{name} as {TYPED}::Masked>::Builder").into())
+            }
+            ty::Arc(inner) => self.codegen_mask_builder_ty(inner),
+            _ => None,
+        }
+    }
+
+    /// Generates the statements removing the parts of `ident`, a `&mut` of
+    /// type `ty`, excluded by the `Option<&FieldMask>` named `fm`.
+    ///
+    /// Returns an empty string if nothing inside `ty` can be masked.
+    pub(crate) fn codegen_prune_ty(&self, ty: &Ty, ident: FastStr, fm: &str) -> FastStr {
+        match &ty.kind {
+            ty::Vec(el) => {
+                let prune_el = self.codegen_prune_ty(el, "item".into(), "item_fm");
+                let (item_fm, prune_el) = if prune_el.is_empty() {
+                    ("_", "".into())
+                } else {
+                    ("item_fm", format!("if exist {{ {prune_el} }}"))
+                };
+                format! {
+                    r#"if let Some(list_fm) = {fm} {{
+                        let mut idx = 0;
+                        {ident}.retain_mut(|item| {{
+                            let ({item_fm}, exist) = list_fm.int(idx as i32);
+                            idx += 1;
+                            {prune_el}
+                            exist
+                        }});
+                    }}"#
+                }
+                .into()
+            }
+            ty::Map(key_ty, val_ty) | ty::BTreeMap(key_ty, val_ty) => {
+                let key_fm = match &key_ty.kind {
+                    ty::String | ty::FastStr => "map_fm.str(key)",
+                    ty::I8 | ty::I16 | ty::I32 | ty::I64 | ty::U8 => "map_fm.int(*key as i32)",
+                    ty::Path(p) if self.is_enum(p.did) => "map_fm.int(key.inner() as i32)",
+                    _ => return "".into(),
+                };
+                let prune_val = self.codegen_prune_ty(val_ty, "val".into(), "item_fm");
+                let (item_fm, prune_val) = if prune_val.is_empty() {
+                    ("_", "".into())
+                } else {
+                    ("item_fm", format!("if exist {{ {prune_val} }}"))
+                };
+                format! {
+                    r#"if let Some(map_fm) = {fm} {{
+                        {ident}.retain(|key, val| {{
+                            let ({item_fm}, exist) = {key_fm};
+                            {prune_val}
+                            exist
+                        }});
+                    }}"#
+                }
+                .into()
+            }
+            ty::Path(p) if self.is_masked_message(p.did) => format! {
+                r#"if let Some(fm) = {fm} {{
+                    {ident}.prune(fm);
+                }}"#
+            }
+            .into(),
+            ty::Arc(inner) => {
+                let prune = self.codegen_prune_ty(inner, "inner".into(), fm);
+                if prune.is_empty() {
+                    return prune;
+                }
+                format! {
+                    r#"{{
+                        let inner = ::std::sync::Arc::make_mut({ident});
+                        {prune}
+                    }}"#
+                }
+                .into()
+            }
+            _ => "".into(),
+        }
+    }
+
     pub(crate) fn need_field_mask(&self, ty: &Ty) -> bool {
         match &ty.kind {
             ty::Vec(el) => self.need_field_mask(el),
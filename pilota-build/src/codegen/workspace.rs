@@ -97,7 +97,7 @@ where
             r#""#
         };
 
-        let fieldmask_dep = if self.cg.config.with_field_mask {
+        let fieldmask_dep = if self.cg.config.with_field_mask && B::PROTOCOL == "thrift" {
             r#"pilota-thrift-fieldmask = "*""#
         } else {
             r#""#
@@ -0,0 +1,194 @@
+//! Detection of breaking changes between two versions of an idl.
+//!
+//! [`check_thrift`] compares two versions of a thrift file and its includes,
+//! and [`check_protobuf`] two sets of protobuf descriptors. Both return a
+//! [`Report`] of the changes that matter to the peers of a service: reused or
+//! changed field ids, type and required-ness changes, removed enum values and
+//! methods, and so on. Each change has a [`Severity`], and the report can be
+//! rendered for humans with `Display` or as JSON with [`Report::to_json`].
+//!
+//! In a build script, [`Report::deny`] fails on the changes of a severity:
+//!
+//! ```ignore
+//! let mut old = ProtobufParser::default();
+//! old.include_dirs(vec!["idl/old".into()]);
+//! old.input("idl/old/service.proto");
+//! // same for the new version
+//! check_protobuf(&old.file_descriptors(), &new.file_descriptors())
+//!     .deny(Severity::Breaking)
+//!     .unwrap();
+//! ```
+
+mod protobuf;
+mod thrift;
+
+use std::fmt;
+
+use serde::Serialize;
+pub use thrift::check_thrift;
+
+pub use self::protobuf::check_protobuf;
+
+/// How a change affects the peers that still use the old version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Compatible, e.g. an optional field was added.
+    Info,
+    /// Compatible on the wire, but the generated code or the semantics
+    /// change, e.g. a field was renamed.
+    Warning,
+    /// Old and new peers may fail to talk to each other.
+    Breaking,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Breaking => "breaking",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// A definition was removed.
+    ItemRemoved,
+    /// A definition was added.
+    ItemAdded,
+    /// A definition became another kind of definition, e.g. a struct became
+    /// a union.
+    ItemKindChanged,
+    /// A field id is used by a field with another name and type.
+    FieldIdReused,
+    /// A field kept its name but got another id.
+    FieldIdChanged,
+    FieldRemoved,
+    FieldAdded,
+    FieldRenamed,
+    FieldTypeChanged,
+    /// A field became or stopped being required.
+    FieldRequirednessChanged,
+    /// A field became or stopped being repeated.
+    FieldLabelChanged,
+    /// A field moved into, out of or between oneofs.
+    OneofMoved,
+    /// A field uses a number or name that was reserved.
+    ReservedViolated,
+    /// A number or name is no longer reserved.
+    ReservedRemoved,
+    EnumValueRemoved,
+    EnumValueAdded,
+    EnumValueRenamed,
+    /// An enum value kept its name but got another number.
+    EnumValueChanged,
+    MethodRemoved,
+    MethodAdded,
+    /// The request type of a method changed.
+    MethodRequestChanged,
+    /// The result type of a method changed.
+    MethodResultChanged,
+    /// A method became or stopped being streaming.
+    MethodStreamingChanged,
+    /// A method became or stopped being oneway.
+    MethodOnewayChanged,
+    /// The service that a service extends changed.
+    ServiceExtendsChanged,
+    /// A service was renamed, keeping its methods.
+    ServiceRenamed,
+    /// The package of a file changed.
+    PackageChanged,
+    /// An exception was renamed, keeping its fields.
+    ExceptionRenamed,
+    /// The type that a typedef aliases changed.
+    TypedefChanged,
+}
+
+/// A change between the two versions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub severity: Severity,
+    pub kind: ChangeKind,
+    /// The file of the change, relative to the directory of the checked
+    /// file.
+    pub file: String,
+    /// The changed definition, e.g. `User.name` or `UserService.get`.
+    pub location: String,
+    pub message: String,
+}
+
+/// The changes between two versions, in the order of the definitions.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub changes: Vec<Change>,
+}
+
+impl Report {
+    /// Returns the highest severity of the changes, or `None` if nothing
+    /// changed.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.changes.iter().map(|c| c.severity).max()
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.max_severity() == Some(Severity::Breaking)
+    }
+
+    /// Returns the changes of at least the given severity.
+    pub fn at_least(&self, severity: Severity) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(move |c| c.severity >= severity)
+    }
+
+    /// Fails with the changes of at least the given severity, if any.
+    pub fn deny(&self, severity: Severity) -> anyhow::Result<()> {
+        let denied = self.at_least(severity).collect::<Vec<_>>();
+        if denied.is_empty() {
+            return Ok(());
+        }
+        let mut message = format!("{} incompatible change(s):", denied.len());
+        for c in denied {
+            message.push_str(&format!(
+                "\n{}: {}: {}: {}",
+                c.severity, c.file, c.location, c.message
+            ));
+        }
+        anyhow::bail!(message)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        kind: ChangeKind,
+        file: &str,
+        location: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.changes.push(Change {
+            severity,
+            kind,
+            file: file.to_string(),
+            location: location.into(),
+            message: message.into(),
+        })
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.changes {
+            writeln!(
+                f,
+                "{}: {}: {}: {}",
+                c.severity, c.file, c.location, c.message
+            )?;
+        }
+        Ok(())
+    }
+}
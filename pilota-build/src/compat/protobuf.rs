@@ -0,0 +1,711 @@
+use std::collections::HashMap;
+
+use protobuf::descriptor::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+    field_descriptor_proto::{Label, Type},
+};
+
+use super::{ChangeKind, Report, Severity};
+
+/// Compares two versions of a set of protobuf files, as returned by
+/// [`ProtobufParser::file_descriptors`](crate::parser::ProtobufParser::file_descriptors).
+///
+/// Files are matched by their name, and definitions by their name in their
+/// file, so that a changed package is reported once instead of as removed
+/// definitions.
+pub fn check_protobuf(old: &[FileDescriptorProto], new: &[FileDescriptorProto]) -> Report {
+    let mut checker = Checker {
+        old: Names::new(old),
+        new: Names::new(new),
+        report: Report::default(),
+    };
+    for old_file in old {
+        if let Some(new_file) = new.iter().find(|f| f.name() == old_file.name()) {
+            checker.file(old_file, new_file);
+        }
+    }
+    checker.report
+}
+
+/// Maps the full names of the messages and enums, e.g. `.pkg.Outer.Inner`, to
+/// their file and their name in it, e.g. `Outer.Inner`.
+struct Names(HashMap<String, (String, String)>);
+
+impl Names {
+    fn new(files: &[FileDescriptorProto]) -> Self {
+        fn message(names: &mut Names, file: &str, scope: &str, local: &str, m: &DescriptorProto) {
+            let full = format!("{scope}.{}", m.name());
+            let local = join(local, m.name());
+            for nested in &m.nested_type {
+                message(names, file, &full, &local, nested);
+            }
+            for e in &m.enum_type {
+                names.0.insert(
+                    format!("{full}.{}", e.name()),
+                    (file.to_string(), join(&local, e.name())),
+                );
+            }
+            names.0.insert(full, (file.to_string(), local));
+        }
+
+        let mut names = Names(HashMap::new());
+        for file in files {
+            let scope = match file.package() {
+                "" => String::new(),
+                package => format!(".{package}"),
+            };
+            for m in &file.message_type {
+                message(&mut names, file.name(), &scope, "", m);
+            }
+            for e in &file.enum_type {
+                names.0.insert(
+                    format!("{scope}.{}", e.name()),
+                    (file.name().to_string(), e.name().to_string()),
+                );
+            }
+        }
+        names
+    }
+
+    /// Returns the name of a type that does not depend on the package.
+    fn local(&self, full_name: &str) -> String {
+        match self.0.get(full_name) {
+            Some((file, local)) => format!("{file}:{local}"),
+            None => full_name.to_string(),
+        }
+    }
+}
+
+struct Checker {
+    old: Names,
+    new: Names,
+    report: Report,
+}
+
+impl Checker {
+    fn file(&mut self, old: &FileDescriptorProto, new: &FileDescriptorProto) {
+        let file = old.name();
+        if old.package() != new.package() {
+            self.report.push(
+                Severity::Breaking,
+                ChangeKind::PackageChanged,
+                file,
+                old.package(),
+                format!(
+                    "the package changed from `{}` to `{}`",
+                    old.package(),
+                    new.package()
+                ),
+            );
+        }
+
+        self.messages(file, "", &old.message_type, &new.message_type);
+        self.enums(file, "", &old.enum_type, &new.enum_type);
+        self.services(file, &old.service, &new.service);
+    }
+
+    fn messages(
+        &mut self,
+        file: &str,
+        scope: &str,
+        old: &[DescriptorProto],
+        new: &[DescriptorProto],
+    ) {
+        for o in old {
+            let location = join(scope, o.name());
+            match new.iter().find(|n| n.name() == o.name()) {
+                Some(n) => self.message(file, &location, o, n),
+                None => self.report.push(
+                    Severity::Warning,
+                    ChangeKind::ItemRemoved,
+                    file,
+                    location.clone(),
+                    format!("message `{location}` was removed"),
+                ),
+            }
+        }
+        for n in new {
+            if old.iter().all(|o| o.name() != n.name()) {
+                let location = join(scope, n.name());
+                self.report.push(
+                    Severity::Info,
+                    ChangeKind::ItemAdded,
+                    file,
+                    location.clone(),
+                    format!("message `{location}` was added"),
+                );
+            }
+        }
+    }
+
+    fn message(
+        &mut self,
+        file: &str,
+        location: &str,
+        old: &DescriptorProto,
+        new: &DescriptorProto,
+    ) {
+        for o in &old.field {
+            let field_location = format!("{location}.{}", o.name());
+            let Some(n) = new.field.iter().find(|n| n.number() == o.number()) else {
+                let (severity, message) = if is_reserved(new, o.number(), o.name()) {
+                    (
+                        Severity::Info,
+                        format!(
+                            "field {} `{}` was removed and reserved",
+                            o.number(),
+                            o.name()
+                        ),
+                    )
+                } else {
+                    (
+                        Severity::Warning,
+                        format!(
+                            "field {} `{}` was removed without reserving its number",
+                            o.number(),
+                            o.name()
+                        ),
+                    )
+                };
+                self.report.push(
+                    severity,
+                    ChangeKind::FieldRemoved,
+                    file,
+                    field_location,
+                    message,
+                );
+                continue;
+            };
+
+            let type_change = self.type_change(o, n);
+            if o.name() != n.name() && type_change == Some(Severity::Breaking) {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::FieldIdReused,
+                    file,
+                    field_location,
+                    format!(
+                        "field number {} was `{} {}` and is now `{} {}`",
+                        o.number(),
+                        self.old.type_name(o),
+                        o.name(),
+                        self.new.type_name(n),
+                        n.name()
+                    ),
+                );
+                continue;
+            }
+            if let Some(severity) = type_change {
+                self.report.push(
+                    severity,
+                    ChangeKind::FieldTypeChanged,
+                    file,
+                    field_location.clone(),
+                    format!(
+                        "the type of field {} changed from `{}` to `{}`",
+                        o.number(),
+                        self.old.type_name(o),
+                        self.new.type_name(n)
+                    ),
+                );
+            }
+            if o.name() != n.name() {
+                self.report.push(
+                    Severity::Warning,
+                    ChangeKind::FieldRenamed,
+                    file,
+                    field_location.clone(),
+                    format!(
+                        "field {} was renamed from `{}` to `{}`",
+                        o.number(),
+                        o.name(),
+                        n.name()
+                    ),
+                );
+            }
+            self.label_change(file, &field_location, o, n);
+
+            let (old_oneof, new_oneof) = (oneof(old, o), oneof(new, n));
+            if old_oneof != new_oneof {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::OneofMoved,
+                    file,
+                    field_location,
+                    match (old_oneof, new_oneof) {
+                        (Some(o), Some(n)) => format!("field moved from oneof `{o}` to `{n}`"),
+                        (Some(o), None) => format!("field moved out of oneof `{o}`"),
+                        (None, Some(n)) => format!("field moved into oneof `{n}`"),
+                        (None, None) => unreachable!(),
+                    },
+                );
+            }
+        }
+
+        for n in &new.field {
+            if old.field.iter().any(|o| o.number() == n.number()) {
+                continue;
+            }
+            let field_location = format!("{location}.{}", n.name());
+            if is_reserved(old, n.number(), n.name()) {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::ReservedViolated,
+                    file,
+                    field_location,
+                    format!(
+                        "field {} `{}` uses a number or name reserved in the old version",
+                        n.number(),
+                        n.name()
+                    ),
+                );
+            } else if n.label() == Label::LABEL_REQUIRED {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::FieldAdded,
+                    file,
+                    field_location,
+                    format!("required field {} `{}` was added", n.number(), n.name()),
+                );
+            } else {
+                self.report.push(
+                    Severity::Info,
+                    ChangeKind::FieldAdded,
+                    file,
+                    field_location,
+                    format!("field {} `{}` was added", n.number(), n.name()),
+                );
+            }
+        }
+
+        for range in &old.reserved_range {
+            let (start, end) = (range.start(), range.end());
+            if !new
+                .reserved_range
+                .iter()
+                .any(|r| r.start() <= start && end <= r.end())
+            {
+                self.report.push(
+                    Severity::Warning,
+                    ChangeKind::ReservedRemoved,
+                    file,
+                    location,
+                    if end - start == 1 {
+                        format!("the reserved number {start} is no longer reserved")
+                    } else {
+                        format!(
+                            "the reserved numbers {start} to {} are no longer reserved",
+                            end - 1
+                        )
+                    },
+                );
+            }
+        }
+        for name in &old.reserved_name {
+            if !new.reserved_name.contains(name) {
+                self.report.push(
+                    Severity::Warning,
+                    ChangeKind::ReservedRemoved,
+                    file,
+                    location,
+                    format!("the reserved name `{name}` is no longer reserved"),
+                );
+            }
+        }
+
+        self.messages(file, location, &old.nested_type, &new.nested_type);
+        self.enums(file, location, &old.enum_type, &new.enum_type);
+    }
+
+    /// Returns the severity of the type change of a field, if its type
+    /// changed.
+    fn type_change(
+        &self,
+        old: &FieldDescriptorProto,
+        new: &FieldDescriptorProto,
+    ) -> Option<Severity> {
+        let (old_ty, new_ty) = (old.type_(), new.type_());
+        if old_ty == new_ty {
+            return match old_ty {
+                Type::TYPE_MESSAGE | Type::TYPE_GROUP | Type::TYPE_ENUM
+                    if self.old.local(old.type_name()) != self.new.local(new.type_name()) =>
+                {
+                    // other enums are encoded alike, other messages are not
+                    Some(if old_ty == Type::TYPE_ENUM {
+                        Severity::Warning
+                    } else {
+                        Severity::Breaking
+                    })
+                }
+                _ => None,
+            };
+        }
+        if wire_class(old_ty) == wire_class(new_ty) {
+            Some(Severity::Warning)
+        } else {
+            Some(Severity::Breaking)
+        }
+    }
+
+    fn label_change(
+        &mut self,
+        file: &str,
+        location: &str,
+        old: &FieldDescriptorProto,
+        new: &FieldDescriptorProto,
+    ) {
+        let (old_label, new_label) = (label(old), label(new));
+        if old_label == new_label {
+            return;
+        }
+        let (severity, kind) = match (old_label, new_label) {
+            ("repeated", _) | (_, "repeated") => {
+                (Severity::Breaking, ChangeKind::FieldLabelChanged)
+            }
+            ("required", _) | (_, "required") => {
+                (Severity::Breaking, ChangeKind::FieldRequirednessChanged)
+            }
+            _ => (Severity::Info, ChangeKind::FieldRequirednessChanged),
+        };
+        self.report.push(
+            severity,
+            kind,
+            file,
+            location,
+            format!(
+                "field {} changed from {old_label} to {new_label}",
+                old.number()
+            ),
+        );
+    }
+
+    fn enums(
+        &mut self,
+        file: &str,
+        scope: &str,
+        old: &[EnumDescriptorProto],
+        new: &[EnumDescriptorProto],
+    ) {
+        for o in old {
+            let location = join(scope, o.name());
+            match new.iter().find(|n| n.name() == o.name()) {
+                Some(n) => self.enum_(file, &location, o, n),
+                None => self.report.push(
+                    Severity::Warning,
+                    ChangeKind::ItemRemoved,
+                    file,
+                    location.clone(),
+                    format!("enum `{location}` was removed"),
+                ),
+            }
+        }
+        for n in new {
+            if old.iter().all(|o| o.name() != n.name()) {
+                let location = join(scope, n.name());
+                self.report.push(
+                    Severity::Info,
+                    ChangeKind::ItemAdded,
+                    file,
+                    location.clone(),
+                    format!("enum `{location}` was added"),
+                );
+            }
+        }
+    }
+
+    fn enum_(
+        &mut self,
+        file: &str,
+        location: &str,
+        old: &EnumDescriptorProto,
+        new: &EnumDescriptorProto,
+    ) {
+        // enum reserved ranges are inclusive
+        let reserved = |number: i32| {
+            new.reserved_range
+                .iter()
+                .any(|r| (r.start()..=r.end()).contains(&number))
+        };
+
+        for o in &old.value {
+            let value_location = format!("{location}.{}", o.name());
+            match new.value.iter().find(|n| n.number() == o.number()) {
+                Some(n) if n.name() != o.name() => self.report.push(
+                    Severity::Warning,
+                    ChangeKind::EnumValueRenamed,
+                    file,
+                    value_location,
+                    format!(
+                        "enum value {} was renamed from `{}` to `{}`",
+                        o.number(),
+                        o.name(),
+                        n.name()
+                    ),
+                ),
+                Some(_) => {}
+                None => match new.value.iter().find(|n| n.name() == o.name()) {
+                    Some(n) => self.report.push(
+                        Severity::Breaking,
+                        ChangeKind::EnumValueChanged,
+                        file,
+                        value_location,
+                        format!(
+                            "enum value `{}` changed from {} to {}",
+                            o.name(),
+                            o.number(),
+                            n.number()
+                        ),
+                    ),
+                    None if reserved(o.number()) => self.report.push(
+                        Severity::Info,
+                        ChangeKind::EnumValueRemoved,
+                        file,
+                        value_location,
+                        format!(
+                            "enum value `{}` ({}) was removed and reserved",
+                            o.name(),
+                            o.number()
+                        ),
+                    ),
+                    None => self.report.push(
+                        Severity::Breaking,
+                        ChangeKind::EnumValueRemoved,
+                        file,
+                        value_location,
+                        format!("enum value `{}` ({}) was removed", o.name(), o.number()),
+                    ),
+                },
+            }
+        }
+
+        for n in &new.value {
+            if old
+                .value
+                .iter()
+                .any(|o| o.number() == n.number() || o.name() == n.name())
+            {
+                continue;
+            }
+            self.report.push(
+                Severity::Info,
+                ChangeKind::EnumValueAdded,
+                file,
+                format!("{location}.{}", n.name()),
+                format!("enum value `{}` ({}) was added", n.name(), n.number()),
+            );
+        }
+    }
+
+    fn services(
+        &mut self,
+        file: &str,
+        old: &[ServiceDescriptorProto],
+        new: &[ServiceDescriptorProto],
+    ) {
+        // a service is renamed if another one with the same methods was added
+        let mut added = new
+            .iter()
+            .filter(|n| old.iter().all(|o| o.name() != n.name()))
+            .collect::<Vec<_>>();
+
+        for o in old {
+            if let Some(n) = new.iter().find(|n| n.name() == o.name()) {
+                self.service(file, o, n);
+                continue;
+            }
+            match added
+                .iter()
+                .position(|n| signatures(&self.old, o) == signatures(&self.new, n))
+            {
+                Some(i) => {
+                    let n = added.remove(i);
+                    self.report.push(
+                        Severity::Breaking,
+                        ChangeKind::ServiceRenamed,
+                        file,
+                        o.name(),
+                        format!(
+                            "service `{}` was renamed to `{}`, which changes the paths of its \
+                             methods",
+                            o.name(),
+                            n.name()
+                        ),
+                    );
+                }
+                None => self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::ItemRemoved,
+                    file,
+                    o.name(),
+                    format!("service `{}` was removed", o.name()),
+                ),
+            }
+        }
+        for n in added {
+            self.report.push(
+                Severity::Info,
+                ChangeKind::ItemAdded,
+                file,
+                n.name(),
+                format!("service `{}` was added", n.name()),
+            );
+        }
+    }
+
+    fn service(&mut self, file: &str, old: &ServiceDescriptorProto, new: &ServiceDescriptorProto) {
+        for o in &old.method {
+            let location = format!("{}.{}", old.name(), o.name());
+            let Some(n) = new.method.iter().find(|n| n.name() == o.name()) else {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodRemoved,
+                    file,
+                    location,
+                    format!("method `{}` was removed", o.name()),
+                );
+                continue;
+            };
+
+            if self.old.local(o.input_type()) != self.new.local(n.input_type()) {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodRequestChanged,
+                    file,
+                    location.clone(),
+                    format!(
+                        "the request of `{}` changed from `{}` to `{}`",
+                        o.name(),
+                        o.input_type(),
+                        n.input_type()
+                    ),
+                );
+            }
+            if self.old.local(o.output_type()) != self.new.local(n.output_type()) {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodResultChanged,
+                    file,
+                    location.clone(),
+                    format!(
+                        "the response of `{}` changed from `{}` to `{}`",
+                        o.name(),
+                        o.output_type(),
+                        n.output_type()
+                    ),
+                );
+            }
+            if o.client_streaming() != n.client_streaming()
+                || o.server_streaming() != n.server_streaming()
+            {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodStreamingChanged,
+                    file,
+                    location,
+                    format!("the streaming of `{}` changed", o.name()),
+                );
+            }
+        }
+
+        for n in &new.method {
+            if old.method.iter().all(|o| o.name() != n.name()) {
+                self.report.push(
+                    Severity::Info,
+                    ChangeKind::MethodAdded,
+                    file,
+                    format!("{}.{}", new.name(), n.name()),
+                    format!("method `{}` was added", n.name()),
+                );
+            }
+        }
+    }
+}
+
+impl Names {
+    fn type_name(&self, field: &FieldDescriptorProto) -> String {
+        match field.type_() {
+            Type::TYPE_MESSAGE | Type::TYPE_GROUP | Type::TYPE_ENUM => {
+                match self.0.get(field.type_name()) {
+                    Some((_, local)) => local.clone(),
+                    None => field.type_name().trim_start_matches('.').to_string(),
+                }
+            }
+            ty => format!("{ty:?}")
+                .trim_start_matches("TYPE_")
+                .to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Returns the methods of a service with their request and response, sorted.
+fn signatures(names: &Names, service: &ServiceDescriptorProto) -> Vec<(String, String, String)> {
+    let mut methods = service
+        .method
+        .iter()
+        .map(|m| {
+            (
+                m.name().to_string(),
+                names.local(m.input_type()),
+                names.local(m.output_type()),
+            )
+        })
+        .collect::<Vec<_>>();
+    methods.sort();
+    methods
+}
+
+/// Groups the types that are encoded alike, so that changing a type within a
+/// group keeps the wire format, if not the values.
+fn wire_class(ty: Type) -> &'static str {
+    match ty {
+        Type::TYPE_INT32
+        | Type::TYPE_INT64
+        | Type::TYPE_UINT32
+        | Type::TYPE_UINT64
+        | Type::TYPE_BOOL
+        | Type::TYPE_ENUM => "varint",
+        Type::TYPE_SINT32 | Type::TYPE_SINT64 => "zigzag",
+        Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 => "fixed32",
+        Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 => "fixed64",
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE => "length-delimited",
+        Type::TYPE_FLOAT => "float",
+        Type::TYPE_DOUBLE => "double",
+        Type::TYPE_GROUP => "group",
+    }
+}
+
+fn label(field: &FieldDescriptorProto) -> &'static str {
+    match field.label() {
+        Label::LABEL_REPEATED => "repeated",
+        Label::LABEL_REQUIRED => "required",
+        Label::LABEL_OPTIONAL if field.proto3_optional() => "optional",
+        Label::LABEL_OPTIONAL => "singular",
+    }
+}
+
+/// Returns the oneof of a field, unless it is the synthetic oneof of a proto3
+/// optional field.
+fn oneof<'a>(message: &'a DescriptorProto, field: &FieldDescriptorProto) -> Option<&'a str> {
+    if field.proto3_optional() {
+        return None;
+    }
+    let index = field.oneof_index? as usize;
+    message.oneof_decl.get(index).map(|o| o.name())
+}
+
+fn is_reserved(message: &DescriptorProto, number: i32, name: &str) -> bool {
+    message
+        .reserved_range
+        .iter()
+        .any(|r| (r.start()..r.end()).contains(&number))
+        || message.reserved_name.iter().any(|n| n == name)
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
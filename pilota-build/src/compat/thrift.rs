@@ -0,0 +1,748 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use pilota_thrift_parser::{
+    Attribute, Enum, Exception, Field, File, FileParser, FileSource, Item, Service, Ty, Type,
+};
+
+use super::{ChangeKind, Report, Severity};
+
+/// Compares two versions of a thrift file, and of the files it includes.
+///
+/// Included files are matched by their path relative to the checked file,
+/// and definitions by their name.
+pub fn check_thrift(old: impl AsRef<Path>, new: impl AsRef<Path>) -> anyhow::Result<Report> {
+    let old = Schema::load(old.as_ref())?;
+    let new = Schema::load(new.as_ref())?;
+
+    let mut checker = Checker {
+        old: &old,
+        new: &new,
+        renames: HashMap::new(),
+        report: Report::default(),
+    };
+    checker.check();
+    Ok(checker.report)
+}
+
+/// A thrift file and the files it includes, keyed by their path relative to
+/// its directory.
+struct Schema {
+    files: Vec<(String, File)>,
+}
+
+impl Schema {
+    fn load(entry: &Path) -> anyhow::Result<Self> {
+        let dir = entry.parent().unwrap_or(Path::new(""));
+        let name = entry
+            .file_name()
+            .with_context(|| format!("{} is not a file", entry.display()))?;
+
+        let mut schema = Schema { files: Vec::new() };
+        let mut queue = VecDeque::from([normalize(Path::new(name))]);
+        while let Some(key) = queue.pop_front() {
+            if schema.file(&key).is_some() {
+                continue;
+            }
+            let path = dir.join(&key);
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let file = FileParser::new(FileSource::new_with_path(path, &source)?).parse()?;
+            for item in &file.items {
+                if let Item::Include(include) = item {
+                    queue.push_back(include_key(&key, &include.path));
+                }
+            }
+            schema.files.push((key, file));
+        }
+        Ok(schema)
+    }
+
+    fn file(&self, key: &str) -> Option<&File> {
+        self.files.iter().find(|(k, _)| k == key).map(|(_, f)| f)
+    }
+
+    /// Returns the name of a definition as written in the checked file.
+    fn qualified(&self, key: &str, name: &str) -> String {
+        if self.files[0].0 == key {
+            name.to_string()
+        } else {
+            format!("{}.{name}", stem(key))
+        }
+    }
+
+    fn resolve_path(
+        &self,
+        key: &str,
+        path: &pilota_thrift_parser::Path,
+    ) -> Option<(String, &Item)> {
+        let file = self.file(key)?;
+        let (key, name) = match &*path.segments {
+            [name] => (key.to_string(), name.as_str()),
+            [scope, name] => {
+                let include = file.items.iter().find_map(|item| match item {
+                    Item::Include(i) if stem(&i.path) == scope.as_str() => Some(&i.path),
+                    _ => None,
+                })?;
+                (include_key(key, include), name.as_str())
+            }
+            _ => return None,
+        };
+        let item = find_item(self.file(&key)?, name)?;
+        Some((key, item))
+    }
+
+    fn resolve(&self, key: &str, ty: &Type) -> Resolved {
+        self.resolve_with_depth(key, ty, 0)
+    }
+
+    fn resolve_with_depth(&self, key: &str, ty: &Type, depth: usize) -> Resolved {
+        let resolve = |ty: &Type| Box::new(self.resolve_with_depth(key, ty, depth));
+        match &ty.0 {
+            Ty::String => Resolved::Base("string"),
+            Ty::Void => Resolved::Base("void"),
+            Ty::Byte => Resolved::Base("byte"),
+            Ty::Bool => Resolved::Base("bool"),
+            Ty::Binary => Resolved::Base("binary"),
+            Ty::I8 => Resolved::Base("i8"),
+            Ty::I16 => Resolved::Base("i16"),
+            Ty::I32 => Resolved::Base("i32"),
+            Ty::I64 => Resolved::Base("i64"),
+            Ty::Double => Resolved::Base("double"),
+            Ty::Uuid => Resolved::Base("uuid"),
+            Ty::List { value, .. } => Resolved::List(resolve(value)),
+            Ty::Set { value, .. } => Resolved::Set(resolve(value)),
+            Ty::Map { key, value, .. } => Resolved::Map(resolve(key), resolve(value)),
+            Ty::Path(path) => match self.resolve_path(key, path) {
+                // typedefs may be cyclic in a broken file
+                Some((key, Item::Typedef(t))) if depth < 32 => {
+                    self.resolve_with_depth(&key, &t.r#type, depth + 1)
+                }
+                Some((key, Item::Enum(e))) => Resolved::Enum(self.qualified(&key, &e.name)),
+                Some((key, Item::Struct(s))) => Resolved::Struct(self.qualified(&key, &s.name)),
+                Some((key, Item::Union(u))) => Resolved::Struct(self.qualified(&key, &u.name)),
+                Some((key, Item::Exception(e))) => Resolved::Struct(self.qualified(&key, &e.name)),
+                _ => Resolved::Unknown(
+                    path.segments
+                        .iter()
+                        .map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join("."),
+                ),
+            },
+        }
+    }
+}
+
+/// A type with its typedefs resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Resolved {
+    Base(&'static str),
+    List(Box<Resolved>),
+    Set(Box<Resolved>),
+    Map(Box<Resolved>, Box<Resolved>),
+    Enum(String),
+    /// A struct, union or exception.
+    Struct(String),
+    /// A path that refers to nothing.
+    Unknown(String),
+}
+
+impl Resolved {
+    /// Returns the type as encoded, where enums are `i32` and strings are
+    /// `binary`.
+    fn wire(&self) -> Resolved {
+        match self {
+            Resolved::Base("string") => Resolved::Base("binary"),
+            Resolved::Base("byte") => Resolved::Base("i8"),
+            Resolved::Enum(_) => Resolved::Base("i32"),
+            Resolved::List(v) => Resolved::List(Box::new(v.wire())),
+            Resolved::Set(v) => Resolved::Set(Box::new(v.wire())),
+            Resolved::Map(k, v) => Resolved::Map(Box::new(k.wire()), Box::new(v.wire())),
+            ty => ty.clone(),
+        }
+    }
+
+    fn rename(&self, renames: &HashMap<String, String>) -> Resolved {
+        match self {
+            Resolved::Struct(name) => {
+                Resolved::Struct(renames.get(name).unwrap_or(name).to_string())
+            }
+            Resolved::List(v) => Resolved::List(Box::new(v.rename(renames))),
+            Resolved::Set(v) => Resolved::Set(Box::new(v.rename(renames))),
+            Resolved::Map(k, v) => {
+                Resolved::Map(Box::new(k.rename(renames)), Box::new(v.rename(renames)))
+            }
+            ty => ty.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Resolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolved::Base(name) => f.write_str(name),
+            Resolved::List(v) => write!(f, "list<{v}>"),
+            Resolved::Set(v) => write!(f, "set<{v}>"),
+            Resolved::Map(k, v) => write!(f, "map<{k}, {v}>"),
+            Resolved::Enum(name) | Resolved::Struct(name) | Resolved::Unknown(name) => {
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+struct Checker<'a> {
+    old: &'a Schema,
+    new: &'a Schema,
+    /// The renamed exceptions, from their old to their new qualified name.
+    renames: HashMap<String, String>,
+    report: Report,
+}
+
+impl Checker<'_> {
+    fn check(&mut self) {
+        let (old, new) = (self.old, self.new);
+        for (key, _) in &old.files {
+            self.find_renamed_exceptions(key);
+        }
+        for (key, old_file) in &old.files {
+            let Some(new_file) = new.file(key) else {
+                continue;
+            };
+
+            for old_item in &old_file.items {
+                let Some(name) = item_name(old_item) else {
+                    continue;
+                };
+                match find_item(new_file, name) {
+                    Some(new_item) => self.item(key, name, old_item, new_item),
+                    None if self.renames.contains_key(&old.qualified(key, name)) => {}
+                    None => {
+                        let severity = if matches!(old_item, Item::Service(_)) {
+                            Severity::Breaking
+                        } else {
+                            Severity::Warning
+                        };
+                        self.report.push(
+                            severity,
+                            ChangeKind::ItemRemoved,
+                            key,
+                            name,
+                            format!("{} `{name}` was removed", item_kind(old_item)),
+                        );
+                    }
+                }
+            }
+
+            for new_item in &new_file.items {
+                let Some(name) = item_name(new_item) else {
+                    continue;
+                };
+                let qualified = new.qualified(key, name);
+                if find_item(old_file, name).is_none()
+                    && !self.renames.values().any(|n| *n == qualified)
+                {
+                    self.report.push(
+                        Severity::Info,
+                        ChangeKind::ItemAdded,
+                        key,
+                        name,
+                        format!("{} `{name}` was added", item_kind(new_item)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Matches the removed exceptions of a file with the added ones that have
+    /// the same fields.
+    fn find_renamed_exceptions(&mut self, key: &str) {
+        let (Some(old_file), Some(new_file)) = (self.old.file(key), self.new.file(key)) else {
+            return;
+        };
+        fn exceptions<'a>(file: &'a File, other: &File) -> Vec<&'a Exception> {
+            file.items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Exception(e) if find_item(other, &e.name).is_none() => Some(e),
+                    _ => None,
+                })
+                .collect()
+        }
+        let fields = |schema: &Schema, fields: &[Field]| {
+            let mut fields = fields
+                .iter()
+                .map(|f| (f.id, schema.resolve(key, &f.ty).wire()))
+                .collect::<Vec<_>>();
+            fields.sort_by_key(|(id, _)| *id);
+            fields
+        };
+
+        let mut added = exceptions(new_file, old_file);
+        for removed in exceptions(old_file, new_file) {
+            let removed_fields = fields(self.old, &removed.fields);
+            let Some(i) = added
+                .iter()
+                .position(|e| fields(self.new, &e.fields) == removed_fields)
+            else {
+                continue;
+            };
+            let renamed = added.remove(i);
+            self.renames.insert(
+                self.old.qualified(key, &removed.name),
+                self.new.qualified(key, &renamed.name),
+            );
+            self.report.push(
+                Severity::Warning,
+                ChangeKind::ExceptionRenamed,
+                key,
+                removed.name.as_str(),
+                format!(
+                    "exception `{}` was renamed to `{}`",
+                    &*removed.name, &*renamed.name
+                ),
+            );
+        }
+    }
+
+    fn item(&mut self, key: &str, name: &str, old: &Item, new: &Item) {
+        let location = self.old.qualified(key, name);
+        match (old, new) {
+            (Item::Struct(o), Item::Struct(n)) => {
+                self.fields(key, &location, &o.fields, &n.fields, false)
+            }
+            (Item::Union(o), Item::Union(n)) => {
+                self.fields(key, &location, &o.fields, &n.fields, false)
+            }
+            (Item::Exception(o), Item::Exception(n)) => {
+                self.fields(key, &location, &o.fields, &n.fields, false)
+            }
+            (Item::Enum(o), Item::Enum(n)) => self.enum_(key, &location, o, n),
+            (Item::Service(o), Item::Service(n)) => self.service(key, &location, o, n),
+            (Item::Typedef(o), Item::Typedef(n)) => {
+                let (old_ty, new_ty) = (
+                    self.old.resolve(key, &o.r#type),
+                    self.new.resolve(key, &n.r#type),
+                );
+                if let Some(severity) = self.type_change(&old_ty, &new_ty) {
+                    self.report.push(
+                        severity,
+                        ChangeKind::TypedefChanged,
+                        key,
+                        location,
+                        format!("typedef `{name}` changed from `{old_ty}` to `{new_ty}`"),
+                    );
+                }
+            }
+            (Item::Constant(_), Item::Constant(_)) => {}
+            _ => {
+                let struct_like = |item: &Item| {
+                    matches!(item, Item::Struct(_) | Item::Union(_) | Item::Exception(_))
+                };
+                let severity = if struct_like(old) && struct_like(new) {
+                    Severity::Warning
+                } else {
+                    Severity::Breaking
+                };
+                self.report.push(
+                    severity,
+                    ChangeKind::ItemKindChanged,
+                    key,
+                    location,
+                    format!(
+                        "`{name}` changed from {} to {}",
+                        item_kind(old),
+                        item_kind(new)
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Compares the fields of a struct, or the arguments of a method if
+    /// `args`, where the parser makes the fields without an attribute
+    /// required.
+    fn fields(&mut self, key: &str, location: &str, old: &[Field], new: &[Field], args: bool) {
+        let attribute = |f: &Field| match f.attribute {
+            Attribute::Required if args => Attribute::Default,
+            ref attribute => attribute.clone(),
+        };
+
+        for o in old {
+            let old_name = o.name.as_str();
+            let field_location = format!("{location}.{old_name}");
+            let Some(n) = new.iter().find(|n| n.id == o.id) else {
+                match new.iter().find(|n| n.name.as_str() == old_name) {
+                    Some(n) => self.report.push(
+                        Severity::Breaking,
+                        ChangeKind::FieldIdChanged,
+                        key,
+                        field_location,
+                        format!(
+                            "field `{old_name}` changed its id from {} to {}",
+                            o.id, n.id
+                        ),
+                    ),
+                    None => {
+                        let (severity, message) = if attribute(o) == Attribute::Required {
+                            (
+                                Severity::Breaking,
+                                format!("required field {} `{old_name}` was removed", o.id),
+                            )
+                        } else {
+                            (
+                                Severity::Warning,
+                                format!(
+                                    "field {} `{old_name}` was removed, its id must not be reused",
+                                    o.id
+                                ),
+                            )
+                        };
+                        self.report.push(
+                            severity,
+                            ChangeKind::FieldRemoved,
+                            key,
+                            field_location,
+                            message,
+                        );
+                    }
+                }
+                continue;
+            };
+
+            let new_name = n.name.as_str();
+            let old_ty = self.old.resolve(key, &o.ty);
+            let new_ty = self.new.resolve(key, &n.ty);
+            let type_change = self.type_change(&old_ty, &new_ty);
+            if old_name != new_name && type_change.is_some() {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::FieldIdReused,
+                    key,
+                    field_location,
+                    format!(
+                        "field id {} was `{old_ty} {old_name}` and is now `{new_ty} {new_name}`",
+                        o.id
+                    ),
+                );
+                continue;
+            }
+            if let Some(severity) = type_change {
+                self.report.push(
+                    severity,
+                    ChangeKind::FieldTypeChanged,
+                    key,
+                    field_location.clone(),
+                    format!(
+                        "the type of field {} changed from `{old_ty}` to `{new_ty}`",
+                        o.id
+                    ),
+                );
+            }
+            if old_name != new_name {
+                self.report.push(
+                    Severity::Warning,
+                    ChangeKind::FieldRenamed,
+                    key,
+                    field_location.clone(),
+                    format!(
+                        "field {} was renamed from `{old_name}` to `{new_name}`",
+                        o.id
+                    ),
+                );
+            }
+            let (old_attribute, new_attribute) = (attribute(o), attribute(n));
+            if old_attribute != new_attribute {
+                let severity = if old_attribute == Attribute::Required
+                    || new_attribute == Attribute::Required
+                {
+                    Severity::Breaking
+                } else {
+                    Severity::Info
+                };
+                self.report.push(
+                    severity,
+                    ChangeKind::FieldRequirednessChanged,
+                    key,
+                    field_location,
+                    format!(
+                        "field {} changed from {} to {}",
+                        o.id,
+                        attribute_name(&old_attribute),
+                        attribute_name(&new_attribute)
+                    ),
+                );
+            }
+        }
+
+        for n in new {
+            if old
+                .iter()
+                .any(|o| o.id == n.id || o.name.as_str() == n.name.as_str())
+            {
+                continue;
+            }
+            let (severity, message) = if attribute(n) == Attribute::Required {
+                (
+                    Severity::Breaking,
+                    format!("required field {} `{}` was added", n.id, &*n.name),
+                )
+            } else {
+                (
+                    Severity::Info,
+                    format!("field {} `{}` was added", n.id, &*n.name),
+                )
+            };
+            self.report.push(
+                severity,
+                ChangeKind::FieldAdded,
+                key,
+                format!("{location}.{}", &*n.name),
+                message,
+            );
+        }
+    }
+
+    fn enum_(&mut self, key: &str, location: &str, old: &Enum, new: &Enum) {
+        let (old_values, new_values) = (enum_values(old), enum_values(new));
+
+        for &(name, value) in &old_values {
+            let value_location = format!("{location}.{name}");
+            match new_values.iter().find(|(n, _)| *n == name) {
+                Some(&(_, new_value)) if new_value != value => self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::EnumValueChanged,
+                    key,
+                    value_location,
+                    format!("enum value `{name}` changed from {value} to {new_value}"),
+                ),
+                Some(_) => {}
+                None => match new_values.iter().find(|(_, v)| *v == value) {
+                    Some(&(new_name, _)) => self.report.push(
+                        Severity::Warning,
+                        ChangeKind::EnumValueRenamed,
+                        key,
+                        value_location,
+                        format!("enum value {value} was renamed from `{name}` to `{new_name}`"),
+                    ),
+                    None => self.report.push(
+                        Severity::Breaking,
+                        ChangeKind::EnumValueRemoved,
+                        key,
+                        value_location,
+                        format!("enum value `{name}` ({value}) was removed"),
+                    ),
+                },
+            }
+        }
+
+        for &(name, value) in &new_values {
+            if old_values.iter().any(|&(n, v)| n == name || v == value) {
+                continue;
+            }
+            self.report.push(
+                Severity::Info,
+                ChangeKind::EnumValueAdded,
+                key,
+                format!("{location}.{name}"),
+                format!("enum value `{name}` ({value}) was added"),
+            );
+        }
+    }
+
+    fn service(&mut self, key: &str, location: &str, old: &Service, new: &Service) {
+        let extends = |s: &Service| {
+            s.extends.as_ref().map(|p| {
+                p.segments
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+        };
+        let (old_extends, new_extends) = (extends(old), extends(new));
+        if old_extends != new_extends {
+            self.report.push(
+                Severity::Warning,
+                ChangeKind::ServiceExtendsChanged,
+                key,
+                location,
+                format!(
+                    "the extended service changed from `{}` to `{}`",
+                    old_extends.as_deref().unwrap_or("none"),
+                    new_extends.as_deref().unwrap_or("none")
+                ),
+            );
+        }
+
+        for o in &old.functions {
+            let name = o.name.as_str();
+            let method_location = format!("{location}.{name}");
+            let Some(n) = new.functions.iter().find(|n| n.name.as_str() == name) else {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodRemoved,
+                    key,
+                    method_location,
+                    format!("method `{name}` was removed"),
+                );
+                continue;
+            };
+
+            let old_ty = self.old.resolve(key, &o.result_type);
+            let new_ty = self.new.resolve(key, &n.result_type);
+            if let Some(severity) = self.type_change(&old_ty, &new_ty) {
+                self.report.push(
+                    severity,
+                    ChangeKind::MethodResultChanged,
+                    key,
+                    method_location.clone(),
+                    format!("the result of `{name}` changed from `{old_ty}` to `{new_ty}`"),
+                );
+            }
+            if o.oneway != n.oneway {
+                self.report.push(
+                    Severity::Breaking,
+                    ChangeKind::MethodOnewayChanged,
+                    key,
+                    method_location.clone(),
+                    if n.oneway {
+                        format!("method `{name}` became oneway")
+                    } else {
+                        format!("method `{name}` is no longer oneway")
+                    },
+                );
+            }
+            self.fields(key, &method_location, &o.arguments, &n.arguments, true);
+            self.fields(
+                key,
+                &format!("{method_location}.throws"),
+                &o.throws,
+                &n.throws,
+                false,
+            );
+        }
+
+        for n in &new.functions {
+            let name = n.name.as_str();
+            if old.functions.iter().all(|o| o.name.as_str() != name) {
+                self.report.push(
+                    Severity::Info,
+                    ChangeKind::MethodAdded,
+                    key,
+                    format!("{location}.{name}"),
+                    format!("method `{name}` was added"),
+                );
+            }
+        }
+    }
+
+    /// Returns the severity of changing a type from `old` to `new`, if it
+    /// changed.
+    fn type_change(&self, old: &Resolved, new: &Resolved) -> Option<Severity> {
+        let old = old.rename(&self.renames);
+        if old == *new {
+            None
+        } else if old.wire() == new.wire() {
+            Some(Severity::Warning)
+        } else {
+            Some(Severity::Breaking)
+        }
+    }
+}
+
+fn item_name(item: &Item) -> Option<&str> {
+    Some(match item {
+        Item::Typedef(t) => &t.alias,
+        Item::Constant(c) => &c.name,
+        Item::Enum(e) => &e.name,
+        Item::Struct(s) => &s.name,
+        Item::Union(u) => &u.name,
+        Item::Exception(e) => &e.name,
+        Item::Service(s) => &s.name,
+        Item::Include(_) | Item::CppInclude(_) | Item::Namespace(_) => return None,
+    })
+}
+
+fn item_kind(item: &Item) -> &'static str {
+    match item {
+        Item::Typedef(_) => "typedef",
+        Item::Constant(_) => "const",
+        Item::Enum(_) => "enum",
+        Item::Struct(_) => "struct",
+        Item::Union(_) => "union",
+        Item::Exception(_) => "exception",
+        Item::Service(_) => "service",
+        Item::Include(_) => "include",
+        Item::CppInclude(_) => "cpp_include",
+        Item::Namespace(_) => "namespace",
+    }
+}
+
+fn find_item<'a>(file: &'a File, name: &str) -> Option<&'a Item> {
+    file.items.iter().find(|item| item_name(item) == Some(name))
+}
+
+fn attribute_name(attribute: &Attribute) -> &'static str {
+    match attribute {
+        Attribute::Required => "required",
+        Attribute::Optional => "optional",
+        Attribute::Default => "default",
+    }
+}
+
+/// Returns the values of an enum with their numbers, counting from the
+/// previous value when omitted.
+fn enum_values(e: &Enum) -> Vec<(&str, i64)> {
+    let mut next = 0;
+    e.values
+        .iter()
+        .map(|v| {
+            let value = v.value.map_or(next, |v| v.0);
+            next = value + 1;
+            (v.name.as_str(), value)
+        })
+        .collect()
+}
+
+fn stem(path: &str) -> &str {
+    Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(path)
+}
+
+fn include_key(from: &str, include: &str) -> String {
+    normalize(
+        &Path::new(from)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(include),
+    )
+}
+
+/// Removes the `.` and `..` of a relative path, without touching the file
+/// system.
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized.to_string_lossy().replace('\\', "/")
+}
@@ -1,3 +1,6 @@
+use std::{fmt, ops::Range, path::PathBuf, sync::Arc};
+
+use ariadne::{Config, IndexType, Label, Report, ReportKind};
 use itertools::Itertools;
 
 #[derive(Default)]
@@ -8,6 +11,7 @@ pub(crate) struct Handler {
 #[derive(Debug)]
 pub enum Message {
     Str(String),
+    Diagnostic(Diagnostic),
 }
 
 impl From<String> for Message {
@@ -22,6 +26,147 @@ impl From<&'_ str> for Message {
     }
 }
 
+impl From<Diagnostic> for Message {
+    fn from(value: Diagnostic) -> Self {
+        Message::Diagnostic(value)
+    }
+}
+
+/// The byte range of an idl node in its file.
+///
+/// The thrift parser records one in the tags of the items, fields and types
+/// it lowers, so that the resolver can point at them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file: Arc<PathBuf>,
+    pub range: Range<usize>,
+}
+
+impl Span {
+    pub fn new(file: Arc<PathBuf>, range: Range<usize>) -> Self {
+        Span { file, range }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A path that refers to no definition.
+    UnresolvedPath,
+    /// Two definitions with the same name in one scope.
+    DuplicateDefinition,
+    /// Two fields with the same id in one struct.
+    DuplicateFieldId,
+    /// An include that is found in none of the include dirs.
+    IncludeNotFound,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnresolvedPath => "E0001",
+            ErrorCode::DuplicateDefinition => "E0002",
+            ErrorCode::DuplicateFieldId => "E0003",
+            ErrorCode::IncludeNotFound => "E0004",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error located in the idl files, rendered like a compiler error.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The first label is the primary one.
+    pub labels: Vec<(Span, String)>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Adds a label, unless the node has no span, e.g. it comes from a
+    /// protobuf file.
+    pub fn with_label(mut self, span: Option<&Span>, message: impl Into<String>) -> Self {
+        if let Some(span) = span {
+            self.labels.push((span.clone(), message.into()));
+        }
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    fn render(&self, color: bool) -> String {
+        let Some((primary, _)) = self.labels.first() else {
+            let mut s = format!("[{}] Error: {}", self.code, self.message);
+            for note in &self.notes {
+                s.push_str(&format!("\n    Note: {note}"));
+            }
+            return s;
+        };
+
+        let id = |span: &Span| span.file.display().to_string();
+        let sources = self
+            .labels
+            .iter()
+            .map(|(span, _)| &span.file)
+            .unique()
+            .map(|file| {
+                (
+                    file.display().to_string(),
+                    std::fs::read_to_string(&**file).unwrap_or_default(),
+                )
+            })
+            .collect_vec();
+
+        let mut report = Report::build(ReportKind::Error, (id(primary), primary.range.clone()))
+            .with_config(
+                Config::new()
+                    .with_index_type(IndexType::Byte)
+                    .with_color(color),
+            )
+            .with_code(self.code)
+            .with_message(&self.message)
+            .with_labels(self.labels.iter().enumerate().map(|(i, (span, message))| {
+                let label = Label::new((id(span), span.range.clone()))
+                    .with_message(message)
+                    .with_order(i as i32);
+                if color {
+                    label.with_color(if i == 0 {
+                        ariadne::Color::Red
+                    } else {
+                        ariadne::Color::Blue
+                    })
+                } else {
+                    label
+                }
+            }));
+        report.with_notes(&self.notes);
+
+        let mut buffer = Vec::new();
+        report
+            .finish()
+            .write(ariadne::sources(sources), &mut buffer)
+            .unwrap();
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
+}
+
 impl Handler {
     pub fn has_errors(&mut self) -> bool {
         !self.errors.is_empty()
@@ -31,21 +176,85 @@ impl Handler {
         self.errors.push(err.into());
     }
 
+    fn render(&self, color: bool) -> String {
+        let mut s = self
+            .errors
+            .iter()
+            .map(|e| match e {
+                Message::Str(s) => format!("Error: {s}\n"),
+                Message::Diagnostic(d) => d.render(color),
+            })
+            .join("\n");
+        s.push_str(&match self.errors.len() {
+            1 => "\naborting due to 1 previous error".to_string(),
+            n => format!("\naborting due to {n} previous errors"),
+        });
+        s
+    }
+
     pub fn abort_if_errors(&mut self) {
         if !self.has_errors() {
             return;
         }
 
-        eprintln!(
-            "Errors: {}",
-            self.errors
-                .iter()
-                .map(|s| match s {
-                    Message::Str(s) => s,
-                })
-                .join("\n")
+        eprintln!("{}", self.render(true));
+
+        // the uncolored report is the payload, so that tests can match on it
+        std::panic::resume_unwind(Box::new(self.render(false)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Arc::new(dir.path().join("a.thrift"));
+        std::fs::write(
+            &*path,
+            "struct A {\n    1: i32 a,\n    1: i32 b,\n}\n\nstruct A {}\n",
+        )
+        .unwrap();
+
+        let mut handler = Handler::default();
+        handler.emit_error(
+            Diagnostic::new(ErrorCode::DuplicateFieldId, "duplicate field id `1` in `A`")
+                .with_label(
+                    Some(&Span::new(path.clone(), 29..38)),
+                    "`1` used again here",
+                )
+                .with_label(Some(&Span::new(path.clone(), 15..24)), "first used here"),
         );
+        handler.emit_error(
+            Diagnostic::new(
+                ErrorCode::DuplicateDefinition,
+                "duplicate definition of `A`",
+            )
+            .with_label(None, "protobuf nodes have no span")
+            .with_note("names must be unique in a file"),
+        );
+        let rendered = handler.render(false);
 
-        std::panic::resume_unwind(Box::new(()));
+        assert_eq!(
+            rendered.replace(&*dir.path().to_string_lossy(), "DIR"),
+            concat!(
+                "[E0003] Error: duplicate field id `1` in `A`\n",
+                "   ╭─[ DIR/a.thrift:3:5 ]\n",
+                "   │\n",
+                " 2 │     1: i32 a,\n",
+                "   │     ────┬────  \n",
+                "   │         ╰────── first used here\n",
+                " 3 │     1: i32 b,\n",
+                "   │     ────┬────  \n",
+                "   │         ╰────── `1` used again here\n",
+                "───╯\n",
+                "\n",
+                "[E0002] Error: duplicate definition of `A`\n",
+                "    Note: names must be unique in a file\n",
+                "aborting due to 2 previous errors",
+            )
+        );
     }
 }
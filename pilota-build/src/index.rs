@@ -11,6 +11,7 @@ pub trait Idx: Sized + Copy + 'static {
         old
     }
 
+    #[allow(dead_code)]
     fn increment_by(&mut self, amount: usize) {
         *self = self.plus(amount);
     }
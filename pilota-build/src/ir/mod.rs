@@ -6,6 +6,7 @@ use itertools::Itertools;
 use pilota::{Bytes, FastStr};
 
 use crate::{
+    errors::Span,
     symbol::{EnumRepr, FileId, Ident, Symbol},
     tags::Tags,
 };
@@ -76,6 +77,7 @@ pub struct Arg {
     pub attribute: FieldKind,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct ExceptionVariant {
     pub id: i32,
@@ -217,9 +219,28 @@ impl Item {
 pub struct Use {
     pub file: FileId,
 }
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+/// A path referring to an item, e.g. `base.User`.
+///
+/// The span points at the path in the idl when the parser knows it, and is
+/// not part of the path's identity.
+#[derive(Debug, Clone, Default)]
 pub struct Path {
     pub segments: Arc<[Ident]>,
+    pub span: Option<Span>,
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        self.segments == other.segments
+    }
+}
+
+impl Eq for Path {}
+
+impl std::hash::Hash for Path {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.segments.hash(state)
+    }
 }
 
 impl Display for Path {
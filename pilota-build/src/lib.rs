@@ -4,9 +4,8 @@
 #![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 #![allow(clippy::mutable_key_type)]
 
-mod util;
-
 pub mod codegen;
+pub mod compat;
 pub mod db;
 pub(crate) mod errors;
 pub mod fmt;
@@ -30,7 +30,7 @@ use crate::{
     tags::{
         PilotaName, RustType, RustWrapperArc, SerdeAttribute, Tags,
         protobuf::{
-            ClientStreaming, Deprecated, OneOf, OptionalRepeated, ProstType, Repeated,
+            ClientStreaming, Deprecated, JsonName, OneOf, OptionalRepeated, ProstType, Repeated,
             ServerStreaming,
         },
     },
@@ -216,6 +216,7 @@ impl Lower {
         }
         ir::Path {
             segments: Arc::from_iter(s.split('.').map(FastStr::new).map(Ident::from)),
+            span: None,
         }
     }
 
@@ -349,6 +350,7 @@ impl Lower {
                     ),
                     parent: parent.map(|p| Path {
                         segments: Arc::from([FastStr::new(p).into()]),
+                        span: None,
                     }),
                 }),
             }),
@@ -458,7 +460,7 @@ impl Lower {
                                     &nested_messages,
                                     false,
                                 )],
-                                tags: Default::default(),
+                                tags: Arc::new(self.extract_variant_tags(f)),
                                 item_exts: ext::ItemExts::Pb(ext::pb::ItemExts {
                                     used_options: ext::pb::UsedOptions::from_pb_unknown_fields(
                                         ExtendeeKind::Field,
@@ -475,6 +477,7 @@ impl Lower {
                             ),
                             parent: Some(Path {
                                 segments: Arc::from([FastStr::new(message.name()).into()]),
+                                span: None,
                             }),
                         }),
                     }),
@@ -493,6 +496,7 @@ impl Lower {
                                     FastStr::new(message.name()).into(),
                                     FastStr::new(d.name()).into(),
                                 ]),
+                                span: None,
                             }),
                             tags: Default::default(),
                         },
@@ -591,6 +595,7 @@ impl Lower {
                                                 segs.push(Ident::from(FastStr::new(default)));
                                                 Some(ir::Literal::Path(ir::Path {
                                                     segments: Arc::from(segs),
+                                                    span: None,
                                                 }))
                                             } else {
                                                 println!("cargo:warning=default value is not an enum member: {}", default);
@@ -635,6 +640,7 @@ impl Lower {
                     ),
                     parent: parent.map(|p| Path {
                         segments: Arc::from([FastStr::new(p).into()]),
+                        span: None,
                     }),
                 }),
             }),
@@ -964,8 +970,17 @@ impl Lower {
         tags
     }
 
-    fn extract_field_tags(&self, field: &FieldDescriptorProto) -> Tags {
+    /// The tags of a field lowered to a oneof variant.
+    fn extract_variant_tags(&self, field: &FieldDescriptorProto) -> Tags {
         let mut tags = Tags::default();
+        if let Some(json_name) = &field.json_name {
+            tags.insert(JsonName(FastStr::new(json_name)));
+        }
+        tags
+    }
+
+    fn extract_field_tags(&self, field: &FieldDescriptorProto) -> Tags {
+        let mut tags = self.extract_variant_tags(field);
         if field.options.is_some() {
             let options = &field.options;
 
@@ -995,6 +1010,15 @@ impl Lower {
     }
 }
 
+impl ProtobufParser {
+    /// Parses the inputs into descriptors without lowering them, e.g. to
+    /// compare two versions with
+    /// [`check_protobuf`](crate::compat::check_protobuf).
+    pub fn file_descriptors(self) -> Vec<protobuf::descriptor::FileDescriptorProto> {
+        self.inner.parse_and_typecheck().unwrap().file_descriptors
+    }
+}
+
 impl Parser for ProtobufParser {
     fn input<P: AsRef<std::path::Path>>(&mut self, path: P) {
         let p = path.as_ref();
@@ -1231,9 +1255,11 @@ mod tests {
 
     #[test]
     fn lower_message_converts_map_entry_to_ir_map() {
-        let mut lower = Lower::default();
-        lower.cur_package = Some("pkg".into());
-        lower.cur_syntax = Syntax::Proto3;
+        let mut lower = Lower {
+            cur_package: Some("pkg".into()),
+            cur_syntax: Syntax::Proto3,
+            ..Default::default()
+        };
 
         let mut map_entry = DescriptorProto::new();
         map_entry.set_name("EntriesEntry".into());
@@ -1292,9 +1318,11 @@ mod tests {
 
     #[test]
     fn lower_message_marks_proto3_optional_scalar_as_optional() {
-        let mut lower = Lower::default();
-        lower.cur_package = Some("pkg".into());
-        lower.cur_syntax = Syntax::Proto3;
+        let mut lower = Lower {
+            cur_package: Some("pkg".into()),
+            cur_syntax: Syntax::Proto3,
+            ..Default::default()
+        };
 
         let mut message = DescriptorProto::new();
         message.set_name("Foo".into());
@@ -12,6 +12,7 @@ use thrift_parser::Annotations;
 
 use crate::{
     IdentName,
+    errors::{self, Diagnostic, ErrorCode},
     index::Idx,
     ir::{
         self, Arg, Enum, EnumVariant, FieldKind, File, Item, ItemKind, Path,
@@ -19,7 +20,6 @@ use crate::{
     },
     symbol::{EnumRepr, FileId, Ident},
     tags::{Annotation, PilotaName, RustWrapperArc, Tags},
-    util::error_abort,
 };
 
 fn generate_short_uuid() -> FastStr {
@@ -87,6 +87,7 @@ pub struct ThriftLower {
     include_dirs: Vec<PathBuf>,
     packages: FxHashMap<Path, Vec<Arc<PathBuf>>>,
     service_name_duplicates: FxHashSet<String>,
+    errors: errors::Handler,
 }
 
 impl ThriftLower {
@@ -102,6 +103,7 @@ impl ThriftLower {
             include_dirs,
             packages: Default::default(),
             service_name_duplicates: Default::default(),
+            errors: Default::default(),
         }
     }
 
@@ -121,6 +123,7 @@ impl ThriftLower {
     fn lower_path(&self, path: &thrift_parser::Path) -> ir::Path {
         Path {
             segments: Arc::from_iter(path.segments.iter().map(|i| self.lower_ident(i))),
+            span: Some(self.lower_span(path.span)),
         }
     }
 
@@ -182,7 +185,8 @@ impl ThriftLower {
                 .collect(),
             item_exts: ext::ItemExts::Thrift,
         });
-        let mut service_item = self.mk_item(kind, Default::default());
+        let mut service_item =
+            self.mk_item(kind, crate::tags!(self.lower_span(service.name.1)).into());
         let mut result = vec![];
 
         let mut related_items = Vec::default();
@@ -393,6 +397,7 @@ impl ThriftLower {
                     segments: Arc::from([Ident::from(format!(
                         "{service_name}{method_name}Exception",
                     ))]),
+                    span: Some(self.lower_span(method.span)),
                 })
             },
             item_exts: ext::ItemExts::Thrift,
@@ -485,7 +490,18 @@ impl ThriftLower {
             _ => &empty_annotations,
         };
 
-        let tags = self.extract_tags(annotations);
+        let name = match item {
+            thrift_parser::Item::Typedef(t) => &t.alias,
+            thrift_parser::Item::Constant(c) => &c.name,
+            thrift_parser::Item::Enum(e) => &e.name,
+            thrift_parser::Item::Struct(s) => &s.name,
+            thrift_parser::Item::Union(u) => &u.name,
+            thrift_parser::Item::Exception(e) => &e.name,
+            _ => unreachable!(),
+        };
+
+        let mut tags = self.extract_tags(annotations);
+        tags.insert(self.lower_span(name.1));
 
         vec![self.mk_item(single, tags.into())]
     }
@@ -518,6 +534,10 @@ impl ThriftLower {
         Ident::from(s.0.clone())
     }
 
+    fn lower_span(&self, span: thrift_parser::Span) -> errors::Span {
+        errors::Span::new(self.cur_file.as_ref().unwrap().path.clone(), span.range())
+    }
+
     fn extract_tags_with_arc_wrapper(&self, annotations: &Annotations, arc_wrapper: bool) -> Tags {
         let mut tags = self.extract_tags(annotations);
         if arc_wrapper && !tags.contains::<RustWrapperArc>() {
@@ -535,7 +555,7 @@ impl ThriftLower {
         self.lower_ty_with_tags(ty, tags)
     }
 
-    fn lower_ty_with_tags(&self, ty: &thrift_parser::Type, tags: Tags) -> ir::Ty {
+    fn lower_ty_with_tags(&self, ty: &thrift_parser::Type, mut tags: Tags) -> ir::Ty {
         let kind = match &ty.0 {
             thrift_parser::Ty::String => ir::TyKind::String,
             thrift_parser::Ty::Void => ir::TyKind::Void,
@@ -553,7 +573,10 @@ impl ThriftLower {
             thrift_parser::Ty::Map { key, value, .. } => {
                 ir::TyKind::Map(self.lower_ty(key).into(), self.lower_ty(value).into())
             }
-            thrift_parser::Ty::Path(path) => ir::TyKind::Path(self.lower_path(path)),
+            thrift_parser::Ty::Path(path) => {
+                tags.insert(self.lower_span(path.span));
+                ir::TyKind::Path(self.lower_path(path))
+            }
         };
 
         ir::Ty {
@@ -627,16 +650,6 @@ impl ThriftLower {
     }
 
     fn lower_struct(&self, s: &thrift_parser::Struct) -> ir::Message {
-        let mut seen_ids = FxHashSet::default();
-        for field in &s.fields {
-            if !seen_ids.insert(field.id) {
-                panic!(
-                    "duplicate ID `{}` in struct `{}`",
-                    field.id,
-                    self.lower_ident(&s.name),
-                );
-            }
-        }
         ir::Message {
             leading_comments: s.leading_comments.clone(),
             trailing_comments: s.trailing_comments.clone(),
@@ -647,17 +660,26 @@ impl ThriftLower {
         }
     }
 
-    fn lower_exception(&self, e: &thrift_parser::Exception) -> ir::Message {
-        let mut seen_ids = FxHashSet::default();
-        for field in &e.fields {
-            if !seen_ids.insert(field.id) {
-                panic!(
-                    "duplicate ID `{}` in struct `{}`",
-                    field.id,
-                    self.lower_ident(&e.name),
+    fn check_field_ids(&mut self, s: &thrift_parser::StructLike) {
+        let mut seen_ids = FxHashMap::default();
+        for field in &s.fields {
+            if let Some(first) = seen_ids.insert(field.id, field.span) {
+                self.errors.emit_error(
+                    Diagnostic::new(
+                        ErrorCode::DuplicateFieldId,
+                        format!("duplicate ID `{}` in struct `{}`", field.id, &*s.name),
+                    )
+                    .with_label(
+                        Some(&self.lower_span(field.span)),
+                        format!("ID `{}` used again here", field.id),
+                    )
+                    .with_label(Some(&self.lower_span(first)), "first used here"),
                 );
             }
         }
+    }
+
+    fn lower_exception(&self, e: &thrift_parser::Exception) -> ir::Message {
         ir::Message {
             leading_comments: e.leading_comments.clone(),
             trailing_comments: e.trailing_comments.clone(),
@@ -668,7 +690,7 @@ impl ThriftLower {
         }
     }
 
-    fn lower_include(&mut self, s: &thrift_parser::Include) -> ir::Use {
+    fn lower_include(&mut self, s: &thrift_parser::Include) -> Option<ir::Use> {
         // add current file's dir to include dirs
         let current_dir = self.cur_file.as_ref().unwrap().path.parent().unwrap();
         let mut include_dirs = vec![current_dir.to_path_buf()];
@@ -682,7 +704,19 @@ impl ThriftLower {
         let target_path = match target_dir {
             Some(dir) => dir.join(&s.path.0),
             None => {
-                error_abort(format!("{}: include file not found", s.path.0));
+                let searched = std::iter::once(current_dir)
+                    .chain(self.include_dirs.iter().map(|p| p.as_path()))
+                    .map(|p| p.display())
+                    .join(", ");
+                self.errors.emit_error(
+                    Diagnostic::new(
+                        ErrorCode::IncludeNotFound,
+                        format!("include file `{}` not found", s.path.0),
+                    )
+                    .with_label(Some(&self.lower_span(s.path.1)), "not found")
+                    .with_note(format!("searched in: {searched}")),
+                );
+                return None;
             }
         };
 
@@ -692,7 +726,7 @@ impl ThriftLower {
 
         let file_id = self.lower(ast);
 
-        ir::Use { file: file_id }
+        Some(ir::Use { file: file_id })
     }
 }
 
@@ -713,6 +747,13 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
         );
 
         let file = self.with_cur_file(f.clone(), |this| {
+            f.items.iter().for_each(|item| match item {
+                thrift_parser::Item::Struct(s) => this.check_field_ids(s),
+                thrift_parser::Item::Union(u) => this.check_field_ids(u),
+                thrift_parser::Item::Exception(e) => this.check_field_ids(e),
+                _ => {}
+            });
+
             let include_files = f
                 .items
                 .iter()
@@ -723,8 +764,9 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
                         None
                     }
                 })
-                .map(|i| {
-                    (
+                .filter_map(|i| {
+                    let u = this.lower_include(i)?;
+                    Some((
                         i.path
                             .0
                             .split('/')
@@ -735,8 +777,8 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
                             .map(FastStr::new)
                             .map(Ident::from)
                             .collect_vec(),
-                        this.lower_include(i),
-                    )
+                        u,
+                    ))
                 })
                 .collect::<Vec<_>>();
 
@@ -755,6 +797,7 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
                     (
                         Path {
                             segments: name.into(),
+                            span: None,
                         },
                         u.file,
                     )
@@ -774,6 +817,7 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
                         .unwrap()
                         .replace('.', "_")
                         .into()]),
+                    span: None,
                 });
 
             this.packages
@@ -820,7 +864,9 @@ impl Lower<Arc<thrift_parser::File>> for ThriftLower {
         file.id
     }
 
-    fn finish(self) -> LowerResult {
+    fn finish(mut self) -> LowerResult {
+        self.errors.abort_if_errors();
+
         self.packages.iter().for_each(|(k, v)| {
             if v.len() > 1 {
                 println!(
@@ -13,10 +13,11 @@ use crate::{
     ty::{self, Ty, Visitor},
 };
 
+mod pb_json;
 mod serde;
 mod workspace;
 
-pub use self::serde::SerdePlugin;
+pub use self::{pb_json::PbJsonPlugin, serde::SerdePlugin};
 
 pub trait Plugin: Sync + Send {
     fn on_codegen_uint(&mut self, cx: &Context, items: &[DefId]) {
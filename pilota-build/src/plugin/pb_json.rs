@@ -0,0 +1,580 @@
+use std::sync::Arc;
+
+use faststr::FastStr;
+use itertools::Itertools;
+use pilota::pb::json::to_json_name;
+
+use crate::{
+    Context, DefId, Plugin,
+    codegen::pb::is_proto3,
+    db::RirDatabase,
+    middle::ext::ItemExts,
+    rir::{Enum, Field, FieldKind, Item, Message, NodeKind},
+    tags::protobuf::{JsonName, OneOf},
+    ty::{Ty, TyKind},
+};
+
+const JSON: &str = "::pilota::pb::json";
+
+/// Implements the proto3 JSON mapping (`::pilota::pb::json::JsonMessage`)
+/// for generated protobuf messages.
+///
+/// Fields are keyed by their `json_name`, which defaults to the
+/// lowerCamelCase form of the proto name; the lowerCamelCase and the original
+/// proto names are accepted too when decoding.
+///
+/// The generated code needs the `pb-json` feature of `pilota`.
+#[derive(Clone, Copy)]
+pub struct PbJsonPlugin;
+
+impl Plugin for PbJsonPlugin {
+    fn on_item(&mut self, cx: &Context, def_id: DefId, item: Arc<Item>) {
+        let code = match &*item {
+            Item::Message(m) if matches!(m.item_exts, ItemExts::Pb(_)) => {
+                Some(codegen_message(cx, def_id, m))
+            }
+            Item::Enum(e) if matches!(e.item_exts, ItemExts::Pb(_)) => {
+                let tags = cx.node(def_id).unwrap().tags;
+                if cx.contains_tag::<OneOf>(tags) {
+                    Some(codegen_oneof(cx, def_id, e))
+                } else {
+                    Some(codegen_enum(cx, def_id, e))
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(code) = code {
+            cx.with_adjust_mut(def_id, |adj| adj.add_nested_item(code.into()));
+        }
+
+        crate::plugin::walk_item(self, cx, def_id, item)
+    }
+}
+
+/// Returns the name of `def_id` if it is a `google.protobuf` well-known type.
+fn wkt_name(cx: &Context, def_id: DefId) -> Option<FastStr> {
+    let file_id = cx.node(def_id)?.file_id;
+    let file = cx.files().get(&file_id)?.clone();
+    let package = file
+        .package
+        .iter()
+        .map(|s| s.0.as_str())
+        .collect::<Vec<_>>();
+    (package == ["google", "protobuf"]).then(|| cx.symbol_name(def_id).0.clone())
+}
+
+/// Returns the full proto name of the message `def_id`, e.g. `pkg.Outer.Inner`.
+fn full_name(cx: &Context, def_id: DefId) -> String {
+    let mut names = Vec::new();
+    let mut cur = Some(def_id);
+    while let Some(did) = cur {
+        cur = None;
+        if let Item::Message(m) = &*cx.item(did).unwrap() {
+            names.push(m.name.raw_str());
+            if let ItemExts::Pb(pb) = &m.item_exts {
+                cur = pb.parent.as_ref().map(|p| p.did);
+            }
+        }
+    }
+    let file = cx
+        .files()
+        .get(&cx.node(def_id).unwrap().file_id)
+        .unwrap()
+        .clone();
+    file.package
+        .iter()
+        .map(|s| s.0.clone())
+        .chain(names.into_iter().rev())
+        .join(".")
+}
+
+/// Whether JSON `null` is a value of `ty` rather than a missing field.
+fn accepts_null(cx: &Context, ty: &Ty) -> bool {
+    match &ty.kind {
+        TyKind::Path(p) => matches!(wkt_name(cx, p.did).as_deref(), Some("Value" | "NullValue")),
+        _ => false,
+    }
+}
+
+fn is_plain_enum(cx: &Context, def_id: DefId) -> bool {
+    let node = cx.node(def_id).unwrap();
+    matches!(node.kind, NodeKind::Item(ref item) if matches!(&**item, Item::Enum(_)))
+        && !cx.contains_tag::<OneOf>(node.tags)
+}
+
+fn is_oneof(cx: &Context, ty: &Ty) -> bool {
+    match &ty.kind {
+        TyKind::Path(p) => cx.contains_tag::<OneOf>(cx.node(p.did).unwrap().tags),
+        _ => false,
+    }
+}
+
+fn module(cx: &Context, ty: &Ty) -> &'static str {
+    match &ty.kind {
+        TyKind::String | TyKind::FastStr => "string",
+        TyKind::Bool => "bool",
+        TyKind::BytesVec | TyKind::Bytes => "bytes",
+        TyKind::I32 => "int32",
+        TyKind::I64 => "int64",
+        TyKind::UInt32 => "uint32",
+        TyKind::UInt64 => "uint64",
+        TyKind::F32 => "float",
+        TyKind::F64 => "double",
+        TyKind::Path(p) if is_plain_enum(cx, p.did) => "enumeration",
+        _ => "message",
+    }
+}
+
+/// Returns a `Result` expression converting the value behind the reference
+/// `value` to JSON.
+fn to_json(cx: &Context, ty: &Ty, value: &str) -> String {
+    match &ty.kind {
+        TyKind::Vec(inner) => format!(
+            "{JSON}::repeated::to_json({value}, |v| {})",
+            to_json(cx, inner, "v")
+        ),
+        TyKind::Map(k, v) => format!(
+            "{JSON}::hash_map::to_json({value}, |k| {JSON}::{}::to_json(k), |v| {})",
+            module(cx, k),
+            to_json(cx, v, "v")
+        ),
+        _ => match module(cx, ty) {
+            "message" => format!("{JSON}::message::to_json({value})"),
+            module => format!("::std::result::Result::Ok({JSON}::{module}::to_json({value}))"),
+        },
+    }
+}
+
+/// Returns a `Value` expression converting the value behind the reference
+/// `value` to JSON, applying `try_op` (e.g. `?`) to conversions that can fail.
+fn to_json_value(cx: &Context, ty: &Ty, value: &str, try_op: &str) -> String {
+    if is_fallible(cx, ty) {
+        format!("{}{try_op}", to_json(cx, ty, value))
+    } else {
+        format!("{JSON}::{}::to_json({value})", module(cx, ty))
+    }
+}
+
+/// Whether converting `ty` to JSON can fail, i.e. it may hold a message.
+fn is_fallible(cx: &Context, ty: &Ty) -> bool {
+    matches!(ty.kind, TyKind::Vec(_) | TyKind::Map(..)) || module(cx, ty) == "message"
+}
+
+/// Returns an expression decoding the JSON value behind the reference
+/// `value`.
+fn from_json(cx: &Context, ty: &Ty, value: &str) -> String {
+    match &ty.kind {
+        TyKind::Vec(inner) => format!(
+            "{JSON}::repeated::from_json({value}, |v| {})",
+            from_json(cx, inner, "v")
+        ),
+        TyKind::Map(k, v) => format!(
+            "{JSON}::hash_map::from_json({value}, |k| {}, |v| {})",
+            from_json(cx, k, "k"),
+            from_json(cx, v, "v")
+        ),
+        _ => format!("{JSON}::{}::from_json({value})", module(cx, ty)),
+    }
+}
+
+/// Returns the JSON key of the field or oneof variant `def_id` named `name`.
+fn json_key(cx: &Context, def_id: DefId, name: &str) -> String {
+    cx.node_tags(def_id)
+        .and_then(|tags| tags.get::<JsonName>().map(|n| n.to_string()))
+        .unwrap_or_else(|| to_json_name(name))
+}
+
+/// Returns the match pattern for the JSON keys of the field or oneof variant
+/// `def_id` named `name`: its JSON key, its lowerCamelCase name and its proto
+/// name.
+fn key_pattern(cx: &Context, def_id: DefId, name: &str) -> String {
+    [
+        json_key(cx, def_id, name),
+        to_json_name(name),
+        name.to_string(),
+    ]
+    .iter()
+    .unique()
+    .map(|key| format!("{key:?}"))
+    .join(" | ")
+}
+
+fn codegen_message(cx: &Context, def_id: DefId, m: &Message) -> String {
+    let name = cx.rust_name(def_id);
+    let type_name = full_name(cx, def_id);
+    let (to_json, merge_json) = match wkt_name(cx, def_id) {
+        Some(wkt) => {
+            codegen_wkt(cx, m, &wkt).unwrap_or_else(|| codegen_message_body(cx, def_id, m))
+        }
+        None => codegen_message_body(cx, def_id, m),
+    };
+
+    format!(
+        r#"
+        impl {JSON}::JsonMessage for {name} {{
+            const TYPE_NAME: &'static str = {type_name:?};
+
+            fn to_json(&self) -> ::std::result::Result<{JSON}::Value, {JSON}::JsonError> {{
+                {to_json}
+            }}
+
+            fn merge_json(&mut self, value: &{JSON}::Value) -> ::std::result::Result<(), {JSON}::JsonError> {{
+                {merge_json}
+            }}
+        }}
+        "#
+    )
+}
+
+fn codegen_message_body(cx: &Context, def_id: DefId, m: &Message) -> (String, String) {
+    let name = cx.rust_name(def_id);
+    let proto3 = is_proto3(cx, cx.node(def_id).unwrap().file_id);
+
+    let mut to_json_fields = String::new();
+    let mut to_json_fallible = false;
+    let mut merge_fields = String::new();
+    let mut merge_oneofs = String::new();
+
+    for field in &m.fields {
+        let field_name = cx.rust_name(field.did);
+        let map_err = format!(
+            r#".map_err(|mut error| {{
+                error.push(STRUCT_NAME, stringify!({field_name}));
+                error
+            }})?"#
+        );
+
+        if is_oneof(cx, &field.ty) {
+            to_json_fallible = true;
+            to_json_fields.push_str(&format!(
+                r#"if let ::std::option::Option::Some(value) = self.{field_name}.as_ref() {{
+                    {JSON}::JsonOneof::to_json(value, &mut object){map_err};
+                }}"#
+            ));
+            merge_oneofs.push_str(&format!(
+                r#"if {JSON}::JsonOneof::merge_json(&mut self.{field_name}, key, value){map_err} {{
+                    continue;
+                }}"#
+            ));
+            continue;
+        }
+
+        to_json_fallible |= is_fallible(cx, &field.ty);
+        let key = json_key(cx, field.did, &field.name.raw_str());
+        let is_container = matches!(field.ty.kind, TyKind::Vec(_) | TyKind::Map(..));
+        let insert = format!(
+            "object.insert({key:?}.into(), {});",
+            to_json_value(cx, &field.ty, "value", &map_err)
+        );
+        to_json_fields.push_str(&match field.kind {
+            FieldKind::Optional => format!(
+                r#"if let ::std::option::Option::Some(value) = self.{field_name}.as_ref() {{
+                    {insert}
+                }}"#
+            ),
+            FieldKind::Required if is_container => format!(
+                r#"if !self.{field_name}.is_empty() {{
+                    let value = &self.{field_name};
+                    {insert}
+                }}"#
+            ),
+            FieldKind::Required if proto3 => format!(
+                r#"if !{JSON}::is_default(&self.{field_name}) {{
+                    let value = &self.{field_name};
+                    {insert}
+                }}"#
+            ),
+            FieldKind::Required => format!(
+                r#"{{
+                    let value = &self.{field_name};
+                    {insert}
+                }}"#
+            ),
+        });
+
+        let decoded = from_json(cx, &field.ty, "value");
+        let assign = match field.kind {
+            FieldKind::Optional => {
+                format!("self.{field_name} = ::std::option::Option::Some({decoded}{map_err});")
+            }
+            FieldKind::Required => format!("self.{field_name} = {decoded}{map_err};"),
+        };
+        let assign = if accepts_null(cx, &field.ty) {
+            assign
+        } else {
+            format!(
+                r#"if !value.is_null() {{
+                    {assign}
+                }}"#
+            )
+        };
+        merge_fields.push_str(&format!(
+            "{} => {{ {assign} }}\n",
+            key_pattern(cx, field.did, &field.name.raw_str())
+        ));
+    }
+
+    let struct_name = if m.fields.is_empty() {
+        String::new()
+    } else {
+        format!("const STRUCT_NAME: &'static str = stringify!({name});")
+    };
+
+    let to_json = format!(
+        r#"{}
+        let mut object = {JSON}::Map::new();
+        {to_json_fields}
+        ::std::result::Result::Ok({JSON}::Value::Object(object))"#,
+        if to_json_fallible { &struct_name } else { "" }
+    );
+    let merge_json = format!(
+        r#"{struct_name}
+        for (key, value) in {JSON}::expect_object(value)? {{
+            match key.as_str() {{
+                {merge_fields}
+                _ => {{
+                    {merge_oneofs}
+                    return ::std::result::Result::Err({JSON}::unknown_field(key));
+                }}
+            }}
+        }}
+        ::std::result::Result::Ok(())"#
+    );
+    (to_json, merge_json)
+}
+
+/// Well-known types with a special JSON form.
+fn codegen_wkt(cx: &Context, m: &Message, wkt: &str) -> Option<(String, String)> {
+    let field = |name: &str| -> Option<&Arc<Field>> {
+        m.fields.iter().find(|f| &*f.name.raw_str() == name)
+    };
+    let field_name = |name: &str| -> Option<FastStr> { Some(cx.rust_name(field(name)?.did).0) };
+
+    Some(match wkt {
+        "Timestamp" | "Duration" => {
+            let kind = wkt.to_lowercase();
+            let seconds = field_name("seconds")?;
+            let nanos = field_name("nanos")?;
+            (
+                format!("{JSON}::wkt::{kind}_to_json(self.{seconds}, self.{nanos})"),
+                format!(
+                    r#"let (seconds, nanos) = {JSON}::wkt::{kind}_from_json(value)?;
+                    self.{seconds} = seconds;
+                    self.{nanos} = nanos;
+                    ::std::result::Result::Ok(())"#
+                ),
+            )
+        }
+        "FieldMask" => {
+            let paths = field_name("paths")?;
+            (
+                format!(
+                    "::std::result::Result::Ok({JSON}::wkt::field_mask_to_json(&self.{paths}))"
+                ),
+                format!(
+                    r#"self.{paths} = {JSON}::wkt::field_mask_from_json(value)?;
+                    ::std::result::Result::Ok(())"#
+                ),
+            )
+        }
+        "Any" => {
+            let type_url = field_name("type_url")?;
+            let value = field_name("value")?;
+            (
+                format!("{JSON}::wkt::any_to_json(&self.{type_url}, &self.{value})"),
+                format!(
+                    r#"let (type_url, value) = {JSON}::wkt::any_from_json(value)?;
+                    self.{type_url} = type_url.into();
+                    self.{value} = value.into();
+                    ::std::result::Result::Ok(())"#
+                ),
+            )
+        }
+        "Value" => {
+            let kind = field_name("kind")?;
+            (
+                format!(
+                    r#"let mut object = {JSON}::Map::new();
+                    if let ::std::option::Option::Some(kind) = self.{kind}.as_ref() {{
+                        {JSON}::JsonOneof::to_json(kind, &mut object)?;
+                    }}
+                    ::std::result::Result::Ok(
+                        object
+                            .into_iter()
+                            .next()
+                            .map(|(_, value)| value)
+                            .unwrap_or({JSON}::Value::Null),
+                    )"#
+                ),
+                format!(
+                    r#"{JSON}::JsonOneof::merge_json(&mut self.{kind}, {JSON}::wkt::value_kind(value), value)?;
+                    ::std::result::Result::Ok(())"#
+                ),
+            )
+        }
+        "Struct" | "ListValue" | "DoubleValue" | "FloatValue" | "Int64Value" | "UInt64Value"
+        | "Int32Value" | "UInt32Value" | "BoolValue" | "StringValue" | "BytesValue" => {
+            // wrappers are written as their single field
+            let [f] = &m.fields[..] else {
+                return None;
+            };
+            let name = cx.rust_name(f.did);
+            (
+                to_json(cx, &f.ty, &format!("&self.{name}")),
+                format!(
+                    r#"self.{name} = {}?;
+                    ::std::result::Result::Ok(())"#,
+                    from_json(cx, &f.ty, "value")
+                ),
+            )
+        }
+        _ => return None,
+    })
+}
+
+fn codegen_oneof(cx: &Context, def_id: DefId, e: &Enum) -> String {
+    let name = cx.rust_name(def_id);
+
+    let to_json = e
+        .variants
+        .iter()
+        .map(|v| {
+            let variant = cx.rust_name(v.did);
+            let key = json_key(cx, v.did, &v.name.raw_str());
+            format!(
+                r#"Self::{variant}(value) => {{
+                    object.insert({key:?}.into(), {});
+                }}"#,
+                to_json_value(cx, &v.fields[0], "value", "?")
+            )
+        })
+        .join("\n");
+
+    let merge_json = e
+        .variants
+        .iter()
+        .map(|v| {
+            let variant = cx.rust_name(v.did);
+            let ty = &v.fields[0];
+            let assign = format!(
+                "*field = ::std::option::Option::Some(Self::{variant}({}?));",
+                from_json(cx, ty, "value")
+            );
+            let assign = if accepts_null(cx, ty) {
+                assign
+            } else {
+                format!(
+                    r#"if !value.is_null() {{
+                        {assign}
+                    }}"#
+                )
+            };
+            format!(
+                "{} => {{ {assign} }}",
+                key_pattern(cx, v.did, &v.name.raw_str())
+            )
+        })
+        .join("\n");
+
+    format!(
+        r#"
+        impl {JSON}::JsonOneof for {name} {{
+            fn to_json(
+                &self,
+                object: &mut {JSON}::Map<::std::string::String, {JSON}::Value>,
+            ) -> ::std::result::Result<(), {JSON}::JsonError> {{
+                match self {{
+                    {to_json}
+                }}
+                ::std::result::Result::Ok(())
+            }}
+
+            fn merge_json(
+                field: &mut ::std::option::Option<Self>,
+                key: &str,
+                value: &{JSON}::Value,
+            ) -> ::std::result::Result<bool, {JSON}::JsonError> {{
+                match key {{
+                    {merge_json}
+                    _ => return ::std::result::Result::Ok(false),
+                }}
+                ::std::result::Result::Ok(true)
+            }}
+        }}
+        "#
+    )
+}
+
+fn codegen_enum(cx: &Context, def_id: DefId, e: &Enum) -> String {
+    let name = cx.rust_name(def_id);
+
+    let json_name = e
+        .variants
+        .iter()
+        .filter_map(|v| Some((v.discr?, v)))
+        .unique_by(|(discr, _)| *discr)
+        .map(|(discr, v)| {
+            format!(
+                "Self({discr}) => ::std::option::Option::Some({:?}),",
+                &*v.name.raw_str()
+            )
+        })
+        .join("\n");
+
+    let from_json_name = e
+        .variants
+        .iter()
+        .map(|v| {
+            format!(
+                "{:?} => ::std::option::Option::Some(Self::{}),",
+                &*v.name.raw_str(),
+                cx.rust_name(v.did)
+            )
+        })
+        .join("\n");
+
+    // `google.protobuf.NullValue` is written as `null`
+    let null_value = if wkt_name(cx, def_id).as_deref() == Some("NullValue") {
+        format!(
+            r#"
+            fn to_json(&self) -> {JSON}::Value {{
+                {JSON}::Value::Null
+            }}
+
+            fn from_json(value: &{JSON}::Value) -> ::std::result::Result<Self, {JSON}::JsonError> {{
+                match value {{
+                    {JSON}::Value::Null => ::std::result::Result::Ok(Self::default()),
+                    {JSON}::Value::String(name) => Self::from_json_name(name).ok_or_else(|| {{
+                        {JSON}::JsonError::new(::std::format!("unknown enum value `{{name}}`"))
+                    }}),
+                    _ => {JSON}::int32::from_json(value).map(Self::from),
+                }}
+            }}
+            "#
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        r#"
+        impl {JSON}::JsonEnum for {name} {{
+            fn json_name(&self) -> ::std::option::Option<&'static str> {{
+                match self {{
+                    {json_name}
+                    _ => ::std::option::Option::None,
+                }}
+            }}
+
+            fn from_json_name(name: &str) -> ::std::option::Option<Self> {{
+                match name {{
+                    {from_json_name}
+                    _ => ::std::option::Option::None,
+                }}
+            }}
+            {null_value}
+        }}
+        "#
+    )
+}
@@ -5,7 +5,7 @@ use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
-    errors,
+    errors::{self, Diagnostic, ErrorCode},
     index::Idx,
     ir::{self, visit::Visitor},
     middle::{
@@ -78,17 +78,26 @@ impl CollectDef<'_> {
 
         tracing::debug!("def {} with DefId({:?})", name, did);
 
-        if match ns {
+        if let Some(prev) = match ns {
             Namespace::Value => table.value.insert(name.clone(), did),
             Namespace::Ty => table.ty.insert(name.clone(), did),
             Namespace::Mod => table.mods.insert(name.clone(), did),
-        }
-        .is_some()
-        {
-            self.resolver
-                .errors
-                .emit_error(format!("duplicate definition of `{name}`"));
+        } {
+            let span = item.tags.get::<errors::Span>();
+            let diagnostic = Diagnostic::new(
+                ErrorCode::DuplicateDefinition,
+                format!("duplicate definition of `{name}`"),
+            )
+            .with_label(span, format!("`{name}` redefined here"))
+            .with_label(
+                self.resolver.def_spans.get(&prev),
+                format!("previous definition of `{name}` here"),
+            );
+            self.resolver.errors.emit_error(diagnostic);
         };
+        if let Some(span) = item.tags.get::<errors::Span>() {
+            self.resolver.def_spans.insert(did, span.clone());
+        }
 
         self.resolver.def_modules.insert(
             did,
@@ -199,6 +208,7 @@ pub struct Resolver {
     cur_file: Option<FileId>,
     ir_files: FxHashMap<FileId, Arc<ir::File>>,
     errors: errors::Handler,
+    def_spans: FxHashMap<DefId, errors::Span>,
     args: FxHashSet<DefId>,
     pb_ext_indexes: FxHashMap<ExtendeeIndex, Arc<Extendee>>, /* for collecting pb options
                                                               * references */
@@ -217,6 +227,7 @@ impl Default for Resolver {
             nodes: Default::default(),
             ir_files: Default::default(),
             errors: Default::default(),
+            def_spans: Default::default(),
             cur_file: None,
             parent_node: None,
             args: Default::default(),
@@ -408,7 +419,7 @@ impl Resolver {
                 parent: exts
                     .parent
                     .as_ref()
-                    .map(|p| self.lower_path(p, Namespace::Ty, false)),
+                    .map(|p| self.lower_path(p, Namespace::Ty, false, p.span.as_ref())),
             }),
             ir::ext::ItemExts::Thrift => ItemExts::Thrift,
         }
@@ -492,7 +503,9 @@ impl Resolver {
                 Arc::from(self.lower_type_for_hash_key(k, false)),
                 Arc::from(self.lower_type(v, false)),
             ),
-            ir::TyKind::Path(p) => ty::Path(self.lower_path(p, Namespace::Ty, is_args)),
+            ir::TyKind::Path(p) => {
+                ty::Path(self.lower_path(p, Namespace::Ty, is_args, ty.tags.get::<errors::Span>()))
+            }
             ir::TyKind::UInt64 => ty::UInt64,
             ir::TyKind::UInt32 => ty::UInt32,
             ir::TyKind::F32 => ty::F32,
@@ -523,7 +536,9 @@ impl Resolver {
                 Arc::from(self.lower_type_for_hash_key(k, false)),
                 Arc::from(self.lower_type(v, false)),
             ),
-            ir::TyKind::Path(p) => ty::Path(self.lower_path(p, Namespace::Ty, is_args)),
+            ir::TyKind::Path(p) => {
+                ty::Path(self.lower_path(p, Namespace::Ty, is_args, ty.tags.get::<errors::Span>()))
+            }
             ir::TyKind::UInt64 => ty::UInt64,
             ir::TyKind::UInt32 => ty::UInt32,
             ir::TyKind::F32 => ty::F32,
@@ -604,7 +619,13 @@ impl Resolver {
         .copied()
     }
 
-    fn lower_path(&mut self, path: &ir::Path, ns: Namespace, is_args: bool) -> Path {
+    fn lower_path(
+        &mut self,
+        path: &ir::Path,
+        ns: Namespace,
+        is_args: bool,
+        span: Option<&errors::Span>,
+    ) -> Path {
         let segs = &path.segments;
         let cur_file = self.ir_files.get(self.cur_file.as_ref().unwrap()).unwrap();
         let path_kind = match ns {
@@ -644,12 +665,19 @@ impl Resolver {
                 _ => None,
             })
             .unwrap_or_else(|| {
-                panic!(
-                    "can not find path {} in file symbols {:?}, {:?}",
-                    path,
-                    self.file_sym_map.get(&self.cur_file.unwrap()),
-                    cur_file.uses,
+                let mut diagnostic = Diagnostic::new(
+                    ErrorCode::UnresolvedPath,
+                    format!("cannot find `{path}` in this scope"),
                 )
+                .with_label(span, "not found in this file or its includes");
+                if path.segments.len() > 1 && !cur_file.uses.is_empty() {
+                    diagnostic = diagnostic.with_note(format!(
+                        "the includes of this file are: {}",
+                        cur_file.uses.iter().map(|(p, _)| p).join(", ")
+                    ));
+                }
+                self.errors.emit_error(diagnostic);
+                DefId::from_usize(0)
             });
 
         if is_args {
@@ -770,7 +798,7 @@ impl Resolver {
                         exceptions: m
                             .exceptions
                             .as_ref()
-                            .map(|p| self.lower_path(p, Namespace::Ty, true)),
+                            .map(|p| self.lower_path(p, Namespace::Ty, true, p.span.as_ref())),
                         item_exts: self.lower_item_exts(&m.item_exts),
                     });
                     self.parent_node = old_parent;
@@ -785,7 +813,7 @@ impl Resolver {
             extend: s
                 .extend
                 .iter()
-                .map(|p| self.lower_path(p, Namespace::Ty, false))
+                .map(|p| self.lower_path(p, Namespace::Ty, false, p.span.as_ref()))
                 .collect(),
             item_exts: self.lower_item_exts(&s.item_exts),
         }
@@ -806,7 +834,9 @@ impl Resolver {
     fn lower_lit(&mut self, l: &ir::Literal) -> Literal {
         match l {
             ir::Literal::Bool(b) => Literal::Bool(*b),
-            ir::Literal::Path(p) => Literal::Path(self.lower_path(p, Namespace::Value, false)),
+            ir::Literal::Path(p) => {
+                Literal::Path(self.lower_path(p, Namespace::Value, false, p.span.as_ref()))
+            }
             ir::Literal::String(s) => Literal::String(s.clone()),
             ir::Literal::Int(i) => Literal::Int(*i),
             ir::Literal::Float(f) => Literal::Float(f.clone()),
@@ -894,9 +924,11 @@ impl Resolver {
                 self.lower_path(
                     &ir::Path {
                         segments: Arc::from([i.clone()]),
+                        span: None,
                     },
                     Namespace::Ty,
                     false,
+                    tags.get::<errors::Span>(),
                 )
                 .did
             })
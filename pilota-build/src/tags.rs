@@ -215,5 +215,8 @@ pub mod protobuf {
         pub struct Deprecated(pub bool);
 
         pub struct OptionalRepeated(pub bool);
+
+        /// The `json_name` of a field, when the idl sets one.
+        pub struct JsonName(pub faststr::FastStr);
     }
 }
@@ -134,6 +134,20 @@ fn test_pb_with_descriptor(source: impl AsRef<Path>, target: impl AsRef<Path>) {
     });
 }
 
+fn test_pb_with_field_mask(source: impl AsRef<Path>, target: impl AsRef<Path>) {
+    test_with_builder(source, target, |source, target| {
+        crate::Builder::pb()
+            .ignore_unused(false)
+            .include_dirs(vec![source.parent().unwrap().to_path_buf()])
+            .with_descriptor(true)
+            .with_field_mask(true)
+            .compile_with_config(
+                vec![IdlService::from_path(source.to_path_buf())],
+                crate::Output::File(target.into()),
+            )
+    });
+}
+
 fn test_pb_with_split(
     source: impl AsRef<Path>,
     target: impl AsRef<Path>,
@@ -607,13 +621,236 @@ fn test_duplicate_field_id() {
     out_path.set_extension("rs");
 
     crate::Builder::thrift()
-        .touch([(file_path.clone().into(), vec!["User"])])
+        .touch([(file_path.clone(), vec!["User"])])
         .compile_with_config(
             vec![IdlService::from_path(file_path)],
             crate::Output::File(out_path),
         );
 }
 
+fn compile_error(file: &str) -> String {
+    let file_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("test_data")
+        .join("diagnostics")
+        .join(file);
+    let out_dir = tempfile::tempdir().unwrap();
+
+    let payload = std::panic::catch_unwind(|| {
+        crate::Builder::thrift().compile_with_config(
+            vec![IdlService::from_path(file_path)],
+            crate::Output::File(out_dir.path().join("out.rs")),
+        )
+    })
+    .unwrap_err();
+    *payload.downcast::<String>().unwrap()
+}
+
+#[test]
+fn test_diagnostics() {
+    let report = compile_error("unresolved_path.thrift");
+    assert_eq!(report.matches("[E0001] Error").count(), 2);
+    assert!(report.contains("cannot find `Missing` in this scope"));
+    assert!(report.contains("unresolved_path.thrift:4:17"));
+    assert!(report.contains("cannot find `base.Gone` in this scope"));
+    assert!(report.contains("unresolved_path.thrift:5:22"));
+    assert!(report.contains("the includes of this file are: base"));
+    assert!(report.ends_with("aborting due to 2 previous errors"));
+
+    let report = compile_error("unresolved_service.thrift");
+    assert!(report.contains("cannot find `base.LIMIT` in this scope"));
+    assert!(report.contains("unresolved_service.thrift:3:27"));
+    assert!(report.contains("cannot find `base.BaseService` in this scope"));
+    assert!(report.contains("unresolved_service.thrift:5:29"));
+    assert!(report.contains("cannot find `base.NotFound` in this scope"));
+    assert!(report.contains("unresolved_service.thrift:6:41"));
+
+    let report = compile_error("duplicate_definition.thrift");
+    assert!(report.contains("[E0002] Error: duplicate definition of `User`"));
+    assert!(report.contains("duplicate_definition.thrift:5:6"));
+    assert!(report.contains("previous definition of `User` here"));
+
+    let report = compile_error("bad_include.thrift");
+    assert!(report.contains("[E0004] Error: include file `not_exist.thrift` not found"));
+    assert!(report.contains("bad_include.thrift:1:9"));
+    assert!(report.contains("[E0003] Error: duplicate ID `1` in struct `User`"));
+    assert!(report.contains("bad_include.thrift:5:5"));
+    assert!(report.ends_with("aborting due to 2 previous errors"));
+}
+
+#[test]
+fn test_compat_thrift() {
+    use crate::compat::{ChangeKind, Severity, check_thrift};
+
+    let report = check_thrift(
+        "test_data/compat/thrift/old/service.thrift",
+        "test_data/compat/thrift/new/service.thrift",
+    )
+    .unwrap();
+    let text = report.to_string();
+    assert_eq!(text.lines().count(), report.changes.len());
+    assert_eq!(
+        text.lines().nth(1),
+        Some(
+            "breaking: service.thrift: User.id: the type of field 1 changed from `i64` to `string`"
+        )
+    );
+
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.severity, c.kind, c.location.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            (Severity::Warning, ChangeKind::ExceptionRenamed, "NotFound"),
+            (Severity::Breaking, ChangeKind::FieldTypeChanged, "User.id"),
+            (Severity::Warning, ChangeKind::FieldRenamed, "User.name"),
+            (Severity::Breaking, ChangeKind::FieldIdReused, "User.age"),
+            (
+                Severity::Breaking,
+                ChangeKind::FieldRequirednessChanged,
+                "User.email"
+            ),
+            (
+                Severity::Warning,
+                ChangeKind::FieldTypeChanged,
+                "User.status"
+            ),
+            (Severity::Info, ChangeKind::FieldAdded, "User.avatar"),
+            (
+                Severity::Breaking,
+                ChangeKind::FieldTypeChanged,
+                "UserService.get.id"
+            ),
+            (
+                Severity::Info,
+                ChangeKind::FieldAdded,
+                "UserService.get.verbose"
+            ),
+            (
+                Severity::Breaking,
+                ChangeKind::MethodRemoved,
+                "UserService.delete"
+            ),
+            (
+                Severity::Breaking,
+                ChangeKind::MethodOnewayChanged,
+                "UserService.ping"
+            ),
+            (
+                Severity::Info,
+                ChangeKind::MethodAdded,
+                "UserService.update"
+            ),
+            (Severity::Breaking, ChangeKind::TypedefChanged, "base.Id"),
+            (
+                Severity::Warning,
+                ChangeKind::EnumValueRenamed,
+                "base.Status.PENDING"
+            ),
+            (
+                Severity::Breaking,
+                ChangeKind::EnumValueRemoved,
+                "base.Status.FAILED"
+            ),
+            (
+                Severity::Info,
+                ChangeKind::EnumValueAdded,
+                "base.Status.RETRYING"
+            ),
+        ]
+    );
+    assert!(report.is_breaking());
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["changes"][0]["severity"], "warning");
+    assert_eq!(json["changes"][0]["kind"], "exception_renamed");
+    assert_eq!(json["changes"][0]["file"], "service.thrift");
+}
+
+#[test]
+fn test_compat_protobuf() {
+    use crate::{
+        compat::{ChangeKind, Severity, check_protobuf},
+        parser::{Parser, ProtobufParser},
+    };
+
+    let descriptors = |dir: &str| {
+        let mut parser = ProtobufParser::default();
+        parser.include_dirs(vec![dir.into()]);
+        parser.input(format!("{dir}/service.proto"));
+        parser.file_descriptors()
+    };
+    let report = check_protobuf(
+        &descriptors("test_data/compat/protobuf/old"),
+        &descriptors("test_data/compat/protobuf/new"),
+    );
+    let text = report.to_string();
+    assert_eq!(text.lines().count(), report.changes.len());
+    assert_eq!(
+        text.lines().next(),
+        Some(
+            "breaking: service.proto: compat.v1: the package changed from `compat.v1` to `compat.v2`"
+        )
+    );
+
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.severity, c.kind, c.location.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            (Severity::Breaking, ChangeKind::PackageChanged, "compat.v1"),
+            (Severity::Warning, ChangeKind::FieldRenamed, "User.name"),
+            (Severity::Warning, ChangeKind::FieldTypeChanged, "User.age"),
+            (
+                Severity::Warning,
+                ChangeKind::FieldTypeChanged,
+                "User.email"
+            ),
+            (Severity::Breaking, ChangeKind::OneofMoved, "User.phone"),
+            (
+                Severity::Breaking,
+                ChangeKind::FieldLabelChanged,
+                "User.tags"
+            ),
+            (Severity::Breaking, ChangeKind::FieldIdReused, "User.score"),
+            (
+                Severity::Breaking,
+                ChangeKind::ReservedViolated,
+                "User.avatar"
+            ),
+            (Severity::Warning, ChangeKind::ReservedRemoved, "User"),
+            (
+                Severity::Warning,
+                ChangeKind::EnumValueRenamed,
+                "Status.STATUS_OK"
+            ),
+            (
+                Severity::Breaking,
+                ChangeKind::EnumValueRemoved,
+                "Status.STATUS_FAILED"
+            ),
+            (
+                Severity::Breaking,
+                ChangeKind::ServiceRenamed,
+                "UserService"
+            ),
+        ]
+    );
+    assert!(report.deny(Severity::Breaking).is_err());
+    assert!(
+        report
+            .deny(Severity::Breaking)
+            .unwrap_err()
+            .to_string()
+            .contains("UserService")
+    );
+}
+
 mod tests {
 
     // use self::decode_error::decode_error::A;
@@ -661,3 +898,24 @@ fn test_pb_options() {
 }
 
 mod enum_key_map_tests;
+
+#[test]
+fn test_protobuf_with_field_mask() {
+    let test_data_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("test_data")
+        .join("protobuf_with_field_mask");
+
+    test_data_dir.read_dir().unwrap().for_each(|f| {
+        let f = f.unwrap();
+
+        let path = f.path();
+
+        if let Some(ext) = path.extension() {
+            if ext == "proto" {
+                let mut rs_path = path.clone();
+                rs_path.set_extension("rs");
+                test_pb_with_field_mask(path, rs_path);
+            }
+        }
+    });
+}
@@ -1,4 +0,0 @@
-pub fn error_abort(msg: String) -> ! {
-    eprintln!("{msg}");
-    std::process::exit(1);
-}
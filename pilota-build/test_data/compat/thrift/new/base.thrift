@@ -0,0 +1,9 @@
+namespace rs base
+
+typedef string Id
+
+enum Status {
+    OK,
+    WAITING,
+    RETRYING = 5,
+}
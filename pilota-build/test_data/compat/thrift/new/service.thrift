@@ -0,0 +1,22 @@
+include "base.thrift"
+
+namespace rs compat
+
+struct User {
+    1: required base.Id id,
+    2: string nickname,
+    3: optional string country,
+    4: required string email,
+    5: optional i32 status,
+    6: optional string avatar,
+}
+
+exception UserNotFound {
+    1: string message,
+}
+
+service UserService {
+    User get(1: base.Id id, 2: bool verbose) throws (1: UserNotFound e),
+    void ping(),
+    void update(1: User user),
+}
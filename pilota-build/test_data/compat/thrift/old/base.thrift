@@ -0,0 +1,9 @@
+namespace rs base
+
+typedef i64 Id
+
+enum Status {
+    OK,
+    PENDING,
+    FAILED = 10,
+}
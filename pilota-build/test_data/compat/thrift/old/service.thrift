@@ -0,0 +1,21 @@
+include "base.thrift"
+
+namespace rs compat
+
+struct User {
+    1: required base.Id id,
+    2: string name,
+    3: optional i32 age,
+    4: optional string email,
+    5: optional base.Status status,
+}
+
+exception NotFound {
+    1: string message,
+}
+
+service UserService {
+    User get(1: base.Id id) throws (1: NotFound e),
+    void delete(1: base.Id id),
+    oneway void ping(),
+}
@@ -0,0 +1,6 @@
+include "not_exist.thrift"
+
+struct User {
+    1: string name,
+    1: i32 id,
+}
@@ -0,0 +1,3 @@
+struct Base {
+    1: string caller,
+}
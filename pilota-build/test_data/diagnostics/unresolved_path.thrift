@@ -0,0 +1,7 @@
+include "base.thrift"
+
+struct User {
+    1: required Missing missing,
+    2: optional list<base.Gone> gone,
+    3: optional base.Base base,
+}
@@ -0,0 +1,7 @@
+include "base.thrift"
+
+const i32 DEFAULT_LIMIT = base.LIMIT
+
+service UserService extends base.BaseService {
+    base.Base get(1: i64 id) throws (1: base.NotFound not_found),
+}
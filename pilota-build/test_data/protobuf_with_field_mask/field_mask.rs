@@ -0,0 +1,564 @@
+pub mod field_mask {
+    #![allow(warnings, clippy::all)]
+    use ::pilota::{Buf as _, BufMut as _, pb::descriptor_getter::*};
+    pub mod field_mask {
+        use ::pilota::{Buf as _, BufMut as _, pb::descriptor_getter::*};
+
+        static FILE_DESCRIPTOR_BYTES_FIELD_MASK: ::pilota::Bytes = ::pilota::Bytes::from_static(b"\n\x10field_mask.proto\x12\nfield_mask\"5\n\x07Address\x12\x12\n\x04city\x18\x01 \x01(\tR\x04city\x12\x16\n\x06street\x18\x02 \x01(\tR\x06street\"\x9c\x03\n\x04User\x12\x17\n\x07user_id\x18\x01 \x01(\x03R\x06userId\x12\x12\n\x04name\x18\x02 \x01(\tR\x04name\x12-\n\x07address\x18\x03 \x01(\x0b2\x13.field_mask.AddressR\x07address\x12\x12\n\x04tags\x18\x04 \x03(\tR\x04tags\x124\n\x06scores\x18\x05 \x03(\x0b2\x1c.field_mask.User.ScoresEntryR\x06scores\x122\n\x07profile\x18\x06 \x01(\x0b2\x18.field_mask.User.ProfileR\x07profile\x12\x16\n\x05email\x18\x07 \x01(\tH\0R\x05email\x12\x16\n\x05phone\x18\x08 \x01(\tH\0R\x05phone\x1a9\n\x0bScoresEntry\x12\x10\n\x03key\x18\x01 \x01(\tR\x03key\x12\x14\n\x05value\x18\x02 \x01(\x03R\x05value:\x028\x01\x1aD\n\x07Profile\x12\x10\n\x03bio\x18\x01 \x01(\tR\x03bio\x12'\n\x04home\x18\x02 \x01(\x0b2\x13.field_mask.AddressR\x04homeB\t\n\x07contactb\x06proto3");
+        static FILE_DESCRIPTOR_PROTO_FIELD_MASK: ::std::sync::LazyLock<
+            ::pilota::pb::descriptor::FileDescriptorProto,
+        > = ::std::sync::LazyLock::new(|| {
+            let data: &[u8] = FILE_DESCRIPTOR_BYTES_FIELD_MASK.as_ref();
+            ::pilota::pb::PbMessage::parse_from_bytes(data)
+                .expect("Failed to decode file descriptor")
+        });
+        pub fn file_descriptor_proto_field_mask()
+        -> &'static ::pilota::pb::descriptor::FileDescriptorProto {
+            &*FILE_DESCRIPTOR_PROTO_FIELD_MASK
+        }
+
+        static FILE_DESCRIPTOR_FIELD_MASK: ::std::sync::LazyLock<
+            ::pilota::pb::reflect::FileDescriptor,
+        > = ::std::sync::LazyLock::new(|| {
+            let mut deps = ::std::vec::Vec::new();
+
+            ::pilota::pb::reflect::FileDescriptor::new_dynamic(
+                file_descriptor_proto_field_mask().clone(),
+                &deps,
+            )
+            .expect("Failed to build dynamic FileDescriptor")
+        });
+
+        pub fn file_descriptor_field_mask() -> &'static ::pilota::pb::reflect::FileDescriptor {
+            &*FILE_DESCRIPTOR_FIELD_MASK
+        }
+        #[derive(PartialOrd, Hash, Eq, Ord, Debug, Default, Clone, PartialEq)]
+        pub struct Address {
+            pub city: ::pilota::FastStr,
+
+            pub street: ::pilota::FastStr,
+            pub _field_mask: ::std::option::Option<::pilota::pb::field_mask::FieldMask>,
+        }
+        impl Address {
+            pub fn get_descriptor_proto()
+            -> Option<&'static ::pilota::pb::descriptor::DescriptorProto> {
+                let file_descriptor = file_descriptor_proto_field_mask();
+                file_descriptor.get_message_descriptor_proto("Address")
+            }
+        }
+        impl Address {
+            pub fn get_message_descriptor() -> Option<::pilota::pb::reflect::MessageDescriptor> {
+                file_descriptor_field_mask().message_by_package_relative_name("Address")
+            }
+
+            /// Sets the field mask used when encoding the message, and the
+            /// sub-masks of its singular message fields.
+            pub fn set_field_mask(&mut self, field_mask: ::pilota::pb::field_mask::FieldMask) {
+                self._field_mask = Some(field_mask);
+            }
+        }
+        impl ::pilota::pb::Message for Address {
+            #[inline]
+            fn encoded_len(&self, ctx: &mut ::pilota::pb::EncodeLengthContext) -> usize {
+                0 + if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                    ::pilota::pb::encoding::faststr::encoded_len(ctx, 1, &self.city)
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                    ::pilota::pb::encoding::faststr::encoded_len(ctx, 2, &self.street)
+                } else {
+                    0
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn encode_raw(&self, buf: &mut ::pilota::LinkedBytes) {
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                    ::pilota::pb::encoding::faststr::encode(1, &self.city, buf);
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                    ::pilota::pb::encoding::faststr::encode(2, &self.street, buf);
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn merge_field(
+                &mut self,
+                tag: u32,
+                wire_type: ::pilota::pb::encoding::WireType,
+                buf: &mut ::pilota::Bytes,
+                ctx: &mut ::pilota::pb::encoding::DecodeContext,
+                is_root: bool,
+            ) -> ::core::result::Result<(), ::pilota::pb::DecodeError> {
+                const STRUCT_NAME: &'static str = stringify!(Address);
+
+                match tag {
+                    1 => {
+                        let mut _inner_pilota_value = &mut self.city;
+                        ::pilota::pb::encoding::faststr::merge(
+                            wire_type,
+                            _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(city));
+                            error
+                        })
+                    }
+                    2 => {
+                        let mut _inner_pilota_value = &mut self.street;
+                        ::pilota::pb::encoding::faststr::merge(
+                            wire_type,
+                            _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(street));
+                            error
+                        })
+                    }
+                    _ => ::pilota::pb::encoding::skip_field(wire_type, tag, buf, ctx),
+                }
+            }
+        }
+        #[derive(Debug, Default, Clone, PartialEq)]
+        pub struct User {
+            pub user_id: i64,
+
+            pub name: ::pilota::FastStr,
+
+            pub address: ::std::option::Option<Address>,
+
+            pub tags: ::std::vec::Vec<::pilota::FastStr>,
+
+            pub scores: ::pilota::AHashMap<::pilota::FastStr, i64>,
+
+            pub profile: ::std::option::Option<user::Profile>,
+
+            pub contact: ::std::option::Option<user::Contact>,
+            pub _field_mask: ::std::option::Option<::pilota::pb::field_mask::FieldMask>,
+        }
+        impl User {
+            pub fn get_descriptor_proto()
+            -> Option<&'static ::pilota::pb::descriptor::DescriptorProto> {
+                let file_descriptor = file_descriptor_proto_field_mask();
+                file_descriptor.get_message_descriptor_proto("User")
+            }
+        }
+        impl User {
+            pub fn get_message_descriptor() -> Option<::pilota::pb::reflect::MessageDescriptor> {
+                file_descriptor_field_mask().message_by_package_relative_name("User")
+            }
+
+            /// Sets the field mask used when encoding the message, and the
+            /// sub-masks of its singular message fields.
+            pub fn set_field_mask(&mut self, field_mask: ::pilota::pb::field_mask::FieldMask) {
+                if let (sub_mask, true) = field_mask.field(3) {
+                    if let Some(value) = &mut self.address {
+                        value.set_field_mask(sub_mask.cloned().unwrap_or_default());
+                    }
+                }
+                if let (sub_mask, true) = field_mask.field(6) {
+                    if let Some(value) = &mut self.profile {
+                        value.set_field_mask(sub_mask.cloned().unwrap_or_default());
+                    }
+                }
+                self._field_mask = Some(field_mask);
+            }
+        }
+        impl ::pilota::pb::Message for User {
+            #[inline]
+            fn encoded_len(&self, ctx: &mut ::pilota::pb::EncodeLengthContext) -> usize {
+                0 + if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                    ::pilota::pb::encoding::int64::encoded_len(ctx, 1, &self.user_id)
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                    ::pilota::pb::encoding::faststr::encoded_len(ctx, 2, &self.name)
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(3).1) {
+                    self.address.as_ref().map_or(0, |msg| {
+                        ::pilota::pb::encoding::message::encoded_len(ctx, 3, msg)
+                    })
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(4).1) {
+                    ::pilota::pb::encoding::faststr::encoded_len_repeated(ctx, 4, &self.tags)
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(5).1) {
+                    ::pilota::pb::encoding::hash_map::encoded_len(
+                        ctx,
+                        ::pilota::pb::encoding::faststr::encoded_len,
+                        ::pilota::pb::encoding::int64::encoded_len,
+                        5,
+                        &self.scores,
+                    )
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(6).1) {
+                    self.profile.as_ref().map_or(0, |msg| {
+                        ::pilota::pb::encoding::message::encoded_len(ctx, 6, msg)
+                    })
+                } else {
+                    0
+                } + if self._field_mask.as_ref().map_or(true, |fm| {
+                    self.contact.as_ref().map_or(true, |v| fm.field(v.tag()).1)
+                }) {
+                    self.contact.as_ref().map_or(0, |msg| msg.encoded_len(ctx))
+                } else {
+                    0
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn encode_raw(&self, buf: &mut ::pilota::LinkedBytes) {
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                    ::pilota::pb::encoding::int64::encode(1, &self.user_id, buf);
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                    ::pilota::pb::encoding::faststr::encode(2, &self.name, buf);
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(3).1) {
+                    if let Some(_pilota_inner_value) = self.address.as_ref() {
+                        ::pilota::pb::encoding::message::encode(3, _pilota_inner_value, buf);
+                    }
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(4).1) {
+                    ::pilota::pb::encoding::faststr::encode_repeated(4, &self.tags, buf);
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(5).1) {
+                    ::pilota::pb::encoding::hash_map::encode(
+                        ::pilota::pb::encoding::faststr::encode,
+                        ::pilota::pb::encoding::faststr::encoded_len,
+                        ::pilota::pb::encoding::int64::encode,
+                        ::pilota::pb::encoding::int64::encoded_len,
+                        5,
+                        &self.scores,
+                        buf,
+                    );
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| fm.field(6).1) {
+                    if let Some(_pilota_inner_value) = self.profile.as_ref() {
+                        ::pilota::pb::encoding::message::encode(6, _pilota_inner_value, buf);
+                    }
+                }
+                if self._field_mask.as_ref().map_or(true, |fm| {
+                    self.contact.as_ref().map_or(true, |v| fm.field(v.tag()).1)
+                }) {
+                    if let Some(_pilota_inner_value) = self.contact.as_ref() {
+                        _pilota_inner_value.encode(buf);
+                    }
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn merge_field(
+                &mut self,
+                tag: u32,
+                wire_type: ::pilota::pb::encoding::WireType,
+                buf: &mut ::pilota::Bytes,
+                ctx: &mut ::pilota::pb::encoding::DecodeContext,
+                is_root: bool,
+            ) -> ::core::result::Result<(), ::pilota::pb::DecodeError> {
+                const STRUCT_NAME: &'static str = stringify!(User);
+
+                match tag {
+                    1 => {
+                        let mut _inner_pilota_value = &mut self.user_id;
+                        ::pilota::pb::encoding::int64::merge(
+                            wire_type,
+                            _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(user_id));
+                            error
+                        })
+                    }
+                    2 => {
+                        let mut _inner_pilota_value = &mut self.name;
+                        ::pilota::pb::encoding::faststr::merge(
+                            wire_type,
+                            _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(name));
+                            error
+                        })
+                    }
+                    3 => {
+                        let mut _inner_pilota_value = &mut self.address;
+                        ::pilota::pb::encoding::message::merge(
+                            wire_type,
+                            _inner_pilota_value
+                                .get_or_insert_with(::core::default::Default::default),
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(address));
+                            error
+                        })
+                    }
+                    4 => {
+                        let mut _inner_pilota_value = &mut self.tags;
+                        ::pilota::pb::encoding::faststr::merge_repeated(
+                            wire_type,
+                            _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(tags));
+                            error
+                        })
+                    }
+                    5 => {
+                        let mut _inner_pilota_value = &mut self.scores;
+                        ::pilota::pb::encoding::hash_map::merge(
+                            ::pilota::pb::encoding::faststr::merge,
+                            ::pilota::pb::encoding::int64::merge,
+                            &mut _inner_pilota_value,
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(scores));
+                            error
+                        })
+                    }
+                    6 => {
+                        let mut _inner_pilota_value = &mut self.profile;
+                        ::pilota::pb::encoding::message::merge(
+                            wire_type,
+                            _inner_pilota_value
+                                .get_or_insert_with(::core::default::Default::default),
+                            buf,
+                            ctx,
+                        )
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(profile));
+                            error
+                        })
+                    }
+                    7 | 8 => {
+                        let mut _inner_pilota_value = &mut self.contact;
+                        user::Contact::merge(_inner_pilota_value, tag, wire_type, buf, ctx).map_err(
+                            |mut error| {
+                                error.push(STRUCT_NAME, stringify!(contact));
+                                error
+                            },
+                        )
+                    }
+                    _ => ::pilota::pb::encoding::skip_field(wire_type, tag, buf, ctx),
+                }
+            }
+        }
+
+        pub mod user {
+            use ::pilota::{Buf as _, BufMut as _, pb::descriptor_getter::*};
+            #[derive(PartialOrd, Hash, Eq, Ord, Debug, Default, Clone, PartialEq)]
+            pub struct Profile {
+                pub bio: ::pilota::FastStr,
+
+                pub home: ::std::option::Option<super::Address>,
+                pub _field_mask: ::std::option::Option<::pilota::pb::field_mask::FieldMask>,
+            }
+            impl Profile {
+                pub fn get_descriptor_proto()
+                -> Option<&'static ::pilota::pb::descriptor::DescriptorProto> {
+                    let message_descriptor = super::User::get_descriptor_proto()?;
+                    message_descriptor.get_message_descriptor_proto("Profile")
+                }
+            }
+            impl Profile {
+                pub fn get_message_descriptor() -> Option<::pilota::pb::reflect::MessageDescriptor>
+                {
+                    super::User::get_message_descriptor()?
+                        .nested_messages()
+                        .find(|m| m.name() == "Profile")
+                }
+
+                /// Sets the field mask used when encoding the message, and the
+                /// sub-masks of its singular message fields.
+                pub fn set_field_mask(&mut self, field_mask: ::pilota::pb::field_mask::FieldMask) {
+                    if let (sub_mask, true) = field_mask.field(2) {
+                        if let Some(value) = &mut self.home {
+                            value.set_field_mask(sub_mask.cloned().unwrap_or_default());
+                        }
+                    }
+                    self._field_mask = Some(field_mask);
+                }
+            }
+            impl ::pilota::pb::Message for Profile {
+                #[inline]
+                fn encoded_len(&self, ctx: &mut ::pilota::pb::EncodeLengthContext) -> usize {
+                    0 + if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                        ::pilota::pb::encoding::faststr::encoded_len(ctx, 1, &self.bio)
+                    } else {
+                        0
+                    } + if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                        self.home.as_ref().map_or(0, |msg| {
+                            ::pilota::pb::encoding::message::encoded_len(ctx, 2, msg)
+                        })
+                    } else {
+                        0
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn encode_raw(&self, buf: &mut ::pilota::LinkedBytes) {
+                    if self._field_mask.as_ref().map_or(true, |fm| fm.field(1).1) {
+                        ::pilota::pb::encoding::faststr::encode(1, &self.bio, buf);
+                    }
+                    if self._field_mask.as_ref().map_or(true, |fm| fm.field(2).1) {
+                        if let Some(_pilota_inner_value) = self.home.as_ref() {
+                            ::pilota::pb::encoding::message::encode(2, _pilota_inner_value, buf);
+                        }
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn merge_field(
+                    &mut self,
+                    tag: u32,
+                    wire_type: ::pilota::pb::encoding::WireType,
+                    buf: &mut ::pilota::Bytes,
+                    ctx: &mut ::pilota::pb::encoding::DecodeContext,
+                    is_root: bool,
+                ) -> ::core::result::Result<(), ::pilota::pb::DecodeError> {
+                    const STRUCT_NAME: &'static str = stringify!(Profile);
+
+                    match tag {
+                        1 => {
+                            let mut _inner_pilota_value = &mut self.bio;
+                            ::pilota::pb::encoding::faststr::merge(
+                                wire_type,
+                                _inner_pilota_value,
+                                buf,
+                                ctx,
+                            )
+                            .map_err(|mut error| {
+                                error.push(STRUCT_NAME, stringify!(bio));
+                                error
+                            })
+                        }
+                        2 => {
+                            let mut _inner_pilota_value = &mut self.home;
+                            ::pilota::pb::encoding::message::merge(
+                                wire_type,
+                                _inner_pilota_value
+                                    .get_or_insert_with(::core::default::Default::default),
+                                buf,
+                                ctx,
+                            )
+                            .map_err(|mut error| {
+                                error.push(STRUCT_NAME, stringify!(home));
+                                error
+                            })
+                        }
+                        _ => ::pilota::pb::encoding::skip_field(wire_type, tag, buf, ctx),
+                    }
+                }
+            }
+
+            impl ::std::default::Default for Contact {
+                fn default() -> Self {
+                    Contact::Email(::std::default::Default::default())
+                }
+            }
+            #[derive(PartialOrd, Hash, Eq, Ord, Debug, Clone, PartialEq)]
+            pub enum Contact {
+                Email(::pilota::FastStr),
+
+                Phone(::pilota::FastStr),
+            }
+
+            impl Contact {
+                pub fn get_descriptor_proto()
+                -> Option<&'static ::pilota::pb::descriptor::OneofDescriptorProto> {
+                    let message_descriptor = super::User::get_descriptor_proto()?;
+                    message_descriptor.get_oneof_descriptor_proto("contact")
+                }
+            }
+
+            impl Contact {
+                #[inline]
+                pub fn tag(&self) -> u32 {
+                    match self {
+                        Contact::Email(_) => 7,
+                        Contact::Phone(_) => 8,
+                    }
+                }
+
+                pub fn encode(&self, buf: &mut ::pilota::LinkedBytes) {
+                    match self {
+                        Contact::Email(value) => {
+                            ::pilota::pb::encoding::faststr::encode(7, &*value, buf);
+                        }
+                        Contact::Phone(value) => {
+                            ::pilota::pb::encoding::faststr::encode(8, &*value, buf);
+                        }
+                    }
+                }
+
+                #[inline]
+                pub fn encoded_len(&self, ctx: &mut ::pilota::pb::EncodeLengthContext) -> usize {
+                    match self {
+                        Contact::Email(value) => {
+                            ::pilota::pb::encoding::faststr::encoded_len(ctx, 7, &*value)
+                        }
+                        Contact::Phone(value) => {
+                            ::pilota::pb::encoding::faststr::encoded_len(ctx, 8, &*value)
+                        }
+                    }
+                }
+
+                #[inline]
+                pub fn merge(
+                    field: &mut ::core::option::Option<Self>,
+                    tag: u32,
+                    wire_type: ::pilota::pb::encoding::WireType,
+                    buf: &mut ::pilota::Bytes,
+                    ctx: &mut ::pilota::pb::encoding::DecodeContext,
+                ) -> ::core::result::Result<(), ::pilota::pb::DecodeError> {
+                    match tag {
+                        7 => match field {
+                            ::core::option::Option::Some(Contact::Email(value)) => {
+                                ::pilota::pb::encoding::faststr::merge(wire_type, value, buf, ctx)?;
+                            }
+                            _ => {
+                                let mut owned_value = ::core::default::Default::default();
+                                let value = &mut owned_value;
+                                ::pilota::pb::encoding::faststr::merge(wire_type, value, buf, ctx)?;
+                                *field = ::core::option::Option::Some(Contact::Email(owned_value));
+                            }
+                        },
+                        8 => match field {
+                            ::core::option::Option::Some(Contact::Phone(value)) => {
+                                ::pilota::pb::encoding::faststr::merge(wire_type, value, buf, ctx)?;
+                            }
+                            _ => {
+                                let mut owned_value = ::core::default::Default::default();
+                                let value = &mut owned_value;
+                                ::pilota::pb::encoding::faststr::merge(wire_type, value, buf, ctx)?;
+                                *field = ::core::option::Option::Some(Contact::Phone(owned_value));
+                            }
+                        },
+                        _ => {
+                            unreachable!(concat!("invalid ", stringify!(Contact), " tag: {}"), tag)
+                        }
+                    };
+                    ::core::result::Result::Ok(())
+                }
+            }
+        }
+    }
+}
@@ -332,6 +332,264 @@ pub mod complex_enum_key_map {
                 }
             }
         }
+        impl NestedItem {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::I32 => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some(__protocol.read_i32()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2)
+                                if field_ident.field_type == ::pilota::thrift::TType::Binary =>
+                            {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some(__protocol.read_faststr()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `NestedItem` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field value is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    value: var_1,
+                    label: var_2,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                        loop {
+                            let field_ident = __protocol.read_field_begin().await?;
+                            if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                                break;
+                            } else {
+                            }
+                            __pilota_decoding_field_id = field_ident.id;
+                            match field_ident.id {
+                                Some(1)
+                                    if field_ident.field_type == ::pilota::thrift::TType::I32 =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(1);
+                                    if exist {
+                                        var_1 = Some(__protocol.read_i32().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(2)
+                                    if field_ident.field_type
+                                        == ::pilota::thrift::TType::Binary =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(2);
+                                    if exist {
+                                        var_2 = Some(__protocol.read_faststr().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                _ => {
+                                    __protocol.skip(field_ident.field_type).await?;
+                                }
+                            }
+
+                            __protocol.read_field_end().await?;
+                        }
+                        ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                    }
+                    .await
+                    {
+                        if let Some(field_id) = __pilota_decoding_field_id {
+                            err.prepend_msg(&format!(
+                                "decode struct `NestedItem` field(#{}) failed, caused by: ",
+                                field_id
+                            ));
+                        }
+                        return ::std::result::Result::Err(err);
+                    };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field value is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        value: var_1,
+                        label: var_2,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl NestedItem {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (_, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.value = ::std::default::Default::default();
+                }
+                let (_, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.label = None;
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of
+        /// [`NestedItem`] field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct NestedItemMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl NestedItemMaskBuilder {
+            /// Selects `value` as a whole.
+            pub fn value(mut self) -> Self {
+                self.0.field(1, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects `label` as a whole.
+            pub fn label(mut self) -> Self {
+                self.0.field(2, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for NestedItemMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for NestedItem {
+            type Builder = NestedItemMaskBuilder;
+        }
+
+        impl NestedItem {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> NestedItemMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl NestedItem {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
@@ -1264,6 +1522,785 @@ pub mod complex_enum_key_map {
                 }
             }
         }
+        impl ComplexEnumKeyMapTest {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = None;
+                let mut var_3 = None;
+                let mut var_4 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Priority =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, __protocol.read_i32()?);
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::I32)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Priority =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(
+                                                    key,
+                                                    match item_fm {
+                                                        Some(fm) => {
+                                                            NestedItem::decode_with_field_mask(
+                                                                __protocol, fm,
+                                                            )?
+                                                        }
+                                                        None => ::pilota::thrift::Message::decode(
+                                                            __protocol,
+                                                        )?,
+                                                    },
+                                                );
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Struct)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(3) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(3);
+                                if exist {
+                                    var_3 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Priority =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, {
+                                                    let map_ident = __protocol.read_map_begin()?;
+                                                    let mut val = ::pilota::AHashMap::with_capacity(
+                                                        map_ident.size,
+                                                    );
+                                                    for _ in 0..map_ident.size {
+                                                        let key: ::pilota::FastStr =
+                                                            __protocol.read_faststr()?;
+                                                        let (item_fm, exist) = match item_fm {
+                                                            Some(map_fm) => {
+                                                                map_fm.str(key.as_str())
+                                                            }
+                                                            None => (None, true),
+                                                        };
+                                                        if exist {
+                                                            val.insert(key, __protocol.read_i32()?);
+                                                        } else {
+                                                            __protocol.skip(
+                                                                ::pilota::thrift::TType::I32,
+                                                            )?;
+                                                        }
+                                                    }
+                                                    __protocol.read_map_end()?;
+                                                    val
+                                                });
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Map)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(4) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(4);
+                                if exist {
+                                    var_4 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Priority =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, {
+                                                    let list_ident =
+                                                        __protocol.read_list_begin()?;
+                                                    let mut val = ::std::vec::Vec::with_capacity(
+                                                        list_ident.size,
+                                                    );
+                                                    for idx in 0..list_ident.size {
+                                                        let (item_fm, exist) = match item_fm {
+                                                            Some(list_fm) => {
+                                                                list_fm.int(idx as i32)
+                                                            }
+                                                            None => (None, true),
+                                                        };
+                                                        if exist {
+                                                            val.push(match item_fm {
+                            Some(fm) => NestedItem::decode_with_field_mask(__protocol, fm)?,
+                            None => ::pilota::thrift::Message::decode(__protocol)?,
+                        });
+                                                        } else {
+                                                            __protocol.skip(
+                                                                ::pilota::thrift::TType::Struct,
+                                                            )?;
+                                                        }
+                                                    }
+                                                    __protocol.read_list_end()?;
+                                                    val
+                                                });
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::List)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `ComplexEnumKeyMapTest` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field priority_counts is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_2 = match var_2 {
+                    Some(var_2) => var_2,
+                    None if !__pilota_field_mask.field(2).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field priority_items is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_3 = match var_3 {
+                    Some(var_3) => var_3,
+                    None if !__pilota_field_mask.field(3).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field nested_maps is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    priority_counts: var_1,
+                    priority_items: var_2,
+                    nested_maps: var_3,
+                    priority_item_lists: var_4,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = None;
+                    let mut var_3 = None;
+                    let mut var_4 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                    loop {
+
+
+                let field_ident = __protocol.read_field_begin().await?;
+                if field_ident.field_type == ::pilota::thrift::TType::Stop {
+
+                    break;
+                } else {
+
+                }
+                __pilota_decoding_field_id = field_ident.id;
+                match field_ident.id {
+                    Some(1) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(1);
+                        if exist {
+                            var_1 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Priority = <Priority as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, __protocol.read_i32().await?);
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::I32).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },Some(2) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(2);
+                        if exist {
+                            var_2 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Priority = <Priority as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, match item_fm {
+                            Some(fm) => NestedItem::decode_with_field_mask_async(__protocol, fm).await?,
+                            None => <NestedItem as ::pilota::thrift::Message>::decode_async(__protocol).await?,
+                        });
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::Struct).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },Some(3) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(3);
+                        if exist {
+                            var_3 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Priority = <Priority as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, {
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: ::pilota::FastStr = __protocol.read_faststr().await?;
+                    let (item_fm, exist) = match item_fm {
+                        Some(map_fm) => map_fm.str(key.as_str()),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, __protocol.read_i32().await?);
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::I32).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::Map).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },Some(4) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(4);
+                        if exist {
+                            var_4 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Priority = <Priority as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, {
+                        let list_ident = __protocol.read_list_begin().await?;
+                        let mut val = ::std::vec::Vec::with_capacity(list_ident.size);
+                        for idx in 0..list_ident.size {
+                            let (item_fm, exist) = match item_fm {
+                                Some(list_fm) => list_fm.int(idx as i32),
+                                None => (None, true),
+                            };
+                            if exist {
+                                val.push(match item_fm {
+                            Some(fm) => NestedItem::decode_with_field_mask_async(__protocol, fm).await?,
+                            None => <NestedItem as ::pilota::thrift::Message>::decode_async(__protocol).await?,
+                        });
+                            } else {
+                                __protocol.skip(::pilota::thrift::TType::Struct).await?;
+                            }
+                        };
+                        __protocol.read_list_end().await?;
+                        val
+                    });
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::List).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },
+                    _ => {
+                        __protocol.skip(field_ident.field_type).await?;
+
+                    },
+                }
+
+                __protocol.read_field_end().await?;
+
+
+            };
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                }.await {
+                if let Some(field_id) = __pilota_decoding_field_id {
+                    err.prepend_msg(&format!("decode struct `ComplexEnumKeyMapTest` field(#{}) failed, caused by: ", field_id));
+                }
+                return ::std::result::Result::Err(err);
+            };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field priority_counts is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_2 = match var_2 {
+                        Some(var_2) => var_2,
+                        None if !__pilota_field_mask.field(2).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field priority_items is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_3 = match var_3 {
+                        Some(var_3) => var_3,
+                        None if !__pilota_field_mask.field(3).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field nested_maps is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        priority_counts: var_1,
+                        priority_items: var_2,
+                        nested_maps: var_3,
+                        priority_item_lists: var_4,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl ComplexEnumKeyMapTest {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (field_fm, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.priority_counts = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.priority_counts).retain(|key, val| {
+                            let (_, exist) = map_fm.int(key.inner() as i32);
+
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.priority_items = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.priority_items).retain(|key, val| {
+                            let (item_fm, exist) = map_fm.int(key.inner() as i32);
+                            if exist {
+                                if let Some(fm) = item_fm {
+                                    val.prune(fm);
+                                }
+                            }
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(3);
+                if !exist {
+                    self.nested_maps = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.nested_maps).retain(|key, val| {
+                            let (item_fm, exist) = map_fm.int(key.inner() as i32);
+                            if exist {
+                                if let Some(map_fm) = item_fm {
+                                    val.retain(|key, val| {
+                                        let (_, exist) = map_fm.str(key);
+
+                                        exist
+                                    });
+                                }
+                            }
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(4);
+                if !exist {
+                    self.priority_item_lists = None;
+                } else if let Some(value) = &mut self.priority_item_lists {
+                    if let Some(map_fm) = field_fm {
+                        value.retain(|key, val| {
+                            let (item_fm, exist) = map_fm.int(key.inner() as i32);
+                            if exist {
+                                if let Some(list_fm) = item_fm {
+                                    let mut idx = 0;
+                                    val.retain_mut(|item| {
+                                        let (item_fm, exist) = list_fm.int(idx as i32);
+                                        idx += 1;
+                                        if exist {
+                                            if let Some(fm) = item_fm {
+                                                item.prune(fm);
+                                            }
+                                        }
+                                        exist
+                                    });
+                                }
+                            }
+                            exist
+                        });
+                    }
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of
+        /// [`ComplexEnumKeyMapTest`] field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct ComplexEnumKeyMapTestMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl ComplexEnumKeyMapTestMaskBuilder {
+            /// Selects `priority_counts` as a whole.
+            pub fn priority_counts(mut self) -> Self {
+                self.0.field(
+                    1,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `priority_counts` given by `f`.
+            pub fn priority_counts_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::ScalarMask,
+                >,
+            ) -> Self {
+                self.0.field(
+                    1,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `priority_items` as a whole.
+            pub fn priority_items(mut self) -> Self {
+                self.0.field(
+                    2,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `priority_items` given by `f`.
+            pub fn priority_items_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                >,
+            ) -> Self {
+                self.0.field(
+                    2,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `nested_maps` as a whole.
+            pub fn nested_maps(mut self) -> Self {
+                self.0.field(
+                    3,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::StrMapMask<
+                            ::pilota_thrift_fieldmask::typed::ScalarMask,
+                        >,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `nested_maps` given by `f`.
+            pub fn nested_maps_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::StrMapMask<
+                            ::pilota_thrift_fieldmask::typed::ScalarMask,
+                        >,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::StrMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    >,
+                >,
+            ) -> Self {
+                self.0.field(
+                    3,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `priority_item_lists` as a whole.
+            pub fn priority_item_lists(mut self) -> Self {
+                self.0.field(
+                    4,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ListMask<
+                            <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                        >,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `priority_item_lists` given by `f`.
+            pub fn priority_item_lists_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ListMask<
+                            <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                        >,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::ListMask<
+                        <NestedItem as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    >,
+                >,
+            ) -> Self {
+                self.0.field(
+                    4,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for ComplexEnumKeyMapTestMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for ComplexEnumKeyMapTest {
+            type Builder = ComplexEnumKeyMapTestMaskBuilder;
+        }
+
+        impl ComplexEnumKeyMapTest {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> ComplexEnumKeyMapTestMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl ComplexEnumKeyMapTest {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
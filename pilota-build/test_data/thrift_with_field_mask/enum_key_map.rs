@@ -338,6 +338,290 @@ pub mod enum_key_map {
                 }
             }
         }
+        impl Item {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::I64 => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some(__protocol.read_i64()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2)
+                                if field_ident.field_type == ::pilota::thrift::TType::Binary =>
+                            {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some(__protocol.read_faststr()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `Item` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field id is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_2 = match var_2 {
+                    Some(var_2) => var_2,
+                    None if !__pilota_field_mask.field(2).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field name is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    id: var_1,
+                    name: var_2,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                        loop {
+                            let field_ident = __protocol.read_field_begin().await?;
+                            if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                                break;
+                            } else {
+                            }
+                            __pilota_decoding_field_id = field_ident.id;
+                            match field_ident.id {
+                                Some(1)
+                                    if field_ident.field_type == ::pilota::thrift::TType::I64 =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(1);
+                                    if exist {
+                                        var_1 = Some(__protocol.read_i64().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(2)
+                                    if field_ident.field_type
+                                        == ::pilota::thrift::TType::Binary =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(2);
+                                    if exist {
+                                        var_2 = Some(__protocol.read_faststr().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                _ => {
+                                    __protocol.skip(field_ident.field_type).await?;
+                                }
+                            }
+
+                            __protocol.read_field_end().await?;
+                        }
+                        ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                    }
+                    .await
+                    {
+                        if let Some(field_id) = __pilota_decoding_field_id {
+                            err.prepend_msg(&format!(
+                                "decode struct `Item` field(#{}) failed, caused by: ",
+                                field_id
+                            ));
+                        }
+                        return ::std::result::Result::Err(err);
+                    };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field id is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_2 = match var_2 {
+                        Some(var_2) => var_2,
+                        None if !__pilota_field_mask.field(2).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field name is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        id: var_1,
+                        name: var_2,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl Item {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (_, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.id = ::std::default::Default::default();
+                }
+                let (_, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.name = ::std::default::Default::default();
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of [`Item`]
+        /// field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct ItemMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl ItemMaskBuilder {
+            /// Selects `id` as a whole.
+            pub fn id(mut self) -> Self {
+                self.0.field(1, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects `name` as a whole.
+            pub fn name(mut self) -> Self {
+                self.0.field(2, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for ItemMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for Item {
+            type Builder = ItemMaskBuilder;
+        }
+
+        impl Item {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> ItemMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl Item {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
@@ -1013,6 +1297,604 @@ pub mod enum_key_map {
                 }
             }
         }
+        impl EnumKeyMapTest {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = None;
+                let mut var_3 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Status =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, __protocol.read_faststr()?);
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Binary)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Status =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(
+                                                    key,
+                                                    match item_fm {
+                                                        Some(fm) => Item::decode_with_field_mask(
+                                                            __protocol, fm,
+                                                        )?,
+                                                        None => ::pilota::thrift::Message::decode(
+                                                            __protocol,
+                                                        )?,
+                                                    },
+                                                );
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Struct)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(3) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(3);
+                                if exist {
+                                    var_3 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: Status =
+                                                ::pilota::thrift::Message::decode(__protocol)?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key.inner() as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, {
+                                                    let list_ident =
+                                                        __protocol.read_list_begin()?;
+                                                    let mut val = ::std::vec::Vec::with_capacity(
+                                                        list_ident.size,
+                                                    );
+                                                    for idx in 0..list_ident.size {
+                                                        let (item_fm, exist) = match item_fm {
+                                                            Some(list_fm) => {
+                                                                list_fm.int(idx as i32)
+                                                            }
+                                                            None => (None, true),
+                                                        };
+                                                        if exist {
+                                                            val.push(match item_fm {
+                            Some(fm) => Item::decode_with_field_mask(__protocol, fm)?,
+                            None => ::pilota::thrift::Message::decode(__protocol)?,
+                        });
+                                                        } else {
+                                                            __protocol.skip(
+                                                                ::pilota::thrift::TType::Struct,
+                                                            )?;
+                                                        }
+                                                    }
+                                                    __protocol.read_list_end()?;
+                                                    val
+                                                });
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::List)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `EnumKeyMapTest` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field status_map is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_2 = match var_2 {
+                    Some(var_2) => var_2,
+                    None if !__pilota_field_mask.field(2).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field status_item_map is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    status_map: var_1,
+                    status_item_map: var_2,
+                    status_list_map: var_3,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = None;
+                    let mut var_3 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                    loop {
+
+
+                let field_ident = __protocol.read_field_begin().await?;
+                if field_ident.field_type == ::pilota::thrift::TType::Stop {
+
+                    break;
+                } else {
+
+                }
+                __pilota_decoding_field_id = field_ident.id;
+                match field_ident.id {
+                    Some(1) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(1);
+                        if exist {
+                            var_1 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Status = <Status as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, __protocol.read_faststr().await?);
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::Binary).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },Some(2) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(2);
+                        if exist {
+                            var_2 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Status = <Status as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, match item_fm {
+                            Some(fm) => Item::decode_with_field_mask_async(__protocol, fm).await?,
+                            None => <Item as ::pilota::thrift::Message>::decode_async(__protocol).await?,
+                        });
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::Struct).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },Some(3) if field_ident.field_type == ::pilota::thrift::TType::Map  => {
+                        let (field_fm, exist) = __pilota_field_mask.field(3);
+                        if exist {
+                            var_3 = Some({
+                let map_ident = __protocol.read_map_begin().await?;
+                let mut val = ::pilota::AHashMap::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let key: Status = <Status as ::pilota::thrift::Message>::decode_async(__protocol).await?;
+                    let (item_fm, exist) = match field_fm {
+                        Some(map_fm) => map_fm.int(key.inner() as i32),
+                        None => (None, true),
+                    };
+                    if exist {
+                        val.insert(key, {
+                        let list_ident = __protocol.read_list_begin().await?;
+                        let mut val = ::std::vec::Vec::with_capacity(list_ident.size);
+                        for idx in 0..list_ident.size {
+                            let (item_fm, exist) = match item_fm {
+                                Some(list_fm) => list_fm.int(idx as i32),
+                                None => (None, true),
+                            };
+                            if exist {
+                                val.push(match item_fm {
+                            Some(fm) => Item::decode_with_field_mask_async(__protocol, fm).await?,
+                            None => <Item as ::pilota::thrift::Message>::decode_async(__protocol).await?,
+                        });
+                            } else {
+                                __protocol.skip(::pilota::thrift::TType::Struct).await?;
+                            }
+                        };
+                        __protocol.read_list_end().await?;
+                        val
+                    });
+                    } else {
+                        __protocol.skip(::pilota::thrift::TType::List).await?;
+                    }
+                }
+                __protocol.read_map_end().await?;
+                val
+            });
+
+                        } else {
+                            __protocol.skip(field_ident.field_type).await?;
+                        }
+                    },
+                    _ => {
+                        __protocol.skip(field_ident.field_type).await?;
+
+                    },
+                }
+
+                __protocol.read_field_end().await?;
+
+
+            };
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                }.await {
+                if let Some(field_id) = __pilota_decoding_field_id {
+                    err.prepend_msg(&format!("decode struct `EnumKeyMapTest` field(#{}) failed, caused by: ", field_id));
+                }
+                return ::std::result::Result::Err(err);
+            };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field status_map is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_2 = match var_2 {
+                        Some(var_2) => var_2,
+                        None if !__pilota_field_mask.field(2).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field status_item_map is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        status_map: var_1,
+                        status_item_map: var_2,
+                        status_list_map: var_3,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl EnumKeyMapTest {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (field_fm, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.status_map = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.status_map).retain(|key, val| {
+                            let (_, exist) = map_fm.int(key.inner() as i32);
+
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.status_item_map = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.status_item_map).retain(|key, val| {
+                            let (item_fm, exist) = map_fm.int(key.inner() as i32);
+                            if exist {
+                                if let Some(fm) = item_fm {
+                                    val.prune(fm);
+                                }
+                            }
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(3);
+                if !exist {
+                    self.status_list_map = None;
+                } else if let Some(value) = &mut self.status_list_map {
+                    if let Some(map_fm) = field_fm {
+                        value.retain(|key, val| {
+                            let (item_fm, exist) = map_fm.int(key.inner() as i32);
+                            if exist {
+                                if let Some(list_fm) = item_fm {
+                                    let mut idx = 0;
+                                    val.retain_mut(|item| {
+                                        let (item_fm, exist) = list_fm.int(idx as i32);
+                                        idx += 1;
+                                        if exist {
+                                            if let Some(fm) = item_fm {
+                                                item.prune(fm);
+                                            }
+                                        }
+                                        exist
+                                    });
+                                }
+                            }
+                            exist
+                        });
+                    }
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of
+        /// [`EnumKeyMapTest`] field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct EnumKeyMapTestMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl EnumKeyMapTestMaskBuilder {
+            /// Selects `status_map` as a whole.
+            pub fn status_map(mut self) -> Self {
+                self.0.field(
+                    1,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `status_map` given by `f`.
+            pub fn status_map_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::ScalarMask,
+                >,
+            ) -> Self {
+                self.0.field(
+                    1,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `status_item_map` as a whole.
+            pub fn status_item_map(mut self) -> Self {
+                self.0.field(
+                    2,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `status_item_map` given by `f`.
+            pub fn status_item_map_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                >,
+            ) -> Self {
+                self.0.field(
+                    2,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `status_list_map` as a whole.
+            pub fn status_list_map(mut self) -> Self {
+                self.0.field(
+                    3,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ListMask<
+                            <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                        >,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `status_list_map` given by `f`.
+            pub fn status_list_map_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ListMask<
+                            <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                        >,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::ListMask<
+                        <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                    >,
+                >,
+            ) -> Self {
+                self.0.field(
+                    3,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for EnumKeyMapTestMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for EnumKeyMapTest {
+            type Builder = EnumKeyMapTestMaskBuilder;
+        }
+
+        impl EnumKeyMapTest {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> EnumKeyMapTestMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl EnumKeyMapTest {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
@@ -237,6 +237,290 @@ pub mod struct_init_with_field_mask {
                 }
             }
         }
+        impl Item {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::I64 => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some(__protocol.read_i64()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2)
+                                if field_ident.field_type == ::pilota::thrift::TType::Binary =>
+                            {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some(__protocol.read_faststr()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `Item` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field id is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_2 = match var_2 {
+                    Some(var_2) => var_2,
+                    None if !__pilota_field_mask.field(2).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field title is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    id: var_1,
+                    title: var_2,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                        loop {
+                            let field_ident = __protocol.read_field_begin().await?;
+                            if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                                break;
+                            } else {
+                            }
+                            __pilota_decoding_field_id = field_ident.id;
+                            match field_ident.id {
+                                Some(1)
+                                    if field_ident.field_type == ::pilota::thrift::TType::I64 =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(1);
+                                    if exist {
+                                        var_1 = Some(__protocol.read_i64().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(2)
+                                    if field_ident.field_type
+                                        == ::pilota::thrift::TType::Binary =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(2);
+                                    if exist {
+                                        var_2 = Some(__protocol.read_faststr().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                _ => {
+                                    __protocol.skip(field_ident.field_type).await?;
+                                }
+                            }
+
+                            __protocol.read_field_end().await?;
+                        }
+                        ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                    }
+                    .await
+                    {
+                        if let Some(field_id) = __pilota_decoding_field_id {
+                            err.prepend_msg(&format!(
+                                "decode struct `Item` field(#{}) failed, caused by: ",
+                                field_id
+                            ));
+                        }
+                        return ::std::result::Result::Err(err);
+                    };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field id is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_2 = match var_2 {
+                        Some(var_2) => var_2,
+                        None if !__pilota_field_mask.field(2).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field title is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        id: var_1,
+                        title: var_2,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl Item {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (_, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.id = ::std::default::Default::default();
+                }
+                let (_, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.title = ::std::default::Default::default();
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of [`Item`]
+        /// field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct ItemMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl ItemMaskBuilder {
+            /// Selects `id` as a whole.
+            pub fn id(mut self) -> Self {
+                self.0.field(1, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects `title` as a whole.
+            pub fn title(mut self) -> Self {
+                self.0.field(2, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for ItemMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for Item {
+            type Builder = ItemMaskBuilder;
+        }
+
+        impl Item {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> ItemMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl Item {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
@@ -861,6 +1145,638 @@ pub mod struct_init_with_field_mask {
                 }
             }
         }
+        impl GetItemRequest {
+            /// Decodes the struct, skipping the fields excluded by the field
+            /// mask.
+            ///
+            /// Required fields that are excluded are set to their default
+            /// value. With `keep_unknown_fields`, excluded fields
+            /// are kept in `_unknown_fields`.
+            pub fn decode_with_field_mask<T: ::pilota::thrift::TInputProtocol>(
+                __protocol: &mut T,
+                __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::result::Result<Self, ::pilota::thrift::ThriftException> {
+                #[allow(unused_imports)]
+                use ::pilota::{Buf, thrift::TLengthProtocolExt};
+
+                let mut var_1 = None;
+                let mut var_2 = Some(DEFAULT_ITEM);
+                let mut var_3 = Some(Item {
+                    id: 1i64,
+                    title: ::pilota::FastStr::from_static_str("a"),
+                    _field_mask: ::std::option::Option::None,
+                });
+                let mut var_4 = None;
+                let mut var_5 = None;
+
+                let mut __pilota_decoding_field_id = None;
+
+                __protocol.read_struct_begin()?;
+                if let ::std::result::Result::Err(mut err) = (|| {
+                    loop {
+                        let field_ident = __protocol.read_field_begin()?;
+                        if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                            __protocol.field_stop_len();
+                            break;
+                        } else {
+                            __protocol.field_begin_len(field_ident.field_type, field_ident.id);
+                        }
+                        __pilota_decoding_field_id = field_ident.id;
+                        match field_ident.id {
+                            Some(1) if field_ident.field_type == ::pilota::thrift::TType::I64 => {
+                                let (field_fm, exist) = __pilota_field_mask.field(1);
+                                if exist {
+                                    var_1 = Some(__protocol.read_i64()?);
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(2)
+                                if field_ident.field_type == ::pilota::thrift::TType::Struct =>
+                            {
+                                let (field_fm, exist) = __pilota_field_mask.field(2);
+                                if exist {
+                                    var_2 = Some(match field_fm {
+                                        Some(fm) => Item::decode_with_field_mask(__protocol, fm)?,
+                                        None => ::pilota::thrift::Message::decode(__protocol)?,
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(3)
+                                if field_ident.field_type == ::pilota::thrift::TType::Struct =>
+                            {
+                                let (field_fm, exist) = __pilota_field_mask.field(3);
+                                if exist {
+                                    var_3 = Some(match field_fm {
+                                        Some(fm) => Item::decode_with_field_mask(__protocol, fm)?,
+                                        None => ::pilota::thrift::Message::decode(__protocol)?,
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(4) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(4);
+                                if exist {
+                                    var_4 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: ::pilota::FastStr =
+                                                __protocol.read_faststr()?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.str(key.as_str()),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, __protocol.read_faststr()?);
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Binary)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            Some(5) if field_ident.field_type == ::pilota::thrift::TType::Map => {
+                                let (field_fm, exist) = __pilota_field_mask.field(5);
+                                if exist {
+                                    var_5 = Some({
+                                        let map_ident = __protocol.read_map_begin()?;
+                                        let mut val =
+                                            ::pilota::AHashMap::with_capacity(map_ident.size);
+                                        for _ in 0..map_ident.size {
+                                            let key: i64 = __protocol.read_i64()?;
+                                            let (item_fm, exist) = match field_fm {
+                                                Some(map_fm) => map_fm.int(key as i32),
+                                                None => (None, true),
+                                            };
+                                            if exist {
+                                                val.insert(key, __protocol.read_faststr()?);
+                                            } else {
+                                                __protocol.skip(::pilota::thrift::TType::Binary)?;
+                                            }
+                                        }
+                                        __protocol.read_map_end()?;
+                                        val
+                                    });
+                                } else {
+                                    __protocol.skip(field_ident.field_type)?;
+                                }
+                            }
+                            _ => {
+                                __protocol.skip(field_ident.field_type)?;
+                            }
+                        }
+
+                        __protocol.read_field_end()?;
+                        __protocol.field_end_len();
+                    }
+                    ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                })() {
+                    if let Some(field_id) = __pilota_decoding_field_id {
+                        err.prepend_msg(&format!(
+                            "decode struct `GetItemRequest` field(#{}) failed, caused by: ",
+                            field_id
+                        ));
+                    }
+                    return ::std::result::Result::Err(err);
+                };
+                __protocol.read_struct_end()?;
+
+                let var_1 = match var_1 {
+                    Some(var_1) => var_1,
+                    None if !__pilota_field_mask.field(1).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field id is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_4 = match var_4 {
+                    Some(var_4) => var_4,
+                    None if !__pilota_field_mask.field(4).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field test_map is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+                let var_5 = match var_5 {
+                    Some(var_5) => var_5,
+                    None if !__pilota_field_mask.field(5).1 => ::std::default::Default::default(),
+                    None => {
+                        return ::std::result::Result::Err(
+                            ::pilota::thrift::new_protocol_exception(
+                                ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                "field test_map2 is required".to_string(),
+                            ),
+                        );
+                    }
+                };
+
+                let data = Self {
+                    id: var_1,
+                    item_opt: var_2,
+                    item_opt2: var_3,
+                    test_map: var_4,
+                    test_map2: var_5,
+                    _field_mask: ::std::option::Option::None,
+                };
+                ::std::result::Result::Ok(data)
+            }
+
+            /// The async version of [`Self::decode_with_field_mask`]. Like
+            /// `decode_async`, it never keeps unknown fields.
+            pub fn decode_with_field_mask_async<'a, T: ::pilota::thrift::TAsyncInputProtocol>(
+                __protocol: &'a mut T,
+                __pilota_field_mask: &'a ::pilota_thrift_fieldmask::FieldMask,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<Self, ::pilota::thrift::ThriftException>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                ::std::boxed::Box::pin(async move {
+                    let mut var_1 = None;
+                    let mut var_2 = Some(DEFAULT_ITEM);
+                    let mut var_3 = Some(Item {
+                        id: 1i64,
+                        title: ::pilota::FastStr::from_static_str("a"),
+                        _field_mask: ::std::option::Option::None,
+                    });
+                    let mut var_4 = None;
+                    let mut var_5 = None;
+
+                    let mut __pilota_decoding_field_id = None;
+
+                    __protocol.read_struct_begin().await?;
+                    if let ::std::result::Result::Err(mut err) = async {
+                        loop {
+                            let field_ident = __protocol.read_field_begin().await?;
+                            if field_ident.field_type == ::pilota::thrift::TType::Stop {
+                                break;
+                            } else {
+                            }
+                            __pilota_decoding_field_id = field_ident.id;
+                            match field_ident.id {
+                                Some(1)
+                                    if field_ident.field_type == ::pilota::thrift::TType::I64 =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(1);
+                                    if exist {
+                                        var_1 = Some(__protocol.read_i64().await?);
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(2)
+                                    if field_ident.field_type
+                                        == ::pilota::thrift::TType::Struct =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(2);
+                                    if exist {
+                                        var_2 = Some(match field_fm {
+                                            Some(fm) => {
+                                                Item::decode_with_field_mask_async(__protocol, fm)
+                                                    .await?
+                                            }
+                                            None => {
+                                                <Item as ::pilota::thrift::Message>::decode_async(
+                                                    __protocol,
+                                                )
+                                                .await?
+                                            }
+                                        });
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(3)
+                                    if field_ident.field_type
+                                        == ::pilota::thrift::TType::Struct =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(3);
+                                    if exist {
+                                        var_3 = Some(match field_fm {
+                                            Some(fm) => {
+                                                Item::decode_with_field_mask_async(__protocol, fm)
+                                                    .await?
+                                            }
+                                            None => {
+                                                <Item as ::pilota::thrift::Message>::decode_async(
+                                                    __protocol,
+                                                )
+                                                .await?
+                                            }
+                                        });
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(4)
+                                    if field_ident.field_type == ::pilota::thrift::TType::Map =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(4);
+                                    if exist {
+                                        var_4 = Some({
+                                            let map_ident = __protocol.read_map_begin().await?;
+                                            let mut val =
+                                                ::pilota::AHashMap::with_capacity(map_ident.size);
+                                            for _ in 0..map_ident.size {
+                                                let key: ::pilota::FastStr =
+                                                    __protocol.read_faststr().await?;
+                                                let (item_fm, exist) = match field_fm {
+                                                    Some(map_fm) => map_fm.str(key.as_str()),
+                                                    None => (None, true),
+                                                };
+                                                if exist {
+                                                    val.insert(
+                                                        key,
+                                                        __protocol.read_faststr().await?,
+                                                    );
+                                                } else {
+                                                    __protocol
+                                                        .skip(::pilota::thrift::TType::Binary)
+                                                        .await?;
+                                                }
+                                            }
+                                            __protocol.read_map_end().await?;
+                                            val
+                                        });
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                Some(5)
+                                    if field_ident.field_type == ::pilota::thrift::TType::Map =>
+                                {
+                                    let (field_fm, exist) = __pilota_field_mask.field(5);
+                                    if exist {
+                                        var_5 = Some({
+                                            let map_ident = __protocol.read_map_begin().await?;
+                                            let mut val =
+                                                ::pilota::AHashMap::with_capacity(map_ident.size);
+                                            for _ in 0..map_ident.size {
+                                                let key: i64 = __protocol.read_i64().await?;
+                                                let (item_fm, exist) = match field_fm {
+                                                    Some(map_fm) => map_fm.int(key as i32),
+                                                    None => (None, true),
+                                                };
+                                                if exist {
+                                                    val.insert(
+                                                        key,
+                                                        __protocol.read_faststr().await?,
+                                                    );
+                                                } else {
+                                                    __protocol
+                                                        .skip(::pilota::thrift::TType::Binary)
+                                                        .await?;
+                                                }
+                                            }
+                                            __protocol.read_map_end().await?;
+                                            val
+                                        });
+                                    } else {
+                                        __protocol.skip(field_ident.field_type).await?;
+                                    }
+                                }
+                                _ => {
+                                    __protocol.skip(field_ident.field_type).await?;
+                                }
+                            }
+
+                            __protocol.read_field_end().await?;
+                        }
+                        ::std::result::Result::Ok::<_, ::pilota::thrift::ThriftException>(())
+                    }
+                    .await
+                    {
+                        if let Some(field_id) = __pilota_decoding_field_id {
+                            err.prepend_msg(&format!(
+                                "decode struct `GetItemRequest` field(#{}) failed, caused by: ",
+                                field_id
+                            ));
+                        }
+                        return ::std::result::Result::Err(err);
+                    };
+                    __protocol.read_struct_end().await?;
+
+                    let var_1 = match var_1 {
+                        Some(var_1) => var_1,
+                        None if !__pilota_field_mask.field(1).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field id is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_4 = match var_4 {
+                        Some(var_4) => var_4,
+                        None if !__pilota_field_mask.field(4).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field test_map is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+                    let var_5 = match var_5 {
+                        Some(var_5) => var_5,
+                        None if !__pilota_field_mask.field(5).1 => {
+                            ::std::default::Default::default()
+                        }
+                        None => {
+                            return ::std::result::Result::Err(
+                                ::pilota::thrift::new_protocol_exception(
+                                    ::pilota::thrift::ProtocolExceptionKind::InvalidData,
+                                    "field test_map2 is required".to_string(),
+                                ),
+                            );
+                        }
+                    };
+
+                    let data = Self {
+                        id: var_1,
+                        item_opt: var_2,
+                        item_opt2: var_3,
+                        test_map: var_4,
+                        test_map2: var_5,
+                        _field_mask: ::std::option::Option::None,
+                    };
+                    ::std::result::Result::Ok(data)
+                })
+            }
+        }
+        impl GetItemRequest {
+            /// Removes the fields, list elements and map entries excluded by
+            /// the field mask in place.
+            ///
+            /// Required fields that are excluded are reset to their default
+            /// value. List elements are matched by their index
+            /// before pruning.
+            pub fn prune(&mut self, __pilota_field_mask: &::pilota_thrift_fieldmask::FieldMask) {
+                let (_, exist) = __pilota_field_mask.field(1);
+                if !exist {
+                    self.id = ::std::default::Default::default();
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(2);
+                if !exist {
+                    self.item_opt = None;
+                } else if let Some(value) = &mut self.item_opt {
+                    if let Some(fm) = field_fm {
+                        value.prune(fm);
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(3);
+                if !exist {
+                    self.item_opt2 = None;
+                } else if let Some(value) = &mut self.item_opt2 {
+                    if let Some(fm) = field_fm {
+                        value.prune(fm);
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(4);
+                if !exist {
+                    self.test_map = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.test_map).retain(|key, val| {
+                            let (_, exist) = map_fm.str(key);
+
+                            exist
+                        });
+                    }
+                }
+                let (field_fm, exist) = __pilota_field_mask.field(5);
+                if !exist {
+                    self.test_map2 = ::std::default::Default::default();
+                } else {
+                    if let Some(map_fm) = field_fm {
+                        (&mut self.test_map2).retain(|key, val| {
+                            let (_, exist) = map_fm.int(*key as i32);
+
+                            exist
+                        });
+                    }
+                }
+            }
+        }
+        /// Builds the [`::pilota_thrift_fieldmask::FieldMask`] of
+        /// [`GetItemRequest`] field by field.
+        #[derive(Debug, Clone, Default)]
+        pub struct GetItemRequestMaskBuilder(::pilota_thrift_fieldmask::typed::StructMask);
+
+        impl GetItemRequestMaskBuilder {
+            /// Selects `id` as a whole.
+            pub fn id(mut self) -> Self {
+                self.0.field(1, <::pilota_thrift_fieldmask::typed::ScalarMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects `item_opt` as a whole.
+            pub fn item_opt(mut self) -> Self {
+                self.0.field(2, <<Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects the part of `item_opt` given by `f`.
+            pub fn item_opt_with(
+                mut self,
+                f: impl FnOnce(
+                    <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                )
+                    -> <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+            ) -> Self {
+                self.0.field(
+                    2,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `item_opt2` as a whole.
+            pub fn item_opt2(mut self) -> Self {
+                self.0.field(3, <<Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole());
+                self
+            }
+            /// Selects the part of `item_opt2` given by `f`.
+            pub fn item_opt2_with(
+                mut self,
+                f: impl FnOnce(
+                    <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+                )
+                    -> <Item as ::pilota_thrift_fieldmask::typed::Masked>::Builder,
+            ) -> Self {
+                self.0.field(
+                    3,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `test_map` as a whole.
+            pub fn test_map(mut self) -> Self {
+                self.0.field(
+                    4,
+                    <::pilota_thrift_fieldmask::typed::StrMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `test_map` given by `f`.
+            pub fn test_map_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::StrMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::StrMapMask<
+                    ::pilota_thrift_fieldmask::typed::ScalarMask,
+                >,
+            ) -> Self {
+                self.0.field(
+                    4,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+            /// Selects `test_map2` as a whole.
+            pub fn test_map2(mut self) -> Self {
+                self.0.field(
+                    5,
+                    <::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    > as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole(
+                    ),
+                );
+                self
+            }
+            /// Selects the part of `test_map2` given by `f`.
+            pub fn test_map2_with(
+                mut self,
+                f: impl FnOnce(
+                    ::pilota_thrift_fieldmask::typed::IntMapMask<
+                        ::pilota_thrift_fieldmask::typed::ScalarMask,
+                    >,
+                ) -> ::pilota_thrift_fieldmask::typed::IntMapMask<
+                    ::pilota_thrift_fieldmask::typed::ScalarMask,
+                >,
+            ) -> Self {
+                self.0.field(
+                    5,
+                    ::pilota_thrift_fieldmask::typed::MaskBuilder::build(f(
+                        ::std::default::Default::default(),
+                    )),
+                );
+                self
+            }
+
+            /// Selects every field.
+            pub fn all(mut self) -> Self {
+                self.0.all();
+                self
+            }
+
+            /// Returns the white list mask built so far.
+            pub fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+
+            /// Returns the black list mask built so far, which excludes what
+            /// was selected.
+            pub fn build_black_list(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build_black_list(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::MaskBuilder for GetItemRequestMaskBuilder {
+            fn whole() -> ::pilota_thrift_fieldmask::FieldMask {
+                <::pilota_thrift_fieldmask::typed::StructMask as ::pilota_thrift_fieldmask::typed::MaskBuilder>::whole()
+            }
+
+            fn build(self) -> ::pilota_thrift_fieldmask::FieldMask {
+                ::pilota_thrift_fieldmask::typed::MaskBuilder::build(self.0)
+            }
+        }
+
+        impl ::pilota_thrift_fieldmask::typed::Masked for GetItemRequest {
+            type Builder = GetItemRequestMaskBuilder;
+        }
+
+        impl GetItemRequest {
+            /// Returns a builder of the field masks of this struct.
+            pub fn mask() -> GetItemRequestMaskBuilder {
+                ::std::default::Default::default()
+            }
+        }
         impl GetItemRequest {
             pub fn set_field_mask(&mut self, field_mask: ::pilota_thrift_fieldmask::FieldMask) {
                 self._field_mask = Some(field_mask.clone());
@@ -0,0 +1,42 @@
+use std::process::Command;
+
+#[test]
+fn test_cli() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let out = dir.path().join("normal.rs");
+    let status = Command::new(env!("CARGO_BIN_EXE_pilota"))
+        .args([
+            "--all",
+            "--out",
+            out.to_str().unwrap(),
+            "test_data/thrift/normal.thrift",
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(
+        std::fs::read_to_string(&out)
+            .unwrap()
+            .contains("pub struct A")
+    );
+
+    let ws = dir.path().join("ws");
+    let status = Command::new(env!("CARGO_BIN_EXE_pilota"))
+        .args([
+            "--workspace",
+            ws.to_str().unwrap(),
+            "test_data/thrift_workspace/input/article.thrift",
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(ws.join("article").join("Cargo.toml").exists());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_pilota"))
+        .arg("test_data/thrift/normal.thrift")
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+}
@@ -86,6 +86,10 @@ pub enum FieldMaskError {
     },
     #[error("field mask error: {message}")]
     GenericError { message: String },
+    #[error("invalid encoded field mask at byte {position}: {message}")]
+    InvalidEncoding { message: FastStr, position: usize },
+    #[error("encoded field mask of {len} bytes exceeds the limit of {max_len} bytes")]
+    EncodingTooLarge { len: usize, max_len: usize },
 }
 
 #[derive(Debug, Clone, Eq, serde::Serialize, serde::Deserialize, Default)]
@@ -556,8 +560,8 @@ impl FieldMaskData {
     serde::Deserialize,
 )]
 pub struct FieldMask {
-    is_black: bool, // black list mode flag
-    data: FieldMaskData,
+    pub(crate) is_black: bool, // black list mode flag
+    pub(crate) data: FieldMaskData,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1495,6 +1499,664 @@ impl FieldMask {
     }
 }
 
+// Methods for set operations
+//
+// Masks are first turned into the selection they describe, as observed through
+// `exist`/`field`/`int`/`str`, so that white-list and black-list masks (and
+// wildcards, which apply to every key without an explicit sub mask) can be
+// combined with each other. The result is then turned back into a mask.
+impl FieldMask {
+    /// Returns a mask selecting what either `self` or `other` selects.
+    pub fn union(&self, other: &FieldMask) -> Result<FieldMask, FieldMaskError> {
+        let sel = Selection::from_mask(self).union(Selection::from_mask(other))?;
+        Ok(sel.into_mask(self.is_black || other.is_black))
+    }
+
+    /// Returns a mask selecting what both `self` and `other` select.
+    pub fn intersect(&self, other: &FieldMask) -> Result<FieldMask, FieldMaskError> {
+        let sel = Selection::from_mask(self).intersect(Selection::from_mask(other))?;
+        Ok(sel.into_mask(self.is_black && other.is_black))
+    }
+
+    /// Returns a mask selecting what `self` selects but `other` does not.
+    pub fn subtract(&self, other: &FieldMask) -> Result<FieldMask, FieldMaskError> {
+        let sel = Selection::from_mask(self).subtract(Selection::from_mask(other))?;
+        Ok(sel.into_mask(self.is_black && !other.is_black))
+    }
+
+    /// Returns whether everything selected by `self` is also selected by
+    /// `other`.
+    pub fn is_subset_of(&self, other: &FieldMask) -> Result<bool, FieldMaskError> {
+        let sel = Selection::from_mask(self).subtract(Selection::from_mask(other))?;
+        Ok(matches!(sel, Selection::Nothing))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SelectionKind {
+    Struct,
+    List,
+    StrMap,
+    IntMap,
+}
+
+impl SelectionKind {
+    /// The [`FieldMaskData::type_name`] of masks of this kind.
+    fn type_name(self) -> &'static str {
+        match self {
+            SelectionKind::Struct => "Struct",
+            SelectionKind::List => "List",
+            SelectionKind::StrMap => "StrMap",
+            SelectionKind::IntMap => "IntMap",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SelectionKey {
+    Int(i32),
+    Str(FastStr),
+}
+
+/// The selection described by a mask, independent of its mode.
+#[derive(Debug, Clone)]
+enum Selection {
+    Nothing,
+    // the kind is kept when known so the mask built back has the same shape
+    Everything(Option<SelectionKind>),
+    Partial {
+        kind: SelectionKind,
+        // keys with an explicit selection
+        children: std::collections::BTreeMap<SelectionKey, Selection>,
+        // the selection of every other key
+        default: Box<Selection>,
+    },
+}
+
+impl Selection {
+    fn from_mask(fm: &FieldMask) -> Self {
+        let (kind, children, wildcard, is_all) = match &fm.data {
+            FieldMaskData::Invalid if fm.is_black => return Selection::Everything(None),
+            FieldMaskData::Invalid => return Selection::Nothing,
+            FieldMaskData::Scalar if fm.is_black => return Selection::Nothing,
+            FieldMaskData::Scalar => return Selection::Everything(None),
+            FieldMaskData::Struct { children, is_all } => (
+                SelectionKind::Struct,
+                children
+                    .iter()
+                    .map(|(k, v)| (SelectionKey::Int(*k), v.as_ref()))
+                    .collect::<Vec<_>>(),
+                None,
+                *is_all,
+            ),
+            FieldMaskData::List {
+                children,
+                wildcard,
+                is_all,
+            } => (
+                SelectionKind::List,
+                children
+                    .iter()
+                    .map(|(k, v)| (SelectionKey::Int(*k), v.as_ref()))
+                    .collect(),
+                wildcard.as_deref(),
+                *is_all,
+            ),
+            FieldMaskData::IntMap {
+                children,
+                wildcard,
+                is_all,
+            } => (
+                SelectionKind::IntMap,
+                children
+                    .iter()
+                    .map(|(k, v)| (SelectionKey::Int(*k), v.as_ref()))
+                    .collect(),
+                wildcard.as_deref(),
+                *is_all,
+            ),
+            FieldMaskData::StrMap {
+                children,
+                wildcard,
+                is_all,
+            } => (
+                SelectionKind::StrMap,
+                children
+                    .iter()
+                    .map(|(k, v)| (SelectionKey::Str(k.clone()), v.as_ref()))
+                    .collect(),
+                wildcard.as_deref(),
+                *is_all,
+            ),
+        };
+
+        let (children, default) = if fm.is_black {
+            if is_all {
+                return Selection::Nothing;
+            }
+            let child = |c: &FieldMask| {
+                if c.all() {
+                    Selection::Nothing
+                } else {
+                    Selection::from_mask(c)
+                }
+            };
+            (
+                children.into_iter().map(|(k, c)| (k, child(c))).collect(),
+                wildcard.map_or(Selection::Everything(None), child),
+            )
+        } else {
+            if is_all && !fm.data.has_children() {
+                return Selection::Everything(Some(kind));
+            }
+            let child = |c: &FieldMask| {
+                if is_all || c.exist() {
+                    Selection::from_mask(c)
+                } else {
+                    Selection::Nothing
+                }
+            };
+            let default = match wildcard {
+                Some(w) => child(w),
+                None if is_all => Selection::Everything(None),
+                None => Selection::Nothing,
+            };
+            (
+                children.into_iter().map(|(k, c)| (k, child(c))).collect(),
+                default,
+            )
+        };
+
+        Selection::Partial {
+            kind,
+            children,
+            default: Box::new(default),
+        }
+        .normalize()
+    }
+
+    fn is_same(&self, other: &Selection) -> bool {
+        match (self, other) {
+            (Selection::Nothing, Selection::Nothing) => true,
+            (Selection::Everything(_), Selection::Everything(_)) => true,
+            (
+                Selection::Partial {
+                    kind: k1,
+                    children: c1,
+                    default: d1,
+                },
+                Selection::Partial {
+                    kind: k2,
+                    children: c2,
+                    default: d2,
+                },
+            ) => {
+                k1 == k2
+                    && d1.is_same(d2)
+                    && c1.len() == c2.len()
+                    && c1
+                        .iter()
+                        .zip(c2.iter())
+                        .all(|((k1, v1), (k2, v2))| k1 == k2 && v1.is_same(v2))
+            }
+            _ => false,
+        }
+    }
+
+    // drops the children equal to the default and collapses a partial
+    // selection of everything
+    fn normalize(self) -> Self {
+        match self {
+            Selection::Partial {
+                kind,
+                children,
+                default,
+            } => {
+                let children: std::collections::BTreeMap<_, _> = children
+                    .into_iter()
+                    .filter(|(_, v)| !v.is_same(&default))
+                    .collect();
+                if children.is_empty() && matches!(*default, Selection::Everything(_)) {
+                    Selection::Everything(Some(kind))
+                } else {
+                    Selection::Partial {
+                        kind,
+                        children,
+                        default,
+                    }
+                }
+            }
+            sel => sel,
+        }
+    }
+
+    fn kind(&self) -> Option<SelectionKind> {
+        match self {
+            Selection::Nothing => None,
+            Selection::Everything(kind) => *kind,
+            Selection::Partial { kind, .. } => Some(*kind),
+        }
+    }
+
+    // the selection of a key, or of every key without an explicit selection
+    fn get(&self, key: Option<&SelectionKey>) -> Selection {
+        match self {
+            Selection::Nothing => Selection::Nothing,
+            // the kind is the one of `self`, not of its children
+            Selection::Everything(_) => Selection::Everything(None),
+            Selection::Partial {
+                children, default, ..
+            } => key
+                .and_then(|key| children.get(key))
+                .unwrap_or(default)
+                .clone(),
+        }
+    }
+
+    fn combine(
+        self,
+        other: Selection,
+        op: fn(Selection, Selection) -> Result<Selection, FieldMaskError>,
+    ) -> Result<Selection, FieldMaskError> {
+        let kind = match (self.kind(), other.kind()) {
+            (Some(k1), Some(k2)) if k1 != k2 => {
+                return Err(FieldMaskError::GenericError {
+                    message: format!("Cannot combine {k1:?} field mask with {k2:?} field mask"),
+                });
+            }
+            (k1, k2) => k1.or(k2).expect("partial selection has a kind"),
+        };
+
+        let mut keys = Vec::new();
+        for sel in [&self, &other] {
+            if let Selection::Partial { children, .. } = sel {
+                keys.extend(children.keys().cloned());
+            }
+        }
+
+        let mut children = std::collections::BTreeMap::new();
+        for key in keys {
+            if children.contains_key(&key) {
+                continue;
+            }
+            let sel = op(self.get(Some(&key)), other.get(Some(&key)))?;
+            children.insert(key, sel);
+        }
+        let default = op(self.get(None), other.get(None))?;
+
+        Ok(Selection::Partial {
+            kind,
+            children,
+            default: Box::new(default),
+        }
+        .normalize())
+    }
+
+    fn union(self, other: Selection) -> Result<Selection, FieldMaskError> {
+        match (self, other) {
+            (Selection::Nothing, sel) | (sel, Selection::Nothing) => Ok(sel),
+            (Selection::Everything(k1), Selection::Everything(k2)) => {
+                Ok(Selection::Everything(k1.or(k2)))
+            }
+            (Selection::Everything(k), sel) | (sel, Selection::Everything(k)) => {
+                Ok(Selection::Everything(k.or(sel.kind())))
+            }
+            (s1, s2) => s1.combine(s2, Selection::union),
+        }
+    }
+
+    fn intersect(self, other: Selection) -> Result<Selection, FieldMaskError> {
+        match (self, other) {
+            (Selection::Nothing, _) | (_, Selection::Nothing) => Ok(Selection::Nothing),
+            (Selection::Everything(k1), Selection::Everything(k2)) => {
+                Ok(Selection::Everything(k1.or(k2)))
+            }
+            (Selection::Everything(_), sel) | (sel, Selection::Everything(_)) => Ok(sel),
+            (s1, s2) => s1.combine(s2, Selection::intersect),
+        }
+    }
+
+    fn subtract(self, other: Selection) -> Result<Selection, FieldMaskError> {
+        let sel = match (self, other) {
+            (Selection::Nothing, _) | (_, Selection::Everything(_)) => Selection::Nothing,
+            (sel, Selection::Nothing) => sel,
+            (s1, s2) => s1.combine(s2, Selection::subtract)?,
+        };
+        // nothing is left once every key is gone
+        match sel {
+            Selection::Partial {
+                children, default, ..
+            } if matches!(*default, Selection::Nothing) && children.is_empty() => {
+                Ok(Selection::Nothing)
+            }
+            sel => Ok(sel),
+        }
+    }
+
+    fn into_mask(self, is_black: bool) -> FieldMask {
+        match self {
+            Selection::Nothing if is_black => FieldMask {
+                is_black,
+                data: FieldMaskData::Scalar,
+            },
+            Selection::Nothing => FieldMask::default(),
+            Selection::Everything(_) if is_black => FieldMask {
+                is_black,
+                data: FieldMaskData::Invalid,
+            },
+            Selection::Everything(kind) => FieldMask {
+                is_black,
+                data: kind.map_or(FieldMaskData::Scalar, |kind| {
+                    let mut data = FieldMaskData::empty(kind);
+                    data.set_all().expect("composite field mask can be set all");
+                    data
+                }),
+            },
+            Selection::Partial {
+                kind,
+                children,
+                default,
+            } => {
+                // a white list can't exclude keys from everything and a black list
+                // can't include keys from nothing
+                let is_black = match *default {
+                    Selection::Nothing => false,
+                    Selection::Everything(_) => true,
+                    Selection::Partial { .. } => is_black,
+                };
+                let mut data = FieldMaskData::empty(kind);
+                let wildcard = match *default {
+                    Selection::Nothing if !is_black => None,
+                    Selection::Everything(_) if is_black => None,
+                    default => Some(Box::new(default.into_mask(is_black))),
+                };
+                match &mut data {
+                    FieldMaskData::Struct { children: c, .. } => {
+                        c.extend(children.into_iter().filter_map(|(k, v)| match k {
+                            SelectionKey::Int(k) => Some((k, Box::new(v.into_mask(is_black)))),
+                            SelectionKey::Str(_) => None,
+                        }))
+                    }
+                    FieldMaskData::List {
+                        children: c,
+                        wildcard: w,
+                        ..
+                    }
+                    | FieldMaskData::IntMap {
+                        children: c,
+                        wildcard: w,
+                        ..
+                    } => {
+                        *w = wildcard;
+                        c.extend(children.into_iter().filter_map(|(k, v)| match k {
+                            SelectionKey::Int(k) => Some((k, Box::new(v.into_mask(is_black)))),
+                            SelectionKey::Str(_) => None,
+                        }))
+                    }
+                    FieldMaskData::StrMap {
+                        children: c,
+                        wildcard: w,
+                        ..
+                    } => {
+                        *w = wildcard;
+                        c.extend(children.into_iter().filter_map(|(k, v)| match k {
+                            SelectionKey::Str(k) => Some((k, Box::new(v.into_mask(is_black)))),
+                            SelectionKey::Int(_) => None,
+                        }))
+                    }
+                    FieldMaskData::Scalar | FieldMaskData::Invalid => unreachable!(),
+                }
+                FieldMask { is_black, data }
+            }
+        }
+    }
+}
+
+impl FieldMaskData {
+    fn empty(kind: SelectionKind) -> Self {
+        match kind {
+            SelectionKind::Struct => FieldMaskData::Struct {
+                children: AHashMap::new(),
+                is_all: false,
+            },
+            SelectionKind::List => FieldMaskData::List {
+                children: AHashMap::new(),
+                wildcard: None,
+                is_all: false,
+            },
+            SelectionKind::StrMap => FieldMaskData::StrMap {
+                children: AHashMap::new(),
+                wildcard: None,
+                is_all: false,
+            },
+            SelectionKind::IntMap => FieldMaskData::IntMap {
+                children: AHashMap::new(),
+                wildcard: None,
+                is_all: false,
+            },
+        }
+    }
+}
+
+/// Paths that rebuild a mask with [`FieldMaskBuilder`], in the black list
+/// mode given by `is_black`.
+///
+/// A bare `Vec<String>` is not enough for [`FieldMask::to_paths`]: paths only
+/// describe a mask together with their mode, and a mask that excludes keys
+/// from a wildcard can only be written in the mode opposite to its own, so
+/// the mode has to travel with the paths. [`FieldMaskPaths::build`] turns
+/// them back into the mask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMaskPaths {
+    pub paths: Vec<String>,
+    /// Whether the paths are the excluded ones, which may differ from the
+    /// mode of the mask they were written from.
+    pub is_black: bool,
+}
+
+impl FieldMaskPaths {
+    /// The options to rebuild the mask with.
+    pub fn options(&self) -> Options {
+        Options::new().with_black_list_mode(self.is_black)
+    }
+
+    /// Rebuilds the mask.
+    pub fn build(&self, desc: &TypeDescriptor) -> Result<FieldMask, FieldMaskError> {
+        FieldMaskBuilder::new(desc, &self.paths)
+            .with_options(self.options())
+            .build()
+    }
+}
+
+// Methods for path serialization
+impl FieldMask {
+    /// Converts the mask back into paths that rebuild an equivalent mask with
+    /// [`FieldMaskBuilder`].
+    ///
+    /// Fields are named after `desc`, and a struct whose fields are all
+    /// selected is collapsed into its own path. List indexes and map keys that
+    /// share the same sub mask are merged into a single path, e.g.
+    /// `$.f13[1,2].a`.
+    ///
+    /// The paths are written in the mode of `self` when it can express the
+    /// mask. The path language has no exclusions though, so a key excluded
+    /// from a wildcard, e.g. `$.f13[*]` without index 1, can only be written
+    /// in the other mode, as `$.f13[1]` and the fields other than `f13` in a
+    /// black list. The mode of the paths is returned with them, and a mask
+    /// that neither mode can express is an error.
+    pub fn to_paths(&self, desc: &TypeDescriptor) -> Result<FieldMaskPaths, FieldMaskError> {
+        let sel = Selection::from_mask(self);
+        for is_black in [self.is_black, !self.is_black] {
+            // black list paths are the excluded ones
+            let sel = if is_black {
+                sel.clone().complement()
+            } else {
+                sel.clone()
+            };
+            let mut paths = Vec::new();
+            if sel.write_paths(desc, "$".to_string(), &mut paths)? {
+                return Ok(FieldMaskPaths { paths, is_black });
+            }
+        }
+        Err(FieldMaskError::GenericError {
+            message: "field mask excludes keys from wildcards in both the white and black list \
+                      modes, which paths cannot express"
+                .into(),
+        })
+    }
+}
+
+impl Selection {
+    fn complement(self) -> Self {
+        match self {
+            Selection::Nothing => Selection::Everything(None),
+            Selection::Everything(_) => Selection::Nothing,
+            Selection::Partial {
+                kind,
+                children,
+                default,
+            } => Selection::Partial {
+                kind,
+                children: children
+                    .into_iter()
+                    .map(|(k, v)| (k, v.complement()))
+                    .collect(),
+                default: Box::new(default.complement()),
+            }
+            .normalize(),
+        }
+    }
+
+    /// Writes the paths of the selection, and returns whether they select
+    /// exactly it.
+    ///
+    /// Fails when the selection doesn't match `desc`, as the paths would
+    /// then silently differ from the mask.
+    fn write_paths(
+        &self,
+        desc: &TypeDescriptor,
+        prefix: String,
+        paths: &mut Vec<String>,
+    ) -> Result<bool, FieldMaskError> {
+        let (kind, children, default) = match self {
+            Selection::Nothing => return Ok(true),
+            Selection::Everything(_) => {
+                paths.push(prefix);
+                return Ok(true);
+            }
+            Selection::Partial {
+                kind,
+                children,
+                default,
+            } => (*kind, children, default),
+        };
+
+        let expected = kind.type_name();
+        if FieldMaskData::new(desc).type_name() != expected {
+            return Err(FieldMaskError::TypeMismatch {
+                detail: Box::new(TypeMismatchDetail {
+                    expected: expected.into(),
+                    actual: desc.name.clone(),
+                    context: "descriptor type check for paths".into(),
+                }),
+                path: Box::new(PathDetail {
+                    position: prefix.len(),
+                    path: prefix.into(),
+                }),
+            });
+        }
+
+        if kind == SelectionKind::Struct {
+            let s = desc.get_struct_desc().unwrap();
+            for key in children.keys() {
+                if let SelectionKey::Int(id) = key
+                    && s.find_field_by_id(*id).is_none()
+                {
+                    return Err(FieldMaskError::FieldNotFound {
+                        field_identifier: id.to_string().into(),
+                        parent_type: desc.name.clone(),
+                        path: Box::new(PathDetail {
+                            position: prefix.len(),
+                            path: prefix.into(),
+                        }),
+                    });
+                }
+            }
+            let mut fields = s.fields.iter().collect::<Vec<_>>();
+            fields.sort_by_key(|f| f.id);
+            let fields = fields
+                .into_iter()
+                .map(|f| (f, self.get(Some(&SelectionKey::Int(f.id)))))
+                .collect::<Vec<_>>();
+            if !fields.is_empty()
+                && fields
+                    .iter()
+                    .all(|(_, sel)| matches!(sel, Selection::Everything(_)))
+            {
+                paths.push(prefix);
+                return Ok(true);
+            }
+            let mut exact = true;
+            for (f, sel) in fields {
+                exact &= sel.write_paths(&f.r#type, format!("{prefix}.{}", f.name), paths)?;
+            }
+            return Ok(exact);
+        }
+
+        // checked by the kind above
+        let element_desc = desc.value_type.as_deref().unwrap();
+        let (open, close) = if kind == SelectionKind::List {
+            ('[', ']')
+        } else {
+            ('{', '}')
+        };
+
+        // keys sharing the same sub mask go into the same path
+        let mut groups: Vec<(Vec<String>, &Selection)> = Vec::new();
+        for (key, sel) in children {
+            let key = match key {
+                SelectionKey::Int(i) => i.to_string(),
+                SelectionKey::Str(s) => quote_key(s),
+            };
+            match groups.iter_mut().find(|(_, s)| s.is_same(sel)) {
+                Some((keys, _)) => keys.push(key),
+                None => groups.push((vec![key], sel)),
+            }
+        }
+        let mut exact = true;
+        let has_default = !matches!(**default, Selection::Nothing);
+        if has_default {
+            exact &= default.write_paths(element_desc, format!("{prefix}{open}*{close}"), paths)?;
+        }
+        for (keys, sel) in groups {
+            let keys = keys.join(",");
+            let len = paths.len();
+            exact &=
+                sel.write_paths(element_desc, format!("{prefix}{open}{keys}{close}"), paths)?;
+            // keys without paths fall back to the wildcard
+            exact &= !has_default || paths.len() > len;
+        }
+        Ok(exact)
+    }
+}
+
+fn quote_key(key: &str) -> String {
+    let mut quoted = String::with_capacity(key.len() + 2);
+    quoted.push('"');
+    for c in key.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, sync::Arc};
@@ -1531,13 +2193,17 @@ mod tests {
         let mut fm = FieldMask::default();
 
         let mut children = AHashMap::new();
-        let mut child1 = FieldMask::default();
-        child1.data = FieldMaskData::Scalar;
-        let mut child2 = FieldMask::default();
-        child2.data = FieldMaskData::List {
-            children: AHashMap::new(),
-            wildcard: None,
-            is_all: false,
+        let child1 = FieldMask {
+            data: FieldMaskData::Scalar,
+            ..Default::default()
+        };
+        let child2 = FieldMask {
+            data: FieldMaskData::List {
+                children: AHashMap::new(),
+                wildcard: None,
+                is_all: false,
+            },
+            ..Default::default()
         };
         children.insert(1, Box::new(child1));
         children.insert(2, Box::new(child2));
@@ -1556,9 +2222,7 @@ mod tests {
                     assert_eq!(mask.typ(), "List");
                     assert!(mask.exist());
                 }
-                _ => {
-                    assert!(false);
-                }
+                _ => unreachable!(),
             }
             count += 1;
             true
@@ -2033,4 +2697,547 @@ mod tests {
             .unwrap();
         assert!(!exist);
     }
+
+    #[test]
+    fn test_set_operations() {
+        let content = std::fs::read_to_string("../examples/idl/fieldmask.thrift").unwrap();
+        let mut ast = pilota_thrift_parser::FileParser::new(
+            pilota_thrift_parser::FileSource::new_with_path(
+                PathBuf::from("../examples/idl/fieldmask.thrift"),
+                &content,
+            )
+            .unwrap(),
+        )
+        .parse()
+        .unwrap();
+        ast.path = Arc::from(
+            PathBuf::from("../examples/idl/fieldmask.thrift")
+                .canonicalize()
+                .unwrap(),
+        );
+        let desc: pilota_thrift_reflect::thrift_reflection::FileDescriptor = (&ast).into();
+        let key = FastStr::new(ast.path.to_string_lossy());
+        pilota_thrift_reflect::service::Register::register(key, desc.clone());
+
+        let req_desc = desc
+            .find_struct_by_name("Request")
+            .unwrap()
+            .type_descriptor();
+        let build = |paths: &[&str]| FieldMaskBuilder::new(&req_desc, paths).build().unwrap();
+        let check = |fm: &FieldMask, included: &[&str], excluded: &[&str]| {
+            for path in included {
+                assert!(fm.path_in_mask(&req_desc, path).unwrap(), "{path}");
+            }
+            for path in excluded {
+                assert!(!fm.path_in_mask(&req_desc, path).unwrap(), "{path}");
+            }
+        };
+
+        // union
+        let a = build(&["$.f1", "$.f11.a", "$.f13[1].a", "$.f14{1}"]);
+        let b = build(&["$.f11.b", "$.f13[*].b", "$.f14{2}", "$.f15{\"key1\"}"]);
+        let union = a.union(&b).unwrap();
+        check(
+            &union,
+            &[
+                "$.f1",
+                "$.f11.a",
+                "$.f11.b",
+                "$.f13[1].a",
+                "$.f13[1].b",
+                "$.f13[2].b",
+                "$.f14{1}",
+                "$.f14{2}",
+                "$.f15{\"key1\"}.a",
+            ],
+            &["$.f7", "$.f13[2].a", "$.f14{3}", "$.f15{\"key2\"}"],
+        );
+        assert!(a.is_subset_of(&union).unwrap());
+        assert!(b.is_subset_of(&union).unwrap());
+        assert!(!union.is_subset_of(&a).unwrap());
+        assert_eq!(union, b.union(&a).unwrap());
+
+        // intersect
+        let a = build(&["$.f11", "$.f13[*].a", "$.f15{*}", "$.f16"]);
+        let b = build(&["$.f11.b", "$.f13[1]", "$.f15{\"key1\"}.a", "$.f1"]);
+        let intersect = a.intersect(&b).unwrap();
+        check(
+            &intersect,
+            &["$.f11.b", "$.f13[1].a", "$.f15{\"key1\"}.a"],
+            &[
+                "$.f1",
+                "$.f11.a",
+                "$.f13[1].b",
+                "$.f13[0]",
+                "$.f15{\"key1\"}.b",
+                "$.f15{\"key2\"}",
+                "$.f16",
+            ],
+        );
+        assert!(intersect.is_subset_of(&a).unwrap());
+        assert!(intersect.is_subset_of(&b).unwrap());
+
+        // subtract
+        let a = build(&["$.f11", "$.f13[*]", "$.f15{\"key1\"}", "$.f17[0]{*}"]);
+        let b = build(&[
+            "$.f11.a",
+            "$.f13[0]",
+            "$.f15{\"key1\"}.a",
+            "$.f17[0]{\"k\"}",
+        ]);
+        let subtract = a.subtract(&b).unwrap();
+        check(
+            &subtract,
+            &[
+                "$.f11.b",
+                "$.f13[1].a",
+                "$.f15{\"key1\"}.b",
+                "$.f17[0]{\"other\"}",
+            ],
+            &[
+                "$.f11.a",
+                "$.f13[0]",
+                "$.f15{\"key1\"}.a",
+                "$.f15{\"key2\"}",
+                "$.f17[0]{\"k\"}",
+                "$.f17[1]",
+            ],
+        );
+        assert!(
+            a.subtract(&a)
+                .unwrap()
+                .is_subset_of(&FieldMask::default())
+                .unwrap()
+        );
+        assert!(!a.subtract(&a).unwrap().exist());
+
+        // subset
+        assert!(
+            build(&["$.f11.a"])
+                .is_subset_of(&build(&["$.f11"]))
+                .unwrap()
+        );
+        assert!(
+            !build(&["$.f11"])
+                .is_subset_of(&build(&["$.f11.a"]))
+                .unwrap()
+        );
+        assert!(
+            build(&["$.f11.a", "$.f11.b"])
+                .is_subset_of(&build(&["$.f11"]))
+                .unwrap()
+        );
+        assert!(
+            build(&["$.f13[1].a"])
+                .is_subset_of(&build(&["$.f13[*]"]))
+                .unwrap()
+        );
+        assert!(
+            !build(&["$.f13[*]"])
+                .is_subset_of(&build(&["$.f13[1]"]))
+                .unwrap()
+        );
+        assert!(
+            build(&["$.f14{1}"])
+                .is_subset_of(&build(&["$.f14"]))
+                .unwrap()
+        );
+        assert!(build(&["$.f1"]).is_subset_of(&build(&["$.*"])).unwrap());
+    }
+
+    #[test]
+    fn test_set_operations_black_list_mode() {
+        let content = std::fs::read_to_string("../examples/idl/fieldmask.thrift").unwrap();
+        let mut ast = pilota_thrift_parser::FileParser::new(
+            pilota_thrift_parser::FileSource::new_with_path(
+                PathBuf::from("../examples/idl/fieldmask.thrift"),
+                &content,
+            )
+            .unwrap(),
+        )
+        .parse()
+        .unwrap();
+        ast.path = Arc::from(
+            PathBuf::from("../examples/idl/fieldmask.thrift")
+                .canonicalize()
+                .unwrap(),
+        );
+        let desc: pilota_thrift_reflect::thrift_reflection::FileDescriptor = (&ast).into();
+        let key = FastStr::new(ast.path.to_string_lossy());
+        pilota_thrift_reflect::service::Register::register(key, desc.clone());
+
+        let req_desc = desc
+            .find_struct_by_name("Request")
+            .unwrap()
+            .type_descriptor();
+        let white = |paths: &[&str]| FieldMaskBuilder::new(&req_desc, paths).build().unwrap();
+        let black = |paths: &[&str]| {
+            FieldMaskBuilder::new(&req_desc, paths)
+                .with_options(Options::new().with_black_list_mode(true))
+                .build()
+                .unwrap()
+        };
+        let check = |fm: &FieldMask, included: &[&str], excluded: &[&str]| {
+            for path in included {
+                assert!(fm.path_in_mask(&req_desc, path).unwrap(), "{path}");
+            }
+            for path in excluded {
+                assert!(!fm.path_in_mask(&req_desc, path).unwrap(), "{path}");
+            }
+        };
+
+        let b1 = black(&["$.f1", "$.f11.a", "$.f15{\"key1\"}"]);
+        let b2 = black(&["$.f7", "$.f11.a", "$.f13[*]"]);
+
+        // excluding what both exclude
+        let union = b1.union(&b2).unwrap();
+        assert!(union.is_black());
+        check(
+            &union,
+            &["$.f1", "$.f7", "$.f11.b", "$.f13[0]", "$.f15{\"key1\"}"],
+            &["$.f11.a"],
+        );
+
+        // excluding what either excludes
+        let intersect = b1.intersect(&b2).unwrap();
+        assert!(intersect.is_black());
+        check(
+            &intersect,
+            &["$.f2", "$.f11.b", "$.f15{\"key2\"}"],
+            &["$.f1", "$.f7", "$.f11.a", "$.f13[0]", "$.f15{\"key1\"}"],
+        );
+        assert!(intersect.is_subset_of(&b1).unwrap());
+        assert!(!b1.is_subset_of(&intersect).unwrap());
+
+        // requested and not forbidden
+        let requested = white(&["$.f1", "$.f2", "$.f11", "$.f15{*}.b"]);
+        let permitted = requested.intersect(&b1).unwrap();
+        assert!(!permitted.is_black());
+        check(
+            &permitted,
+            &["$.f2", "$.f11.b", "$.f15{\"key2\"}.b"],
+            &[
+                "$.f1",
+                "$.f7",
+                "$.f11.a",
+                "$.f15{\"key1\"}",
+                "$.f15{\"key2\"}.a",
+            ],
+        );
+        assert_eq!(
+            permitted,
+            requested
+                .subtract(&white(&["$.f1", "$.f11.a", "$.f15{\"key1\"}"]))
+                .unwrap()
+        );
+        assert!(permitted.is_subset_of(&b1).unwrap());
+        assert!(!requested.is_subset_of(&b1).unwrap());
+
+        // forbidden but requested
+        let subtract = requested.subtract(&b1).unwrap();
+        check(
+            &subtract,
+            &["$.f1", "$.f11.a"],
+            &["$.f2", "$.f11.b", "$.f15{\"key2\"}"],
+        );
+
+        // everything but the requested fields
+        let subtract = black(&[]).subtract(&requested).unwrap();
+        assert!(subtract.is_black());
+        check(
+            &subtract,
+            &["$.f7", "$.f15{\"key1\"}.a"],
+            &["$.f1", "$.f2", "$.f11.a", "$.f15{\"key1\"}.b"],
+        );
+        assert!(white(&["$.*"]).is_subset_of(&black(&[])).unwrap());
+        assert!(black(&[]).is_subset_of(&white(&["$.*"])).unwrap());
+    }
+
+    #[test]
+    fn test_set_operations_type_mismatch() {
+        let list = FieldMask {
+            is_black: false,
+            data: FieldMaskData::List {
+                children: AHashMap::new(),
+                wildcard: None,
+                is_all: false,
+            },
+        };
+        let map = FieldMask {
+            is_black: false,
+            data: FieldMaskData::StrMap {
+                children: AHashMap::from_iter([(
+                    FastStr::new("key"),
+                    Box::new(FieldMask {
+                        is_black: false,
+                        data: FieldMaskData::Scalar,
+                    }),
+                )]),
+                wildcard: None,
+                is_all: false,
+            },
+        };
+        assert!(matches!(
+            list.union(&map),
+            Err(FieldMaskError::GenericError { .. })
+        ));
+        assert_eq!(list.union(&FieldMask::default()).unwrap(), list);
+    }
+
+    /// The selection of a mask over the fields declared by `desc`, so that
+    /// masks of different modes compare equal when they select the same
+    /// fields.
+    fn declared_selection(sel: Selection, desc: &TypeDescriptor) -> Selection {
+        let Selection::Partial {
+            kind,
+            children,
+            default,
+        } = sel
+        else {
+            return sel;
+        };
+        if kind == SelectionKind::Struct {
+            let fields = desc.get_struct_desc().unwrap().fields.clone();
+            let sel = Selection::Partial {
+                kind,
+                children,
+                default,
+            };
+            let children = fields
+                .iter()
+                .map(|f| {
+                    let key = SelectionKey::Int(f.id);
+                    let child = declared_selection(sel.get(Some(&key)), &f.r#type);
+                    (key, child)
+                })
+                .collect::<std::collections::BTreeMap<_, _>>();
+            return if children
+                .values()
+                .all(|c| matches!(c, Selection::Everything(_)))
+            {
+                Selection::Everything(Some(kind))
+            } else if children.values().all(|c| matches!(c, Selection::Nothing)) {
+                Selection::Nothing
+            } else {
+                Selection::Partial {
+                    kind,
+                    children,
+                    default: Box::new(Selection::Nothing),
+                }
+                .normalize()
+            };
+        }
+        let element = desc.value_type.as_deref().unwrap();
+        let sel = Selection::Partial {
+            kind,
+            children: children
+                .into_iter()
+                .map(|(k, c)| (k, declared_selection(c, element)))
+                .collect(),
+            default: Box::new(declared_selection(*default, element)),
+        }
+        .normalize();
+        match sel {
+            Selection::Partial {
+                children, default, ..
+            } if children.is_empty() && matches!(*default, Selection::Nothing) => {
+                Selection::Nothing
+            }
+            sel => sel,
+        }
+    }
+
+    /// Parses and registers an idl of the examples.
+    fn register_idl(path: &str) -> pilota_thrift_reflect::thrift_reflection::FileDescriptor {
+        let content = std::fs::read_to_string(path).unwrap();
+        let mut ast = pilota_thrift_parser::FileParser::new(
+            pilota_thrift_parser::FileSource::new_with_path(PathBuf::from(path), &content).unwrap(),
+        )
+        .parse()
+        .unwrap();
+        ast.path = Arc::from(PathBuf::from(path).canonicalize().unwrap());
+        let desc: pilota_thrift_reflect::thrift_reflection::FileDescriptor = (&ast).into();
+        let key = FastStr::new(ast.path.to_string_lossy());
+        pilota_thrift_reflect::service::Register::register(key, desc.clone());
+        desc
+    }
+
+    #[test]
+    fn test_to_paths() {
+        let desc = register_idl("../examples/idl/fieldmask.thrift");
+        register_idl("../examples/idl/base.thrift");
+
+        let req_desc = desc
+            .find_struct_by_name("Request")
+            .unwrap()
+            .type_descriptor();
+        let round_trip = |paths: &[&str], black: bool, expected: &[&str]| {
+            let opts = Options::new().with_black_list_mode(black);
+            let fm = FieldMaskBuilder::new(&req_desc, paths)
+                .with_options(opts)
+                .build()
+                .unwrap();
+            let to_paths = fm.to_paths(&req_desc).unwrap();
+            assert_eq!(to_paths.paths, expected);
+            assert_eq!(to_paths.is_black, black);
+            assert_eq!(to_paths.build(&req_desc).unwrap(), fm, "{paths:?}");
+        };
+
+        round_trip(&[], false, &[]);
+        round_trip(&["$"], false, &["$"]);
+        round_trip(&["$.*"], false, &["$"]);
+        round_trip(
+            &["$.f7", "$.1", "$.f11.a"],
+            false,
+            &["$.f1", "$.f7", "$.f11.a"],
+        );
+        round_trip(&["$.f13[1].a", "$.f13[0].a"], false, &["$.f13[0,1].a"]);
+        round_trip(
+            &["$.f13[*].a", "$.f13[2].b"],
+            false,
+            &["$.f13[*].a", "$.f13[2].b"],
+        );
+        round_trip(&["$.f14{2, 1}"], false, &["$.f14{1,2}"]);
+        round_trip(
+            &["$.f15{\"b\\\"q\"}.a", "$.f15{\"a\"}.a", "$.f16{*}[0]"],
+            false,
+            &["$.f15{\"a\",\"b\\\"q\"}.a", "$.f16{*}[0]"],
+        );
+        round_trip(
+            &["$.f17[*]{\"k\"}", "$.f12[1][0]"],
+            false,
+            &["$.f12[1][0]", "$.f17[*]{\"k\"}"],
+        );
+        round_trip(&["$.f1", "$.f11.a"], true, &["$.f1", "$.f11.a"]);
+        round_trip(&["$.f15{\"k\"}.b"], true, &["$.f15{\"k\"}.b"]);
+
+        // a struct with every field selected is collapsed
+        let fm = FieldMaskBuilder::new(&req_desc, &["$.f11.a", "$.f11.b"])
+            .build()
+            .unwrap();
+        assert_eq!(fm.to_paths(&req_desc).unwrap().paths, &["$.f11"]);
+
+        // combined masks are written in the mode of the result
+        let white = FieldMaskBuilder::new(&req_desc, &["$.f1", "$.f11"])
+            .build()
+            .unwrap();
+        let black = FieldMaskBuilder::new(&req_desc, &["$.f11.a"])
+            .with_options(Options::new().with_black_list_mode(true))
+            .build()
+            .unwrap();
+        assert_eq!(
+            white
+                .intersect(&black)
+                .unwrap()
+                .to_paths(&req_desc)
+                .unwrap(),
+            FieldMaskPaths {
+                paths: vec!["$.f1".into(), "$.f11.b".into()],
+                is_black: false,
+            }
+        );
+        assert_eq!(
+            black.subtract(&white).unwrap().to_paths(&req_desc).unwrap(),
+            FieldMaskPaths {
+                paths: vec!["$.f1".into(), "$.f11".into()],
+                is_black: true,
+            }
+        );
+
+        let build = |paths: &[&str], black: bool| {
+            FieldMaskBuilder::new(&req_desc, paths)
+                .with_options(Options::new().with_black_list_mode(black))
+                .build()
+                .unwrap()
+        };
+        let assert_same = |fm: &FieldMask, paths: &FieldMaskPaths| {
+            let rebuilt = paths.build(&req_desc).unwrap();
+            assert!(
+                declared_selection(Selection::from_mask(fm), &req_desc).is_same(
+                    &declared_selection(Selection::from_mask(&rebuilt), &req_desc)
+                ),
+                "{paths:?}"
+            );
+        };
+
+        // a key excluded from a wildcard is written in the other mode
+        let fm = build(&["$.f13[*]"], false)
+            .subtract(&build(&["$.f13[1]"], false))
+            .unwrap();
+        let paths = fm.to_paths(&req_desc).unwrap();
+        assert!(paths.is_black);
+        assert!(paths.paths.contains(&"$.f13[1]".to_string()), "{paths:?}");
+        assert!(!paths.paths.contains(&"$.f13".to_string()), "{paths:?}");
+        assert_same(&fm, &paths);
+        // rather than widened to the whole wildcard
+        let wide = build(&["$.f13[*]"], false);
+        assert!(
+            !declared_selection(Selection::from_mask(&wide), &req_desc)
+                .is_same(&declared_selection(Selection::from_mask(&fm), &req_desc))
+        );
+
+        let fm = build(&["$.f15{*}"], true)
+            .union(&build(&["$.f15{\"k\"}"], false))
+            .unwrap();
+        let paths = fm.to_paths(&req_desc).unwrap();
+        assert_same(&fm, &paths);
+
+        let fm = build(&["$.f1", "$.f11.a"], true);
+        let paths = fm.to_paths(&req_desc).unwrap();
+        assert!(paths.is_black);
+        assert_same(&fm, &paths);
+
+        // an exclusion in each mode can't be written in either
+        let fm = build(&["$.f13[*]"], false)
+            .subtract(&build(&["$.f13[1]"], false))
+            .unwrap()
+            .union(&build(&["$.f12[*][0]", "$.f12[1]"], false))
+            .unwrap();
+        assert!(fm.to_paths(&req_desc).is_err());
+    }
+
+    #[test]
+    fn test_to_paths_mismatched_descriptor() {
+        let desc = register_idl("../examples/idl/fieldmask.thrift");
+        register_idl("../examples/idl/base.thrift");
+
+        let req_desc = desc
+            .find_struct_by_name("Request")
+            .unwrap()
+            .type_descriptor();
+        let a_desc = desc.find_struct_by_name("A").unwrap().type_descriptor();
+        let build = |paths: &[&str], black: bool| {
+            FieldMaskBuilder::new(&req_desc, paths)
+                .with_options(Options::new().with_black_list_mode(black))
+                .build()
+                .unwrap()
+        };
+
+        // fields that `A` doesn't declare
+        for black in [false, true] {
+            let err = build(&["$.f1", "$.f13[0].a"], black)
+                .to_paths(&a_desc)
+                .unwrap_err();
+            assert!(matches!(err, FieldMaskError::FieldNotFound { .. }), "{err}");
+        }
+
+        // a struct mask against a list descriptor
+        let f13 = &req_desc
+            .get_struct_desc()
+            .unwrap()
+            .find_field_by_id(13)
+            .unwrap()
+            .r#type
+            .clone();
+        let err = build(&["$.f11.a"], false).to_paths(f13).unwrap_err();
+        assert!(matches!(err, FieldMaskError::TypeMismatch { .. }), "{err}");
+
+        // a list mask against a struct descriptor
+        let a = build(&["$.f13[0].a"], false);
+        let FieldMaskData::Struct { children, .. } = &a.data else {
+            unreachable!()
+        };
+        let err = children[&13].to_paths(&a_desc).unwrap_err();
+        assert!(matches!(err, FieldMaskError::TypeMismatch { .. }), "{err}");
+    }
 }
@@ -1,5 +1,9 @@
 pub mod fieldmask;
 pub mod path;
+pub mod typed;
+pub mod wire;
 
-pub use fieldmask::{FieldMask, FieldMaskBuilder, FieldMaskData, FieldMaskError, Options};
+pub use fieldmask::{
+    FieldMask, FieldMaskBuilder, FieldMaskData, FieldMaskError, FieldMaskPaths, Options,
+};
 pub use path::PathError;
@@ -0,0 +1,401 @@
+//! Typed builders of [`FieldMask`].
+//!
+//! With `with_field_mask(true)`, pilota-build generates a builder for every
+//! thrift struct, returned by `Foo::mask()`, with one method per field:
+//!
+//! ```ignore
+//! let mask = User::mask()
+//!     .name()
+//!     .friends_with(|friends| friends.each_with(|friend| friend.name()))
+//!     .build();
+//! ```
+//!
+//! `field()` selects a field as a whole, and `field_with()` selects a part of
+//! a struct, list or map field through the builder of its type. Masks built
+//! this way need neither paths nor descriptors, so a mistyped field is a
+//! compile error instead of a [`FieldMaskError`](crate::FieldMaskError).
+
+use std::marker::PhantomData;
+
+use ahash::AHashMap;
+use pilota::FastStr;
+
+use crate::{FieldMask, FieldMaskData};
+
+/// A builder of the masks of values of one type.
+pub trait MaskBuilder: Default {
+    /// Returns the mask selecting a whole value.
+    fn whole() -> FieldMask;
+
+    /// Returns the white list mask built so far.
+    fn build(self) -> FieldMask;
+
+    /// Returns the black list mask built so far, which excludes what was
+    /// selected instead.
+    fn build_black_list(self) -> FieldMask {
+        let mut mask = self.build();
+        mask.set_black(true);
+        mask
+    }
+}
+
+/// Associates a generated struct with the builder of its masks.
+pub trait Masked {
+    type Builder: MaskBuilder;
+}
+
+/// The builder of values that can only be selected as a whole.
+#[derive(Debug, Clone, Default)]
+pub struct ScalarMask;
+
+impl MaskBuilder for ScalarMask {
+    fn whole() -> FieldMask {
+        FieldMask::new_white(FieldMaskData::Scalar)
+    }
+
+    fn build(self) -> FieldMask {
+        Self::whole()
+    }
+}
+
+/// The builder wrapped by the generated builder of a struct.
+#[derive(Debug, Clone)]
+pub struct StructMask(FieldMask);
+
+impl Default for StructMask {
+    fn default() -> Self {
+        Self(FieldMask::new_white(FieldMaskData::Struct {
+            children: AHashMap::new(),
+            is_all: false,
+        }))
+    }
+}
+
+impl StructMask {
+    /// Selects the field `id` as given by `mask`.
+    pub fn field(&mut self, id: i32, mask: FieldMask) {
+        if let FieldMaskData::Struct { children, .. } = &mut self.0.data {
+            insert(children, id, mask);
+        }
+    }
+
+    /// Selects every field.
+    pub fn all(&mut self) {
+        if let FieldMaskData::Struct { is_all, .. } = &mut self.0.data {
+            *is_all = true;
+        }
+    }
+}
+
+impl MaskBuilder for StructMask {
+    fn whole() -> FieldMask {
+        let mut mask = Self::default();
+        mask.all();
+        mask.0
+    }
+
+    fn build(self) -> FieldMask {
+        self.0
+    }
+}
+
+macro_rules! collection_mask {
+    ($(#[$doc:meta])* $name:ident, $variant:ident, $key:ty) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone)]
+        pub struct $name<T> {
+            mask: FieldMask,
+            _marker: PhantomData<fn() -> T>,
+        }
+
+        impl<T> Default for $name<T> {
+            fn default() -> Self {
+                Self {
+                    mask: FieldMask::new_white(FieldMaskData::$variant {
+                        children: AHashMap::new(),
+                        wildcard: None,
+                        is_all: false,
+                    }),
+                    _marker: PhantomData,
+                }
+            }
+        }
+
+        impl<T: MaskBuilder> $name<T> {
+            fn insert(mut self, key: $key, mask: FieldMask) -> Self {
+                if let FieldMaskData::$variant { children, .. } = &mut self.mask.data {
+                    insert(children, key, mask);
+                }
+                self
+            }
+
+            fn insert_wildcard(mut self, mask: FieldMask) -> Self {
+                if let FieldMaskData::$variant { wildcard, .. } = &mut self.mask.data {
+                    match wildcard {
+                        Some(wildcard) => merge(wildcard, mask),
+                        None => *wildcard = Some(Box::new(mask)),
+                    }
+                }
+                self
+            }
+
+            /// Selects every element as given by `f`.
+            pub fn each_with(self, f: impl FnOnce(T) -> T) -> Self {
+                self.insert_wildcard(f(T::default()).build())
+            }
+
+            /// Selects every element as a whole.
+            pub fn each(self) -> Self {
+                self.insert_wildcard(T::whole())
+            }
+
+            /// Selects the whole collection.
+            pub fn all(mut self) -> Self {
+                if let FieldMaskData::$variant { is_all, .. } = &mut self.mask.data {
+                    *is_all = true;
+                }
+                self
+            }
+        }
+
+        impl<T: MaskBuilder> MaskBuilder for $name<T> {
+            fn whole() -> FieldMask {
+                Self::default().all().mask
+            }
+
+            fn build(self) -> FieldMask {
+                self.mask
+            }
+        }
+    };
+}
+
+collection_mask!(
+    /// The builder of the masks of lists, keyed by index.
+    ListMask,
+    List,
+    i32
+);
+collection_mask!(
+    /// The builder of the masks of maps with string keys.
+    StrMapMask,
+    StrMap,
+    FastStr
+);
+collection_mask!(
+    /// The builder of the masks of maps with integer or enum keys.
+    IntMapMask,
+    IntMap,
+    i32
+);
+
+impl<T: MaskBuilder> ListMask<T> {
+    /// Selects the element at `index` as a whole.
+    pub fn index(self, index: i32) -> Self {
+        self.insert(index, ScalarMask::whole())
+    }
+
+    /// Selects the element at `index` as given by `f`.
+    pub fn index_with(self, index: i32, f: impl FnOnce(T) -> T) -> Self {
+        self.insert(index, f(T::default()).build())
+    }
+}
+
+impl<T: MaskBuilder> StrMapMask<T> {
+    /// Selects the value of `key` as a whole.
+    pub fn key(self, key: impl Into<FastStr>) -> Self {
+        self.insert(key.into(), ScalarMask::whole())
+    }
+
+    /// Selects the value of `key` as given by `f`.
+    pub fn key_with(self, key: impl Into<FastStr>, f: impl FnOnce(T) -> T) -> Self {
+        self.insert(key.into(), f(T::default()).build())
+    }
+}
+
+impl<T: MaskBuilder> IntMapMask<T> {
+    /// Selects the value of `key` as a whole.
+    pub fn key(self, key: i32) -> Self {
+        self.insert(key, ScalarMask::whole())
+    }
+
+    /// Selects the value of `key` as given by `f`.
+    pub fn key_with(self, key: i32, f: impl FnOnce(T) -> T) -> Self {
+        self.insert(key, f(T::default()).build())
+    }
+}
+
+impl FieldMask {
+    fn new_white(data: FieldMaskData) -> Self {
+        Self {
+            is_black: false,
+            data,
+        }
+    }
+
+    fn set_black(&mut self, is_black: bool) {
+        self.is_black = is_black;
+        let (children, wildcard) = match &mut self.data {
+            FieldMaskData::Invalid | FieldMaskData::Scalar => return,
+            FieldMaskData::Struct { children, .. } => (children.values_mut().collect(), None),
+            FieldMaskData::List {
+                children, wildcard, ..
+            }
+            | FieldMaskData::IntMap {
+                children, wildcard, ..
+            } => (children.values_mut().collect::<Vec<_>>(), wildcard.as_mut()),
+            FieldMaskData::StrMap {
+                children, wildcard, ..
+            } => (children.values_mut().collect(), wildcard.as_mut()),
+        };
+        for child in children.into_iter().chain(wildcard) {
+            child.set_black(is_black);
+        }
+    }
+}
+
+fn insert<K: std::hash::Hash + Eq>(
+    children: &mut AHashMap<K, Box<FieldMask>>,
+    key: K,
+    mask: FieldMask,
+) {
+    match children.get_mut(&key) {
+        Some(child) => merge(child, mask),
+        None => {
+            children.insert(key, Box::new(mask));
+        }
+    }
+}
+
+/// Merges `src` into `dst`, both white list masks of the same type.
+fn merge(dst: &mut FieldMask, src: FieldMask) {
+    match (&mut dst.data, src.data) {
+        (FieldMaskData::Scalar, _) => {}
+        (_, FieldMaskData::Scalar) => dst.data = FieldMaskData::Scalar,
+        (
+            FieldMaskData::Struct { children, is_all },
+            FieldMaskData::Struct {
+                children: src_children,
+                is_all: src_is_all,
+            },
+        ) => {
+            *is_all |= src_is_all;
+            for (key, mask) in src_children {
+                insert(children, key, *mask);
+            }
+        }
+        (
+            FieldMaskData::List {
+                children,
+                wildcard,
+                is_all,
+            },
+            FieldMaskData::List {
+                children: src_children,
+                wildcard: src_wildcard,
+                is_all: src_is_all,
+            },
+        )
+        | (
+            FieldMaskData::IntMap {
+                children,
+                wildcard,
+                is_all,
+            },
+            FieldMaskData::IntMap {
+                children: src_children,
+                wildcard: src_wildcard,
+                is_all: src_is_all,
+            },
+        ) => {
+            *is_all |= src_is_all;
+            for (key, mask) in src_children {
+                insert(children, key, *mask);
+            }
+            merge_wildcard(wildcard, src_wildcard);
+        }
+        (
+            FieldMaskData::StrMap {
+                children,
+                wildcard,
+                is_all,
+            },
+            FieldMaskData::StrMap {
+                children: src_children,
+                wildcard: src_wildcard,
+                is_all: src_is_all,
+            },
+        ) => {
+            *is_all |= src_is_all;
+            for (key, mask) in src_children {
+                insert(children, key, *mask);
+            }
+            merge_wildcard(wildcard, src_wildcard);
+        }
+        // generated builders never mix types
+        (_, data) => dst.data = data,
+    }
+}
+
+fn merge_wildcard(dst: &mut Option<Box<FieldMask>>, src: Option<Box<FieldMask>>) {
+    match (dst, src) {
+        (Some(dst), Some(src)) => merge(dst, *src),
+        (dst @ None, src) => *dst = src,
+        (_, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_builders() {
+        let mut item = StructMask::default();
+        item.field(1, ScalarMask::whole());
+
+        let mut mask = StructMask::default();
+        mask.field(
+            13,
+            ListMask::<StructMask>::default()
+                .index(0)
+                .index_with(1, |item| {
+                    let mut item = item;
+                    item.field(2, ScalarMask::whole());
+                    item
+                })
+                .build(),
+        );
+        mask.field(
+            13,
+            ListMask::<StructMask>::default()
+                .index_with(1, |_| item.clone())
+                .build(),
+        );
+        mask.field(15, StrMapMask::<ScalarMask>::default().key("key1").build());
+        mask.field(16, IntMapMask::<ScalarMask>::default().each().build());
+        let mask = mask.build();
+
+        let (list_fm, exist) = mask.field(13);
+        assert!(exist);
+        let list_fm = list_fm.unwrap();
+        assert!(list_fm.int(0).1);
+        assert!(!list_fm.int(2).1);
+        let item_fm = list_fm.int(1).0.unwrap();
+        assert!(item_fm.field(1).1);
+        assert!(item_fm.field(2).1);
+        assert!(!item_fm.field(3).1);
+
+        let map_fm = mask.field(15).0.unwrap();
+        assert!(map_fm.str("key1").1);
+        assert!(!map_fm.str("key2").1);
+        assert!(mask.field(16).0.unwrap().int(100).1);
+        assert!(!mask.field(1).1);
+
+        // a whole value swallows its parts
+        let mut mask = StructMask::default();
+        mask.field(1, ListMask::<ScalarMask>::whole());
+        mask.field(1, ListMask::<ScalarMask>::default().index(0).build());
+        assert!(mask.build().field(1).0.unwrap().int(1).1);
+    }
+}
@@ -0,0 +1,495 @@
+//! Compact binary encoding of [`FieldMask`].
+//!
+//! The encoding is meant to carry a mask in request metadata across services,
+//! so that it can be applied without parsing paths against descriptors again:
+//!
+//! ```text
+//! mask   := version:u8 node
+//! node   := header:u8 [count:varint (key node)* [wildcard:node]]
+//! header := kind | BLACK | ALL | WILDCARD
+//! key    := zigzag varint     (struct, list, int map)
+//!         | len:varint utf-8  (str map)
+//! ```
+//!
+//! Keys are written in ascending order, so equal masks have equal encodings.
+
+use pilota::{BufMut, Bytes, BytesMut, FastStr};
+
+use crate::{FieldMask, FieldMaskData, FieldMaskError};
+
+/// The version written as the first byte of every encoded mask.
+pub const VERSION: u8 = 1;
+
+/// The encoded length accepted by [`FieldMask::decode`].
+pub const DEFAULT_MAX_ENCODED_LEN: usize = 64 * 1024;
+
+/// The deepest nesting accepted when decoding.
+const MAX_DEPTH: usize = 128;
+
+const KIND_INVALID: u8 = 0;
+const KIND_SCALAR: u8 = 1;
+const KIND_STRUCT: u8 = 2;
+const KIND_LIST: u8 = 3;
+const KIND_STR_MAP: u8 = 4;
+const KIND_INT_MAP: u8 = 5;
+const KIND_MASK: u8 = 0x0f;
+
+const FLAG_BLACK: u8 = 0x10;
+const FLAG_ALL: u8 = 0x20;
+const FLAG_WILDCARD: u8 = 0x40;
+
+impl FieldMask {
+    /// Encodes the mask in the compact binary format described in
+    /// [`wire`](crate::wire).
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(self.encoded_len());
+        buf.put_u8(VERSION);
+        encode_node(self, &mut buf);
+        buf.freeze()
+    }
+
+    /// Returns the length of [`FieldMask::encode`]'s output.
+    pub fn encoded_len(&self) -> usize {
+        1 + node_len(self)
+    }
+
+    /// Decodes a mask encoded by [`FieldMask::encode`], rejecting inputs
+    /// longer than [`DEFAULT_MAX_ENCODED_LEN`].
+    pub fn decode(buf: &[u8]) -> Result<FieldMask, FieldMaskError> {
+        Self::decode_with_max_len(buf, DEFAULT_MAX_ENCODED_LEN)
+    }
+
+    /// Decodes a mask encoded by [`FieldMask::encode`], rejecting inputs
+    /// longer than `max_len`.
+    pub fn decode_with_max_len(buf: &[u8], max_len: usize) -> Result<FieldMask, FieldMaskError> {
+        if buf.len() > max_len {
+            return Err(FieldMaskError::EncodingTooLarge {
+                len: buf.len(),
+                max_len,
+            });
+        }
+
+        let mut reader = Reader { buf, pos: 0 };
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(reader.error(format!("unsupported version {version}")));
+        }
+        let fm = reader.node(0)?;
+        if reader.pos != buf.len() {
+            return Err(reader.error("trailing bytes"));
+        }
+        Ok(fm)
+    }
+}
+
+fn sorted<K: Ord, V>(children: impl Iterator<Item = (K, V)>) -> Vec<(K, V)> {
+    let mut children = children.collect::<Vec<_>>();
+    children.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    children
+}
+
+fn header(fm: &FieldMask) -> u8 {
+    let (kind, is_all, wildcard) = match &fm.data {
+        FieldMaskData::Invalid => (KIND_INVALID, false, false),
+        FieldMaskData::Scalar => (KIND_SCALAR, false, false),
+        FieldMaskData::Struct { is_all, .. } => (KIND_STRUCT, *is_all, false),
+        FieldMaskData::List {
+            is_all, wildcard, ..
+        } => (KIND_LIST, *is_all, wildcard.is_some()),
+        FieldMaskData::StrMap {
+            is_all, wildcard, ..
+        } => (KIND_STR_MAP, *is_all, wildcard.is_some()),
+        FieldMaskData::IntMap {
+            is_all, wildcard, ..
+        } => (KIND_INT_MAP, *is_all, wildcard.is_some()),
+    };
+    let mut header = kind;
+    if fm.is_black {
+        header |= FLAG_BLACK;
+    }
+    if is_all {
+        header |= FLAG_ALL;
+    }
+    if wildcard {
+        header |= FLAG_WILDCARD;
+    }
+    header
+}
+
+fn encode_node(fm: &FieldMask, buf: &mut BytesMut) {
+    buf.put_u8(header(fm));
+    match &fm.data {
+        FieldMaskData::Invalid | FieldMaskData::Scalar => {}
+        FieldMaskData::Struct { children, .. } => {
+            put_varint(buf, children.len() as u64);
+            for (k, v) in sorted(children.iter()) {
+                put_varint(buf, zigzag(*k));
+                encode_node(v, buf);
+            }
+        }
+        FieldMaskData::List {
+            children, wildcard, ..
+        }
+        | FieldMaskData::IntMap {
+            children, wildcard, ..
+        } => {
+            put_varint(buf, children.len() as u64);
+            for (k, v) in sorted(children.iter()) {
+                put_varint(buf, zigzag(*k));
+                encode_node(v, buf);
+            }
+            if let Some(wildcard) = wildcard {
+                encode_node(wildcard, buf);
+            }
+        }
+        FieldMaskData::StrMap {
+            children, wildcard, ..
+        } => {
+            put_varint(buf, children.len() as u64);
+            for (k, v) in sorted(children.iter()) {
+                put_varint(buf, k.len() as u64);
+                buf.put_slice(k.as_bytes());
+                encode_node(v, buf);
+            }
+            if let Some(wildcard) = wildcard {
+                encode_node(wildcard, buf);
+            }
+        }
+    }
+}
+
+fn node_len(fm: &FieldMask) -> usize {
+    1 + match &fm.data {
+        FieldMaskData::Invalid | FieldMaskData::Scalar => 0,
+        FieldMaskData::Struct { children, .. } => {
+            varint_len(children.len() as u64)
+                + children
+                    .iter()
+                    .map(|(k, v)| varint_len(zigzag(*k)) + node_len(v))
+                    .sum::<usize>()
+        }
+        FieldMaskData::List {
+            children, wildcard, ..
+        }
+        | FieldMaskData::IntMap {
+            children, wildcard, ..
+        } => {
+            varint_len(children.len() as u64)
+                + children
+                    .iter()
+                    .map(|(k, v)| varint_len(zigzag(*k)) + node_len(v))
+                    .sum::<usize>()
+                + wildcard.as_deref().map_or(0, node_len)
+        }
+        FieldMaskData::StrMap {
+            children, wildcard, ..
+        } => {
+            varint_len(children.len() as u64)
+                + children
+                    .iter()
+                    .map(|(k, v)| varint_len(k.len() as u64) + k.len() + node_len(v))
+                    .sum::<usize>()
+                + wildcard.as_deref().map_or(0, node_len)
+        }
+    }
+}
+
+#[inline]
+fn zigzag(v: i32) -> u64 {
+    ((v << 1) ^ (v >> 31)) as u32 as u64
+}
+
+#[inline]
+fn unzigzag(v: u32) -> i32 {
+    ((v >> 1) as i32) ^ -((v & 1) as i32)
+}
+
+fn put_varint(buf: &mut BytesMut, mut v: u64) {
+    while v >= 0x80 {
+        buf.put_u8((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.put_u8(v as u8);
+}
+
+fn varint_len(v: u64) -> usize {
+    // the number of 7 bit groups, at least one
+    (64 - (v | 1).leading_zeros() as usize).div_ceil(7)
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: impl Into<String>) -> FieldMaskError {
+        FieldMaskError::InvalidEncoding {
+            message: FastStr::new(message.into()),
+            position: self.pos,
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, FieldMaskError> {
+        let b = *self
+            .buf
+            .get(self.pos)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, FieldMaskError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.u8()?;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b < 0x80 {
+                return Ok(v);
+            }
+        }
+        Err(self.error("varint overflow"))
+    }
+
+    fn int_key(&mut self) -> Result<i32, FieldMaskError> {
+        let v = self.varint()?;
+        let v = u32::try_from(v).map_err(|_| self.error("key out of range"))?;
+        Ok(unzigzag(v))
+    }
+
+    fn str_key(&mut self) -> Result<FastStr, FieldMaskError> {
+        let len = self.varint()? as usize;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let key = std::str::from_utf8(&self.buf[self.pos..end])
+            .map_err(|_| self.error("key is not valid utf-8"))?;
+        self.pos = end;
+        Ok(FastStr::new(key))
+    }
+
+    fn count(&mut self) -> Result<usize, FieldMaskError> {
+        let count = self.varint()?;
+        // every child takes at least two bytes, which bounds the allocation
+        if count > ((self.buf.len() - self.pos) / 2) as u64 {
+            return Err(self.error("child count exceeds input length"));
+        }
+        Ok(count as usize)
+    }
+
+    fn node(&mut self, depth: usize) -> Result<FieldMask, FieldMaskError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+
+        let header = self.u8()?;
+        if header & !(KIND_MASK | FLAG_BLACK | FLAG_ALL | FLAG_WILDCARD) != 0 {
+            return Err(self.error(format!("unknown flags in header {header:#04x}")));
+        }
+        let is_black = header & FLAG_BLACK != 0;
+        let is_all = header & FLAG_ALL != 0;
+        let has_wildcard = header & FLAG_WILDCARD != 0;
+        let kind = header & KIND_MASK;
+
+        if (is_all || has_wildcard) && matches!(kind, KIND_INVALID | KIND_SCALAR)
+            || has_wildcard && kind == KIND_STRUCT
+        {
+            return Err(self.error(format!("invalid flags for kind {kind}")));
+        }
+
+        let data = match kind {
+            KIND_INVALID => FieldMaskData::Invalid,
+            KIND_SCALAR => FieldMaskData::Scalar,
+            KIND_STRUCT => {
+                let count = self.count()?;
+                let mut children = pilota::AHashMap::with_capacity(count);
+                for _ in 0..count {
+                    let key = self.int_key()?;
+                    children.insert(key, Box::new(self.node(depth + 1)?));
+                }
+                FieldMaskData::Struct { children, is_all }
+            }
+            KIND_LIST | KIND_INT_MAP => {
+                let count = self.count()?;
+                let mut children = pilota::AHashMap::with_capacity(count);
+                for _ in 0..count {
+                    let key = self.int_key()?;
+                    children.insert(key, Box::new(self.node(depth + 1)?));
+                }
+                let wildcard = if has_wildcard {
+                    Some(Box::new(self.node(depth + 1)?))
+                } else {
+                    None
+                };
+                if kind == KIND_LIST {
+                    FieldMaskData::List {
+                        children,
+                        wildcard,
+                        is_all,
+                    }
+                } else {
+                    FieldMaskData::IntMap {
+                        children,
+                        wildcard,
+                        is_all,
+                    }
+                }
+            }
+            KIND_STR_MAP => {
+                let count = self.count()?;
+                let mut children = pilota::AHashMap::with_capacity(count);
+                for _ in 0..count {
+                    let key = self.str_key()?;
+                    children.insert(key, Box::new(self.node(depth + 1)?));
+                }
+                let wildcard = if has_wildcard {
+                    Some(Box::new(self.node(depth + 1)?))
+                } else {
+                    None
+                };
+                FieldMaskData::StrMap {
+                    children,
+                    wildcard,
+                    is_all,
+                }
+            }
+            kind => return Err(self.error(format!("unknown kind {kind}"))),
+        };
+
+        Ok(FieldMask { is_black, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use super::*;
+    use crate::{FieldMaskBuilder, Options};
+
+    #[test]
+    fn test_varint() {
+        for v in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut buf = BytesMut::new();
+            put_varint(&mut buf, v);
+            assert_eq!(buf.len(), varint_len(v));
+            let mut reader = Reader { buf: &buf, pos: 0 };
+            assert_eq!(reader.varint().unwrap(), v);
+        }
+        for v in [0, 1, -1, i32::MAX, i32::MIN] {
+            assert_eq!(unzigzag(zigzag(v) as u32), v);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let content = std::fs::read_to_string("../examples/idl/fieldmask.thrift").unwrap();
+        let mut ast = pilota_thrift_parser::FileParser::new(
+            pilota_thrift_parser::FileSource::new_with_path(
+                PathBuf::from("../examples/idl/fieldmask.thrift"),
+                &content,
+            )
+            .unwrap(),
+        )
+        .parse()
+        .unwrap();
+        ast.path = Arc::from(
+            PathBuf::from("../examples/idl/fieldmask.thrift")
+                .canonicalize()
+                .unwrap(),
+        );
+        let desc: pilota_thrift_reflect::thrift_reflection::FileDescriptor = (&ast).into();
+        let key = FastStr::new(ast.path.to_string_lossy());
+        pilota_thrift_reflect::service::Register::register(key, desc.clone());
+
+        let req_desc = desc
+            .find_struct_by_name("Request")
+            .unwrap()
+            .type_descriptor();
+        let paths = [
+            "$.f1",
+            "$.f11.a",
+            "$.f13[*].b",
+            "$.f13[0, 2]",
+            "$.f14{1, 2}",
+            "$.f15{\"key1\", \"k\\\"2\"}.a",
+            "$.f16{*}[0].b",
+            "$.f17[1]{\"key\"}",
+        ];
+        for black in [false, true] {
+            let fm = FieldMaskBuilder::new(&req_desc, &paths)
+                .with_options(Options::new().with_black_list_mode(black))
+                .build()
+                .unwrap();
+            let encoded = fm.encode();
+            assert_eq!(encoded.len(), fm.encoded_len());
+            assert_eq!(encoded[0], VERSION);
+            assert_eq!(FieldMask::decode(&encoded).unwrap(), fm);
+            // the encoding doesn't depend on the iteration order of the children
+            assert_eq!(fm.clone().encode(), encoded);
+        }
+
+        let all = FieldMaskBuilder::new(&req_desc, &["$"]).build().unwrap();
+        assert_eq!(all.encode().as_ref(), &[VERSION, KIND_STRUCT | FLAG_ALL, 0]);
+        assert_eq!(
+            FieldMask::decode(&FieldMask::default().encode()).unwrap(),
+            FieldMask::default()
+        );
+
+        let encoded = FieldMaskBuilder::new(&req_desc, &paths)
+            .build()
+            .unwrap()
+            .encode();
+        assert!(matches!(
+            FieldMask::decode_with_max_len(&encoded, encoded.len() - 1),
+            Err(FieldMaskError::EncodingTooLarge { .. })
+        ));
+        for len in 0..encoded.len() {
+            assert!(matches!(
+                FieldMask::decode(&encoded[..len]),
+                Err(FieldMaskError::InvalidEncoding { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let invalid: &[&[u8]] = &[
+            // unsupported version
+            &[2, KIND_SCALAR],
+            // trailing bytes
+            &[VERSION, KIND_SCALAR, 0],
+            // unknown kind
+            &[VERSION, 9],
+            // unknown flag
+            &[VERSION, KIND_SCALAR | 0x80],
+            // wildcard on a struct
+            &[VERSION, KIND_STRUCT | FLAG_WILDCARD, 0, KIND_SCALAR],
+            // child count larger than the input
+            &[VERSION, KIND_STRUCT, 100, 2, KIND_SCALAR],
+            // invalid utf-8 key
+            &[VERSION, KIND_STR_MAP, 1, 1, 0xff, KIND_SCALAR],
+        ];
+        for buf in invalid {
+            assert!(
+                matches!(
+                    FieldMask::decode(buf),
+                    Err(FieldMaskError::InvalidEncoding { .. })
+                ),
+                "{buf:?}"
+            );
+        }
+
+        let mut deep = vec![VERSION];
+        for _ in 0..=MAX_DEPTH {
+            deep.extend([KIND_STRUCT, 1, 2]);
+        }
+        deep.push(KIND_SCALAR);
+        assert!(matches!(
+            FieldMask::decode(&deep),
+            Err(FieldMaskError::InvalidEncoding { .. })
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+//! Formats thrift files in place.
+//!
+//! ```text
+//! thrift-fmt [--check] [FILE]...
+//! ```
+//!
+//! Without files, it formats the standard input to the standard output. With
+//! `--check`, it writes nothing and fails if a file is not formatted.
+
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use pilota_thrift_parser::{FileParser, FileSource, format};
+
+const USAGE: &str = "usage: thrift-fmt [--check] [FILE]...";
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option `{arg}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if files.is_empty() {
+        let mut source = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut source) {
+            eprintln!("failed to read the standard input: {e}");
+            return ExitCode::FAILURE;
+        }
+        let formatted = match FileParser::new(FileSource::new(&source)).parse() {
+            Ok(file) => format(&file),
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        if check {
+            return if formatted == source {
+                ExitCode::SUCCESS
+            } else {
+                eprintln!("the standard input is not formatted");
+                ExitCode::FAILURE
+            };
+        }
+        let _ = std::io::stdout().write_all(formatted.as_bytes());
+        return ExitCode::SUCCESS;
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for path in files {
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("failed to read {}: {e}", path.display());
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let file = FileSource::new_with_path(path.clone(), &source)
+            .and_then(|source| FileParser::new(source).parse());
+        let formatted = match file {
+            Ok(file) => format(&file),
+            Err(e) => {
+                eprintln!("{e}");
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("{} is not formatted", path.display());
+            code = ExitCode::FAILURE;
+        } else if let Err(e) = std::fs::write(&path, formatted) {
+            eprintln!("failed to write {}: {e}", path.display());
+            code = ExitCode::FAILURE;
+        }
+    }
+    code
+}
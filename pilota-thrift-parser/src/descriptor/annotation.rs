@@ -1,11 +1,12 @@
 use std::ops::Deref;
 
-use super::Literal;
+use super::{Literal, Span};
 
 #[derive(Debug, Clone)]
 pub struct Annotation {
     pub key: String,
     pub value: Literal,
+    pub span: Span,
 }
 
 impl Deref for Annotations {
@@ -2,7 +2,7 @@ use std::{ops::Deref, sync::Arc};
 
 use faststr::FastStr;
 
-use super::{Annotations, Ident, Literal, Path, Type};
+use super::{Annotations, Ident, Literal, Path, Span, Type};
 
 #[derive(Debug, Clone)]
 pub enum ConstValue {
@@ -23,6 +23,7 @@ pub struct Constant {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-pub use super::{Annotations, Ident, IntConstant};
+pub use super::{Annotations, Ident, IntConstant, Span};
 
 #[derive(Debug)]
 pub struct EnumValue {
@@ -9,6 +9,7 @@ pub struct EnumValue {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -18,4 +19,5 @@ pub struct Enum {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-use super::{Annotations, ConstValue, Ident, Type};
+use super::{Annotations, ConstValue, Ident, Span, Type};
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub enum Attribute {
@@ -20,4 +20,5 @@ pub struct Field {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-use super::{Annotations, Field, Ident, Type};
+use super::{Annotations, Field, Ident, Span, Type};
 
 #[derive(Debug)]
 pub struct Function {
@@ -12,4 +12,5 @@ pub struct Function {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
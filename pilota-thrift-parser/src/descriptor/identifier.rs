@@ -3,8 +3,11 @@ use std::{
     sync::Arc,
 };
 
+use super::Span;
+
+/// An identifier and its span.
 #[derive(Debug, Clone)]
-pub struct Ident(pub Arc<str>);
+pub struct Ident(pub Arc<str>, pub Span);
 
 impl PartialEq<&str> for Ident {
     fn eq(&self, other: &&str) -> bool {
@@ -32,7 +35,7 @@ impl Ident {
 
 impl<T: Into<Arc<str>>> From<T> for Ident {
     fn from(s: T) -> Self {
-        Ident(s.into())
+        Ident(s.into(), Span::default())
     }
 }
 
@@ -1,12 +1,13 @@
 use faststr::FastStr;
 
-use super::Literal;
+use super::{Literal, Span};
 
 #[derive(Debug)]
 pub struct Include {
     pub path: Literal,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -14,4 +15,5 @@ pub struct CppInclude {
     pub path: Literal,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
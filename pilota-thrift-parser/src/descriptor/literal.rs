@@ -1,7 +1,10 @@
 use std::ops::Deref;
 
+use super::Span;
+
+/// A string literal, unquoted, and its span.
 #[derive(Debug, Clone)]
-pub struct Literal(pub String);
+pub struct Literal(pub String, pub Span);
 
 impl Deref for Literal {
     type Target = str;
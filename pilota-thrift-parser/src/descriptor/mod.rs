@@ -8,6 +8,7 @@ mod include;
 mod literal;
 mod namespace;
 mod service;
+mod span;
 mod struct_;
 mod ty;
 mod typedef;
@@ -26,6 +27,8 @@ pub use include::{CppInclude, Include};
 pub use literal::Literal;
 pub use namespace::{Namespace, Scope};
 pub use service::Service;
+pub(crate) use span::line_col;
+pub use span::{FileId, Span};
 pub use struct_::{Exception, Struct, StructLike, Union};
 pub use ty::{CppType, Ty, Type};
 pub use typedef::Typedef;
@@ -35,6 +38,7 @@ pub struct Components {}
 #[derive(Debug, Clone)]
 pub struct Path {
     pub segments: Arc<[Ident]>,
+    pub span: Span,
 }
 
 impl<Item> FromIterator<Item> for Path
@@ -44,6 +48,7 @@ where
     fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
         Path {
             segments: iter.into_iter().map(Into::into).collect(),
+            span: Span::default(),
         }
     }
 }
@@ -83,11 +88,14 @@ item_from!(Service);
 #[derive(Default, Debug)]
 pub struct File {
     pub path: Arc<PathBuf>,
+    pub file_id: FileId,
     pub uuid: FastStr,
     pub package: Option<Path>,
     pub items: Vec<Item>,
     pub descriptor: Bytes,
     pub comments: FastStr,
+    /// The comments after the last item.
+    pub trailing_comments: FastStr,
 }
 
 impl PartialEq for File {
@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-use crate::{Annotations, Path};
+use crate::{Annotations, Path, Span};
 
 #[derive(Debug, Clone)]
 pub struct Scope(pub String);
@@ -12,4 +12,5 @@ pub struct Namespace {
     pub annotations: Option<Annotations>,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
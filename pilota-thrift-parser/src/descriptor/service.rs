@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-use super::{Annotations, Function, Ident, Path};
+use super::{Annotations, Function, Ident, Path, Span};
 
 #[derive(Debug)]
 pub struct Service {
@@ -10,4 +10,5 @@ pub struct Service {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
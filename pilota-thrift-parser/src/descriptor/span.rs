@@ -0,0 +1,61 @@
+use std::ops::Range;
+
+/// Identifies the file a [`Span`] points into, as given by
+/// [`FileSource::with_file_id`](crate::FileSource::with_file_id).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(pub u32);
+
+/// The byte range of a node in the source of its file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file_id: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file_id: FileId, start: usize, end: usize) -> Self {
+        Span {
+            file_id,
+            start,
+            end,
+        }
+    }
+
+    /// Returns the span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..self
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the 1-based line and column of the start of the span in
+    /// `source`.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        line_col(source, self.start)
+    }
+}
+
+pub(crate) fn line_col(source: &str, pos: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut col = 1;
+
+    for (i, ch) in source.char_indices() {
+        if i >= pos {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+
+    (line, col)
+}
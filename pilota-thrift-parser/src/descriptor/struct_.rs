@@ -2,13 +2,14 @@ use std::ops::{Deref, DerefMut};
 
 use faststr::FastStr;
 
-use super::{Annotations, Field, Ident};
+use super::{Annotations, Field, Ident, Span};
 
 #[derive(Debug)]
 pub struct Struct {
     pub struct_like: StructLike,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 macro_rules! struct_like {
@@ -35,6 +36,7 @@ pub struct Union {
     pub struct_like: StructLike,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 struct_like!(Union);
@@ -44,6 +46,7 @@ pub struct Exception {
     pub struct_like: StructLike,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
 
 struct_like!(Exception);
@@ -54,4 +57,5 @@ pub struct StructLike {
     pub fields: Vec<Field>,
     pub annotations: Annotations,
     pub comments: FastStr,
+    pub span: Span,
 }
@@ -1,10 +1,10 @@
 use std::{ops::Deref, sync::Arc};
 
-use super::{Annotations, Literal, Path};
+use super::{Annotations, Literal, Path, Span};
 
-/// Type with annotations
+/// Type with annotations and its span
 #[derive(Debug, Clone)]
-pub struct Type(pub Ty, pub Annotations);
+pub struct Type(pub Ty, pub Annotations, pub Span);
 
 impl Deref for Type {
     type Target = Ty;
@@ -1,6 +1,6 @@
 use faststr::FastStr;
 
-use super::{Annotations, Ident, Type};
+use super::{Annotations, Ident, Span, Type};
 
 #[derive(Debug)]
 pub struct Typedef {
@@ -9,4 +9,5 @@ pub struct Typedef {
     pub annotations: Annotations,
     pub leading_comments: FastStr,
     pub trailing_comments: FastStr,
+    pub span: Span,
 }
@@ -0,0 +1,613 @@
+//! A pretty-printer of thrift files.
+//!
+//! [`format`] renders a parsed [`File`] back to thrift text in one canonical
+//! style:
+//!
+//! - members are indented by four spaces, and field ids are right aligned in
+//!   each struct and argument list;
+//! - fields and enum values end with `,`, and string literals use double
+//!   quotes;
+//! - annotations are sorted by key, and empty annotation lists are dropped;
+//! - includes and namespaces are grouped, and every other item is followed by
+//!   a blank line.
+//!
+//! Leading and trailing comments are kept, as are comments at the end of the
+//! body of a struct, union or exception. Comments at the end of the body of
+//! an enum or a service are moved above it, since the parser does not tell
+//! them from its leading comments, and comments between the tokens of a
+//! definition are dropped.
+//!
+//! Formatting is idempotent: formatting the output again yields the same
+//! text.
+
+use chumsky::prelude::*;
+
+use crate::{
+    Annotations, Attribute, Components, ConstValue, Enum, Error, Field, File, FileParser,
+    FileSource, Function, Item, Path, Service, StructLike, Ty, Type,
+};
+
+const INDENT: &str = "    ";
+
+/// Renders `file` as canonical thrift text.
+pub fn format(file: &File) -> String {
+    let mut printer = Printer::default();
+    printer.file(file);
+    printer.out
+}
+
+/// Parses `source` and renders it as canonical thrift text.
+pub fn format_source(source: &str) -> Result<String, Error> {
+    let file = FileParser::new(FileSource::new(source)).parse()?;
+    Ok(format(&file))
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+/// Which items are printed together, without blank lines between them.
+#[derive(PartialEq)]
+enum Group {
+    Include,
+    Namespace,
+    Definition,
+}
+
+impl Printer {
+    fn file(&mut self, file: &File) {
+        let mut last_group = None;
+        for item in &file.items {
+            let (group, leading) = match item {
+                Item::Include(i) => (Group::Include, &i.leading_comments),
+                Item::CppInclude(i) => (Group::Include, &i.leading_comments),
+                Item::Namespace(n) => (Group::Namespace, &n.leading_comments),
+                Item::Typedef(t) => (Group::Definition, &t.leading_comments),
+                Item::Constant(c) => (Group::Definition, &c.leading_comments),
+                Item::Enum(e) => (Group::Definition, &e.leading_comments),
+                Item::Struct(s) => (Group::Definition, &s.leading_comments),
+                Item::Union(u) => (Group::Definition, &u.leading_comments),
+                Item::Exception(e) => (Group::Definition, &e.leading_comments),
+                Item::Service(s) => (Group::Definition, &s.leading_comments),
+            };
+            if let Some(last_group) = last_group {
+                if last_group != group
+                    || group == Group::Definition
+                    || !split_comments(leading).is_empty()
+                {
+                    self.out.push('\n');
+                }
+            }
+            self.item(item);
+            last_group = Some(group);
+        }
+
+        let trailing = split_comments(&file.trailing_comments);
+        if !trailing.is_empty() && !file.items.is_empty() {
+            self.out.push('\n');
+        }
+        for comment in trailing {
+            self.comment(&comment);
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Include(i) => {
+                self.comments(&i.leading_comments);
+                self.out.push_str("include ");
+                self.out.push_str(&literal(&i.path));
+                self.end_line(&i.trailing_comments);
+            }
+            Item::CppInclude(i) => {
+                self.comments(&i.leading_comments);
+                self.out.push_str("cpp_include ");
+                self.out.push_str(&literal(&i.path));
+                self.end_line(&i.trailing_comments);
+            }
+            Item::Namespace(n) => {
+                self.comments(&n.leading_comments);
+                self.out.push_str("namespace ");
+                self.out.push_str(&n.scope.0);
+                self.out.push(' ');
+                self.out.push_str(&path(&n.name));
+                if let Some(annotations) = &n.annotations {
+                    self.out.push_str(&self::annotations(annotations));
+                }
+                self.end_line(&n.trailing_comments);
+            }
+            Item::Typedef(t) => {
+                self.comments(&t.leading_comments);
+                self.out.push_str("typedef ");
+                self.out.push_str(&ty(&t.r#type));
+                self.out.push(' ');
+                self.out.push_str(&t.alias);
+                self.out.push_str(&annotations(&t.annotations));
+                self.end_line(&t.trailing_comments);
+            }
+            Item::Constant(c) => {
+                self.comments(&c.leading_comments);
+                self.out.push_str("const ");
+                self.out.push_str(&ty(&c.r#type));
+                self.out.push(' ');
+                self.out.push_str(&c.name);
+                self.out.push_str(" = ");
+                self.out.push_str(&const_value(&c.value));
+                self.out.push_str(&annotations(&c.annotations));
+                self.end_line(&c.trailing_comments);
+            }
+            Item::Enum(e) => self.enum_(e),
+            Item::Struct(s) => {
+                // the comments of the body are appended to the leading ones
+                let leading = s
+                    .leading_comments
+                    .strip_suffix(&*s.struct_like.comments)
+                    .unwrap_or(&s.leading_comments);
+                self.comments(leading);
+                self.struct_like("struct", &s.struct_like, &s.trailing_comments);
+            }
+            Item::Union(u) => {
+                self.comments(&u.leading_comments);
+                self.struct_like("union", &u.struct_like, &u.trailing_comments);
+            }
+            Item::Exception(e) => {
+                self.comments(&e.leading_comments);
+                self.struct_like("exception", &e.struct_like, &e.trailing_comments);
+            }
+            Item::Service(s) => self.service(s),
+        }
+    }
+
+    fn enum_(&mut self, e: &Enum) {
+        self.comments(&e.leading_comments);
+        self.out.push_str("enum ");
+        self.out.push_str(&e.name);
+        self.out.push_str(" {\n");
+        self.depth += 1;
+        for (i, value) in e.values.iter().enumerate() {
+            self.member_comments(i, &value.leading_comments);
+            self.indent();
+            self.out.push_str(&value.name);
+            if let Some(v) = value.value {
+                self.out.push_str(&format!(" = {}", v.0));
+            }
+            self.out.push_str(&annotations(&value.annotations));
+            self.out.push(',');
+            self.end_line(&value.trailing_comments);
+        }
+        self.depth -= 1;
+        self.out.push('}');
+        self.out.push_str(&annotations(&e.annotations));
+        self.end_line(&e.trailing_comments);
+    }
+
+    fn struct_like(&mut self, keyword: &str, s: &StructLike, trailing: &str) {
+        self.out.push_str(keyword);
+        self.out.push(' ');
+        self.out.push_str(&s.name);
+
+        let body_comments = split_comments(&s.comments);
+        if s.fields.is_empty() && body_comments.is_empty() {
+            self.out.push_str(" {}");
+        } else {
+            self.out.push_str(" {\n");
+            self.depth += 1;
+            self.fields(&s.fields, true);
+            for comment in body_comments {
+                self.comment(&comment);
+            }
+            self.depth -= 1;
+            self.out.push('}');
+        }
+        self.out.push_str(&annotations(&s.annotations));
+        self.end_line(trailing);
+    }
+
+    fn service(&mut self, s: &Service) {
+        self.comments(&s.leading_comments);
+        self.out.push_str("service ");
+        self.out.push_str(&s.name);
+        if let Some(extends) = &s.extends {
+            self.out.push_str(" extends ");
+            self.out.push_str(&path(extends));
+        }
+
+        if s.functions.is_empty() {
+            self.out.push_str(" {}");
+        } else {
+            self.out.push_str(" {\n");
+            self.depth += 1;
+            for (i, f) in s.functions.iter().enumerate() {
+                self.member_comments(i, &f.leading_comments);
+                self.function(f);
+            }
+            self.depth -= 1;
+            self.out.push('}');
+        }
+        self.out.push_str(&annotations(&s.annotations));
+        self.end_line(&s.trailing_comments);
+    }
+
+    fn function(&mut self, f: &Function) {
+        self.indent();
+        if f.oneway {
+            self.out.push_str("oneway ");
+        }
+        self.out.push_str(&ty(&f.result_type));
+        self.out.push(' ');
+        self.out.push_str(&f.name);
+        // the parser makes the arguments without an attribute required
+        self.field_list(&f.arguments, false);
+        if !f.throws.is_empty() {
+            self.out.push_str(" throws ");
+            self.field_list(&f.throws, true);
+        }
+        self.out.push_str(&annotations(&f.annotations));
+        self.end_line(&f.trailing_comments);
+    }
+
+    /// Prints the arguments of a function, on one line unless they have
+    /// comments.
+    fn field_list(&mut self, fields: &[Field], required: bool) {
+        let has_comments = fields.iter().any(|f| {
+            !split_comments(&f.leading_comments).is_empty() || !f.trailing_comments.is_empty()
+        });
+        if !has_comments {
+            let width = id_width(fields);
+            let fields = fields
+                .iter()
+                .map(|f| field(f, width, required))
+                .collect::<Vec<_>>();
+            self.out.push('(');
+            self.out.push_str(&fields.join(", "));
+            self.out.push(')');
+            return;
+        }
+
+        self.out.push_str("(\n");
+        self.depth += 1;
+        self.fields(fields, required);
+        self.depth -= 1;
+        self.indent();
+        self.out.push(')');
+    }
+
+    /// Prints one field per line, with aligned ids.
+    fn fields(&mut self, fields: &[Field], required: bool) {
+        let width = id_width(fields);
+        for (i, f) in fields.iter().enumerate() {
+            self.member_comments(i, &f.leading_comments);
+            self.indent();
+            self.out.push_str(&field(f, width, required));
+            self.out.push(',');
+            self.end_line(&f.trailing_comments);
+        }
+    }
+
+    /// Prints the leading comments of the `i`th member of a body, after a
+    /// blank line unless it is the first one.
+    fn member_comments(&mut self, i: usize, comments: &str) {
+        let comments = split_comments(comments);
+        if i > 0 && !comments.is_empty() {
+            self.out.push('\n');
+        }
+        for comment in comments {
+            self.comment(&comment);
+        }
+    }
+
+    fn comments(&mut self, comments: &str) {
+        for comment in split_comments(comments) {
+            self.comment(&comment);
+        }
+    }
+
+    /// Prints a comment on its own lines, aligning the `*` of the lines of a
+    /// block comment.
+    fn comment(&mut self, comment: &str) {
+        for (i, line) in comment.trim().lines().enumerate() {
+            let line = if i == 0 { line.trim_end() } else { line.trim() };
+            if !line.is_empty() {
+                self.indent();
+                if i > 0 && line.starts_with('*') {
+                    self.out.push(' ');
+                }
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+        }
+    }
+
+    fn end_line(&mut self, trailing_comment: &str) {
+        let trailing_comment = trailing_comment.trim();
+        if !trailing_comment.is_empty() {
+            self.out.push(' ');
+            self.out.push_str(trailing_comment);
+        }
+        self.out.push('\n');
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+    }
+}
+
+/// Splits comments joined by the parser back into single comments.
+fn split_comments(comments: &str) -> Vec<String> {
+    Components::comment()
+        .map(|c| c.to_string())
+        .repeated()
+        .collect::<Vec<_>>()
+        .then_ignore(Components::blank())
+        .parse(comments)
+        .into_output()
+        .unwrap_or_default()
+}
+
+fn id_width(fields: &[Field]) -> usize {
+    fields
+        .iter()
+        .map(|f| f.id.to_string().len())
+        .max()
+        .unwrap_or_default()
+}
+
+fn field(f: &Field, width: usize, required: bool) -> String {
+    let attribute = match f.attribute {
+        Attribute::Required if required => "required ",
+        Attribute::Optional => "optional ",
+        _ => "",
+    };
+    let mut s = format!("{:>width$}: {attribute}{} {}", f.id, ty(&f.ty), &*f.name);
+    if let Some(default) = &f.default {
+        s.push_str(" = ");
+        s.push_str(&const_value(default));
+    }
+    s.push_str(&annotations(&f.annotations));
+    s
+}
+
+fn ty(t: &Type) -> String {
+    let cpp_type = |cpp_type: &Option<crate::CppType>| match cpp_type {
+        Some(cpp_type) => format!(" cpp_type {}", literal(&cpp_type.0)),
+        None => String::new(),
+    };
+    let mut s = match &t.0 {
+        Ty::String => "string".to_string(),
+        Ty::Void => "void".to_string(),
+        Ty::Byte => "byte".to_string(),
+        Ty::Bool => "bool".to_string(),
+        Ty::Binary => "binary".to_string(),
+        Ty::I8 => "i8".to_string(),
+        Ty::I16 => "i16".to_string(),
+        Ty::I32 => "i32".to_string(),
+        Ty::I64 => "i64".to_string(),
+        Ty::Double => "double".to_string(),
+        Ty::Uuid => "uuid".to_string(),
+        Ty::List { value, cpp_type: c } => format!("list<{}>{}", ty(value), cpp_type(c)),
+        Ty::Set { value, cpp_type: c } => format!("set{}<{}>", cpp_type(c), ty(value)),
+        Ty::Map {
+            key,
+            value,
+            cpp_type: c,
+        } => format!("map{}<{}, {}>", cpp_type(c), ty(key), ty(value)),
+        Ty::Path(p) => path(p),
+    };
+    s.push_str(&annotations(&t.1));
+    s
+}
+
+fn path(p: &Path) -> String {
+    p.segments
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn const_value(value: &ConstValue) -> String {
+    match value {
+        ConstValue::Bool(b) => b.to_string(),
+        ConstValue::Path(p) => path(p),
+        ConstValue::String(s) => literal(s),
+        ConstValue::Int(i) => i.0.to_string(),
+        ConstValue::Double(d) => d.0.to_string(),
+        ConstValue::List(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(const_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ConstValue::Map(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", const_value(k), const_value(v)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Renders the annotations sorted by key, with a leading space.
+fn annotations(annotations: &Annotations) -> String {
+    if annotations.is_empty() {
+        return String::new();
+    }
+    let mut annotations = annotations.iter().collect::<Vec<_>>();
+    annotations.sort_by(|a, b| a.key.cmp(&b.key));
+    let annotations = annotations
+        .iter()
+        .map(|a| format!("{} = {}", a.key, literal(&a.value)))
+        .collect::<Vec<_>>();
+    format!(" ({})", annotations.join(", "))
+}
+
+/// Quotes a string literal with the escapes the parser understands.
+fn literal(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+/*
+   * Licensed under the Apache License.
+ */
+include 'base.thrift'
+include "shared.thrift" // shared types
+namespace rs demo
+# the go namespace
+namespace go demo (b = "2", a = '1')
+
+typedef list<string> Names
+
+const map<string, list<i32>> M = {"a": [1, 0x2], 'b': []}
+const double PI = 3.14 ;
+
+/** Status */
+enum Status { OK = 0, ERR (k = "v") // error
+}
+
+// A user
+struct User {
+    1: required i64 id (go.tag = 'json:"id"', api.query = "id"),
+    // the name
+    10: optional string name = "anon",
+    2: list<base.Item> items;
+    // end of user
+} (x = "y")
+
+union U {}
+exception E { 1: string msg }
+
+service S extends base.Base {
+    oneway void ping()
+    // get a user
+    User get(1: i64 id, 2: string name) throws (1: E e) (api.get = "/user")
+    void set(
+        // the user
+        1: User user, // new value
+    )
+}
+// the end
+"#;
+
+    const EXPECTED: &str = r#"/*
+ * Licensed under the Apache License.
+ */
+include "base.thrift"
+include "shared.thrift" // shared types
+
+namespace rs demo
+
+// the go namespace
+namespace go demo (a = "1", b = "2")
+
+typedef list<string> Names
+
+const map<string, list<i32>> M = {"a": [1, 2], "b": []}
+
+const double PI = 3.14
+
+/** Status */
+enum Status {
+    OK = 0,
+    ERR (k = "v"), // error
+}
+
+// A user
+struct User {
+     1: required i64 id (api.query = "id", go.tag = "json:\"id\""),
+
+    // the name
+    10: optional string name = "anon",
+     2: list<base.Item> items,
+    // end of user
+} (x = "y")
+
+union U {}
+
+exception E {
+    1: string msg,
+}
+
+service S extends base.Base {
+    oneway void ping()
+
+    // get a user
+    User get(1: i64 id, 2: string name) throws (1: E e) (api.get = "/user")
+    void set(
+        // the user
+        1: User user, // new value
+    )
+}
+
+// the end
+"#;
+
+    #[test]
+    fn test_format() {
+        let formatted = format_source(SOURCE).unwrap();
+        assert_eq!(formatted, EXPECTED);
+    }
+
+    #[test]
+    fn test_format_idempotent() {
+        let formatted = format_source(SOURCE).unwrap();
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    /// Formats the idls of the pilota-build tests, which must reparse to the
+    /// same items and format to themselves.
+    #[test]
+    fn test_format_corpus() {
+        use crate::{FileParser, FileSource};
+
+        let root =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../pilota-build/test_data");
+        let dirs = ["thrift", "thrift_with_field_mask"];
+        for entry in dirs
+            .iter()
+            .flat_map(|dir| std::fs::read_dir(root.join(dir)).unwrap())
+        {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "thrift") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let file = FileParser::new(FileSource::new(&source)).parse().unwrap();
+
+            let formatted = format(&file);
+            let reparsed = FileParser::new(FileSource::new(&formatted))
+                .parse()
+                .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            assert_eq!(reparsed.items.len(), file.items.len(), "{}", path.display());
+            assert_eq!(
+                format_source(&formatted).unwrap(),
+                formatted,
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
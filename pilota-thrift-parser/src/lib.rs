@@ -4,9 +4,11 @@
 #![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 
 pub mod descriptor;
+pub mod formatter;
 pub mod parser;
 
 pub use descriptor::*;
+pub use formatter::{format, format_source};
 pub use parser::{
     error::Error,
     thrift::{FileParser, FileSource},
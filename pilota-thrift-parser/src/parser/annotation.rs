@@ -7,7 +7,7 @@ use crate::{
 };
 
 impl Annotation {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Annotations, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Annotations, Extra<'a>> {
         let leading_blank = Components::blank_with_comments().or_not();
 
         let key = Ident::ident_with_dot();
@@ -16,7 +16,8 @@ impl Annotation {
         let annotation = key
             .then_ignore(just("=").padded_by(Components::blank_with_comments().or_not()))
             .then(value)
-            .map(|(key, value)| Annotation { key, value })
+            .spanned()
+            .map(|((key, value), span)| Annotation { key, value, span })
             .then_ignore(Components::blank_with_comments().or_not());
 
         let separator =
@@ -8,7 +8,7 @@ use super::super::{
 use crate::{Annotation, Literal, Type};
 
 impl ConstValue {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, ConstValue, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, ConstValue, Extra<'a>> {
         recursive(|const_value| {
             let list_value = just("[")
                 .ignore_then(
@@ -58,29 +58,36 @@ impl ConstValue {
 }
 
 impl Constant {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Constant, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Constant, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("const"))
-            .then(Type::get_parser().padded_by(Components::blank_with_comments()))
-            .then(Ident::get_parser())
-            .then_ignore(just("=").padded_by(Components::blank_with_comments().or_not()))
-            .then(ConstValue::get_parser())
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("const")
+                    .ignore_then(Type::get_parser().padded_by(Components::blank_with_comments()))
+                    .then(Ident::parse())
+                    .then_ignore(just("=").padded_by(Components::blank_with_comments().or_not()))
+                    .then(ConstValue::get_parser())
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
-                |(((((comments, r#type), name), value), annotations), trailing_comments)| {
+                |(
+                    (comments, ((((r#type, name), value), annotations), span)),
+                    trailing_comments,
+                )| {
                     Constant {
                         leading_comments: FastStr::from(comments.join("\n\n")),
-                        name: Ident(name.into()),
+                        name,
                         r#type,
                         value,
                         annotations: annotations.unwrap_or_default(),
                         trailing_comments: trailing_comments.unwrap_or_default(),
+                        span,
                     }
                 },
             )
@@ -88,7 +95,7 @@ impl Constant {
 }
 
 impl IntConstant {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, IntConstant, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, IntConstant, Extra<'a>> {
         recursive(|int_constant| {
             choice((
                 just("-")
@@ -115,7 +122,7 @@ impl IntConstant {
 }
 
 impl DoubleConstant {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, DoubleConstant, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, DoubleConstant, Extra<'a>> {
         let digits = any()
             .filter(|c: &char| c.is_ascii_digit())
             .repeated()
@@ -8,58 +8,65 @@ use super::super::{
 use crate::{Annotation, IntConstant};
 
 impl EnumValue {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, EnumValue, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, EnumValue, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then(Ident::get_parser())
             .then(
-                Components::blank_with_comments()
-                    .or_not()
-                    .ignore_then(just("="))
-                    .ignore_then(Components::blank_with_comments().or_not())
-                    .ignore_then(IntConstant::parse())
-                    .or_not(),
+                Ident::parse()
+                    .then(
+                        Components::blank_with_comments()
+                            .or_not()
+                            .ignore_then(just("="))
+                            .ignore_then(Components::blank_with_comments().or_not())
+                            .ignore_then(IntConstant::parse())
+                            .or_not(),
+                    )
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
             )
-            .then(Annotation::get_parser().or_not())
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
-                |((((comments, name), value), annotations), trailing_comments)| EnumValue {
+                |((comments, (((name, value), annotations), span)), trailing_comments)| EnumValue {
                     leading_comments: FastStr::from(comments.join("\n\n")),
-                    name: Ident(name.into()),
+                    name,
                     value,
                     annotations: annotations.unwrap_or_default(),
                     trailing_comments: trailing_comments.unwrap_or_default(),
+                    span,
                 },
             )
     }
 }
 
 impl Enum {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Enum, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Enum, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("enum"))
-            .then_ignore(Components::blank_with_comments())
-            .then(Ident::get_parser())
-            .then(Components::comment().repeated().collect::<Vec<_>>())
-            .then_ignore(Components::blank().or_not())
-            .then_ignore(just("{"))
-            .then(EnumValue::get_parser().repeated().collect())
-            .then(Components::comment().repeated().collect::<Vec<_>>())
-            .then_ignore(Components::blank().or_not())
-            .then_ignore(just("}"))
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("enum")
+                    .ignore_then(Components::blank_with_comments())
+                    .ignore_then(Ident::parse())
+                    .then(Components::comment().repeated().collect::<Vec<_>>())
+                    .then_ignore(Components::blank().or_not())
+                    .then_ignore(just("{"))
+                    .then(EnumValue::get_parser().repeated().collect())
+                    .then(Components::comment().repeated().collect::<Vec<_>>())
+                    .then_ignore(Components::blank().or_not())
+                    .then_ignore(just("}"))
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
                 |(
-                    (((((leading, name), name_comments), values), comments), annotations),
+                    (leading, (((((name, name_comments), values), comments), annotations), span)),
                     trailing,
                 )| Enum {
                     leading_comments: FastStr::from(format!(
@@ -68,10 +75,11 @@ impl Enum {
                         comments.join("\n\n"),
                         name_comments.join("\n\n"),
                     )),
-                    name: Ident(name.into()),
+                    name,
                     values,
                     annotations: annotations.unwrap_or_default(),
                     trailing_comments: trailing.unwrap_or_default(),
+                    span,
                 },
             )
     }
@@ -8,7 +8,7 @@ use super::super::{
 use crate::{Annotation, ConstValue, Type};
 
 impl Attribute {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Attribute, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Attribute, Extra<'a>> {
         choice((
             just("required").to(Attribute::Required),
             just("optional").to(Attribute::Optional),
@@ -17,39 +17,46 @@ impl Attribute {
 }
 
 impl Field {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Field, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Field, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then(text::int(10))
-            .then_ignore(just(":").padded_by(Components::blank_with_comments().or_not()))
-            .then(Attribute::get_parser().or_not())
-            .then(Type::get_parser().padded_by(Components::blank_with_comments().or_not()))
-            .then(Ident::get_parser().padded_by(Components::blank_with_comments().or_not()))
             .then(
-                just("=")
-                    .padded_by(Components::blank_with_comments().or_not())
-                    .ignore_then(ConstValue::get_parser())
-                    .or_not(),
+                text::int(10)
+                    .then_ignore(just(":").padded_by(Components::blank_with_comments().or_not()))
+                    .then(Attribute::get_parser().or_not())
+                    .then(Type::get_parser().padded_by(Components::blank_with_comments().or_not()))
+                    .then(Ident::parse())
+                    .then(
+                        Components::blank_with_comments()
+                            .or_not()
+                            .ignore_then(just("="))
+                            .then_ignore(Components::blank_with_comments().or_not())
+                            .ignore_then(ConstValue::get_parser())
+                            .or_not(),
+                    )
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
             )
-            .then(Annotation::get_parser().or_not())
+            .then_ignore(Components::blank_with_comments().or_not())
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
                 |(
-                    ((((((comments, id), attribute), r#type), name), value), annotations),
+                    (comments, ((((((id, attribute), r#type), name), value), annotations), span)),
                     trailing_comments,
                 )| Field {
                     leading_comments: FastStr::from(comments.join("\n\n")),
                     id: id.parse().unwrap(),
                     attribute: attribute.unwrap_or_default(),
                     ty: r#type,
-                    name: Ident(name.into()),
+                    name,
                     default: value,
                     annotations: annotations.unwrap_or_default(),
                     trailing_comments: trailing_comments.unwrap_or_default(),
+                    span,
                 },
             )
     }
@@ -8,7 +8,7 @@ use super::super::{
 use crate::{Annotation, Field, Type};
 
 impl Function {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Function, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Function, Extra<'a>> {
         let fields = Field::get_parser()
             .padded_by(Components::blank().or_not())
             .repeated()
@@ -29,22 +29,30 @@ impl Function {
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then(just("oneway").then_ignore(Components::blank()).or_not())
-            .then(Type::get_parser())
-            .then_ignore(Components::blank())
-            .then(Ident::get_parser())
-            .then_ignore(just("(").padded_by(Components::blank().or_not()))
-            .then(fields.clone().or_not())
-            .then_ignore(Components::blank_with_comments().or_not())
-            .then_ignore(just(")"))
-            .then(throws.or_not())
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("oneway")
+                    .then_ignore(Components::blank())
+                    .or_not()
+                    .then(Type::get_parser())
+                    .then_ignore(Components::blank())
+                    .then(Ident::parse())
+                    .then_ignore(just("(").padded_by(Components::blank().or_not()))
+                    .then(fields.clone().or_not())
+                    .then_ignore(Components::blank_with_comments().or_not())
+                    .then_ignore(just(")"))
+                    .then(throws.or_not())
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
                 |(
-                    ((((((comments, oneway), r#type), name), arguments), throws), annotations),
+                    (
+                        comments,
+                        ((((((oneway, r#type), name), arguments), throws), annotations), span),
+                    ),
                     trailing_comments,
                 )| {
                     let ow = oneway.is_some();
@@ -56,13 +64,14 @@ impl Function {
                     });
                     Function {
                         leading_comments: FastStr::from(comments.join("\n\n")),
-                        name: Ident(name.into()),
+                        name,
                         oneway: ow,
                         result_type: r#type,
                         arguments: args,
                         throws: throws.unwrap_or_default(),
                         annotations: annotations.unwrap_or_default(),
                         trailing_comments: trailing_comments.unwrap_or_default(),
+                        span,
                     }
                 },
             )
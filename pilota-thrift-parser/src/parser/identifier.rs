@@ -1,13 +1,20 @@
 use chumsky::prelude::*;
 
+use super::{Extra, SpannedExt};
 use crate::Ident;
 
 impl Ident {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, String, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Ident, Extra<'a>> {
+        Self::get_parser()
+            .spanned()
+            .map(|(name, span)| Ident(name.into(), span))
+    }
+
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, String, Extra<'a>> {
         text::ascii::ident().map(|ident: &str| ident.to_string())
     }
 
-    pub fn ident_with_dot<'a>() -> impl Parser<'a, &'a str, String, extra::Err<Rich<'a, char>>> {
+    pub fn ident_with_dot<'a>() -> impl Parser<'a, &'a str, String, Extra<'a>> {
         any()
             .filter(|c: &char| c.is_ascii_alphabetic() || *c == '_')
             .then(
@@ -8,39 +8,49 @@ use super::super::{
 use crate::Literal;
 
 impl Include {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Include, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Include, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("include").padded_by(Components::blank().or_not()))
-            .then(Literal::parse())
+            .then(
+                just("include")
+                    .then_ignore(Components::blank().or_not())
+                    .ignore_then(Literal::parse())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
-            .map(|((comments, path), trailing_comments)| Include {
+            .map(|((comments, (path, span)), trailing_comments)| Include {
                 leading_comments: FastStr::from(comments.join("\n\n")),
                 path,
                 trailing_comments: trailing_comments.unwrap_or_default(),
+                span,
             })
     }
 }
 
 impl CppInclude {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, CppInclude, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, CppInclude, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("cpp_include").padded_by(Components::blank().or_not()))
-            .then(Literal::parse())
+            .then(
+                just("cpp_include")
+                    .then_ignore(Components::blank().or_not())
+                    .ignore_then(Literal::parse())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
-            .map(|((comments, path), trailing_comments)| CppInclude {
+            .map(|((comments, (path, span)), trailing_comments)| CppInclude {
                 leading_comments: FastStr::from(comments.join("\n\n")),
                 path,
                 trailing_comments: trailing_comments.unwrap_or_default(),
+                span,
             })
     }
 }
@@ -1,8 +1,9 @@
 use chumsky::prelude::*;
 
-use super::super::descriptor::Literal;
+use super::{Extra, SpannedExt};
+use crate::descriptor::Literal;
 
-fn quoted_string<'a>(quote: char) -> impl Parser<'a, &'a str, String, extra::Err<Rich<'a, char>>> {
+fn quoted_string<'a>(quote: char) -> impl Parser<'a, &'a str, String, Extra<'a>> {
     let normal_char = none_of([quote, '\\']);
 
     let escape_char = just('\\').ignore_then(one_of(['\'', '"', 'n', '\\']));
@@ -19,17 +20,20 @@ fn quoted_string<'a>(quote: char) -> impl Parser<'a, &'a str, String, extra::Err
         .then_ignore(just(quote))
 }
 
-fn single_quote<'a>() -> impl Parser<'a, &'a str, String, extra::Err<Rich<'a, char>>> {
+fn single_quote<'a>() -> impl Parser<'a, &'a str, String, Extra<'a>> {
     quoted_string('\'')
 }
 
-fn double_quote<'a>() -> impl Parser<'a, &'a str, String, extra::Err<Rich<'a, char>>> {
+fn double_quote<'a>() -> impl Parser<'a, &'a str, String, Extra<'a>> {
     quoted_string('"')
 }
 
 impl Literal {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, Literal, extra::Err<Rich<'a, char>>> {
-        single_quote().map(Literal).or(double_quote().map(Literal))
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Literal, Extra<'a>> {
+        single_quote()
+            .or(double_quote())
+            .spanned()
+            .map(|(s, span)| Literal(s, span))
     }
 }
 
@@ -18,42 +18,78 @@ pub mod thrift;
 mod ty;
 mod typedef;
 
-use chumsky::prelude::*;
+use chumsky::{
+    input::{Checkpoint, Cursor},
+    inspector::Inspector,
+    prelude::*,
+};
 use faststr::FastStr;
 
-use super::descriptor::{Components, Path};
+use super::descriptor::{Components, FileId, Path, Span};
 use crate::Ident;
 
+/// The extra of every parser, whose state gives the id of the file that the
+/// spans point into.
+pub type Extra<'a> = extra::Full<Rich<'a, char>, State, ()>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct State {
+    pub file_id: FileId,
+}
+
+impl<'a> Inspector<'a, &'a str> for State {
+    type Checkpoint = ();
+
+    fn on_token(&mut self, _: &char) {}
+
+    fn on_save<'parse>(&self, _: &Cursor<'a, 'parse, &'a str>) -> Self::Checkpoint {}
+
+    fn on_rewind<'parse>(&mut self, _: &Checkpoint<'a, 'parse, &'a str, Self::Checkpoint>) {}
+}
+
+pub(crate) trait SpannedExt<'a, O>: Parser<'a, &'a str, O, Extra<'a>> + Sized {
+    /// Also yields the span of what the parser consumed.
+    fn spanned(self) -> impl Parser<'a, &'a str, (O, Span), Extra<'a>> {
+        self.map_with(|o, e| {
+            let span = e.span();
+            (o, Span::new(e.state().file_id, span.start, span.end))
+        })
+    }
+}
+
+impl<'a, O, P: Parser<'a, &'a str, O, Extra<'a>>> SpannedExt<'a, O> for P {}
+
 impl Path {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, Path, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Path, Extra<'a>> {
         Components::blank()
-            .ignore_then(Ident::get_parser())
-            .separated_by(just('.'))
-            .at_least(1)
-            .collect()
+            .ignore_then(
+                Ident::parse()
+                    .separated_by(just('.'))
+                    .at_least(1)
+                    .collect::<Vec<_>>()
+                    .spanned(),
+            )
             .then_ignore(Components::blank_without_newline())
-            .map(|s: Vec<String>| {
-                let idents: Vec<Ident> = s.into_iter().map(Ident::from).collect();
-                Path {
-                    segments: idents.into(),
-                }
+            .map(|(idents, span)| Path {
+                segments: idents.into(),
+                span,
             })
     }
 }
 
 impl Components {
-    pub fn list_separator<'a>() -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> {
+    pub fn list_separator<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
         Components::blank()
             .or_not()
             .ignore_then(one_of(",;"))
             .ignored()
     }
 
-    pub fn blank<'a>() -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> {
+    pub fn blank<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
         one_of(" \t\r\n").repeated().ignored()
     }
 
-    pub fn comment<'a>() -> impl Parser<'a, &'a str, FastStr, extra::Err<Rich<'a, char>>> {
+    pub fn comment<'a>() -> impl Parser<'a, &'a str, FastStr, Extra<'a>> {
         choice((
             just("//")
                 .then(
@@ -88,7 +124,7 @@ impl Components {
         ))
     }
 
-    pub fn trailing_comment<'a>() -> impl Parser<'a, &'a str, FastStr, extra::Err<Rich<'a, char>>> {
+    pub fn trailing_comment<'a>() -> impl Parser<'a, &'a str, FastStr, Extra<'a>> {
         just(" ")
             .repeated()
             .ignored()
@@ -127,7 +163,7 @@ impl Components {
             .map(|(_, c)| c)
     }
 
-    pub fn blank_with_comments<'a>() -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> {
+    pub fn blank_with_comments<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
         choice((
             just("//")
                 .then(any().and_is(just('\n').not()).repeated())
@@ -145,12 +181,11 @@ impl Components {
         .ignored()
     }
 
-    pub fn blank_without_newline<'a>() -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> {
+    pub fn blank_without_newline<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
         one_of(" \t\r").repeated().ignored()
     }
 
-    pub fn not_alphanumeric_or_underscore<'a>()
-    -> impl Parser<'a, &'a str, char, extra::Err<Rich<'a, char>>> {
+    pub fn not_alphanumeric_or_underscore<'a>() -> impl Parser<'a, &'a str, char, Extra<'a>> {
         any()
             .rewind()
             .filter(|c: &char| !c.is_alphanumeric() && *c != '_')
@@ -163,7 +198,7 @@ mod tests {
 
     #[test]
     fn test_blank() {
-        let _ = Components::blank().parse(" \t\r\n").unwrap();
+        Components::blank().parse(" \t\r\n").unwrap();
     }
 
     #[test]
@@ -181,31 +216,31 @@ mod tests {
 
     #[test]
     fn test_comment() {
-        let _ = Components::comment().parse("// foo").unwrap();
-        let _ = Components::comment()
+        Components::comment().parse("// foo").unwrap();
+        Components::comment()
             .parse("# From 133120 ~ 134143\n")
             .unwrap();
-        let _ = Components::comment().parse("/* foo */").unwrap();
+        Components::comment().parse("/* foo */").unwrap();
     }
 
     #[test]
     fn test_trailing_comment() {
-        let _ = Components::trailing_comment().parse(" // foo").unwrap();
-        let _ = Components::trailing_comment().parse(" # foo").unwrap();
-        let _ = Components::trailing_comment().parse(" /* foo */").unwrap();
+        Components::trailing_comment().parse(" // foo").unwrap();
+        Components::trailing_comment().parse(" # foo").unwrap();
+        Components::trailing_comment().parse(" /* foo */").unwrap();
     }
 
     #[test]
     fn test_blank_with_comments() {
-        let _ = Components::blank_with_comments().parse(" // foo").unwrap();
-        let _ = Components::blank_with_comments().parse(" # foo").unwrap();
-        let _ = Components::blank_with_comments()
+        Components::blank_with_comments().parse(" // foo").unwrap();
+        Components::blank_with_comments().parse(" # foo").unwrap();
+        Components::blank_with_comments()
             .parse(" /* foo */")
             .unwrap();
     }
 
     #[test]
     fn test_blank_without_newline() {
-        let _ = Components::blank_without_newline().parse(" \t\r").unwrap();
+        Components::blank_without_newline().parse(" \t\r").unwrap();
     }
 }
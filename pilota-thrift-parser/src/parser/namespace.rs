@@ -5,27 +5,31 @@ use super::super::parser::*;
 use crate::{Annotation, Namespace, Scope};
 
 impl Namespace {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Namespace, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Namespace, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("namespace"))
-            .then_ignore(Components::blank())
-            .then(Scope::parse())
-            .then_ignore(Components::blank())
-            .then(Path::parse())
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("namespace")
+                    .ignore_then(Components::blank())
+                    .ignore_then(Scope::parse())
+                    .then_ignore(Components::blank())
+                    .then(Path::parse())
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
-                |((((comments, scope), name), annotations), trailing_comments)| Namespace {
+                |((comments, (((scope, name), annotations), span)), trailing_comments)| Namespace {
                     leading_comments: FastStr::from(comments.join("\n\n")),
                     scope,
                     name,
                     annotations,
                     trailing_comments: trailing_comments.unwrap_or_default(),
+                    span,
                 },
             )
     }
@@ -36,7 +40,7 @@ fn is_white_space(c: &char) -> bool {
 }
 
 impl Scope {
-    fn parse<'a>() -> impl Parser<'a, &'a str, Scope, extra::Err<Rich<'a, char>>> {
+    fn parse<'a>() -> impl Parser<'a, &'a str, Scope, Extra<'a>> {
         any()
             .filter(|c: &char| !is_white_space(c))
             .repeated()
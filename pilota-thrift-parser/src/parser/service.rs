@@ -5,7 +5,7 @@ use super::super::{descriptor::Service, parser::*};
 use crate::{Annotation, Function, Ident};
 
 impl Service {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Service, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Service, Extra<'a>> {
         let extends = just("extends")
             .padded_by(Components::blank())
             .ignore_then(Path::parse());
@@ -19,26 +19,35 @@ impl Service {
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("service"))
-            .then_ignore(Components::blank())
-            .then(Ident::get_parser())
-            .then(extends.or_not())
-            .then(Components::comment().repeated().collect::<Vec<_>>())
-            .then_ignore(Components::blank().or_not())
-            .then_ignore(just("{"))
-            .then(functions)
-            .then(Components::comment().repeated().collect::<Vec<_>>())
-            .then_ignore(Components::blank().or_not())
-            .then_ignore(just("}"))
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("service")
+                    .ignore_then(Components::blank())
+                    .ignore_then(Ident::parse())
+                    .then(extends.or_not())
+                    .then(Components::comment().repeated().collect::<Vec<_>>())
+                    .then_ignore(Components::blank().or_not())
+                    .then_ignore(just("{"))
+                    .then(functions)
+                    .then(Components::comment().repeated().collect::<Vec<_>>())
+                    .then_ignore(Components::blank().or_not())
+                    .then_ignore(just("}"))
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
                 |(
                     (
-                        (((((leading, name), extends), name_comments), functions), comments),
-                        annotations,
+                        leading,
+                        (
+                            (
+                                ((((name, extends), name_comments), functions), comments),
+                                annotations,
+                            ),
+                            span,
+                        ),
                     ),
                     trailing,
                 )| {
@@ -49,11 +58,12 @@ impl Service {
                             name_comments.join("\n\n"),
                             comments.join("\n\n")
                         )),
-                        name: Ident(name.into()),
+                        name,
                         extends,
                         functions,
                         annotations: annotations.unwrap_or_default(),
                         trailing_comments: trailing.unwrap_or_default(),
+                        span,
                     }
                 },
             )
@@ -8,78 +8,99 @@ use super::super::{
 use crate::{Annotation, Field, Ident};
 
 impl Struct {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Struct, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Struct, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("struct"))
+            .then(just("struct").spanned())
             .then_ignore(Components::blank())
             .then(StructLike::parse())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
-            .map(|((comments, struct_like), trailing_comments)| {
-                let leading_comments = FastStr::from(format!(
-                    "{}\n\n{}",
-                    comments.join("\n\n"),
-                    struct_like.comments
-                ));
-                Struct {
-                    leading_comments,
-                    struct_like,
-                    trailing_comments: trailing_comments.unwrap_or_default(),
-                }
-            })
+            .map(
+                |(((comments, (_, keyword)), struct_like), trailing_comments)| {
+                    let leading_comments = FastStr::from(format!(
+                        "{}\n\n{}",
+                        comments.join("\n\n"),
+                        struct_like.comments
+                    ));
+                    Struct {
+                        leading_comments,
+                        span: keyword.to(struct_like.span),
+                        struct_like,
+                        trailing_comments: trailing_comments.unwrap_or_default(),
+                    }
+                },
+            )
     }
 }
 
 impl Union {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, Union, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Union, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("union"))
+            .then(just("union").spanned())
             .then_ignore(Components::blank())
             .then(StructLike::parse())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
-            .map(|((comments, struct_like), trailing_comments)| Union {
-                leading_comments: FastStr::from(comments.join("\n\n")),
-                struct_like,
-                trailing_comments: trailing_comments.unwrap_or_default(),
-            })
+            .map(
+                |(((comments, (_, keyword)), struct_like), trailing_comments)| Union {
+                    leading_comments: FastStr::from(comments.join("\n\n")),
+                    span: keyword.to(struct_like.span),
+                    struct_like,
+                    trailing_comments: trailing_comments.unwrap_or_default(),
+                },
+            )
     }
 }
 
 impl Exception {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, Exception, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Exception, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("exception"))
+            .then(just("exception").spanned())
             .then_ignore(Components::blank())
             .then(StructLike::parse())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
-            .map(|((comments, struct_like), trailing_comments)| Exception {
-                leading_comments: FastStr::from(comments.join("\n\n")),
-                struct_like,
-                trailing_comments: trailing_comments.unwrap_or_default(),
-            })
+            .map(
+                |(((comments, (_, keyword)), struct_like), trailing_comments)| Exception {
+                    leading_comments: FastStr::from(comments.join("\n\n")),
+                    span: keyword.to(struct_like.span),
+                    struct_like,
+                    trailing_comments: trailing_comments.unwrap_or_default(),
+                },
+            )
     }
 }
 
+/// Skips the rest of the line of a malformed field, but not the closing brace
+/// of the struct.
+fn skip_field<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
+    Components::blank_with_comments()
+        .ignore_then(none_of("}\n").repeated().at_least(1))
+        .ignored()
+}
+
 impl StructLike {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, StructLike, extra::Err<Rich<'a, char>>> {
-        Ident::get_parser()
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, StructLike, Extra<'a>> {
+        Ident::parse()
             .then(Components::comment().repeated().collect::<Vec<_>>())
             .then_ignore(Components::blank().or_not())
             .then_ignore(just("{"))
             .then(
                 Components::blank()
-                    .ignore_then(Field::get_parser())
+                    .ignore_then(
+                        Field::get_parser()
+                            .map(Some)
+                            .recover_with(via_parser(skip_field().map(|_| None))),
+                    )
                     .repeated()
                     .collect::<Vec<_>>(),
             )
@@ -87,17 +108,19 @@ impl StructLike {
             .then_ignore(Components::blank().or_not())
             .then_ignore(just("}"))
             .then(Annotation::get_parser().or_not())
+            .spanned()
             .then_ignore(Components::list_separator().or_not())
             .map(
-                |((((name, name_comments), fields), comments), annotations)| StructLike {
-                    name: Ident(name.into()),
-                    fields,
+                |(((((name, name_comments), fields), comments), annotations), span)| StructLike {
+                    name,
+                    fields: fields.into_iter().flatten().collect(),
                     annotations: annotations.unwrap_or_default(),
                     comments: FastStr::from(format!(
                         "{}\n\n{}",
                         name_comments.join("\n\n"),
                         comments.join("\n\n")
                     )),
+                    span,
                 },
             )
     }
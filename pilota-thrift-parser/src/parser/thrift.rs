@@ -6,12 +6,12 @@ use faststr::FastStr;
 
 use super::super::{descriptor::File, parser::*};
 use crate::{
-    Constant, CppInclude, Enum, Exception, Include, Item, Namespace, Service, Struct, Typedef,
-    Union,
+    Constant, CppInclude, Enum, Exception, FileId, Include, Item, Namespace, Service, Struct,
+    Typedef, Union, descriptor::line_col,
 };
 
 impl Item {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, Item, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, Item, Extra<'a>> {
         choice((
             Include::get_parser().map(Item::Include),
             CppInclude::parse().map(Item::CppInclude),
@@ -25,11 +25,40 @@ impl Item {
             Service::get_parser().map(Item::Service),
         ))
     }
+
+    /// Skips a malformed item, up to the next line starting with the keyword
+    /// of an item.
+    ///
+    /// It fails if only blanks and comments are left, so that they are not
+    /// reported as an error.
+    fn skip<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
+        let keyword = choice((
+            just("include"),
+            just("cpp_include"),
+            just("namespace"),
+            just("typedef"),
+            just("const"),
+            just("enum"),
+            just("struct"),
+            just("union"),
+            just("exception"),
+            just("service"),
+        ))
+        .then(Components::not_alphanumeric_or_underscore());
+        let next_item = just('\n')
+            .then(Components::blank_with_comments())
+            .then(keyword);
+
+        Components::blank_with_comments()
+            .ignore_then(any().and_is(next_item.not()).repeated().at_least(1))
+            .ignored()
+    }
 }
 
 pub struct FileSource<'a> {
     path: Option<PathBuf>,
     content: &'a str,
+    file_id: FileId,
 }
 
 impl<'a> FileSource<'a> {
@@ -37,6 +66,7 @@ impl<'a> FileSource<'a> {
         Self {
             path: None,
             content: inline,
+            file_id: FileId::default(),
         }
     }
 
@@ -48,8 +78,15 @@ impl<'a> FileSource<'a> {
         Ok(Self {
             path: Some(path),
             content,
+            file_id: FileId::default(),
         })
     }
+
+    /// Sets the file id recorded in the spans of the parsed nodes.
+    pub fn with_file_id(mut self, file_id: FileId) -> Self {
+        self.file_id = file_id;
+        self
+    }
 }
 
 pub struct FileParser<'a> {
@@ -62,9 +99,28 @@ impl<'a> FileParser<'a> {
     }
 
     pub fn parse(&self) -> Result<File, error::Error> {
+        match self.parse_recovering() {
+            (Some(ast), None) => Ok(ast),
+            (_, err) => Err(err.unwrap()),
+        }
+    }
+
+    /// Parses the file, skipping malformed items and fields to report all the
+    /// syntax errors at once.
+    ///
+    /// The file built from the well-formed items is returned along with the
+    /// errors, if any.
+    pub fn parse_recovering(&self) -> (Option<File>, Option<error::Error>) {
+        let mut state = State {
+            file_id: self.source.file_id,
+        };
         let (ast, errs) = File::get_parser()
-            .parse(self.source.content)
+            .parse_with_state(self.source.content, &mut state)
             .into_output_errors();
+        let ast = ast.map(|mut ast| {
+            ast.file_id = self.source.file_id;
+            ast
+        });
 
         let path_str = match &self.source.path {
             Some(path) => &path.display().to_string(),
@@ -115,13 +171,16 @@ impl<'a> FileParser<'a> {
             let summary = create_error_summary(&errs, path_str, self.source.content).into();
             let custom_error = CustomSyntaxError { report };
 
-            return Err(error::Error::Syntax {
-                summary,
-                source: anyhow::anyhow!(custom_error),
-            });
+            return (
+                ast,
+                Some(error::Error::Syntax {
+                    summary,
+                    source: anyhow::anyhow!(custom_error),
+                }),
+            );
         }
 
-        Ok(ast.unwrap())
+        (ast, None)
     }
 }
 
@@ -135,12 +194,12 @@ fn create_error_summary(errs: &[chumsky::error::Rich<char>], path_str: &str, tex
     if errs.len() == 1 {
         let err = &errs[0];
         // 计算行号和列号
-        let (line, col) = calculate_line_col(err.span().start, text);
+        let (line, col) = line_col(text, err.span().start);
         summary.push_str(&format!(" at line {}:{} - {}", line, col, err.reason()));
     } else {
         summary.push_str(&format!(" ({} errors found):", errs.len()));
         for (i, err) in errs.iter().enumerate() {
-            let (line, col) = calculate_line_col(err.span().start, text);
+            let (line, col) = line_col(text, err.span().start);
             summary.push_str(&format!(
                 "\n  {}. Line {}:{} - {}",
                 i + 1,
@@ -154,25 +213,6 @@ fn create_error_summary(errs: &[chumsky::error::Rich<char>], path_str: &str, tex
     summary
 }
 
-fn calculate_line_col(pos: usize, text: &str) -> (usize, usize) {
-    let mut line = 1;
-    let mut col = 1;
-
-    for (i, ch) in text.char_indices() {
-        if i >= pos {
-            break;
-        }
-        if ch == '\n' {
-            line += 1;
-            col = 1;
-        } else {
-            col += 1;
-        }
-    }
-
-    (line, col)
-}
-
 #[derive(Debug)]
 pub struct CustomSyntaxError {
     pub report: FastStr,
@@ -187,14 +227,17 @@ impl std::fmt::Display for CustomSyntaxError {
 impl std::error::Error for CustomSyntaxError {}
 
 impl File {
-    pub(crate) fn get_parser<'a>() -> impl Parser<'a, &'a str, File, extra::Err<Rich<'a, char>>> {
+    pub(crate) fn get_parser<'a>() -> impl Parser<'a, &'a str, File, Extra<'a>> {
         Item::parse()
+            .map(Some)
+            .recover_with(via_parser(Item::skip().map(|_| None)))
             .repeated()
-            .collect()
+            .collect::<Vec<_>>()
             .then(Components::comment().repeated().collect::<Vec<_>>())
             .then_ignore(Components::blank().or_not())
             .then_ignore(end())
-            .map(|(items, c): (Vec<Item>, Vec<FastStr>)| {
+            .map(|(items, c)| {
+                let items = items.into_iter().flatten().collect::<Vec<_>>();
                 let mut comments = String::default();
                 for item in &items {
                     match item {
@@ -213,15 +256,16 @@ impl File {
                         _ => {}
                     }
                 }
-                for comment in c {
+                for comment in &c {
                     comments.push('\n');
-                    comments.push_str(&comment);
+                    comments.push_str(comment);
                     comments.push('\n');
                 }
 
                 let mut file = File {
                     items,
                     comments: comments.into(),
+                    trailing_comments: c.join("\n\n").into(),
                     ..Default::default()
                 };
 
@@ -495,4 +539,131 @@ service TestService {
         let file = File::get_parser().parse(body).unwrap();
         println!("{:?}", file.comments);
     }
+
+    #[test]
+    fn test_recovery() {
+        let body = r#"namespace rs recovery
+
+struct A {
+    1: required i64 id,
+    2: optional string = name,
+    // comment
+    3: optional string title,
+}
+
+struct B {
+    1: i32 a
+    2 i32 b
+}
+
+enum Broken {
+    X = ,
+}
+
+service S {
+    A get(1: i64 id)
+}
+"#;
+        let (file, err) = FileParser::new(FileSource::new(body)).parse_recovering();
+        let file = file.unwrap();
+        let err = format!("{:?}", err.unwrap());
+        assert!(err.starts_with("Failed to parse thrift file: inline (3 errors found):"));
+        // the line and column of each error in the summary
+        let positions = err
+            .lines()
+            .filter_map(|line| line.split_once(". Line "))
+            .map(|(_, rest)| rest.split(" - ").next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(positions, ["5:24", "12:7", "16:9"]);
+
+        let names = file
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Namespace(_) => "namespace".to_string(),
+                Item::Struct(s) => {
+                    format!(
+                        "{}({})",
+                        &*s.name,
+                        s.fields
+                            .iter()
+                            .map(|f| &*f.name)
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    )
+                }
+                Item::Service(s) => s.name.to_string(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["namespace", "A(id,title)", "B(a)", "S"]);
+
+        assert!(FileParser::new(FileSource::new(body)).parse().is_err());
+    }
+
+    #[test]
+    fn test_spans() {
+        let body = r#"namespace rs span
+
+// comment
+struct Item {
+    1: required i64 id, // trailing
+    2: optional list<base.Base> bases (go.tag = "bases")
+}
+
+service ItemService {
+    Item get(1: i64 id)
+}
+"#;
+        let file = FileParser::new(FileSource::new(body).with_file_id(FileId(7)))
+            .parse()
+            .unwrap();
+        assert_eq!(file.file_id, FileId(7));
+        let text = |span: crate::Span| {
+            assert_eq!(span.file_id, FileId(7));
+            &body[span.range()]
+        };
+
+        let Item::Namespace(ns) = &file.items[0] else {
+            panic!()
+        };
+        assert_eq!(text(ns.span), "namespace rs span");
+        assert_eq!(text(ns.name.span), "span");
+
+        let Item::Struct(s) = &file.items[1] else {
+            panic!()
+        };
+        assert!(text(s.span).starts_with("struct Item {"));
+        assert!(text(s.span).ends_with('}'));
+        assert_eq!(s.span.line_col(body), (4, 1));
+        assert_eq!(text(s.name.1), "Item");
+
+        let id = &s.fields[0];
+        assert_eq!(text(id.span), "1: required i64 id");
+        assert_eq!(text(id.ty.2), "i64");
+        assert_eq!(text(id.name.1), "id");
+        assert_eq!(id.name.1.line_col(body), (5, 21));
+
+        let bases = &s.fields[1];
+        assert_eq!(
+            text(bases.span),
+            r#"2: optional list<base.Base> bases (go.tag = "bases")"#
+        );
+        let crate::Ty::List { value, .. } = &bases.ty.0 else {
+            panic!()
+        };
+        let crate::Ty::Path(path) = &value.0 else {
+            panic!()
+        };
+        assert_eq!(text(path.span), "base.Base");
+        assert_eq!(text(path.segments[1].1), "Base");
+        assert_eq!(text(bases.annotations[0].span), r#"go.tag = "bases""#);
+        assert_eq!(text(bases.annotations[0].value.1), r#""bases""#);
+
+        let Item::Service(service) = &file.items[2] else {
+            panic!()
+        };
+        assert_eq!(text(service.functions[0].span), "Item get(1: i64 id)");
+        assert_eq!(text(service.functions[0].arguments[0].span), "1: i64 id");
+    }
 }
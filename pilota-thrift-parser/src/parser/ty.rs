@@ -9,7 +9,7 @@ use super::super::{
 use crate::{Annotation, Literal};
 
 impl CppType {
-    pub fn parse<'a>() -> impl Parser<'a, &'a str, CppType, extra::Err<Rich<'a, char>>> {
+    pub fn parse<'a>() -> impl Parser<'a, &'a str, CppType, Extra<'a>> {
         just("cpp_type")
             .ignore_then(Components::blank())
             .ignore_then(Literal::parse())
@@ -18,7 +18,7 @@ impl CppType {
 }
 
 impl Type {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Type, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Type, Extra<'a>> {
         recursive(|self_parser| {
             let base_ty = choice((
                 just("string").to(Ty::String),
@@ -95,7 +95,8 @@ impl Type {
 
             ty_parser
                 .then(Annotation::get_parser().or_not())
-                .map(|(ty, an)| Type(ty, an.unwrap_or_default()))
+                .spanned()
+                .map(|((ty, an), span)| Type(ty, an.unwrap_or_default(), span))
                 .boxed()
         })
         .boxed()
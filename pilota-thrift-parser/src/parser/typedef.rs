@@ -5,27 +5,31 @@ use super::super::{descriptor::Typedef, parser::*};
 use crate::{Annotation, Type, descriptor::Ident};
 
 impl Typedef {
-    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Typedef, extra::Err<Rich<'a, char>>> {
+    pub fn get_parser<'a>() -> impl Parser<'a, &'a str, Typedef, Extra<'a>> {
         Components::comment()
             .repeated()
             .collect::<Vec<_>>()
             .then_ignore(Components::blank().or_not())
-            .then_ignore(just("typedef"))
-            .then_ignore(Components::blank())
-            .then(Type::get_parser())
-            .then_ignore(Components::blank())
-            .then(Ident::get_parser())
-            .then(Annotation::get_parser().or_not())
+            .then(
+                just("typedef")
+                    .ignore_then(Components::blank())
+                    .ignore_then(Type::get_parser())
+                    .then_ignore(Components::blank())
+                    .then(Ident::parse())
+                    .then(Annotation::get_parser().or_not())
+                    .spanned(),
+            )
             .then_ignore(Components::list_separator().or_not())
             .then(Components::trailing_comment().or_not())
             .then_ignore(Components::blank().or_not())
             .map(
-                |((((comments, r#type), alias), annotations), trailing_comments)| Typedef {
+                |((comments, (((r#type, alias), annotations), span)), trailing_comments)| Typedef {
                     leading_comments: FastStr::from(comments.join("\n\n")),
                     r#type,
-                    alias: Ident(alias.into()),
+                    alias,
                     annotations: annotations.unwrap_or_default(),
                     trailing_comments: trailing_comments.unwrap_or_default(),
+                    span,
                 },
             )
     }
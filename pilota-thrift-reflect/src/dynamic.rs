@@ -0,0 +1,546 @@
+//! Schema-driven thrift values.
+//!
+//! [`decode_dynamic`] reads a struct described by a [`StructDescriptor`] into
+//! a [`DynamicStruct`] without generated Rust types, and [`encode_dynamic`]
+//! writes it back. Types referenced by the descriptor are looked up in
+//! [`Register`], so the file declaring the struct and its includes must be
+//! registered first.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use pilota::{
+    FastStr,
+    thrift::{
+        ProtocolExceptionKind, TInputProtocol, TListIdentifier, TMapIdentifier, TOutputProtocol,
+        TSetIdentifier, TStructIdentifier, TType, ThriftException, new_protocol_exception,
+    },
+};
+
+use crate::{
+    ThriftType,
+    service::{IncludePath, Register},
+    thrift_reflection::*,
+};
+
+pub(crate) const MAX_DEPTH: usize = 64;
+
+const DYNAMIC_IDENT: TStructIdentifier = TStructIdentifier { name: "" };
+
+/// A thrift value decoded without generated types.
+///
+/// Enums are represented by their `I32` value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Double(f64),
+    String(FastStr),
+    Binary(Bytes),
+    Uuid([u8; 16]),
+    List(Vec<DynamicValue>),
+    Set(Vec<DynamicValue>),
+    Map(Vec<(DynamicValue, DynamicValue)>),
+    Struct(DynamicStruct),
+}
+
+impl DynamicValue {
+    /// Returns the wire type of this value.
+    pub fn ttype(&self) -> TType {
+        match self {
+            DynamicValue::Bool(_) => TType::Bool,
+            DynamicValue::I8(_) => TType::I8,
+            DynamicValue::I16(_) => TType::I16,
+            DynamicValue::I32(_) => TType::I32,
+            DynamicValue::I64(_) => TType::I64,
+            DynamicValue::Double(_) => TType::Double,
+            DynamicValue::String(_) | DynamicValue::Binary(_) => TType::Binary,
+            DynamicValue::Uuid(_) => TType::Uuid,
+            DynamicValue::List(_) => TType::List,
+            DynamicValue::Set(_) => TType::Set,
+            DynamicValue::Map(_) => TType::Map,
+            DynamicValue::Struct(_) => TType::Struct,
+        }
+    }
+}
+
+/// A struct, union or exception decoded without generated types, with its
+/// fields keyed by id.
+///
+/// Fields not declared in the descriptor are skipped when decoding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DynamicStruct {
+    pub fields: BTreeMap<i16, DynamicValue>,
+}
+
+impl DynamicStruct {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: i16) -> Option<&DynamicValue> {
+        self.fields.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: i16) -> Option<&mut DynamicValue> {
+        self.fields.get_mut(&id)
+    }
+
+    /// Returns the field named `name` in `desc`.
+    pub fn get_by_name(&self, desc: &StructDescriptor, name: &str) -> Option<&DynamicValue> {
+        self.get(desc.find_field_by_name(name)?.id as i16)
+    }
+
+    pub fn insert(&mut self, id: i16, value: DynamicValue) -> Option<DynamicValue> {
+        self.fields.insert(id, value)
+    }
+
+    pub fn remove(&mut self, id: i16) -> Option<DynamicValue> {
+        self.fields.remove(&id)
+    }
+}
+
+/// Decodes a struct described by `desc` from `protocol`.
+pub fn decode_dynamic<T: TInputProtocol>(
+    desc: &StructDescriptor,
+    protocol: &mut T,
+) -> Result<DynamicStruct, ThriftException> {
+    Resolver::default().decode_struct(desc, protocol, MAX_DEPTH)
+}
+
+/// Encodes `value` as the struct described by `desc` to `protocol`.
+pub fn encode_dynamic<T: TOutputProtocol>(
+    desc: &StructDescriptor,
+    value: &DynamicStruct,
+    protocol: &mut T,
+) -> Result<(), ThriftException> {
+    Resolver::default().encode_struct(desc, value, protocol)
+}
+
+/// A field type with typedefs resolved.
+#[derive(Debug, Clone)]
+pub(crate) enum Ty {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    Double,
+    String,
+    Binary,
+    Uuid,
+    List(Box<Ty>),
+    Set(Box<Ty>),
+    Map(Box<Ty>, Box<Ty>),
+    Struct(Arc<StructDescriptor>),
+    /// An enum, encoded as `i32`.
+    Enum(Arc<EnumDescriptor>),
+}
+
+impl Ty {
+    pub(crate) fn ttype(&self) -> TType {
+        match self {
+            Ty::Bool => TType::Bool,
+            Ty::I8 => TType::I8,
+            Ty::I16 => TType::I16,
+            Ty::I32 | Ty::Enum(_) => TType::I32,
+            Ty::I64 => TType::I64,
+            Ty::Double => TType::Double,
+            Ty::String | Ty::Binary => TType::Binary,
+            Ty::Uuid => TType::Uuid,
+            Ty::List(_) => TType::List,
+            Ty::Set(_) => TType::Set,
+            Ty::Map(..) => TType::Map,
+            Ty::Struct(_) => TType::Struct,
+        }
+    }
+}
+
+pub(crate) struct Field {
+    pub(crate) id: i16,
+    pub(crate) name: FastStr,
+    pub(crate) required: bool,
+    pub(crate) ty: Ty,
+}
+
+#[cold]
+pub(crate) fn invalid_data(message: String) -> ThriftException {
+    new_protocol_exception(ProtocolExceptionKind::InvalidData, message)
+}
+
+/// Resolves field types through [`Register`], caching the fields of each
+/// struct for the duration of one call.
+#[derive(Default)]
+pub(crate) struct Resolver {
+    structs: AHashMap<(FastStr, FastStr), Arc<[Field]>>,
+}
+
+impl Resolver {
+    pub(crate) fn fields(
+        &mut self,
+        desc: &StructDescriptor,
+    ) -> Result<Arc<[Field]>, ThriftException> {
+        let key = (desc.filepath.clone(), desc.name.clone());
+        if let Some(fields) = self.structs.get(&key) {
+            return Ok(fields.clone());
+        }
+        let fields = desc
+            .fields
+            .iter()
+            .map(|f| {
+                Ok(Field {
+                    id: f.id as i16,
+                    name: f.name.clone(),
+                    required: f.requiredness == "required",
+                    ty: resolve(&f.r#type)?,
+                })
+            })
+            .collect::<Result<Arc<[Field]>, ThriftException>>()?;
+        self.structs.insert(key, fields.clone());
+        Ok(fields)
+    }
+
+    pub(crate) fn decode_struct<T: TInputProtocol>(
+        &mut self,
+        desc: &StructDescriptor,
+        protocol: &mut T,
+        depth: usize,
+    ) -> Result<DynamicStruct, ThriftException> {
+        let fields = self.fields(desc)?;
+        let mut value = DynamicStruct::new();
+
+        protocol.read_struct_begin()?;
+        loop {
+            let field_ident = protocol.read_field_begin()?;
+            if field_ident.field_type == TType::Stop {
+                break;
+            }
+            let id = field_ident.id.unwrap_or_default();
+            match fields.iter().find(|f| f.id == id) {
+                Some(f) if f.ty.ttype() == field_ident.field_type => {
+                    let v = self
+                        .decode_value(&f.ty, protocol, depth)
+                        .map_err(|mut err| {
+                            err.prepend_msg(&format!(
+                                "decode struct `{}` field `{}` failed, caused by: ",
+                                desc.name, f.name
+                            ));
+                            err
+                        })?;
+                    value.insert(id, v);
+                }
+                _ => {
+                    protocol.skip(field_ident.field_type)?;
+                }
+            }
+            protocol.read_field_end()?;
+        }
+        protocol.read_struct_end()?;
+
+        if let Some(f) = fields
+            .iter()
+            .find(|f| f.required && !value.fields.contains_key(&f.id))
+        {
+            return Err(invalid_data(format!(
+                "field {} is required in {}",
+                f.name, desc.name
+            )));
+        }
+        Ok(value)
+    }
+
+    fn decode_value<T: TInputProtocol>(
+        &mut self,
+        ty: &Ty,
+        protocol: &mut T,
+        depth: usize,
+    ) -> Result<DynamicValue, ThriftException> {
+        if depth == 0 {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::DepthLimit,
+                format!("cannot decode past {:?}", ty.ttype()),
+            ));
+        }
+        Ok(match ty {
+            Ty::Bool => DynamicValue::Bool(protocol.read_bool()?),
+            Ty::I8 => DynamicValue::I8(protocol.read_i8()?),
+            Ty::I16 => DynamicValue::I16(protocol.read_i16()?),
+            Ty::I32 | Ty::Enum(_) => DynamicValue::I32(protocol.read_i32()?),
+            Ty::I64 => DynamicValue::I64(protocol.read_i64()?),
+            Ty::Double => DynamicValue::Double(protocol.read_double()?),
+            Ty::String => DynamicValue::String(protocol.read_faststr()?),
+            Ty::Binary => DynamicValue::Binary(protocol.read_bytes()?),
+            Ty::Uuid => DynamicValue::Uuid(protocol.read_uuid()?),
+            Ty::List(element) => {
+                let list_ident = protocol.read_list_begin()?;
+                let mut list = Vec::with_capacity(list_ident.size);
+                for _ in 0..list_ident.size {
+                    list.push(self.decode_value(element, protocol, depth - 1)?);
+                }
+                protocol.read_list_end()?;
+                DynamicValue::List(list)
+            }
+            Ty::Set(element) => {
+                let set_ident = protocol.read_set_begin()?;
+                let mut set = Vec::with_capacity(set_ident.size);
+                for _ in 0..set_ident.size {
+                    set.push(self.decode_value(element, protocol, depth - 1)?);
+                }
+                protocol.read_set_end()?;
+                DynamicValue::Set(set)
+            }
+            Ty::Map(key, value) => {
+                let map_ident = protocol.read_map_begin()?;
+                let mut map = Vec::with_capacity(map_ident.size);
+                for _ in 0..map_ident.size {
+                    let k = self.decode_value(key, protocol, depth - 1)?;
+                    let v = self.decode_value(value, protocol, depth - 1)?;
+                    map.push((k, v));
+                }
+                protocol.read_map_end()?;
+                DynamicValue::Map(map)
+            }
+            Ty::Struct(desc) => {
+                DynamicValue::Struct(self.decode_struct(desc, protocol, depth - 1)?)
+            }
+        })
+    }
+
+    pub(crate) fn encode_struct<T: TOutputProtocol>(
+        &mut self,
+        desc: &StructDescriptor,
+        value: &DynamicStruct,
+        protocol: &mut T,
+    ) -> Result<(), ThriftException> {
+        let fields = self.fields(desc)?;
+        if let Some(id) = value
+            .fields
+            .keys()
+            .find(|id| !fields.iter().any(|f| f.id == **id))
+        {
+            return Err(invalid_data(format!(
+                "field id {id} is not declared in {}",
+                desc.name
+            )));
+        }
+
+        protocol.write_struct_begin(&DYNAMIC_IDENT)?;
+        for f in fields.iter() {
+            match value.get(f.id) {
+                Some(v) => {
+                    protocol.write_field_begin(f.ty.ttype(), f.id)?;
+                    self.encode_value(&f.ty, v, protocol).map_err(|mut err| {
+                        err.prepend_msg(&format!(
+                            "encode struct `{}` field `{}` failed, caused by: ",
+                            desc.name, f.name
+                        ));
+                        err
+                    })?;
+                    protocol.write_field_end()?;
+                }
+                None if f.required => {
+                    return Err(invalid_data(format!(
+                        "field {} is required in {}",
+                        f.name, desc.name
+                    )));
+                }
+                None => {}
+            }
+        }
+        protocol.write_field_stop()?;
+        protocol.write_struct_end()
+    }
+
+    fn encode_value<T: TOutputProtocol>(
+        &mut self,
+        ty: &Ty,
+        value: &DynamicValue,
+        protocol: &mut T,
+    ) -> Result<(), ThriftException> {
+        match (ty, value) {
+            (Ty::Bool, DynamicValue::Bool(v)) => protocol.write_bool(*v),
+            (Ty::I8, DynamicValue::I8(v)) => protocol.write_i8(*v),
+            (Ty::I16, DynamicValue::I16(v)) => protocol.write_i16(*v),
+            (Ty::I32 | Ty::Enum(_), DynamicValue::I32(v)) => protocol.write_i32(*v),
+            (Ty::I64, DynamicValue::I64(v)) => protocol.write_i64(*v),
+            (Ty::Double, DynamicValue::Double(v)) => protocol.write_double(*v),
+            (Ty::String, DynamicValue::String(v)) => protocol.write_faststr(v.clone()),
+            (Ty::Binary, DynamicValue::Binary(v)) => protocol.write_bytes(v.clone()),
+            (Ty::Uuid, DynamicValue::Uuid(v)) => protocol.write_uuid(*v),
+            (Ty::List(element), DynamicValue::List(list)) => {
+                protocol.write_list_begin(TListIdentifier::new(element.ttype(), list.len()))?;
+                for v in list {
+                    self.encode_value(element, v, protocol)?;
+                }
+                protocol.write_list_end()
+            }
+            (Ty::Set(element), DynamicValue::Set(set)) => {
+                protocol.write_set_begin(TSetIdentifier::new(element.ttype(), set.len()))?;
+                for v in set {
+                    self.encode_value(element, v, protocol)?;
+                }
+                protocol.write_set_end()
+            }
+            (Ty::Map(key, value), DynamicValue::Map(map)) => {
+                protocol.write_map_begin(TMapIdentifier::new(
+                    key.ttype(),
+                    value.ttype(),
+                    map.len(),
+                ))?;
+                for (k, v) in map {
+                    self.encode_value(key, k, protocol)?;
+                    self.encode_value(value, v, protocol)?;
+                }
+                protocol.write_map_end()
+            }
+            (Ty::Struct(desc), DynamicValue::Struct(s)) => self.encode_struct(desc, s, protocol),
+            _ => Err(invalid_data(format!(
+                "expected a value of type {:?}, found {:?}",
+                ty.ttype(),
+                value.ttype()
+            ))),
+        }
+    }
+}
+
+pub(crate) fn resolve(ty: &TypeDescriptor) -> Result<Ty, ThriftException> {
+    let element = |ty: &Option<Box<TypeDescriptor>>| match ty {
+        Some(ty) => resolve(ty).map(Box::new),
+        None => Err(invalid_data(format!(
+            "missing element type of {}",
+            ty_name(ty)
+        ))),
+    };
+    Ok(match ThriftType::from(ty.name.as_str()) {
+        ThriftType::Bool => Ty::Bool,
+        ThriftType::Byte | ThriftType::I8 => Ty::I8,
+        ThriftType::I16 => Ty::I16,
+        ThriftType::I32 => Ty::I32,
+        ThriftType::I64 => Ty::I64,
+        ThriftType::Double => Ty::Double,
+        ThriftType::String => Ty::String,
+        ThriftType::Binary => Ty::Binary,
+        ThriftType::Uuid => Ty::Uuid,
+        ThriftType::List => Ty::List(element(&ty.value_type)?),
+        ThriftType::Set => Ty::Set(element(&ty.value_type)?),
+        ThriftType::Map => Ty::Map(element(&ty.key_type)?, element(&ty.value_type)?),
+        ThriftType::Void => return Err(invalid_data("void is not a field type".into())),
+        ThriftType::Path(path) => resolve_path(&ty.filepath, &path)?,
+    })
+}
+
+fn ty_name(ty: &Option<Box<TypeDescriptor>>) -> &str {
+    ty.as_ref().map(|ty| ty.name.as_str()).unwrap_or_default()
+}
+
+/// Resolves a user defined type named `path` in the file `filepath`.
+fn resolve_path(filepath: &FastStr, path: &str) -> Result<Ty, ThriftException> {
+    let include_path = IncludePath::try_from(path).map_err(|e| invalid_data(e.to_string()))?;
+    let not_registered = |filepath: &str| invalid_data(format!("{filepath} is not registered"));
+
+    let filepath = if include_path.prefix.is_empty() {
+        filepath.clone()
+    } else {
+        Register::get(filepath)
+            .ok_or_else(|| not_registered(filepath))?
+            .includes
+            .get(include_path.prefix.as_str())
+            .cloned()
+            .ok_or_else(|| {
+                invalid_data(format!("include path not found: {}", include_path.prefix))
+            })?
+    };
+
+    let typedef = {
+        let file = Register::get(&filepath).ok_or_else(|| not_registered(&filepath))?;
+        let name = include_path.name.as_str();
+        if let Some(s) = file.find_struct(name) {
+            return Ok(Ty::Struct(Arc::new(s.clone())));
+        }
+        if let Some(e) = file.find_enum_by_name(name) {
+            return Ok(Ty::Enum(Arc::new(e.clone())));
+        }
+        file.typedefs
+            .iter()
+            .find(|t| t.alias == name)
+            .map(|t| t.r#type.clone())
+    };
+    match typedef {
+        Some(ty) => resolve(&ty),
+        None => Err(invalid_data(format!("type {path} not found in {filepath}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pilota::{BytesMut, thrift::binary::TBinaryProtocol};
+
+    use super::*;
+
+    fn register(idl: &str) -> FileDescriptor {
+        let ast = pilota_thrift_parser::FileParser::new(pilota_thrift_parser::FileSource::new(idl))
+            .parse()
+            .unwrap();
+        let file = FileDescriptor::from(&ast);
+        Register::register(file.filepath.clone(), file.clone());
+        file
+    }
+
+    #[test]
+    fn test_dynamic_round_trip() {
+        let file = register(
+            r#"
+            enum Status { ACTIVE = 1 }
+            typedef list<string> Names
+            struct Node {
+                1: required i64 id,
+                2: optional Status status,
+                3: Names names,
+                4: map<i32, Node> children,
+                5: binary data,
+            }
+            "#,
+        );
+        let desc = file.find_struct_by_name("Node").unwrap();
+
+        let mut child = DynamicStruct::new();
+        child.insert(1, DynamicValue::I64(2));
+        let mut node = DynamicStruct::new();
+        node.insert(1, DynamicValue::I64(1));
+        node.insert(2, DynamicValue::I32(1));
+        node.insert(
+            3,
+            DynamicValue::List(vec![DynamicValue::String("a".into())]),
+        );
+        node.insert(
+            4,
+            DynamicValue::Map(vec![(DynamicValue::I32(2), DynamicValue::Struct(child))]),
+        );
+        node.insert(5, DynamicValue::Binary(Bytes::from_static(b"\x00\x01")));
+
+        let mut buf = BytesMut::new();
+        encode_dynamic(desc, &node, &mut TBinaryProtocol::new(&mut buf, true)).unwrap();
+        let mut bytes = buf.freeze();
+        let decoded = decode_dynamic(desc, &mut TBinaryProtocol::new(&mut bytes, true)).unwrap();
+        assert_eq!(decoded, node);
+        assert_eq!(
+            decoded.get_by_name(desc, "names"),
+            Some(&DynamicValue::List(vec![DynamicValue::String("a".into())]))
+        );
+
+        // a mismatched value is rejected
+        node.insert(2, DynamicValue::I64(1));
+        let mut buf = BytesMut::new();
+        assert!(encode_dynamic(desc, &node, &mut TBinaryProtocol::new(&mut buf, true)).is_err());
+
+        // so is a missing required field
+        node.remove(1);
+        node.insert(2, DynamicValue::I32(1));
+        let mut buf = BytesMut::new();
+        assert!(encode_dynamic(desc, &node, &mut TBinaryProtocol::new(&mut buf, true)).is_err());
+    }
+}
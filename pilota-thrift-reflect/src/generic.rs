@@ -0,0 +1,704 @@
+//! Generic calls: thrift messages built from JSON without generated types.
+//!
+//! [`GenericCodec`] encodes a JSON object holding the arguments of a method
+//! of a [`ServiceDescriptor`] into a `TBinaryProtocol` call message and
+//! decodes the reply back into JSON, so that a gateway can forward requests
+//! to thrift services it wasn't compiled against. As with [`crate::dynamic`],
+//! the file declaring the service and its includes must be registered in
+//! [`Register`](crate::service::Register) first.
+//!
+//! Values are mapped to JSON as follows:
+//!
+//! - structs, unions and exceptions are objects keyed by field name, where
+//!   `null` stands for an unset field;
+//! - integers are numbers, and are also accepted as decimal strings;
+//! - doubles are numbers, or `"NaN"`, `"Infinity"` and `"-Infinity"`;
+//! - enums are the names of their variants, and are also accepted as numbers;
+//! - binary is base64, with or without padding;
+//! - uuids are strings in the hyphenated form;
+//! - lists and sets are arrays;
+//! - maps are objects if their keys are neither structs nor containers, and
+//!   arrays of `[key, value]` pairs otherwise.
+
+use ahash::AHashMap;
+use base64::{
+    Engine as _, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use bytes::{Bytes, BytesMut};
+use pilota::{
+    FastStr,
+    thrift::{
+        ApplicationException, ApplicationExceptionKind, Message as _, TInputProtocol,
+        TMessageIdentifier, TMessageType, TOutputProtocol, ThriftException,
+        binary::TBinaryProtocol, new_application_exception,
+    },
+};
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    ThriftType,
+    dynamic::{DynamicStruct, DynamicValue, MAX_DEPTH, Resolver, Ty, invalid_data},
+    thrift_reflection::*,
+};
+
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// The outcome of a call, as carried by a reply message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenericReply {
+    /// The method returned normally, with `null` standing for `void`.
+    Success(Value),
+    /// The method threw one of the exceptions it declares, named after its
+    /// field in the `throws` clause.
+    Exception { name: FastStr, value: Value },
+}
+
+/// The args and result structs of a method.
+struct Method {
+    desc: MethodDescriptor,
+    args: StructDescriptor,
+    result: StructDescriptor,
+    is_void: bool,
+}
+
+/// Encodes and decodes the messages of a service from and to JSON, using
+/// `TBinaryProtocol`.
+pub struct GenericCodec {
+    service: FastStr,
+    methods: AHashMap<FastStr, Method>,
+}
+
+impl GenericCodec {
+    pub fn new(service: &ServiceDescriptor) -> Self {
+        let methods = service
+            .methods
+            .iter()
+            .map(|m| {
+                let is_void = m.response.as_ref().is_none_or(|ty| {
+                    matches!(ThriftType::from(ty.name.as_str()), ThriftType::Void)
+                });
+                let args = StructDescriptor {
+                    filepath: m.filepath.clone(),
+                    name: format!("{}_args", m.name).into(),
+                    fields: m.args.clone(),
+                    ..Default::default()
+                };
+                let success = m
+                    .response
+                    .iter()
+                    .filter(|_| !is_void)
+                    .map(|ty| FieldDescriptor {
+                        filepath: m.filepath.clone(),
+                        name: "success".into(),
+                        r#type: ty.clone(),
+                        requiredness: "optional".into(),
+                        id: 0,
+                        ..Default::default()
+                    });
+                let exceptions = m.throw_exceptions.iter().map(|f| FieldDescriptor {
+                    requiredness: "optional".into(),
+                    ..f.clone()
+                });
+                let result = StructDescriptor {
+                    filepath: m.filepath.clone(),
+                    name: format!("{}_result", m.name).into(),
+                    fields: success.chain(exceptions).collect(),
+                    ..Default::default()
+                };
+                let method = Method {
+                    desc: m.clone(),
+                    args,
+                    result,
+                    is_void,
+                };
+                (m.name.clone(), method)
+            })
+            .collect();
+        Self {
+            service: service.name.clone(),
+            methods,
+        }
+    }
+
+    /// Returns the method named `name`.
+    pub fn method(&self, name: &str) -> Option<&MethodDescriptor> {
+        self.methods.get(name).map(|m| &m.desc)
+    }
+
+    fn find(&self, name: &str) -> Result<&Method, ThriftException> {
+        self.methods.get(name).ok_or_else(|| {
+            new_application_exception(
+                ApplicationExceptionKind::UNKNOWN_METHOD,
+                format!("method {name} not found in service {}", self.service),
+            )
+        })
+    }
+
+    /// Encodes a call of `method` whose arguments are given by the JSON
+    /// object `args`, keyed by argument name.
+    pub fn encode_call(
+        &self,
+        method: &str,
+        seq_id: i32,
+        args: &Value,
+    ) -> Result<Bytes, ThriftException> {
+        let m = self.find(method)?;
+        let message_type = if m.desc.is_oneway {
+            TMessageType::OneWay
+        } else {
+            TMessageType::Call
+        };
+        let mut resolver = Resolver::default();
+        let args = resolver.struct_from_json(&m.args, args)?;
+
+        let mut buf = BytesMut::new();
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        protocol.write_message_begin(&TMessageIdentifier::new(
+            m.desc.name.clone(),
+            message_type,
+            seq_id,
+        ))?;
+        resolver.encode_struct(&m.args, &args, &mut protocol)?;
+        protocol.write_message_end()?;
+        Ok(buf.freeze())
+    }
+
+    /// Decodes a call message into the JSON object of its arguments.
+    pub fn decode_call(
+        &self,
+        mut buf: Bytes,
+    ) -> Result<(TMessageIdentifier, Value), ThriftException> {
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        let ident = protocol.read_message_begin()?;
+        if !matches!(
+            ident.message_type,
+            TMessageType::Call | TMessageType::OneWay
+        ) {
+            return Err(new_application_exception(
+                ApplicationExceptionKind::INVALID_MESSAGE_TYPE,
+                format!("expected a call, found {:?}", ident.message_type),
+            ));
+        }
+        let m = self.find(&ident.name)?;
+        let mut resolver = Resolver::default();
+        let args = resolver.decode_struct(&m.args, &mut protocol, MAX_DEPTH)?;
+        protocol.read_message_end()?;
+        let args = resolver.struct_to_json(&m.args, &args)?;
+        Ok((ident, Value::Object(args)))
+    }
+
+    /// Encodes the reply to a call of `method`.
+    pub fn encode_reply(
+        &self,
+        method: &str,
+        seq_id: i32,
+        reply: &GenericReply,
+    ) -> Result<Bytes, ThriftException> {
+        let m = self.find(method)?;
+        let result = match reply {
+            GenericReply::Success(value) if m.is_void && value.is_null() => Map::new(),
+            GenericReply::Success(value) => Map::from_iter([("success".into(), value.clone())]),
+            GenericReply::Exception { name, value } => {
+                if !m.desc.throw_exceptions.iter().any(|f| f.name == *name) {
+                    return Err(invalid_data(format!(
+                        "exception {name} is not declared by method {method}"
+                    )));
+                }
+                Map::from_iter([(name.to_string(), value.clone())])
+            }
+        };
+        let mut resolver = Resolver::default();
+        let result = resolver.struct_from_json(&m.result, &Value::Object(result))?;
+
+        let mut buf = BytesMut::new();
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        protocol.write_message_begin(&TMessageIdentifier::new(
+            m.desc.name.clone(),
+            TMessageType::Reply,
+            seq_id,
+        ))?;
+        resolver.encode_struct(&m.result, &result, &mut protocol)?;
+        protocol.write_message_end()?;
+        Ok(buf.freeze())
+    }
+
+    /// Decodes a reply message.
+    ///
+    /// An `Exception` message is returned as the
+    /// [`ThriftException::Application`] it carries.
+    pub fn decode_reply(
+        &self,
+        mut buf: Bytes,
+    ) -> Result<(TMessageIdentifier, GenericReply), ThriftException> {
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        let ident = protocol.read_message_begin()?;
+        match ident.message_type {
+            TMessageType::Reply => {}
+            TMessageType::Exception => {
+                let e = ApplicationException::decode(&mut protocol)?;
+                protocol.read_message_end()?;
+                return Err(e.into());
+            }
+            message_type => {
+                return Err(new_application_exception(
+                    ApplicationExceptionKind::INVALID_MESSAGE_TYPE,
+                    format!("expected a reply, found {message_type:?}"),
+                ));
+            }
+        }
+        let m = self.find(&ident.name)?;
+        let mut resolver = Resolver::default();
+        let result = resolver.decode_struct(&m.result, &mut protocol, MAX_DEPTH)?;
+        protocol.read_message_end()?;
+
+        let result = resolver.struct_to_json(&m.result, &result)?;
+        // at most one field of the result is set
+        let reply = match result.into_iter().next() {
+            Some((name, value)) if name == "success" => GenericReply::Success(value),
+            Some((name, value)) => GenericReply::Exception {
+                name: name.into(),
+                value,
+            },
+            None if m.is_void => GenericReply::Success(Value::Null),
+            None => {
+                return Err(new_application_exception(
+                    ApplicationExceptionKind::MISSING_RESULT,
+                    format!("method {} returned no result", m.desc.name),
+                ));
+            }
+        };
+        Ok((ident, reply))
+    }
+}
+
+#[cold]
+fn mismatch(expected: &str, found: &Value) -> ThriftException {
+    let found = match found {
+        Value::Null => "null",
+        Value::Bool(_) => "a bool",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    invalid_data(format!("expected {expected}, found {found}"))
+}
+
+fn int_from_json<I: TryFrom<i64>>(json: &Value) -> Result<I, ThriftException> {
+    let v = match json {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    v.and_then(|v| I::try_from(v).ok())
+        .ok_or_else(|| mismatch(std::any::type_name::<I>(), json))
+}
+
+fn double_from_json(json: &Value) -> Result<f64, ThriftException> {
+    match json {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            s => s.parse().ok(),
+        },
+        _ => None,
+    }
+    .ok_or_else(|| mismatch("a double", json))
+}
+
+fn double_to_json(v: f64) -> Value {
+    match Number::from_f64(v) {
+        Some(n) => Value::Number(n),
+        None if v.is_nan() => Value::String("NaN".into()),
+        None if v > 0.0 => Value::String("Infinity".into()),
+        None => Value::String("-Infinity".into()),
+    }
+}
+
+fn uuid_from_str(s: &str) -> Option<[u8; 16]> {
+    let hex = s.bytes().filter(|b| *b != b'-').collect::<Vec<_>>();
+    if hex.len() != 32 || !matches!(s.len(), 32 | 36) {
+        return None;
+    }
+    let mut uuid = [0; 16];
+    for (byte, pair) in uuid.iter_mut().zip(hex.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(uuid)
+}
+
+fn uuid_to_string(uuid: &[u8; 16]) -> String {
+    let mut s = String::with_capacity(36);
+    for (i, b) in uuid.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            s.push('-');
+        }
+        s.push_str(&format!("{b:02x}"));
+    }
+    s
+}
+
+/// Returns whether map keys of type `ty` are written as object keys.
+fn is_object_key(ty: &Ty) -> bool {
+    !matches!(ty, Ty::List(_) | Ty::Set(_) | Ty::Map(..) | Ty::Struct(_))
+}
+
+impl Resolver {
+    fn struct_from_json(
+        &mut self,
+        desc: &StructDescriptor,
+        json: &Value,
+    ) -> Result<DynamicStruct, ThriftException> {
+        let Value::Object(object) = json else {
+            return Err(mismatch("an object", json));
+        };
+        let fields = self.fields(desc)?;
+        let mut value = DynamicStruct::new();
+        for (name, v) in object {
+            let Some(f) = fields.iter().find(|f| f.name == name) else {
+                return Err(invalid_data(format!(
+                    "field {name} is not declared in {}",
+                    desc.name
+                )));
+            };
+            if v.is_null() {
+                continue;
+            }
+            let v = self.value_from_json(&f.ty, v).map_err(|mut err| {
+                err.prepend_msg(&format!(
+                    "convert struct `{}` field `{}` failed, caused by: ",
+                    desc.name, f.name
+                ));
+                err
+            })?;
+            value.insert(f.id, v);
+        }
+        Ok(value)
+    }
+
+    fn value_from_json(&mut self, ty: &Ty, json: &Value) -> Result<DynamicValue, ThriftException> {
+        Ok(match ty {
+            Ty::Bool => DynamicValue::Bool(json.as_bool().ok_or_else(|| mismatch("a bool", json))?),
+            Ty::I8 => DynamicValue::I8(int_from_json(json)?),
+            Ty::I16 => DynamicValue::I16(int_from_json(json)?),
+            Ty::I32 => DynamicValue::I32(int_from_json(json)?),
+            Ty::I64 => DynamicValue::I64(int_from_json(json)?),
+            Ty::Double => DynamicValue::Double(double_from_json(json)?),
+            Ty::String => DynamicValue::String(FastStr::new(
+                json.as_str().ok_or_else(|| mismatch("a string", json))?,
+            )),
+            Ty::Binary => {
+                let s = json
+                    .as_str()
+                    .ok_or_else(|| mismatch("a base64 string", json))?;
+                let data = BASE64
+                    .decode(s)
+                    .map_err(|e| invalid_data(format!("invalid base64: {e}")))?;
+                DynamicValue::Binary(data.into())
+            }
+            Ty::Uuid => DynamicValue::Uuid(
+                json.as_str()
+                    .and_then(uuid_from_str)
+                    .ok_or_else(|| mismatch("a uuid", json))?,
+            ),
+            Ty::Enum(desc) => match json {
+                Value::String(name) => match desc.values.iter().find(|v| v.name == name) {
+                    Some(v) => DynamicValue::I32(v.value as i32),
+                    None => DynamicValue::I32(int_from_json(json).map_err(|_| {
+                        invalid_data(format!("{name} is not a variant of enum {}", desc.name))
+                    })?),
+                },
+                _ => DynamicValue::I32(int_from_json(json)?),
+            },
+            Ty::List(element) | Ty::Set(element) => {
+                let array = json.as_array().ok_or_else(|| mismatch("an array", json))?;
+                let values = array
+                    .iter()
+                    .map(|v| self.value_from_json(element, v))
+                    .collect::<Result<_, _>>()?;
+                if matches!(ty, Ty::List(_)) {
+                    DynamicValue::List(values)
+                } else {
+                    DynamicValue::Set(values)
+                }
+            }
+            Ty::Map(key, value) => {
+                let mut map = Vec::new();
+                match json {
+                    Value::Object(object) if is_object_key(key) => {
+                        for (k, v) in object {
+                            let k = match **key {
+                                Ty::Bool => k
+                                    .parse()
+                                    .map(Value::Bool)
+                                    .map_err(|_| invalid_data(format!("invalid bool key {k}")))?,
+                                _ => Value::String(k.clone()),
+                            };
+                            map.push((
+                                self.value_from_json(key, &k)?,
+                                self.value_from_json(value, v)?,
+                            ));
+                        }
+                    }
+                    Value::Array(pairs) => {
+                        for pair in pairs {
+                            let Some([k, v]) = pair.as_array().map(Vec::as_slice) else {
+                                return Err(mismatch("a [key, value] pair", pair));
+                            };
+                            map.push((
+                                self.value_from_json(key, k)?,
+                                self.value_from_json(value, v)?,
+                            ));
+                        }
+                    }
+                    _ if is_object_key(key) => return Err(mismatch("an object", json)),
+                    _ => return Err(mismatch("an array of [key, value] pairs", json)),
+                }
+                DynamicValue::Map(map)
+            }
+            Ty::Struct(desc) => DynamicValue::Struct(self.struct_from_json(desc, json)?),
+        })
+    }
+
+    fn struct_to_json(
+        &mut self,
+        desc: &StructDescriptor,
+        value: &DynamicStruct,
+    ) -> Result<Map<String, Value>, ThriftException> {
+        let fields = self.fields(desc)?;
+        let mut object = Map::new();
+        for f in fields.iter() {
+            if let Some(v) = value.get(f.id) {
+                object.insert(f.name.to_string(), self.value_to_json(&f.ty, v)?);
+            }
+        }
+        Ok(object)
+    }
+
+    fn value_to_json(&mut self, ty: &Ty, value: &DynamicValue) -> Result<Value, ThriftException> {
+        Ok(match (ty, value) {
+            (_, DynamicValue::Bool(v)) => Value::Bool(*v),
+            (Ty::Enum(desc), DynamicValue::I32(v)) => {
+                match desc.values.iter().find(|e| e.value == *v as i64) {
+                    Some(e) => Value::String(e.name.to_string()),
+                    None => Value::from(*v),
+                }
+            }
+            (_, DynamicValue::I8(v)) => Value::from(*v),
+            (_, DynamicValue::I16(v)) => Value::from(*v),
+            (_, DynamicValue::I32(v)) => Value::from(*v),
+            (_, DynamicValue::I64(v)) => Value::from(*v),
+            (_, DynamicValue::Double(v)) => double_to_json(*v),
+            (_, DynamicValue::String(v)) => Value::String(v.to_string()),
+            (_, DynamicValue::Binary(v)) => Value::String(BASE64.encode(v)),
+            (_, DynamicValue::Uuid(v)) => Value::String(uuid_to_string(v)),
+            (Ty::List(element) | Ty::Set(element), DynamicValue::List(values))
+            | (Ty::List(element) | Ty::Set(element), DynamicValue::Set(values)) => Value::Array(
+                values
+                    .iter()
+                    .map(|v| self.value_to_json(element, v))
+                    .collect::<Result<_, _>>()?,
+            ),
+            (Ty::Map(key, value), DynamicValue::Map(map)) if is_object_key(key) => {
+                let mut object = Map::new();
+                for (k, v) in map {
+                    let k = match self.value_to_json(key, k)? {
+                        Value::String(s) => s,
+                        k => k.to_string(),
+                    };
+                    object.insert(k, self.value_to_json(value, v)?);
+                }
+                Value::Object(object)
+            }
+            (Ty::Map(key, value), DynamicValue::Map(map)) => Value::Array(
+                map.iter()
+                    .map(|(k, v)| {
+                        Ok(Value::Array(vec![
+                            self.value_to_json(key, k)?,
+                            self.value_to_json(value, v)?,
+                        ]))
+                    })
+                    .collect::<Result<_, ThriftException>>()?,
+            ),
+            (Ty::Struct(desc), DynamicValue::Struct(s)) => {
+                Value::Object(self.struct_to_json(desc, s)?)
+            }
+            _ => {
+                return Err(invalid_data(format!(
+                    "expected a value of type {:?}, found {:?}",
+                    ty.ttype(),
+                    value.ttype()
+                )));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::service::Register;
+
+    fn register(path: &str, idl: &str) -> FileDescriptor {
+        let mut ast =
+            pilota_thrift_parser::FileParser::new(pilota_thrift_parser::FileSource::new(idl))
+                .parse()
+                .unwrap();
+        ast.path = Arc::new(PathBuf::from(path));
+        let file = FileDescriptor::from(&ast);
+        Register::register(file.filepath.clone(), file.clone());
+        file
+    }
+
+    fn codec() -> GenericCodec {
+        register(
+            "/generic/base.thrift",
+            r#"
+            enum Status { ACTIVE = 1, DELETED = 2 }
+            typedef i64 Id
+            exception NotFound { 1: required Id id }
+            "#,
+        );
+        let file = register(
+            "/generic/item.thrift",
+            r#"
+            include "base.thrift"
+            struct Item {
+                1: required base.Id id,
+                2: optional base.Status status,
+                3: map<base.Status, double> scores,
+                4: map<Item, bool> related,
+                5: binary data,
+                6: uuid token,
+            }
+            service ItemService {
+                Item get(1: base.Id id, 2: list<base.Status> statuses) throws (1: base.NotFound not_found),
+                void touch(1: base.Id id),
+                oneway void ping(),
+            }
+            "#,
+        );
+        GenericCodec::new(&file.services[0])
+    }
+
+    #[test]
+    fn test_generic_call() {
+        let codec = codec();
+        let args = json!({"id": "42", "statuses": ["ACTIVE", 2]});
+        let call = codec.encode_call("get", 7, &args).unwrap();
+        let (ident, decoded) = codec.decode_call(call).unwrap();
+        assert_eq!(ident.name, "get");
+        assert_eq!(ident.message_type, TMessageType::Call);
+        assert_eq!(ident.sequence_number, 7);
+        assert_eq!(
+            decoded,
+            json!({"id": 42, "statuses": ["ACTIVE", "DELETED"]})
+        );
+
+        let (ident, _) = codec
+            .decode_call(codec.encode_call("ping", 8, &json!({})).unwrap())
+            .unwrap();
+        assert_eq!(ident.message_type, TMessageType::OneWay);
+
+        // unknown methods, fields and variants are rejected
+        assert!(codec.encode_call("put", 1, &json!({})).is_err());
+        assert!(codec.encode_call("get", 1, &json!({"ids": 1})).is_err());
+        assert!(
+            codec
+                .encode_call("get", 1, &json!({"id": 1, "statuses": ["UNKNOWN"]}))
+                .is_err()
+        );
+        assert!(codec.encode_call("get", 1, &json!({"id": 1.5})).is_err());
+    }
+
+    #[test]
+    fn test_generic_reply() {
+        let codec = codec();
+        let item = json!({
+            "id": 1,
+            "status": "ACTIVE",
+            "scores": {"DELETED": 0.5},
+            "related": [[{"id": 2}, true]],
+            "data": "AAE=",
+            "token": "00112233-4455-6677-8899-aabbccddeeff",
+        });
+        let reply = GenericReply::Success(item.clone());
+        let buf = codec.encode_reply("get", 3, &reply).unwrap();
+        let (ident, decoded) = codec.decode_reply(buf).unwrap();
+        assert_eq!(ident.message_type, TMessageType::Reply);
+        assert_eq!(ident.sequence_number, 3);
+        assert_eq!(decoded, reply);
+
+        let reply = GenericReply::Exception {
+            name: "not_found".into(),
+            value: json!({"id": 1}),
+        };
+        let buf = codec.encode_reply("get", 4, &reply).unwrap();
+        assert_eq!(codec.decode_reply(buf).unwrap().1, reply);
+
+        let reply = GenericReply::Success(Value::Null);
+        let buf = codec.encode_reply("touch", 5, &reply).unwrap();
+        assert_eq!(codec.decode_reply(buf).unwrap().1, reply);
+
+        // a missing required field of the exception is rejected
+        let reply = GenericReply::Exception {
+            name: "not_found".into(),
+            value: json!({}),
+        };
+        assert!(codec.encode_reply("get", 6, &reply).is_err());
+
+        // a result without a value is only valid for void methods
+        let mut buf = BytesMut::new();
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        protocol
+            .write_message_begin(&TMessageIdentifier::new(
+                "get".into(),
+                TMessageType::Reply,
+                7,
+            ))
+            .unwrap();
+        protocol.write_field_stop().unwrap();
+        protocol.write_message_end().unwrap();
+        assert!(matches!(
+            codec.decode_reply(buf.freeze()),
+            Err(ThriftException::Application(e))
+                if e.kind() == ApplicationExceptionKind::MISSING_RESULT
+        ));
+    }
+
+    #[test]
+    fn test_generic_application_exception() {
+        let codec = codec();
+        let mut buf = BytesMut::new();
+        let mut protocol = TBinaryProtocol::new(&mut buf, true);
+        protocol
+            .write_message_begin(&TMessageIdentifier::new(
+                "get".into(),
+                TMessageType::Exception,
+                1,
+            ))
+            .unwrap();
+        ApplicationException::new(ApplicationExceptionKind::INTERNAL_ERROR, "boom")
+            .encode(&mut protocol)
+            .unwrap();
+        protocol.write_message_end().unwrap();
+
+        match codec.decode_reply(buf.freeze()) {
+            Err(ThriftException::Application(e)) => {
+                assert_eq!(e.kind(), ApplicationExceptionKind::INTERNAL_ERROR);
+                assert_eq!(e.message(), "boom");
+            }
+            other => panic!("unexpected reply: {other:?}"),
+        }
+    }
+}
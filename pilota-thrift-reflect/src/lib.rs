@@ -24,8 +24,15 @@ fn normalize_path(path: &std::path::Path) -> PathBuf {
 include!("descriptor.rs");
 pub use descriptor::*;
 
+pub mod dynamic;
 pub mod error;
+pub mod generic;
 pub mod service;
+pub mod simple_json;
+
+pub use dynamic::{DynamicStruct, DynamicValue, decode_dynamic, encode_dynamic};
+pub use generic::{GenericCodec, GenericReply};
+pub use simple_json::SimpleJsonNames;
 
 pub enum ThriftType {
     String,
@@ -560,6 +567,16 @@ impl From<(FastStr, &pilota_thrift_parser::Exception)> for thrift_reflection::St
     }
 }
 
+impl thrift_reflection::FileDescriptor {
+    fn find_struct(&self, name: &str) -> Option<&thrift_reflection::StructDescriptor> {
+        self.structs
+            .iter()
+            .chain(&self.unions)
+            .chain(&self.exceptions)
+            .find(|s| s.name == name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
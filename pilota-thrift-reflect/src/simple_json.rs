@@ -0,0 +1,255 @@
+//! Names for [`pilota::thrift::TSimpleJsonOutputProtocol`].
+//!
+//! The protocol only sees the bare name of each struct it writes, so
+//! [`SimpleJsonNames`] collects the structs of a file together with every
+//! struct its fields reach through includes and typedefs, and keys them by
+//! that name. Types are looked up in [`Register`](crate::service::Register),
+//! so the file and its includes must be registered first.
+
+use ahash::AHashMap;
+use pilota::{
+    FastStr,
+    thrift::{TSimpleJsonElement, TSimpleJsonNames, ThriftException},
+};
+
+use crate::{
+    dynamic::{Ty, invalid_data, resolve},
+    thrift_reflection::*,
+};
+
+/// The variant names by value of the enums in a field type, by their path
+/// through its containers.
+type EnumNames = Vec<(Vec<TSimpleJsonElement>, AHashMap<i32, FastStr>)>;
+
+struct FieldNames {
+    name: FastStr,
+    enums: EnumNames,
+}
+
+struct StructNames {
+    filepath: FastStr,
+    fields: AHashMap<i16, FieldNames>,
+}
+
+/// The field and enum variant names of the structs reachable from a file.
+#[derive(Default)]
+pub struct SimpleJsonNames {
+    structs: AHashMap<FastStr, StructNames>,
+}
+
+impl SimpleJsonNames {
+    /// Collects the names of the structs, unions and exceptions declared in
+    /// `file`, of the arguments and exceptions of its services, and of every
+    /// struct their fields refer to.
+    ///
+    /// Fails if two of these structs from different files share a name, as
+    /// the protocol couldn't tell them apart.
+    pub fn new(file: &FileDescriptor) -> Result<Self, ThriftException> {
+        let mut names = Self::default();
+        for s in file
+            .structs
+            .iter()
+            .chain(&file.unions)
+            .chain(&file.exceptions)
+        {
+            names.add_struct(s)?;
+        }
+        for m in file.services.iter().flat_map(|s| &s.methods) {
+            for f in m.args.iter().chain(&m.throw_exceptions) {
+                names.add_ty(&resolve(&f.r#type)?, &mut Vec::new(), &mut Vec::new())?;
+            }
+        }
+        Ok(names)
+    }
+
+    fn add_struct(&mut self, desc: &StructDescriptor) -> Result<(), ThriftException> {
+        if let Some(s) = self.structs.get(&desc.name) {
+            if s.filepath != desc.filepath {
+                return Err(invalid_data(format!(
+                    "struct {} is declared in both {} and {}",
+                    desc.name, s.filepath, desc.filepath
+                )));
+            }
+            return Ok(());
+        }
+        // reserve the name first so that recursive structs terminate
+        self.structs.insert(
+            desc.name.clone(),
+            StructNames {
+                filepath: desc.filepath.clone(),
+                fields: AHashMap::new(),
+            },
+        );
+
+        let mut fields = AHashMap::with_capacity(desc.fields.len());
+        for f in &desc.fields {
+            let mut enums = Vec::new();
+            self.add_ty(&resolve(&f.r#type)?, &mut Vec::new(), &mut enums)?;
+            fields.insert(
+                f.id as i16,
+                FieldNames {
+                    name: f.name.clone(),
+                    enums,
+                },
+            );
+        }
+        if let Some(s) = self.structs.get_mut(&desc.name) {
+            s.fields = fields;
+        }
+        Ok(())
+    }
+
+    /// Adds the structs `ty` refers to, and records the enums it contains
+    /// under their path from `path`.
+    fn add_ty(
+        &mut self,
+        ty: &Ty,
+        path: &mut Vec<TSimpleJsonElement>,
+        enums: &mut EnumNames,
+    ) -> Result<(), ThriftException> {
+        let mut add = |this: &mut Self, step, ty| {
+            path.push(step);
+            let res = this.add_ty(ty, path, enums);
+            path.pop();
+            res
+        };
+        match ty {
+            Ty::Struct(desc) => self.add_struct(desc),
+            Ty::Enum(e) => {
+                let variants = e
+                    .values
+                    .iter()
+                    .map(|v| (v.value as i32, v.name.clone()))
+                    .collect();
+                enums.push((path.clone(), variants));
+                Ok(())
+            }
+            Ty::List(ty) | Ty::Set(ty) => add(self, TSimpleJsonElement::Element, ty),
+            Ty::Map(key, value) => {
+                add(self, TSimpleJsonElement::Key, key)?;
+                add(self, TSimpleJsonElement::Value, value)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn field(&self, struct_name: &str, id: i16) -> Option<&FieldNames> {
+        self.structs.get(struct_name)?.fields.get(&id)
+    }
+}
+
+impl TSimpleJsonNames for SimpleJsonNames {
+    fn field_name(&self, struct_name: &str, id: i16) -> Option<&str> {
+        self.field(struct_name, id).map(|f| f.name.as_str())
+    }
+
+    fn enum_name(
+        &self,
+        struct_name: &str,
+        id: i16,
+        path: &[TSimpleJsonElement],
+        value: i32,
+    ) -> Option<&str> {
+        self.field(struct_name, id)?
+            .enums
+            .iter()
+            .find(|(p, _)| p == path)?
+            .1
+            .get(&value)
+            .map(|v| v.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use super::*;
+    use crate::service::Register;
+
+    fn register(path: &str, idl: &str) -> FileDescriptor {
+        let mut ast =
+            pilota_thrift_parser::FileParser::new(pilota_thrift_parser::FileSource::new(idl))
+                .parse()
+                .unwrap();
+        ast.path = Arc::new(PathBuf::from(path));
+        let file = FileDescriptor::from(&ast);
+        Register::register(file.filepath.clone(), file.clone());
+        file
+    }
+
+    #[test]
+    fn test_names_across_includes() {
+        register(
+            "/simple_json/base.thrift",
+            r#"
+            enum Status { ACTIVE = 1, DELETED = 2 }
+            typedef Status State
+            struct Owner { 1: required string name, 2: optional State state }
+            exception NotFound { 1: required i64 id }
+            "#,
+        );
+        let file = register(
+            "/simple_json/item.thrift",
+            r#"
+            include "base.thrift"
+            typedef base.State ItemState
+            struct Item {
+                1: required i64 id,
+                2: optional ItemState state,
+                3: optional base.Status status,
+                4: list<base.Owner> owners,
+                5: optional Item parent,
+                6: optional list<base.Status> history,
+                7: optional map<base.Status, list<ItemState>> transitions,
+            }
+            service ItemService {
+                Item get(1: i64 id) throws (1: base.NotFound not_found),
+            }
+            "#,
+        );
+        let names = SimpleJsonNames::new(&file).unwrap();
+
+        use TSimpleJsonElement::*;
+
+        assert_eq!(names.field_name("Item", 1), Some("id"));
+        assert_eq!(names.field_name("Item", 8), None);
+        assert_eq!(names.enum_name("Item", 2, &[], 2), Some("DELETED"));
+        assert_eq!(names.enum_name("Item", 3, &[], 1), Some("ACTIVE"));
+        assert_eq!(names.enum_name("Item", 3, &[], 3), None);
+        assert_eq!(names.enum_name("Item", 1, &[], 1), None);
+
+        // enums inside containers
+        assert_eq!(names.enum_name("Item", 6, &[Element], 2), Some("DELETED"));
+        assert_eq!(names.enum_name("Item", 6, &[], 2), None);
+        assert_eq!(names.enum_name("Item", 7, &[Key], 1), Some("ACTIVE"));
+        assert_eq!(
+            names.enum_name("Item", 7, &[Value, Element], 2),
+            Some("DELETED")
+        );
+        assert_eq!(names.enum_name("Item", 7, &[Value], 2), None);
+
+        // structs reached through an included file, by field or by service
+        assert_eq!(names.field_name("Owner", 1), Some("name"));
+        assert_eq!(names.enum_name("Owner", 2, &[], 1), Some("ACTIVE"));
+        assert_eq!(names.field_name("NotFound", 1), Some("id"));
+        assert_eq!(names.field_name("Other", 1), None);
+    }
+
+    #[test]
+    fn test_ambiguous_struct_names() {
+        register(
+            "/simple_json_ambiguous/base.thrift",
+            "struct Item { 1: required string name }",
+        );
+        let file = register(
+            "/simple_json_ambiguous/item.thrift",
+            r#"
+            include "base.thrift"
+            struct Item { 1: required i64 id, 2: optional base.Item base }
+            "#,
+        );
+        let err = SimpleJsonNames::new(&file).err().unwrap();
+        assert!(err.to_string().contains("struct Item is declared in both"));
+    }
+}
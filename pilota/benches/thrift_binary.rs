@@ -1,6 +1,6 @@
 #![allow(clippy::redundant_clone)]
 
-use std::{hint::black_box, ptr};
+use std::hint::black_box;
 
 use bytes::{Bytes, BytesMut};
 use criterion::{criterion_group, criterion_main};
@@ -23,7 +23,6 @@ fn binary_bench(c: &mut criterion::Criterion) {
     for i in &v {
         p.write_i64(*i).unwrap();
     }
-    drop(p);
     let buf = buf.freeze();
     assert_eq!(buf.len(), 8 * size);
 
@@ -33,7 +32,6 @@ fn binary_bench(c: &mut criterion::Criterion) {
     for i in &v {
         p.write_i64(*i).unwrap();
     }
-    drop(p);
     let buf_le = buf_le.freeze();
     assert_eq!(buf_le.len(), 8 * size);
 
@@ -43,82 +41,64 @@ fn binary_bench(c: &mut criterion::Criterion) {
 
     group.bench_function("big endian decode vec i64", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf.clone();
-                black_box(read_be(b, size));
-            });
+            let b = buf.clone();
+            black_box(read_be(b, size));
         })
     });
 
     group.bench_function("big endian decode vec i64 unsafe", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf.clone();
-                black_box(read_be_unsafe(b, size));
-            });
+            let b = buf.clone();
+            black_box(read_be_unsafe(b, size));
         })
     });
 
     group.bench_function("big endian decode vec i64 unsafe vec", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf.clone();
-                black_box(read_be_unsafe_vec(b, size));
-            });
+            let b = buf.clone();
+            black_box(read_be_unsafe_vec(b, size));
         })
     });
 
     group.bench_function("big endian decode vec i64 unsafe optimized", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf.clone();
-                black_box(read_be_unsafe_optimized(b, size));
-            });
+            let b = buf.clone();
+            black_box(read_be_unsafe_optimized(b, size));
         })
     });
 
     group.bench_function("big endian encode vec i64", |b| {
         b.iter(|| {
-            black_box({
-                let mut b = BytesMut::with_capacity(8 * size);
-                write_be(&mut b, &v, size);
-                black_box(());
-            });
+            let mut b = BytesMut::with_capacity(8 * size);
+            write_be(&mut b, &v, size);
+            black_box(());
         })
     });
 
     group.bench_function("big endian encode vec i64 unsafe", |b| {
         b.iter(|| {
-            black_box({
-                let mut b = BytesMut::with_capacity(8 * size);
-                write_be_unsafe(&mut b, &v, size);
-                black_box(());
-            });
+            let mut b = BytesMut::with_capacity(8 * size);
+            write_be_unsafe(&mut b, &v, size);
+            black_box(());
         })
     });
 
     group.bench_function("little endian decode vec i64", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf_le.clone();
-                black_box(read_le(b, size));
-            });
+            let b = buf_le.clone();
+            black_box(read_le(b, size));
         })
     });
     group.bench_function("little endian decode vec i64 unsafe optimized", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf_le.clone();
-                black_box(read_le_unsafe_optimized(b, size));
-            });
+            let b = buf_le.clone();
+            black_box(read_le_unsafe_optimized(b, size));
         })
     });
     group.bench_function("little endian decode vec i64 optimized", |b| {
         b.iter(|| {
-            black_box({
-                let b = buf_le.clone();
-                black_box(read_le_optimized(b, size));
-            });
+            let b = buf_le.clone();
+            black_box(read_le_optimized(b, size));
         })
     });
 
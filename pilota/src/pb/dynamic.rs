@@ -0,0 +1,991 @@
+//! Descriptor-driven protobuf messages.
+//!
+//! [`DynamicMessage`] holds the fields of a message whose type is only known
+//! at runtime through a [`MessageDescriptor`], e.g. one produced by the
+//! `file_descriptor_*()` functions generated with `with_descriptor`, or one
+//! built with [`FileDescriptor::new_dynamic`].
+//!
+//! The descriptor is only consulted for the schema; encoding and decoding go
+//! through pilota's own [`encoding`](super::encoding) functions, so a dynamic
+//! message shares the zero-copy `Bytes`/`LinkedBytes` handling with generated
+//! code and can be nested in (or wrap) generated messages freely.
+//!
+//! [`FileDescriptor::new_dynamic`]: protobuf::reflect::FileDescriptor::new_dynamic
+
+use std::collections::BTreeMap;
+
+use bytes::{Buf, BufMut, Bytes};
+use faststr::FastStr;
+use linkedbytes::LinkedBytes;
+use protobuf::{
+    descriptor::field_descriptor_proto::Type,
+    reflect::{
+        EnumDescriptor, FieldDescriptor, MessageDescriptor, RuntimeFieldType, RuntimeType, Syntax,
+    },
+};
+
+use super::{
+    DecodeError, Message, ZERO_COPY_THRESHOLD,
+    encoding::{
+        self, DecodeContext, EncodeLengthContext, WireType, check_wire_type, decode_key,
+        encode_key, encode_varint, encoded_len_varint, group, key_len, merge_loop, message,
+        skip_field,
+    },
+};
+use crate::BytesVec;
+
+/// A protobuf message whose schema is given by a [`MessageDescriptor`].
+///
+/// Fields are keyed by field number. Singular fields are present only when
+/// they have been set or decoded; repeated and map fields hold a
+/// [`Value::List`] and a [`Value::Map`] respectively. Fields not declared in
+/// the descriptor are kept as raw bytes and written back when encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMessage {
+    descriptor: MessageDescriptor,
+    fields: BTreeMap<u32, Value>,
+    unknown_fields: BytesVec,
+}
+
+/// A field value of a [`DynamicMessage`].
+///
+/// The variant depends on the declared type only, not on the wire encoding:
+/// `sint32` and `sfixed32` fields are both [`Value::I32`], for example.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(FastStr),
+    Bytes(Bytes),
+    /// The number of an enum value. Numbers not declared by the enum are
+    /// kept as is.
+    EnumNumber(i32),
+    Message(DynamicMessage),
+    List(Vec<Value>),
+    Map(BTreeMap<MapKey, Value>),
+}
+
+/// A key of a protobuf map field.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapKey {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    String(FastStr),
+}
+
+impl MapKey {
+    fn from_value(value: Value) -> Option<Self> {
+        Some(match value {
+            Value::Bool(v) => MapKey::Bool(v),
+            Value::I32(v) => MapKey::I32(v),
+            Value::I64(v) => MapKey::I64(v),
+            Value::U32(v) => MapKey::U32(v),
+            Value::U64(v) => MapKey::U64(v),
+            Value::String(v) => MapKey::String(v),
+            _ => return None,
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            MapKey::Bool(v) => Value::Bool(*v),
+            MapKey::I32(v) => Value::I32(*v),
+            MapKey::I64(v) => Value::I64(*v),
+            MapKey::U32(v) => Value::U32(*v),
+            MapKey::U64(v) => Value::U64(*v),
+            MapKey::String(v) => Value::String(v.clone()),
+        }
+    }
+}
+
+/// The declared type of a single (non-repeated) value.
+#[derive(Debug, Clone)]
+enum Kind {
+    Double,
+    Float,
+    Int64,
+    Uint64,
+    Int32,
+    Fixed64,
+    Fixed32,
+    Bool,
+    String,
+    Bytes,
+    Uint32,
+    Sfixed32,
+    Sfixed64,
+    Sint32,
+    Sint64,
+    Enum(EnumDescriptor),
+    Message(MessageDescriptor),
+    Group(MessageDescriptor),
+}
+
+/// The declared cardinality of a field.
+enum Shape {
+    Singular(Kind),
+    Repeated(Kind),
+    Map(Kind, Kind),
+}
+
+impl Shape {
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Shape::Singular(kind), value) => kind.matches(value),
+            (Shape::Repeated(kind), Value::List(list)) => list.iter().all(|v| kind.matches(v)),
+            (Shape::Map(key, val), Value::Map(map)) => map
+                .iter()
+                .all(|(k, v)| key.matches(&k.to_value()) && val.matches(v)),
+            _ => false,
+        }
+    }
+}
+
+impl Kind {
+    fn new(ty: Type, runtime: RuntimeType) -> Self {
+        match (ty, runtime) {
+            (Type::TYPE_DOUBLE, _) => Kind::Double,
+            (Type::TYPE_FLOAT, _) => Kind::Float,
+            (Type::TYPE_INT64, _) => Kind::Int64,
+            (Type::TYPE_UINT64, _) => Kind::Uint64,
+            (Type::TYPE_INT32, _) => Kind::Int32,
+            (Type::TYPE_FIXED64, _) => Kind::Fixed64,
+            (Type::TYPE_FIXED32, _) => Kind::Fixed32,
+            (Type::TYPE_BOOL, _) => Kind::Bool,
+            (Type::TYPE_STRING, _) => Kind::String,
+            (Type::TYPE_BYTES, _) => Kind::Bytes,
+            (Type::TYPE_UINT32, _) => Kind::Uint32,
+            (Type::TYPE_SFIXED32, _) => Kind::Sfixed32,
+            (Type::TYPE_SFIXED64, _) => Kind::Sfixed64,
+            (Type::TYPE_SINT32, _) => Kind::Sint32,
+            (Type::TYPE_SINT64, _) => Kind::Sint64,
+            (Type::TYPE_GROUP, RuntimeType::Message(desc)) => Kind::Group(desc),
+            (_, RuntimeType::Enum(desc)) => Kind::Enum(desc),
+            (_, RuntimeType::Message(desc)) => Kind::Message(desc),
+            (ty, runtime) => unreachable!("field type {ty:?} resolved to {runtime:?}"),
+        }
+    }
+
+    fn of(field: &FieldDescriptor) -> Self {
+        Kind::new(field.proto().type_(), field.singular_runtime_type())
+    }
+
+    fn wire_type(&self) -> WireType {
+        match self {
+            Kind::Int64
+            | Kind::Uint64
+            | Kind::Int32
+            | Kind::Bool
+            | Kind::Uint32
+            | Kind::Sint32
+            | Kind::Sint64
+            | Kind::Enum(_) => WireType::Varint,
+            Kind::Fixed64 | Kind::Sfixed64 | Kind::Double => WireType::SixtyFourBit,
+            Kind::Fixed32 | Kind::Sfixed32 | Kind::Float => WireType::ThirtyTwoBit,
+            Kind::String | Kind::Bytes | Kind::Message(_) => WireType::LengthDelimited,
+            Kind::Group(_) => WireType::StartGroup,
+        }
+    }
+
+    fn is_packable(&self) -> bool {
+        !matches!(
+            self,
+            Kind::String | Kind::Bytes | Kind::Message(_) | Kind::Group(_)
+        )
+    }
+
+    fn default_value(&self) -> Value {
+        match self {
+            Kind::Double => Value::F64(0.0),
+            Kind::Float => Value::F32(0.0),
+            Kind::Int64 | Kind::Sfixed64 | Kind::Sint64 => Value::I64(0),
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(0),
+            Kind::Int32 | Kind::Sfixed32 | Kind::Sint32 => Value::I32(0),
+            Kind::Uint32 | Kind::Fixed32 => Value::U32(0),
+            Kind::Bool => Value::Bool(false),
+            Kind::String => Value::String(FastStr::empty()),
+            Kind::Bytes => Value::Bytes(Bytes::new()),
+            Kind::Enum(desc) => Value::EnumNumber(desc.default_value().value()),
+            Kind::Message(desc) | Kind::Group(desc) => {
+                Value::Message(DynamicMessage::new(desc.clone()))
+            }
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Kind::Double, Value::F64(_))
+            | (Kind::Float, Value::F32(_))
+            | (Kind::Int64 | Kind::Sfixed64 | Kind::Sint64, Value::I64(_))
+            | (Kind::Uint64 | Kind::Fixed64, Value::U64(_))
+            | (Kind::Int32 | Kind::Sfixed32 | Kind::Sint32, Value::I32(_))
+            | (Kind::Uint32 | Kind::Fixed32, Value::U32(_))
+            | (Kind::Bool, Value::Bool(_))
+            | (Kind::String, Value::String(_))
+            | (Kind::Bytes, Value::Bytes(_))
+            | (Kind::Enum(_), Value::EnumNumber(_)) => true,
+            (Kind::Message(desc) | Kind::Group(desc), Value::Message(msg)) => {
+                msg.descriptor == *desc
+            }
+            _ => false,
+        }
+    }
+
+    /// Decodes one value of this kind. Messages are merged into `value`, all
+    /// other kinds replace it.
+    fn merge(
+        &self,
+        tag: u32,
+        wire_type: WireType,
+        value: &mut Value,
+        buf: &mut Bytes,
+        ctx: &mut DecodeContext,
+    ) -> Result<(), DecodeError> {
+        macro_rules! merge_as {
+            ($module:ident, $variant:ident) => {{
+                let mut v = Default::default();
+                encoding::$module::merge(wire_type, &mut v, buf, ctx)?;
+                *value = Value::$variant(v);
+            }};
+        }
+
+        match self {
+            Kind::Double => merge_as!(double, F64),
+            Kind::Float => merge_as!(float, F32),
+            Kind::Int64 => merge_as!(int64, I64),
+            Kind::Uint64 => merge_as!(uint64, U64),
+            Kind::Int32 => merge_as!(int32, I32),
+            Kind::Fixed64 => merge_as!(fixed64, U64),
+            Kind::Fixed32 => merge_as!(fixed32, U32),
+            Kind::Bool => merge_as!(bool, Bool),
+            Kind::String => {
+                let mut v = FastStr::empty();
+                encoding::faststr::merge(wire_type, &mut v, buf, ctx)?;
+                if std::str::from_utf8(v.as_bytes()).is_err() {
+                    return Err(DecodeError::new(
+                        "invalid string value: data is not UTF-8 encoded",
+                    ));
+                }
+                *value = Value::String(v);
+            }
+            Kind::Bytes => merge_as!(bytes, Bytes),
+            Kind::Uint32 => merge_as!(uint32, U32),
+            Kind::Sfixed32 => merge_as!(sfixed32, I32),
+            Kind::Sfixed64 => merge_as!(sfixed64, I64),
+            Kind::Sint32 => merge_as!(sint32, I32),
+            Kind::Sint64 => merge_as!(sint64, I64),
+            Kind::Enum(_) => merge_as!(int32, EnumNumber),
+            Kind::Message(_) | Kind::Group(_) => {
+                if !self.matches(value) {
+                    *value = self.default_value();
+                }
+                let Value::Message(msg) = value else {
+                    unreachable!()
+                };
+                if let Kind::Group(_) = self {
+                    group::merge(tag, wire_type, msg, buf, ctx)?;
+                } else {
+                    message::merge(wire_type, msg, buf, ctx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes a scalar value without its key.
+    fn encode_payload(&self, value: &Value, buf: &mut LinkedBytes) {
+        match (self, value) {
+            (Kind::Double, Value::F64(v)) => buf.put_f64_le(*v),
+            (Kind::Float, Value::F32(v)) => buf.put_f32_le(*v),
+            (Kind::Int64, Value::I64(v)) => encode_varint(*v as u64, buf),
+            (Kind::Sint64, Value::I64(v)) => encode_varint(((v << 1) ^ (v >> 63)) as u64, buf),
+            (Kind::Sfixed64, Value::I64(v)) => buf.put_i64_le(*v),
+            (Kind::Uint64, Value::U64(v)) => encode_varint(*v, buf),
+            (Kind::Fixed64, Value::U64(v)) => buf.put_u64_le(*v),
+            (Kind::Int32, Value::I32(v)) | (Kind::Enum(_), Value::EnumNumber(v)) => {
+                encode_varint(*v as u64, buf)
+            }
+            (Kind::Sint32, Value::I32(v)) => {
+                encode_varint(((v << 1) ^ (v >> 31)) as u32 as u64, buf)
+            }
+            (Kind::Sfixed32, Value::I32(v)) => buf.put_i32_le(*v),
+            (Kind::Uint32, Value::U32(v)) => encode_varint(*v as u64, buf),
+            (Kind::Fixed32, Value::U32(v)) => buf.put_u32_le(*v),
+            (Kind::Bool, Value::Bool(v)) => encode_varint(*v as u64, buf),
+            _ => unreachable!("{value:?} is not a scalar value of {self:?}"),
+        }
+    }
+
+    /// Returns the length of a scalar value without its key.
+    fn payload_len(&self, value: &Value) -> usize {
+        match (self, value) {
+            (Kind::Double | Kind::Fixed64 | Kind::Sfixed64, _) => 8,
+            (Kind::Float | Kind::Fixed32 | Kind::Sfixed32, _) => 4,
+            (Kind::Int64, Value::I64(v)) => encoded_len_varint(*v as u64),
+            (Kind::Sint64, Value::I64(v)) => encoded_len_varint(((v << 1) ^ (v >> 63)) as u64),
+            (Kind::Uint64, Value::U64(v)) => encoded_len_varint(*v),
+            (Kind::Int32, Value::I32(v)) | (Kind::Enum(_), Value::EnumNumber(v)) => {
+                encoded_len_varint(*v as u64)
+            }
+            (Kind::Sint32, Value::I32(v)) => {
+                encoded_len_varint(((v << 1) ^ (v >> 31)) as u32 as u64)
+            }
+            (Kind::Uint32, Value::U32(v)) => encoded_len_varint(*v as u64),
+            (Kind::Bool, _) => 1,
+            _ => unreachable!("{value:?} is not a scalar value of {self:?}"),
+        }
+    }
+
+    /// Encodes a value together with its key.
+    fn encode(&self, tag: u32, value: &Value, buf: &mut LinkedBytes) {
+        match (self, value) {
+            (Kind::String, Value::String(v)) => encoding::faststr::encode_raw(tag, v, buf),
+            (Kind::Bytes, Value::Bytes(v)) => {
+                encode_key(tag, WireType::LengthDelimited, buf);
+                encode_varint(v.len() as u64, buf);
+                if v.len() >= ZERO_COPY_THRESHOLD {
+                    buf.insert(v.clone());
+                } else {
+                    buf.put_slice(v);
+                }
+            }
+            (Kind::Message(_), Value::Message(msg)) => message::encode(tag, msg, buf),
+            (Kind::Group(_), Value::Message(msg)) => group::encode(tag, msg, buf),
+            _ => {
+                encode_key(tag, self.wire_type(), buf);
+                self.encode_payload(value, buf);
+            }
+        }
+    }
+
+    /// Returns the encoded length of a value together with its key.
+    fn encoded_len(&self, ctx: &mut EncodeLengthContext, tag: u32, value: &Value) -> usize {
+        match (self, value) {
+            (Kind::String, Value::String(v)) => length_delimited_len(ctx, tag, v.len()),
+            (Kind::Bytes, Value::Bytes(v)) => length_delimited_len(ctx, tag, v.len()),
+            (Kind::Message(_), Value::Message(msg)) => message::encoded_len(ctx, tag, msg),
+            (Kind::Group(_), Value::Message(msg)) => group::encoded_len(ctx, tag, msg),
+            _ => key_len(tag) + self.payload_len(value),
+        }
+    }
+}
+
+fn length_delimited_len(ctx: &mut EncodeLengthContext, tag: u32, len: usize) -> usize {
+    if len >= ZERO_COPY_THRESHOLD {
+        ctx.zero_copy_len += len;
+    }
+    key_len(tag) + encoded_len_varint(len as u64) + len
+}
+
+impl Value {
+    /// Whether the value would be omitted from the wire by a field without
+    /// explicit presence.
+    fn is_default(&self) -> bool {
+        match self {
+            Value::Bool(v) => !*v,
+            Value::I32(v) | Value::EnumNumber(v) => *v == 0,
+            Value::I64(v) => *v == 0,
+            Value::U32(v) => *v == 0,
+            Value::U64(v) => *v == 0,
+            Value::F32(v) => *v == 0.0,
+            Value::F64(v) => *v == 0.0,
+            Value::String(v) => v.is_empty(),
+            Value::Bytes(v) => v.is_empty(),
+            Value::Message(_) => false,
+            Value::List(v) => v.is_empty(),
+            Value::Map(v) => v.is_empty(),
+        }
+    }
+}
+
+fn shape(field: &FieldDescriptor) -> Shape {
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(runtime) => {
+            Shape::Singular(Kind::new(field.proto().type_(), runtime))
+        }
+        RuntimeFieldType::Repeated(runtime) => {
+            Shape::Repeated(Kind::new(field.proto().type_(), runtime))
+        }
+        RuntimeFieldType::Map(..) => {
+            let type_name = field.proto().type_name().trim_start_matches('.');
+            let entry = field
+                .containing_message()
+                .nested_messages()
+                .find(|m| m.is_map_entry() && m.full_name() == type_name)
+                .expect("map entry message of a map field");
+            let key = entry.field_by_number(1).expect("map entry key field");
+            let value = entry.field_by_number(2).expect("map entry value field");
+            Shape::Map(Kind::of(&key), Kind::of(&value))
+        }
+    }
+}
+
+fn is_proto3(field: &FieldDescriptor) -> bool {
+    field.containing_message().file_descriptor().syntax() == Syntax::Proto3
+}
+
+/// Whether a singular field is written even when it holds the default value.
+fn has_presence(field: &FieldDescriptor, kind: &Kind) -> bool {
+    !is_proto3(field)
+        || field.containing_oneof_including_synthetic().is_some()
+        || matches!(kind, Kind::Message(_) | Kind::Group(_))
+}
+
+fn is_packed(field: &FieldDescriptor, kind: &Kind) -> bool {
+    if !kind.is_packable() {
+        return false;
+    }
+    let options = field.proto().options.get_or_default();
+    if is_proto3(field) {
+        options.packed.unwrap_or(true)
+    } else {
+        options.packed.unwrap_or(false)
+    }
+}
+
+fn skip_default() -> bool {
+    !cfg!(feature = "pb-encode-default-value")
+}
+
+impl DynamicMessage {
+    /// Creates an empty message of the given type.
+    pub fn new(descriptor: MessageDescriptor) -> Self {
+        Self {
+            descriptor,
+            fields: BTreeMap::new(),
+            unknown_fields: BytesVec::new(),
+        }
+    }
+
+    /// Decodes a message of the given type from a buffer.
+    ///
+    /// The entire buffer will be consumed.
+    pub fn decode(descriptor: MessageDescriptor, buf: Bytes) -> Result<Self, DecodeError> {
+        let mut msg = Self::new(descriptor);
+        msg.merge(buf)?;
+        Ok(msg)
+    }
+
+    /// Returns the descriptor of the message type.
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.descriptor
+    }
+
+    /// Returns the value of the field with the given number, if present.
+    pub fn get(&self, number: u32) -> Option<&Value> {
+        self.fields.get(&number)
+    }
+
+    /// Returns a mutable reference to the value of the field with the given
+    /// number, if present.
+    ///
+    /// The value should keep the type declared by the descriptor: a field
+    /// whose value does not match it is skipped when encoding, and replaced
+    /// when decoding into the message.
+    pub fn get_mut(&mut self, number: u32) -> Option<&mut Value> {
+        self.fields.get_mut(&number)
+    }
+
+    /// Returns the value of the field with the given name, if present.
+    pub fn get_by_name(&self, name: &str) -> Option<&Value> {
+        let field = self.descriptor.field_by_name(name)?;
+        self.get(field.number() as u32)
+    }
+
+    /// Sets the value of a field, returning the previous one.
+    ///
+    /// Setting a member of a oneof clears the other members.
+    ///
+    /// # Panics
+    ///
+    /// If the field is not declared by the descriptor, or the value does not
+    /// match the declared type.
+    pub fn set(&mut self, number: u32, value: Value) -> Option<Value> {
+        let field = self.descriptor.field_by_number(number).unwrap_or_else(|| {
+            panic!(
+                "field {number} is not declared by `{}`",
+                self.descriptor.full_name()
+            )
+        });
+        assert!(
+            shape(&field).matches(&value),
+            "{value:?} does not match the type of field `{}`",
+            field.full_name()
+        );
+        self.clear_oneof_siblings(&field);
+        self.fields.insert(number, value)
+    }
+
+    /// Sets the value of the field with the given name, returning the previous
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`DynamicMessage::set`].
+    pub fn set_by_name(&mut self, name: &str, value: Value) -> Option<Value> {
+        let field = self.descriptor.field_by_name(name).unwrap_or_else(|| {
+            panic!(
+                "field `{name}` is not declared by `{}`",
+                self.descriptor.full_name()
+            )
+        });
+        self.set(field.number() as u32, value)
+    }
+
+    /// Removes the value of a field, returning it.
+    pub fn remove(&mut self, number: u32) -> Option<Value> {
+        self.fields.remove(&number)
+    }
+
+    /// Iterates over the present fields in field number order.
+    pub fn fields(&self) -> impl Iterator<Item = (FieldDescriptor, &Value)> + '_ {
+        self.fields
+            .iter()
+            .filter_map(|(number, value)| Some((self.descriptor.field_by_number(*number)?, value)))
+    }
+
+    /// Iterates over the fields to encode, with their shapes, skipping the
+    /// values that do not match their declared type.
+    fn encodable_fields(&self) -> impl Iterator<Item = (FieldDescriptor, Shape, &Value)> + '_ {
+        self.fields().filter_map(|(field, value)| {
+            let shape = shape(&field);
+            shape.matches(value).then_some((field, shape, value))
+        })
+    }
+
+    /// Returns the raw bytes of the fields not declared by the descriptor.
+    pub fn unknown_fields(&self) -> &BytesVec {
+        &self.unknown_fields
+    }
+
+    /// Drops the fields not declared by the descriptor.
+    pub fn clear_unknown_fields(&mut self) {
+        self.unknown_fields = BytesVec::new();
+    }
+
+    fn clear_oneof_siblings(&mut self, field: &FieldDescriptor) {
+        if let Some(oneof) = field.containing_oneof() {
+            for sibling in oneof.fields() {
+                if sibling.number() != field.number() {
+                    self.fields.remove(&(sibling.number() as u32));
+                }
+            }
+        }
+    }
+
+    fn merge_map_entry(
+        key_kind: &Kind,
+        val_kind: &Kind,
+        map: &mut BTreeMap<MapKey, Value>,
+        buf: &mut Bytes,
+        ctx: &mut DecodeContext,
+    ) -> Result<(), DecodeError> {
+        let mut key = key_kind.default_value();
+        let mut val = val_kind.default_value();
+        ctx.limit_reached()?;
+        ctx.enter_recursion();
+        merge_loop(
+            &mut (&mut key, &mut val),
+            buf,
+            ctx,
+            |(key, val), buf, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                match tag {
+                    1 => key_kind.merge(tag, wire_type, key, buf, ctx),
+                    2 => val_kind.merge(tag, wire_type, val, buf, ctx),
+                    _ => skip_field(wire_type, tag, buf, ctx),
+                }
+            },
+        )?;
+        ctx.exit_recursion();
+        let key = MapKey::from_value(key).ok_or_else(|| DecodeError::new("invalid map key"))?;
+        map.insert(key, val);
+        Ok(())
+    }
+
+    fn map_entry_len(key_kind: &Kind, val_kind: &Kind, key: &Value, val: &Value) -> usize {
+        let mut ctx = EncodeLengthContext::default();
+        (if skip_default() && key.is_default() {
+            0
+        } else {
+            key_kind.encoded_len(&mut ctx, 1, key)
+        }) + (if skip_default() && val.is_default() {
+            0
+        } else {
+            val_kind.encoded_len(&mut ctx, 2, val)
+        })
+    }
+}
+
+impl Message for DynamicMessage {
+    fn encode_raw(&self, buf: &mut LinkedBytes) {
+        for (field, shape, value) in self.encodable_fields() {
+            let tag = field.number() as u32;
+            match (shape, value) {
+                (Shape::Singular(kind), value) => {
+                    if skip_default() && !has_presence(&field, &kind) && value.is_default() {
+                        continue;
+                    }
+                    kind.encode(tag, value, buf);
+                }
+                (Shape::Repeated(kind), Value::List(list)) => {
+                    if is_packed(&field, &kind) {
+                        if list.is_empty() {
+                            continue;
+                        }
+                        encode_key(tag, WireType::LengthDelimited, buf);
+                        let len: usize = list.iter().map(|v| kind.payload_len(v)).sum();
+                        encode_varint(len as u64, buf);
+                        for v in list {
+                            kind.encode_payload(v, buf);
+                        }
+                    } else {
+                        for v in list {
+                            kind.encode(tag, v, buf);
+                        }
+                    }
+                }
+                (Shape::Map(key_kind, val_kind), Value::Map(map)) => {
+                    for (key, val) in map {
+                        let key = key.to_value();
+                        let len = Self::map_entry_len(&key_kind, &val_kind, &key, val);
+                        encode_key(tag, WireType::LengthDelimited, buf);
+                        encode_varint(len as u64, buf);
+                        if !(skip_default() && key.is_default()) {
+                            key_kind.encode(1, &key, buf);
+                        }
+                        if !(skip_default() && val.is_default()) {
+                            val_kind.encode(2, val, buf);
+                        }
+                    }
+                }
+                _ => unreachable!("{value:?} does not match field `{}`", field.full_name()),
+            }
+        }
+        for bytes in self.unknown_fields.list.iter() {
+            buf.insert(bytes.clone());
+        }
+    }
+
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut Bytes,
+        ctx: &mut DecodeContext,
+        _is_root: bool,
+    ) -> Result<(), DecodeError> {
+        let Some(field) = self.descriptor.field_by_number(tag) else {
+            skip_field(wire_type, tag, buf, ctx)?;
+            let end = buf.chunk().as_ptr();
+            let len = end as usize - ctx.raw_bytes_cursor();
+            let val = ctx.raw_bytes_split_to(len);
+            self.unknown_fields.push_back(val);
+            return Ok(());
+        };
+
+        let result = match shape(&field) {
+            Shape::Singular(kind) => {
+                self.clear_oneof_siblings(&field);
+                let value = self
+                    .fields
+                    .entry(tag)
+                    .or_insert_with(|| kind.default_value());
+                kind.merge(tag, wire_type, value, buf, ctx)
+            }
+            Shape::Repeated(kind) => {
+                let value = self
+                    .fields
+                    .entry(tag)
+                    .or_insert_with(|| Value::List(Vec::new()));
+                if !matches!(value, Value::List(_)) {
+                    *value = Value::List(Vec::new());
+                }
+                let Value::List(list) = value else {
+                    unreachable!()
+                };
+                if kind.is_packable() && wire_type == WireType::LengthDelimited {
+                    merge_loop(list, buf, ctx, |list, buf, ctx| {
+                        let mut v = kind.default_value();
+                        kind.merge(tag, kind.wire_type(), &mut v, buf, ctx)?;
+                        list.push(v);
+                        Ok(())
+                    })
+                } else {
+                    let mut v = kind.default_value();
+                    kind.merge(tag, wire_type, &mut v, buf, ctx)
+                        .map(|_| list.push(v))
+                }
+            }
+            Shape::Map(key_kind, val_kind) => {
+                let value = self
+                    .fields
+                    .entry(tag)
+                    .or_insert_with(|| Value::Map(BTreeMap::new()));
+                if !matches!(value, Value::Map(_)) {
+                    *value = Value::Map(BTreeMap::new());
+                }
+                let Value::Map(map) = value else {
+                    unreachable!()
+                };
+                check_wire_type(WireType::LengthDelimited, wire_type)
+                    .and_then(|_| Self::merge_map_entry(&key_kind, &val_kind, map, buf, ctx))
+            }
+        };
+
+        result.map_err(|mut err| {
+            err.push(self.descriptor.name().to_owned(), field.name().to_owned());
+            err
+        })
+    }
+
+    fn encoded_len(&self, ctx: &mut EncodeLengthContext) -> usize {
+        let mut len = 0;
+        for (field, shape, value) in self.encodable_fields() {
+            let tag = field.number() as u32;
+            len += match (shape, value) {
+                (Shape::Singular(kind), value) => {
+                    if skip_default() && !has_presence(&field, &kind) && value.is_default() {
+                        0
+                    } else {
+                        kind.encoded_len(ctx, tag, value)
+                    }
+                }
+                (Shape::Repeated(kind), Value::List(list)) => {
+                    if is_packed(&field, &kind) {
+                        if list.is_empty() {
+                            0
+                        } else {
+                            let len: usize = list.iter().map(|v| kind.payload_len(v)).sum();
+                            key_len(tag) + encoded_len_varint(len as u64) + len
+                        }
+                    } else {
+                        list.iter().map(|v| kind.encoded_len(ctx, tag, v)).sum()
+                    }
+                }
+                (Shape::Map(key_kind, val_kind), Value::Map(map)) => map
+                    .iter()
+                    .map(|(key, val)| {
+                        let key = key.to_value();
+                        let mut entry_len = 0;
+                        if !(skip_default() && key.is_default()) {
+                            entry_len += key_kind.encoded_len(ctx, 1, &key);
+                        }
+                        if !(skip_default() && val.is_default()) {
+                            entry_len += val_kind.encoded_len(ctx, 2, val);
+                        }
+                        key_len(tag) + encoded_len_varint(entry_len as u64) + entry_len
+                    })
+                    .sum(),
+                _ => unreachable!("{value:?} does not match field `{}`", field.full_name()),
+            };
+        }
+        len + self.unknown_fields.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::{
+        descriptor::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions,
+            OneofDescriptorProto,
+            field_descriptor_proto::{Label, Type},
+        },
+        reflect::FileDescriptor,
+    };
+
+    use super::*;
+
+    fn field(name: &str, number: i32, label: Label, ty: Type) -> FieldDescriptorProto {
+        let mut field = FieldDescriptorProto::new();
+        field.set_name(name.into());
+        field.set_number(number);
+        field.set_label(label);
+        field.set_type(ty);
+        field
+    }
+
+    fn message_field(
+        name: &str,
+        number: i32,
+        label: Label,
+        type_name: &str,
+    ) -> FieldDescriptorProto {
+        let mut field = field(name, number, label, Type::TYPE_MESSAGE);
+        field.set_type_name(type_name.into());
+        field
+    }
+
+    fn file() -> FileDescriptor {
+        let mut entry = DescriptorProto::new();
+        entry.set_name("LabelsEntry".into());
+        entry
+            .field
+            .push(field("key", 1, Label::LABEL_OPTIONAL, Type::TYPE_STRING));
+        entry
+            .field
+            .push(field("value", 2, Label::LABEL_OPTIONAL, Type::TYPE_SINT64));
+        let mut options = MessageOptions::new();
+        options.set_map_entry(true);
+        entry.options = Some(options).into();
+
+        let mut msg = DescriptorProto::new();
+        msg.set_name("Msg".into());
+        msg.field
+            .push(field("id", 1, Label::LABEL_OPTIONAL, Type::TYPE_INT32));
+        msg.field
+            .push(field("name", 2, Label::LABEL_OPTIONAL, Type::TYPE_STRING));
+        msg.field.push(field(
+            "scores",
+            3,
+            Label::LABEL_REPEATED,
+            Type::TYPE_FIXED32,
+        ));
+        msg.field.push(message_field(
+            "labels",
+            4,
+            Label::LABEL_REPEATED,
+            ".test.Msg.LabelsEntry",
+        ));
+        msg.field.push(message_field(
+            "child",
+            5,
+            Label::LABEL_OPTIONAL,
+            ".test.Msg",
+        ));
+        let mut a = field("a", 6, Label::LABEL_OPTIONAL, Type::TYPE_STRING);
+        a.set_oneof_index(0);
+        msg.field.push(a);
+        let mut b = field("b", 7, Label::LABEL_OPTIONAL, Type::TYPE_BYTES);
+        b.set_oneof_index(0);
+        msg.field.push(b);
+        let mut choice = OneofDescriptorProto::new();
+        choice.set_name("choice".into());
+        msg.oneof_decl.push(choice);
+        msg.nested_type.push(entry);
+
+        let mut file = FileDescriptorProto::new();
+        file.set_name("test.proto".into());
+        file.set_package("test".into());
+        file.set_syntax("proto3".into());
+        file.message_type.push(msg);
+        FileDescriptor::new_dynamic(file, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_dynamic_message_roundtrip() {
+        let desc = file().message_by_package_relative_name("Msg").unwrap();
+
+        let mut child = DynamicMessage::new(desc.clone());
+        child.set(1, Value::I32(-7));
+
+        let mut msg = DynamicMessage::new(desc.clone());
+        msg.set_by_name("id", Value::I32(42));
+        msg.set(2, Value::String("pilota".into()));
+        msg.set(3, Value::List(vec![Value::U32(1), Value::U32(2)]));
+        msg.set(
+            4,
+            Value::Map(BTreeMap::from([
+                (MapKey::String("a".into()), Value::I64(-1)),
+                (MapKey::String("b".into()), Value::I64(0)),
+            ])),
+        );
+        msg.set(5, Value::Message(child));
+        msg.set(6, Value::String("".into()));
+        msg.set(7, Value::Bytes(Bytes::from_static(b"\x01")));
+        assert!(msg.get(6).is_none());
+
+        let mut buf = LinkedBytes::new();
+        msg.encode(&mut buf).unwrap();
+        let bytes = buf.concat().freeze();
+        assert_eq!(
+            bytes.len(),
+            msg.encoded_len(&mut EncodeLengthContext::default())
+        );
+
+        let decoded = DynamicMessage::decode(desc.clone(), bytes).unwrap();
+        assert_eq!(decoded, msg);
+        assert_eq!(
+            decoded.get_by_name("name"),
+            Some(&Value::String("pilota".into()))
+        );
+
+        // an empty string in a oneof keeps its presence
+        msg.set(6, Value::String("".into()));
+        assert!(msg.get(7).is_none());
+        let mut buf = LinkedBytes::new();
+        msg.encode(&mut buf).unwrap();
+        let decoded = DynamicMessage::decode(desc, buf.concat().freeze()).unwrap();
+        assert_eq!(decoded.get(6), Some(&Value::String("".into())));
+    }
+
+    #[test]
+    fn test_dynamic_message_unknown_fields() {
+        let desc = file().message_by_package_relative_name("Msg").unwrap();
+
+        // field 1 = 150, field 100 = "hi", field 3 unpacked fixed32 = 5
+        let raw = Bytes::from_static(&[
+            0x08, 0x96, 0x01, 0xa2, 0x06, 0x02, b'h', b'i', 0x1d, 0x05, 0x00, 0x00, 0x00,
+        ]);
+        let msg = DynamicMessage::decode(desc.clone(), raw).unwrap();
+        assert_eq!(msg.get(1), Some(&Value::I32(150)));
+        assert_eq!(msg.get(3), Some(&Value::List(vec![Value::U32(5)])));
+        assert_eq!(msg.unknown_fields().size(), 5);
+
+        let mut buf = LinkedBytes::new();
+        msg.encode(&mut buf).unwrap();
+        let decoded = DynamicMessage::decode(desc.clone(), buf.concat().freeze()).unwrap();
+        assert_eq!(decoded, msg);
+
+        let err = DynamicMessage::decode(desc, Bytes::from_static(&[0x0d, 0, 0, 0, 0]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Msg.id"), "{err}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dynamic_message_set_mismatch() {
+        let desc = file().message_by_package_relative_name("Msg").unwrap();
+        DynamicMessage::new(desc).set(1, Value::String("1".into()));
+    }
+
+    #[test]
+    fn test_dynamic_message_get_mut_mismatch() {
+        let desc = file().message_by_package_relative_name("Msg").unwrap();
+        let mut msg = DynamicMessage::new(desc.clone());
+        msg.set(1, Value::I32(1));
+        msg.set(2, Value::String("pilota".into()));
+        msg.set(3, Value::List(vec![Value::U32(1)]));
+        *msg.get_mut(1).unwrap() = Value::String("1".into());
+        *msg.get_mut(3).unwrap() = Value::List(vec![Value::Bool(true)]);
+
+        // the mismatched fields are skipped
+        let mut buf = LinkedBytes::new();
+        msg.encode(&mut buf).unwrap();
+        let bytes = buf.concat().freeze();
+        assert_eq!(
+            bytes.len(),
+            msg.encoded_len(&mut EncodeLengthContext::default())
+        );
+        let decoded = DynamicMessage::decode(desc, bytes.clone()).unwrap();
+        assert!(decoded.get(1).is_none());
+        assert!(decoded.get(3).is_none());
+        assert_eq!(decoded.get(2), Some(&Value::String("pilota".into())));
+
+        // and replaced when decoding into the message
+        *msg.get_mut(3).unwrap() = Value::Bool(true);
+        msg.merge(Bytes::from_static(&[0x1d, 0x05, 0x00, 0x00, 0x00]))
+            .unwrap();
+        assert_eq!(msg.get(3), Some(&Value::List(vec![Value::U32(5)])));
+    }
+}
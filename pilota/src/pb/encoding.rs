@@ -1985,9 +1985,10 @@ pub struct EncodeLengthContext {
 }
 
 #[cfg(test)]
+#[allow(unexpected_cfgs)]
 mod test {
     use alloc::string::ToString;
-    use core::{fmt::Debug, u64};
+    use core::fmt::Debug;
 
     use ::bytes::Bytes;
     use proptest::{prelude::*, test_runner::TestCaseResult};
@@ -2406,14 +2407,14 @@ mod test {
         let e = ::faststr::FastStr::from("");
         faststr::encode(1, &e, &mut buf);
         if cfg!(feature = "pb-encode-default-value") {
-            assert!(buf.len() > 0);
+            assert!(!buf.is_empty());
         } else {
             assert_eq!(buf.len(), 0);
         }
         let mut buf2 = LinkedBytes::new();
         let n = ::faststr::FastStr::from("x");
         faststr::encode(1, &n, &mut buf2);
-        assert!(buf2.len() > 0);
+        assert!(!buf2.is_empty());
     }
 
     #[test]
@@ -2437,14 +2438,14 @@ mod test {
         let e = Bytes::new();
         bytes::encode(1, &e, &mut buf);
         if cfg!(feature = "pb-encode-default-value") {
-            assert!(buf.len() > 0);
+            assert!(!buf.is_empty());
         } else {
             assert_eq!(buf.len(), 0);
         }
         let mut buf2 = LinkedBytes::new();
         let n = Bytes::from_static(b"x");
         bytes::encode(1, &n, &mut buf2);
-        assert!(buf2.len() > 0);
+        assert!(!buf2.is_empty());
     }
 
     #[test]
@@ -2452,13 +2453,13 @@ mod test {
         let mut buf = LinkedBytes::new();
         int32::encode(1, &0i32, &mut buf);
         if cfg!(feature = "pb-encode-default-value") {
-            assert!(buf.len() > 0);
+            assert!(!buf.is_empty());
         } else {
             assert_eq!(buf.len(), 0);
         }
         let mut buf2 = LinkedBytes::new();
         int32::encode(1, &1i32, &mut buf2);
-        assert!(buf2.len() > 0);
+        assert!(!buf2.is_empty());
     }
 
     macro_rules! gen_skip_default_numeric_tests {
@@ -2482,13 +2483,13 @@ mod test {
                     let mut buf = LinkedBytes::new();
                     super::super::$mod::encode(1, &$zero, &mut buf);
                     if cfg!(feature = "pb-encode-default-value") {
-                        assert!(buf.len() > 0);
+                        assert!(!buf.is_empty());
                     } else {
                         assert_eq!(buf.len(), 0);
                     }
                     let mut buf2 = LinkedBytes::new();
                     super::super::$mod::encode(1, &$nonzero, &mut buf2);
-                    assert!(buf2.len() > 0);
+                    assert!(!buf2.is_empty());
                 }
             }
         };
@@ -24,7 +24,7 @@ struct Inner {
     /// A stack of (message, field) name pairs, which identify the specific
     /// message type and field where decoding failed. The stack contains an
     /// entry per level of nesting.
-    stack: Vec<(&'static str, &'static str)>,
+    stack: Vec<(Cow<'static, str>, Cow<'static, str>)>,
 }
 
 impl DecodeError {
@@ -46,8 +46,12 @@ impl DecodeError {
     ///
     /// Meant to be used only by `Message` implementations.
     #[doc(hidden)]
-    pub fn push(&mut self, message: &'static str, field: &'static str) {
-        self.inner.stack.push((message, field));
+    pub fn push(
+        &mut self,
+        message: impl Into<Cow<'static, str>>,
+        field: impl Into<Cow<'static, str>>,
+    ) {
+        self.inner.stack.push((message.into(), field.into()));
     }
 }
 
@@ -69,7 +73,7 @@ impl fmt::Debug for DecodeError {
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("failed to decode Protobuf message: ")?;
-        for &(message, field) in &self.inner.stack {
+        for (message, field) in &self.inner.stack {
             write!(f, "{message}.{field}: ")?;
         }
         f.write_str(&self.inner.description)
@@ -90,6 +94,76 @@ impl From<DecodeError> for std::io::Error {
     }
 }
 
+/// A Protobuf JSON decoding error.
+///
+/// `JsonError` indicates that the input is not valid JSON, or does not follow
+/// the proto3 JSON mapping of the message being decoded.
+#[cfg(feature = "pb-json")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct JsonError {
+    inner: Box<Inner>,
+}
+
+#[cfg(feature = "pb-json")]
+impl JsonError {
+    /// Creates a new `JsonError` with a root cause description.
+    ///
+    /// Meant to be used only by `JsonMessage` implementations.
+    #[doc(hidden)]
+    #[cold]
+    pub fn new(description: impl Into<Cow<'static, str>>) -> JsonError {
+        JsonError {
+            inner: Box::new(Inner {
+                description: description.into(),
+                stack: Vec::new(),
+            }),
+        }
+    }
+
+    /// Pushes a (message, field) name location pair on to the location stack.
+    ///
+    /// Meant to be used only by `JsonMessage` implementations.
+    #[doc(hidden)]
+    pub fn push(
+        &mut self,
+        message: impl Into<Cow<'static, str>>,
+        field: impl Into<Cow<'static, str>>,
+    ) {
+        self.inner.stack.push((message.into(), field.into()));
+    }
+}
+
+#[cfg(feature = "pb-json")]
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new(error.to_string())
+    }
+}
+
+#[cfg(feature = "pb-json")]
+impl fmt::Debug for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonError")
+            .field("description", &self.inner.description)
+            .field("stack", &self.inner.stack)
+            .finish()
+    }
+}
+
+#[cfg(feature = "pb-json")]
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to decode Protobuf JSON: ")?;
+        for (message, field) in &self.inner.stack {
+            write!(f, "{message}.{field}: ")?;
+        }
+        f.write_str(&self.inner.description)
+    }
+}
+
+#[cfg(feature = "pb-json")]
+impl std::error::Error for JsonError {}
+
 /// A Protobuf message encoding error.
 ///
 /// `EncodeError` always indicates that a message failed to encode because the
@@ -255,6 +255,13 @@ where
 }
 
 #[cfg(test)]
+#[allow(
+    unused_imports,
+    clippy::identity_op,
+    clippy::inherent_to_string,
+    clippy::redundant_static_lifetimes,
+    clippy::wrong_self_convention
+)]
 mod tests {
     use protobuf::Message as PbMessage;
 
@@ -268,7 +275,7 @@ mod tests {
         let mut m = protobuf::descriptor::FileOptions::new();
         m.mut_unknown_fields().add_varint(1, 1);
         let v = field.get(&m).unwrap();
-        assert_eq!(v, true);
+        assert!(v);
     }
 
     #[test]
@@ -0,0 +1,236 @@
+//! Field masks for protobuf messages.
+//!
+//! A [`FieldMask`] selects a subset of the fields of a message, using the
+//! path syntax of [`google.protobuf.FieldMask`]: each path is a dot-separated
+//! list of field names, where every segment but the last must name a singular
+//! message field. Messages generated with `with_field_mask` only encode the
+//! selected fields once a mask is set on them.
+//!
+//! [`google.protobuf.FieldMask`]: https://protobuf.dev/reference/protobuf/google.protobuf/#field-mask
+
+use std::collections::BTreeMap;
+
+use faststr::FastStr;
+use protobuf::{
+    reflect::{MessageDescriptor, RuntimeFieldType, RuntimeType},
+    well_known_types::field_mask::FieldMask as PbFieldMask,
+};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FieldMaskError {
+    #[error("path '{path}' is empty")]
+    EmptyPath { path: FastStr },
+    #[error("path '{path}', field '{field}' not found in message '{message}'")]
+    FieldNotFound {
+        path: FastStr,
+        field: FastStr,
+        message: FastStr,
+    },
+    #[error("path '{path}', field '{field}' of message '{message}' is not a singular message")]
+    NotAMessage {
+        path: FastStr,
+        field: FastStr,
+        message: FastStr,
+    },
+}
+
+/// A set of selected fields, keyed by field number.
+///
+/// A mask without children selects every field of the message, including
+/// all the fields of nested messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FieldMask {
+    children: Option<BTreeMap<u32, FieldMask>>,
+}
+
+impl FieldMask {
+    /// Returns a mask selecting every field.
+    pub fn all() -> Self {
+        Self { children: None }
+    }
+
+    /// Builds a mask for messages of type `desc` from `google.protobuf.FieldMask`
+    /// paths.
+    ///
+    /// Segments may use either the proto field name or its JSON name.
+    pub fn from_paths<S: AsRef<str>>(
+        desc: &MessageDescriptor,
+        paths: &[S],
+    ) -> Result<Self, FieldMaskError> {
+        let mut mask = Self {
+            children: Some(BTreeMap::new()),
+        };
+        for path in paths {
+            mask.insert_path(desc, path.as_ref())?;
+        }
+        Ok(mask)
+    }
+
+    /// Builds a mask for messages of type `desc` from a
+    /// `google.protobuf.FieldMask` message.
+    pub fn from_proto(
+        desc: &MessageDescriptor,
+        mask: &PbFieldMask,
+    ) -> Result<Self, FieldMaskError> {
+        Self::from_paths(desc, &mask.paths)
+    }
+
+    /// Returns whether every field is selected.
+    pub fn is_all(&self) -> bool {
+        self.children.is_none()
+    }
+
+    /// Looks up the field with the given number.
+    ///
+    /// Returns the mask of the field's own fields, `None` if the field is
+    /// selected as a whole, and whether the field is selected at all.
+    pub fn field(&self, number: u32) -> (Option<&FieldMask>, bool) {
+        match &self.children {
+            None => (None, true),
+            Some(children) => match children.get(&number) {
+                Some(child) => (Some(child).filter(|c| !c.is_all()), true),
+                None => (None, false),
+            },
+        }
+    }
+
+    fn insert_path(&mut self, desc: &MessageDescriptor, path: &str) -> Result<(), FieldMaskError> {
+        if path.is_empty() {
+            return Err(FieldMaskError::EmptyPath {
+                path: FastStr::new(path),
+            });
+        }
+
+        let mut mask = self;
+        let mut desc = desc.clone();
+        let mut segments = path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let Some(field) = desc.field_by_name_or_json_name(segment) else {
+                return Err(FieldMaskError::FieldNotFound {
+                    path: FastStr::new(path),
+                    field: FastStr::new(segment),
+                    message: FastStr::new(desc.full_name()),
+                });
+            };
+            let Some(children) = mask.children.as_mut() else {
+                // an ancestor is already selected as a whole
+                return Ok(());
+            };
+            let child = children
+                .entry(field.number() as u32)
+                .or_insert_with(|| FieldMask {
+                    children: Some(BTreeMap::new()),
+                });
+
+            if segments.peek().is_none() {
+                child.children = None;
+                return Ok(());
+            }
+            match field.runtime_field_type() {
+                RuntimeFieldType::Singular(RuntimeType::Message(nested)) => desc = nested,
+                _ => {
+                    return Err(FieldMaskError::NotAMessage {
+                        path: FastStr::new(path),
+                        field: FastStr::new(segment),
+                        message: FastStr::new(desc.full_name()),
+                    });
+                }
+            }
+            mask = child;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::{
+        descriptor::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+            field_descriptor_proto::{Label, Type},
+        },
+        reflect::FileDescriptor,
+    };
+
+    use super::*;
+
+    fn desc() -> MessageDescriptor {
+        let field = |name: &str, number: i32, label: Label, ty: Type| {
+            let mut field = FieldDescriptorProto::new();
+            field.set_name(name.into());
+            field.set_number(number);
+            field.set_label(label);
+            field.set_type(ty);
+            if ty == Type::TYPE_MESSAGE {
+                field.set_type_name(".test.Msg".into());
+            }
+            field
+        };
+        let mut msg = DescriptorProto::new();
+        msg.set_name("Msg".into());
+        msg.field
+            .push(field("user_id", 1, Label::LABEL_OPTIONAL, Type::TYPE_INT64));
+        msg.field
+            .push(field("child", 2, Label::LABEL_OPTIONAL, Type::TYPE_MESSAGE));
+        msg.field.push(field(
+            "children",
+            3,
+            Label::LABEL_REPEATED,
+            Type::TYPE_MESSAGE,
+        ));
+
+        let mut file = FileDescriptorProto::new();
+        file.set_name("test.proto".into());
+        file.set_package("test".into());
+        file.set_syntax("proto3".into());
+        file.message_type.push(msg);
+        FileDescriptor::new_dynamic(file, &[])
+            .unwrap()
+            .message_by_package_relative_name("Msg")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_field_mask_from_paths() {
+        let desc = desc();
+        let mask =
+            FieldMask::from_paths(&desc, &["userId", "child.child.user_id", "children"]).unwrap();
+
+        assert_eq!(mask.field(1), (None, true));
+        assert_eq!(mask.field(3), (None, true));
+        let (child, exist) = mask.field(2);
+        assert!(exist);
+        let child = child.unwrap();
+        assert_eq!(child.field(1), (None, false));
+        let (grandchild, exist) = child.field(2);
+        assert!(exist);
+        assert_eq!(grandchild.unwrap().field(1), (None, true));
+
+        // a whole field swallows its sub-paths
+        let mask =
+            FieldMask::from_paths(&desc, &["child.user_id", "child", "child.child"]).unwrap();
+        assert_eq!(mask.field(2), (None, true));
+        assert!(FieldMask::all().field(100).1);
+    }
+
+    #[test]
+    fn test_field_mask_invalid_paths() {
+        let desc = desc();
+        assert!(matches!(
+            FieldMask::from_paths(&desc, &[""]),
+            Err(FieldMaskError::EmptyPath { .. })
+        ));
+        assert!(matches!(
+            FieldMask::from_paths(&desc, &["child.unknown"]),
+            Err(FieldMaskError::FieldNotFound { .. })
+        ));
+        assert!(matches!(
+            FieldMask::from_paths(&desc, &["children.user_id"]),
+            Err(FieldMaskError::NotAMessage { .. })
+        ));
+        assert!(matches!(
+            FieldMask::from_paths(&desc, &["user_id.x"]),
+            Err(FieldMaskError::NotAMessage { .. })
+        ));
+    }
+}
@@ -0,0 +1,954 @@
+//! Proto3 canonical JSON mapping.
+//!
+//! Messages generated with `PbJsonPlugin` implement [`JsonMessage`]: fields
+//! are keyed by their lowerCamelCase JSON name (the original proto name is
+//! accepted too), 64-bit integers are strings, enums are written by name,
+//! bytes are base64 and the well-known types use their special forms. See
+//! <https://protobuf.dev/programming-guides/json/>.
+//!
+//! A `google.protobuf.Any` is written with the fields of the message it
+//! packs, so that message type has to be known by the full name in the type
+//! URL: every type that may be packed must be added with [`register_any`].
+//!
+//! The modules below are used by generated code, in the same way as
+//! [`encoding`](super::encoding) is for the binary format.
+
+use std::sync::{Arc, LazyLock, RwLock};
+
+use ahash::AHashMap;
+use base64::{
+    Engine as _,
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+};
+pub use serde_json::{Map, Value};
+
+pub use super::JsonError;
+use super::{EncodeLengthContext, Message, bytes::Bytes};
+
+/// A message that can be converted to and from its proto3 JSON form.
+pub trait JsonMessage: Default {
+    /// The full proto name of the message, e.g. `google.protobuf.Duration`.
+    const TYPE_NAME: &'static str;
+
+    /// Converts the message to JSON.
+    ///
+    /// Fails if an `Any` in the message packs a type that isn't registered
+    /// with [`register_any`].
+    fn to_json(&self) -> Result<Value, JsonError>;
+
+    /// Merges the fields set in `value` into `self`.
+    fn merge_json(&mut self, value: &Value) -> Result<(), JsonError>;
+
+    /// Decodes a message from JSON.
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        let mut message = Self::default();
+        message.merge_json(value)?;
+        Ok(message)
+    }
+
+    /// Converts the message to a JSON string.
+    fn to_json_string(&self) -> Result<String, JsonError> {
+        self.to_json().map(|value| value.to_string())
+    }
+
+    /// Decodes a message from a JSON string.
+    fn from_json_str(s: &str) -> Result<Self, JsonError> {
+        Self::from_json(&serde_json::from_str(s)?)
+    }
+}
+
+impl<M: JsonMessage> JsonMessage for Box<M> {
+    const TYPE_NAME: &'static str = M::TYPE_NAME;
+
+    fn to_json(&self) -> Result<Value, JsonError> {
+        (**self).to_json()
+    }
+
+    fn merge_json(&mut self, value: &Value) -> Result<(), JsonError> {
+        (**self).merge_json(value)
+    }
+}
+
+impl<M: JsonMessage + Clone> JsonMessage for Arc<M> {
+    const TYPE_NAME: &'static str = M::TYPE_NAME;
+
+    fn to_json(&self) -> Result<Value, JsonError> {
+        (**self).to_json()
+    }
+
+    fn merge_json(&mut self, value: &Value) -> Result<(), JsonError> {
+        Arc::make_mut(self).merge_json(value)
+    }
+}
+
+/// An enum, written as the name of its value. Values without a name are
+/// written as numbers.
+pub trait JsonEnum: Copy + Into<i32> + From<i32> {
+    /// Returns the proto name of this value.
+    fn json_name(&self) -> Option<&'static str>;
+
+    /// Returns the value with the proto name `name`.
+    fn from_json_name(name: &str) -> Option<Self>;
+
+    fn to_json(&self) -> Value {
+        match self.json_name() {
+            Some(name) => Value::String(name.to_string()),
+            None => Value::from((*self).into()),
+        }
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value {
+            Value::String(name) => Self::from_json_name(name)
+                .ok_or_else(|| JsonError::new(format!("unknown enum value `{name}`"))),
+            _ => int32::from_json(value).map(Self::from),
+        }
+    }
+}
+
+/// A oneof, written as the field of its active variant.
+pub trait JsonOneof: Sized {
+    /// Writes the active variant into `object`.
+    fn to_json(&self, object: &mut Map<String, Value>) -> Result<(), JsonError>;
+
+    /// Decodes `value` into `field` if `key` names one of the variants, and
+    /// returns whether it did.
+    fn merge_json(field: &mut Option<Self>, key: &str, value: &Value) -> Result<bool, JsonError>;
+}
+
+impl<T: JsonOneof> JsonOneof for Box<T> {
+    fn to_json(&self, object: &mut Map<String, Value>) -> Result<(), JsonError> {
+        (**self).to_json(object)
+    }
+
+    fn merge_json(field: &mut Option<Self>, key: &str, value: &Value) -> Result<bool, JsonError> {
+        let mut inner = field.take().map(|b| *b);
+        let merged = T::merge_json(&mut inner, key, value);
+        *field = inner.map(Box::new);
+        merged
+    }
+}
+
+/// Converts a message packed in an `Any` between its encoded and JSON forms.
+#[derive(Clone, Copy)]
+struct AnyType {
+    to_json: fn(&[u8]) -> Result<Value, JsonError>,
+    from_json: fn(&Value) -> Result<Vec<u8>, JsonError>,
+    /// Whether the type has a special JSON form, which is nested under
+    /// `value` instead of being inlined.
+    special: bool,
+}
+
+static ANY_TYPES: LazyLock<RwLock<AHashMap<&'static str, AnyType>>> =
+    LazyLock::new(Default::default);
+
+/// The well-known types whose JSON form isn't an object of their fields.
+const SPECIAL_TYPES: &[&str] = &[
+    "google.protobuf.Any",
+    "google.protobuf.Duration",
+    "google.protobuf.FieldMask",
+    "google.protobuf.Timestamp",
+    "google.protobuf.Struct",
+    "google.protobuf.ListValue",
+    "google.protobuf.Value",
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int64Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Int32Value",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.BoolValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.BytesValue",
+];
+
+/// Registers `M` as a type that can be packed in a `google.protobuf.Any`
+/// converted to or from JSON. Registering a type again replaces it.
+pub fn register_any<M>()
+where
+    M: JsonMessage + Message + 'static,
+{
+    fn to_json<M: JsonMessage + Message>(value: &[u8]) -> Result<Value, JsonError> {
+        M::decode(Bytes::copy_from_slice(value))
+            .map_err(|e| JsonError::new(format!("invalid `{}` in `Any`: {e}", M::TYPE_NAME)))?
+            .to_json()
+    }
+
+    fn from_json<M: JsonMessage + Message>(value: &Value) -> Result<Vec<u8>, JsonError> {
+        Ok(M::from_json(value)?.encode_to_vec(&mut EncodeLengthContext::default()))
+    }
+
+    ANY_TYPES.write().unwrap().insert(
+        M::TYPE_NAME,
+        AnyType {
+            to_json: to_json::<M>,
+            from_json: from_json::<M>,
+            special: SPECIAL_TYPES.contains(&M::TYPE_NAME),
+        },
+    );
+}
+
+/// Finds the registered type named by the last segment of `type_url`.
+fn any_type(type_url: &str) -> Result<AnyType, JsonError> {
+    let name = type_url.rsplit_once('/').map_or(type_url, |(_, name)| name);
+    ANY_TYPES
+        .read()
+        .unwrap()
+        .get(name)
+        .copied()
+        .ok_or_else(|| JsonError::new(format!("unknown type URL `{type_url}` in `Any`")))
+}
+
+/// Whether `value` is the default of its type, in which case proto3 fields
+/// without presence are left out.
+#[doc(hidden)]
+#[inline]
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn expect_object(value: &Value) -> Result<&Map<String, Value>, JsonError> {
+    value
+        .as_object()
+        .ok_or_else(|| JsonError::new(format!("expected an object, found `{value}`")))
+}
+
+#[doc(hidden)]
+#[cold]
+pub fn unknown_field(key: &str) -> JsonError {
+    JsonError::new(format!("unknown field `{key}`"))
+}
+
+/// Converts a proto field name to its lowerCamelCase JSON name.
+pub fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            json_name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// Converts a lowerCamelCase JSON name back to a proto field name.
+pub fn from_json_name(json_name: &str) -> String {
+    let mut name = String::with_capacity(json_name.len() + 4);
+    for c in json_name.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+#[cold]
+fn unexpected(expected: &str, value: &Value) -> JsonError {
+    JsonError::new(format!("expected {expected}, found `{value}`"))
+}
+
+fn parse_integer<T>(value: &Value) -> Result<T, JsonError>
+where
+    T: TryFrom<i64> + TryFrom<u64>,
+{
+    let out_of_range = || JsonError::new(format!("integer `{value}` is out of range"));
+    let from_f64 = |f: f64| {
+        if f.fract() != 0.0 || !f.is_finite() {
+            Err(unexpected("an integer", value))
+        } else if f < 0.0 {
+            T::try_from(f as i64).map_err(|_| out_of_range())
+        } else {
+            T::try_from(f as u64).map_err(|_| out_of_range())
+        }
+    };
+    match value {
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                T::try_from(i).map_err(|_| out_of_range())
+            } else if let Some(u) = n.as_u64() {
+                T::try_from(u).map_err(|_| out_of_range())
+            } else {
+                from_f64(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(s) => {
+            if let Ok(i) = s.parse::<i64>() {
+                T::try_from(i).map_err(|_| out_of_range())
+            } else if let Ok(u) = s.parse::<u64>() {
+                T::try_from(u).map_err(|_| out_of_range())
+            } else {
+                from_f64(
+                    s.parse::<f64>()
+                        .map_err(|_| unexpected("an integer", value))?,
+                )
+            }
+        }
+        _ => Err(unexpected("an integer", value)),
+    }
+}
+
+fn parse_double(value: &Value) -> Result<f64, JsonError> {
+    match value {
+        Value::Number(n) => n.as_f64().ok_or_else(|| unexpected("a number", value)),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => s
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| unexpected("a number", value)),
+        },
+        _ => Err(unexpected("a number", value)),
+    }
+}
+
+fn double_to_json(d: f64) -> Value {
+    if d.is_nan() {
+        Value::String("NaN".into())
+    } else if d == f64::INFINITY {
+        Value::String("Infinity".into())
+    } else if d == f64::NEG_INFINITY {
+        Value::String("-Infinity".into())
+    } else {
+        Value::from(d)
+    }
+}
+
+macro_rules! number {
+    ($proto_ty:ident, $ty:ty) => {
+        pub mod $proto_ty {
+            use super::*;
+
+            #[inline]
+            pub fn to_json(value: &$ty) -> Value {
+                Value::from(*value)
+            }
+
+            #[inline]
+            pub fn from_json(value: &Value) -> Result<$ty, JsonError> {
+                parse_integer(value)
+            }
+        }
+    };
+}
+
+number!(int32, i32);
+number!(uint32, u32);
+
+macro_rules! long {
+    ($proto_ty:ident, $ty:ty) => {
+        pub mod $proto_ty {
+            use super::*;
+
+            #[inline]
+            pub fn to_json(value: &$ty) -> Value {
+                Value::String(value.to_string())
+            }
+
+            #[inline]
+            pub fn from_json(value: &Value) -> Result<$ty, JsonError> {
+                parse_integer(value)
+            }
+        }
+    };
+}
+
+long!(int64, i64);
+long!(uint64, u64);
+
+pub mod double {
+    use super::*;
+
+    #[inline]
+    pub fn to_json(value: &f64) -> Value {
+        double_to_json(*value)
+    }
+
+    #[inline]
+    pub fn from_json(value: &Value) -> Result<f64, JsonError> {
+        parse_double(value)
+    }
+}
+
+pub mod float {
+    use super::*;
+
+    #[inline]
+    pub fn to_json(value: &f32) -> Value {
+        if value.is_finite() {
+            // Going through the shortest `f32` representation keeps `0.1f32`
+            // from being written as `0.10000000149011612`.
+            Value::from(value.to_string().parse::<f64>().unwrap_or(*value as f64))
+        } else {
+            double_to_json(*value as f64)
+        }
+    }
+
+    #[inline]
+    pub fn from_json(value: &Value) -> Result<f32, JsonError> {
+        let d = parse_double(value)?;
+        if d.is_finite() && d.abs() > f32::MAX as f64 {
+            return Err(JsonError::new(format!("float `{value}` is out of range")));
+        }
+        Ok(d as f32)
+    }
+}
+
+pub mod bool {
+    use super::*;
+
+    #[inline]
+    pub fn to_json(value: &bool) -> Value {
+        Value::Bool(*value)
+    }
+
+    #[inline]
+    pub fn from_json(value: &Value) -> Result<bool, JsonError> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            // map keys are always strings
+            Value::String(s) if s == "true" => Ok(true),
+            Value::String(s) if s == "false" => Ok(false),
+            _ => Err(unexpected("a bool", value)),
+        }
+    }
+}
+
+pub mod string {
+    use super::*;
+
+    #[inline]
+    pub fn to_json<T: AsRef<str>>(value: &T) -> Value {
+        Value::String(value.as_ref().to_string())
+    }
+
+    #[inline]
+    pub fn from_json<T: From<String>>(value: &Value) -> Result<T, JsonError> {
+        match value {
+            Value::String(s) => Ok(T::from(s.clone())),
+            _ => Err(unexpected("a string", value)),
+        }
+    }
+}
+
+pub mod bytes {
+    use super::*;
+
+    #[inline]
+    pub fn to_json<T: AsRef<[u8]>>(value: &T) -> Value {
+        Value::String(STANDARD.encode(value.as_ref()))
+    }
+
+    /// Accepts both the standard and the URL-safe alphabet, with or without
+    /// padding.
+    pub fn from_json<T: From<Vec<u8>>>(value: &Value) -> Result<T, JsonError> {
+        let Value::String(s) = value else {
+            return Err(unexpected("a base64 string", value));
+        };
+        let normalized = s.trim_end_matches('=').replace('-', "+").replace('_', "/");
+        STANDARD_NO_PAD
+            .decode(normalized)
+            .map(T::from)
+            .map_err(|e| JsonError::new(format!("invalid base64 `{s}`: {e}")))
+    }
+}
+
+pub mod enumeration {
+    use super::*;
+
+    #[inline]
+    pub fn to_json<T: JsonEnum>(value: &T) -> Value {
+        value.to_json()
+    }
+
+    #[inline]
+    pub fn from_json<T: JsonEnum>(value: &Value) -> Result<T, JsonError> {
+        T::from_json(value)
+    }
+}
+
+pub mod message {
+    use super::*;
+
+    #[inline]
+    pub fn to_json<M: JsonMessage>(value: &M) -> Result<Value, JsonError> {
+        value.to_json()
+    }
+
+    #[inline]
+    pub fn from_json<M: JsonMessage>(value: &Value) -> Result<M, JsonError> {
+        M::from_json(value)
+    }
+}
+
+pub mod repeated {
+    use super::*;
+
+    pub fn to_json<T>(
+        values: &[T],
+        f: impl Fn(&T) -> Result<Value, JsonError>,
+    ) -> Result<Value, JsonError> {
+        values
+            .iter()
+            .map(f)
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
+    }
+
+    pub fn from_json<T>(
+        value: &Value,
+        f: impl Fn(&Value) -> Result<T, JsonError>,
+    ) -> Result<Vec<T>, JsonError> {
+        match value {
+            Value::Array(values) => values.iter().map(f).collect(),
+            _ => Err(unexpected("an array", value)),
+        }
+    }
+}
+
+pub mod hash_map {
+    use super::*;
+
+    pub fn to_json<'a, K: 'a, V: 'a>(
+        map: impl IntoIterator<Item = (&'a K, &'a V)>,
+        kf: impl Fn(&K) -> Value,
+        vf: impl Fn(&V) -> Result<Value, JsonError>,
+    ) -> Result<Value, JsonError> {
+        map.into_iter()
+            .map(|(k, v)| {
+                let key = match kf(k) {
+                    Value::String(s) => s,
+                    key => key.to_string(),
+                };
+                Ok((key, vf(v)?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::Object)
+    }
+
+    pub fn from_json<K, V, M: FromIterator<(K, V)>>(
+        value: &Value,
+        kf: impl Fn(&Value) -> Result<K, JsonError>,
+        vf: impl Fn(&Value) -> Result<V, JsonError>,
+    ) -> Result<M, JsonError> {
+        expect_object(value)?
+            .iter()
+            .map(|(k, v)| Ok((kf(&Value::String(k.clone()))?, vf(v)?)))
+            .collect()
+    }
+}
+
+/// Special JSON forms of the `google.protobuf` well-known types.
+pub mod wkt {
+    use super::*;
+
+    const MIN_SECONDS: i64 = -62_135_596_800; // 0001-01-01T00:00:00Z
+    const MAX_SECONDS: i64 = 253_402_300_799; // 9999-12-31T23:59:59Z
+    const MAX_DURATION_SECONDS: i64 = 315_576_000_000;
+
+    // Howard Hinnant's `days_from_civil` and `civil_from_days`.
+    fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+        let y = if m <= 2 { y - 1 } else { y };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    fn civil_from_days(z: i64) -> (i64, i64, i64) {
+        let z = z + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = doy - (153 * mp + 2) / 5 + 1;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 };
+        let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+        (y, m, d)
+    }
+
+    /// Formats nanoseconds with 0, 3, 6 or 9 fractional digits.
+    fn push_nanos(s: &mut String, nanos: u32) {
+        if nanos == 0 {
+        } else if nanos % 1_000_000 == 0 {
+            s.push_str(&format!(".{:03}", nanos / 1_000_000));
+        } else if nanos % 1_000 == 0 {
+            s.push_str(&format!(".{:06}", nanos / 1_000));
+        } else {
+            s.push_str(&format!(".{nanos:09}"));
+        }
+    }
+
+    /// Parses the digits after a `.` as nanoseconds.
+    fn parse_nanos(digits: &str) -> Option<i32> {
+        if digits.is_empty() || digits.len() > 9 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let n: i32 = digits.parse().ok()?;
+        Some(n * 10_i32.pow(9 - digits.len() as u32))
+    }
+
+    fn expect_str<'a>(value: &'a Value, expected: &str) -> Result<&'a str, JsonError> {
+        value.as_str().ok_or_else(|| unexpected(expected, value))
+    }
+
+    /// Formats a `Timestamp` as RFC 3339 in UTC, e.g.
+    /// `1972-01-01T10:00:20.021Z`.
+    ///
+    /// Fails outside 0001-01-01T00:00:00Z..=9999-12-31T23:59:59.999999999Z,
+    /// which RFC 3339 can't represent.
+    pub fn timestamp_to_json(seconds: i64, nanos: i32) -> Result<Value, JsonError> {
+        if !(MIN_SECONDS..=MAX_SECONDS).contains(&seconds) || !(0..1_000_000_000).contains(&nanos) {
+            return Err(JsonError::new(format!(
+                "timestamp out of range: {seconds}s {nanos}ns"
+            )));
+        }
+        let days = seconds.div_euclid(86_400);
+        let secs = seconds.rem_euclid(86_400);
+        let (y, m, d) = civil_from_days(days);
+        let mut s = format!(
+            "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+        push_nanos(&mut s, nanos as u32);
+        s.push('Z');
+        Ok(Value::String(s))
+    }
+
+    /// Parses an RFC 3339 timestamp with any UTC offset.
+    pub fn timestamp_from_json(value: &Value) -> Result<(i64, i32), JsonError> {
+        let s = expect_str(value, "a timestamp")?;
+        let invalid = || JsonError::new(format!("invalid timestamp `{s}`"));
+        let num = |range: std::ops::Range<usize>| -> Result<i64, JsonError> {
+            let digits = s.get(range).ok_or_else(invalid)?;
+            if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            digits.parse().map_err(|_| invalid())
+        };
+        let b = s.as_bytes();
+        if b.len() < 20
+            || b[4] != b'-'
+            || b[7] != b'-'
+            || !matches!(b[10], b'T' | b't')
+            || b[13] != b':'
+            || b[16] != b':'
+        {
+            return Err(invalid());
+        }
+        let (y, mo, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+        let (h, mi, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+        if !(1..=12).contains(&mo) || !(1..=31).contains(&d) || h > 23 || mi > 59 || sec > 59 {
+            return Err(invalid());
+        }
+
+        let mut rest = &s[19..];
+        let mut nanos = 0;
+        if let Some(frac) = rest.strip_prefix('.') {
+            let end = frac
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(frac.len());
+            nanos = parse_nanos(&frac[..end]).ok_or_else(invalid)?;
+            rest = &frac[end..];
+        }
+        let offset = match rest {
+            "Z" | "z" => 0,
+            _ => {
+                let ob = rest.as_bytes();
+                if ob.len() != 6 || !matches!(ob[0], b'+' | b'-') || ob[3] != b':' {
+                    return Err(invalid());
+                }
+                let parse = |r: &str| -> Result<i64, JsonError> {
+                    if !r.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(invalid());
+                    }
+                    r.parse().map_err(|_| invalid())
+                };
+                let minutes = parse(&rest[1..3])? * 60 + parse(&rest[4..6])?;
+                if ob[0] == b'+' { minutes } else { -minutes }
+            }
+        };
+
+        let seconds = days_from_civil(y, mo, d) * 86_400 + h * 3600 + mi * 60 + sec - offset * 60;
+        if !(MIN_SECONDS..=MAX_SECONDS).contains(&seconds) {
+            return Err(JsonError::new(format!("timestamp `{s}` is out of range")));
+        }
+        Ok((seconds, nanos))
+    }
+
+    /// Formats a `Duration` as seconds with an `s` suffix, e.g. `-1.5s`.
+    ///
+    /// Fails outside the ±10,000 years a `Duration` can span, or when the
+    /// signs of `seconds` and `nanos` differ.
+    pub fn duration_to_json(seconds: i64, nanos: i32) -> Result<Value, JsonError> {
+        if seconds.unsigned_abs() > MAX_DURATION_SECONDS as u64
+            || nanos.unsigned_abs() >= 1_000_000_000
+            || (seconds < 0 && nanos > 0)
+            || (seconds > 0 && nanos < 0)
+        {
+            return Err(JsonError::new(format!(
+                "duration out of range: {seconds}s {nanos}ns"
+            )));
+        }
+        let mut s = String::new();
+        if seconds < 0 || nanos < 0 {
+            s.push('-');
+        }
+        s.push_str(&seconds.unsigned_abs().to_string());
+        push_nanos(&mut s, nanos.unsigned_abs());
+        s.push('s');
+        Ok(Value::String(s))
+    }
+
+    pub fn duration_from_json(value: &Value) -> Result<(i64, i32), JsonError> {
+        let s = expect_str(value, "a duration")?;
+        let invalid = || JsonError::new(format!("invalid duration `{s}`"));
+        let body = s.strip_suffix('s').ok_or_else(invalid)?;
+        let (negative, body) = match body.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, body),
+        };
+        let (secs, frac) = body.split_once('.').unwrap_or((body, ""));
+        if secs.is_empty() || !secs.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let secs: i64 = secs.parse().map_err(|_| invalid())?;
+        let nanos = if frac.is_empty() && !body.contains('.') {
+            0
+        } else {
+            parse_nanos(frac).ok_or_else(invalid)?
+        };
+        if secs > MAX_DURATION_SECONDS {
+            return Err(JsonError::new(format!("duration `{s}` is out of range")));
+        }
+        Ok(if negative {
+            (-secs, -nanos)
+        } else {
+            (secs, nanos)
+        })
+    }
+
+    /// Joins `FieldMask` paths with `,`, converting each to lowerCamelCase.
+    pub fn field_mask_to_json<T: AsRef<str>>(paths: &[T]) -> Value {
+        Value::String(
+            paths
+                .iter()
+                .map(|p| {
+                    p.as_ref()
+                        .split('.')
+                        .map(to_json_name)
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+
+    pub fn field_mask_from_json<T: From<String>>(value: &Value) -> Result<Vec<T>, JsonError> {
+        let s = expect_str(value, "a field mask")?;
+        if s.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(s.split(',')
+            .map(|p| {
+                T::from(
+                    p.split('.')
+                        .map(from_json_name)
+                        .collect::<Vec<_>>()
+                        .join("."),
+                )
+            })
+            .collect())
+    }
+
+    /// Writes an `Any` as its `@type` next to the fields of the packed
+    /// message, or next to a `value` holding the special form of a packed
+    /// well-known type. The packed type must be registered with
+    /// [`register_any`].
+    pub fn any_to_json(type_url: &str, value: &[u8]) -> Result<Value, JsonError> {
+        if type_url.is_empty() && value.is_empty() {
+            return Ok(Value::Object(Map::new()));
+        }
+        let ty = any_type(type_url)?;
+        let mut object = Map::new();
+        object.insert("@type".into(), Value::String(type_url.to_string()));
+        match (ty.to_json)(value)? {
+            Value::Object(fields) if !ty.special => object.extend(fields),
+            packed => {
+                object.insert("value".into(), packed);
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    pub fn any_from_json(value: &Value) -> Result<(String, Vec<u8>), JsonError> {
+        let object = expect_object(value)?;
+        let type_url: String = match object.get("@type") {
+            Some(t) => string::from_json(t)?,
+            None if object.is_empty() => return Ok(Default::default()),
+            None => return Err(JsonError::new("`Any` is missing `@type`")),
+        };
+        let ty = any_type(&type_url)?;
+        let value = if ty.special {
+            let packed = object
+                .get("value")
+                .ok_or_else(|| JsonError::new("`Any` is missing `value`"))?;
+            (ty.from_json)(packed)?
+        } else {
+            let mut fields = object.clone();
+            fields.remove("@type");
+            (ty.from_json)(&Value::Object(fields))?
+        };
+        Ok((type_url, value))
+    }
+
+    /// Returns the JSON name of the `google.protobuf.Value` variant holding
+    /// `value`.
+    pub fn value_kind(value: &Value) -> &'static str {
+        match value {
+            Value::Null => "nullValue",
+            Value::Bool(_) => "boolValue",
+            Value::Number(_) => "numberValue",
+            Value::String(_) => "stringValue",
+            Value::Array(_) => "listValue",
+            Value::Object(_) => "structValue",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_json_name() {
+        assert_eq!(to_json_name("foo_bar_baz"), "fooBarBaz");
+        assert_eq!(to_json_name("foo"), "foo");
+        assert_eq!(from_json_name("fooBarBaz"), "foo_bar_baz");
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(int64::to_json(&-3), json!("-3"));
+        assert_eq!(uint64::to_json(&u64::MAX), json!("18446744073709551615"));
+        assert_eq!(int32::to_json(&7), json!(7));
+        assert_eq!(int32::from_json(&json!("12")).unwrap(), 12);
+        assert_eq!(int32::from_json(&json!(1e2)).unwrap(), 100);
+        assert!(int32::from_json(&json!(1.5)).is_err());
+        assert!(int32::from_json(&json!(1_i64 << 40)).is_err());
+        assert!(uint32::from_json(&json!(-1)).is_err());
+        assert_eq!(
+            uint64::from_json(&json!("18446744073709551615")).unwrap(),
+            u64::MAX
+        );
+
+        assert_eq!(float::to_json(&0.1), json!(0.1));
+        assert_eq!(double::to_json(&f64::NAN), json!("NaN"));
+        assert_eq!(
+            double::from_json(&json!("-Infinity")).unwrap(),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(double::from_json(&json!("2.5")).unwrap(), 2.5);
+        assert!(float::from_json(&json!(1e300)).is_err());
+
+        assert_eq!(bytes::to_json(&[0xfb_u8, 0xff]), json!("+/8="));
+        let b: Vec<u8> = bytes::from_json(&json!("-_8")).unwrap();
+        assert_eq!(b, [0xfb, 0xff]);
+        assert!(bool::from_json(&json!("true")).unwrap());
+    }
+
+    #[test]
+    fn test_map() {
+        let map = std::collections::BTreeMap::from([(1, "a".to_string()), (2, "b".to_string())]);
+        let value = hash_map::to_json(&map, int32::to_json, |v| Ok(string::to_json(v))).unwrap();
+        assert_eq!(value, json!({"1": "a", "2": "b"}));
+
+        let decoded: AHashMap<i32, String> =
+            hash_map::from_json(&value, int32::from_json, string::from_json).unwrap();
+        assert_eq!(decoded, map.into_iter().collect());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let cases = [
+            (0, 0, "1970-01-01T00:00:00Z"),
+            (63_108_020, 21_000_000, "1972-01-01T10:00:20.021Z"),
+            (-1, 500_000, "1969-12-31T23:59:59.000500Z"),
+            (
+                253_402_300_799,
+                999_999_999,
+                "9999-12-31T23:59:59.999999999Z",
+            ),
+            (-62_135_596_800, 0, "0001-01-01T00:00:00Z"),
+        ];
+        for (seconds, nanos, s) in cases {
+            assert_eq!(wkt::timestamp_to_json(seconds, nanos).unwrap(), json!(s));
+            assert_eq!(
+                wkt::timestamp_from_json(&json!(s)).unwrap(),
+                (seconds, nanos)
+            );
+        }
+        assert_eq!(
+            wkt::timestamp_from_json(&json!("1972-01-01T18:00:20.021+08:00")).unwrap(),
+            (63_108_020, 21_000_000)
+        );
+        assert!(wkt::timestamp_from_json(&json!("1972-01-01 10:00:20Z")).is_err());
+        assert!(wkt::timestamp_from_json(&json!("1972-13-01T10:00:20Z")).is_err());
+
+        assert!(wkt::timestamp_to_json(253_402_300_800, 0).is_err());
+        assert!(wkt::timestamp_to_json(-62_135_596_801, 0).is_err());
+        assert!(wkt::timestamp_to_json(0, -1).is_err());
+        assert!(wkt::timestamp_to_json(0, 1_000_000_000).is_err());
+    }
+
+    #[test]
+    fn test_duration() {
+        let cases = [
+            (1, 0, "1s"),
+            (1, 340_012, "1.000340012s"),
+            (-1, -500_000_000, "-1.500s"),
+            (0, -1_000, "-0.000001s"),
+        ];
+        for (seconds, nanos, s) in cases {
+            assert_eq!(wkt::duration_to_json(seconds, nanos).unwrap(), json!(s));
+            assert_eq!(
+                wkt::duration_from_json(&json!(s)).unwrap(),
+                (seconds, nanos)
+            );
+        }
+        assert!(wkt::duration_from_json(&json!("1")).is_err());
+        assert!(wkt::duration_from_json(&json!("1.s")).is_err());
+
+        assert!(wkt::duration_to_json(315_576_000_001, 0).is_err());
+        assert!(wkt::duration_to_json(1, -1).is_err());
+        assert!(wkt::duration_to_json(0, 1_000_000_000).is_err());
+    }
+
+    #[test]
+    fn test_field_mask() {
+        let paths = ["user.display_name", "photo"];
+        let v = wkt::field_mask_to_json(&paths);
+        assert_eq!(v, json!("user.displayName,photo"));
+        let back: Vec<String> = wkt::field_mask_from_json(&v).unwrap();
+        assert_eq!(back, paths);
+    }
+}
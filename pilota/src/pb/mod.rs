@@ -6,13 +6,19 @@ mod message;
 mod types;
 
 pub mod descriptor_getter;
+pub mod dynamic;
 #[doc(hidden)]
 pub mod encoding;
 pub mod extension;
+pub mod field_mask;
+#[cfg(feature = "pb-json")]
+pub mod json;
 
 use bytes::{BufMut, Bytes};
 pub use encoding::{DecodeContext, EncodeLengthContext};
 use encoding::{decode_varint, encode_varint, encoded_len_varint};
+#[cfg(feature = "pb-json")]
+pub use error::JsonError;
 pub use error::{DecodeError, EncodeError};
 pub use linkedbytes::LinkedBytes;
 pub use message::{EnumMessage, Message};
@@ -74,6 +80,15 @@ pub fn decode_length_delimiter(mut buf: Bytes) -> Result<usize, DecodeError> {
     Ok(length as usize)
 }
 
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+// According to the benchmark, 1KB is the suitable threshold for zero-copy on
+// Apple Silicon.
+const ZERO_COPY_THRESHOLD: usize = 1024;
+
+#[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+// While 4KB is better for other platforms (mainly amd64 linux).
+const ZERO_COPY_THRESHOLD: usize = 4 * 1024;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,12 +107,3 @@ mod tests {
         assert_eq!(buf.bytes().len(), 1);
     }
 }
-
-#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-// According to the benchmark, 1KB is the suitable threshold for zero-copy on
-// Apple Silicon.
-const ZERO_COPY_THRESHOLD: usize = 1024;
-
-#[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
-// While 4KB is better for other platforms (mainly amd64 linux).
-const ZERO_COPY_THRESHOLD: usize = 4 * 1024;
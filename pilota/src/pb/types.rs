@@ -512,6 +512,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_f32_message_encoding() {
         let mut buf = create_test_buffer();
         let mut ctx = EncodeLengthContext::default();
@@ -530,6 +531,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_f32_message_decoding() {
         let mut ctx = DecodeContext::new(Bytes::new());
 
@@ -541,6 +543,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_f64_message_encoding() {
         let mut buf = create_test_buffer();
         let mut ctx = EncodeLengthContext::default();
@@ -559,6 +562,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_f64_message_decoding() {
         let mut ctx = DecodeContext::new(Bytes::new());
 
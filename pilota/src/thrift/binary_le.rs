@@ -10,6 +10,7 @@ use super::{
     TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol,
     TSetIdentifier, TStructIdentifier, TType, ThriftException, ZERO_COPY_THRESHOLD,
     error::ProtocolExceptionKind,
+    limits::{CountingReader, DecodeLimits, Limiter},
     new_protocol_exception,
     rw_ext::{ReadExt, WriteExt},
 };
@@ -22,6 +23,9 @@ pub struct TBinaryProtocol<T> {
 
     zero_copy: bool,
     zero_copy_len: usize,
+    limiter: Limiter,
+    strict_read: bool,
+    strict_write: bool,
 }
 
 impl<T> TBinaryProtocol<T> {
@@ -33,8 +37,34 @@ impl<T> TBinaryProtocol<T> {
             trans,
             zero_copy,
             zero_copy_len: 0,
+            limiter: Limiter::default(),
+            strict_read: true,
+            strict_write: true,
         }
     }
+
+    /// Sets the limits that the input protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self
+    }
+
+    /// Whether the input protocol rejects the message headers without a
+    /// version, which old clients send. Defaults to `true`.
+    #[inline]
+    pub fn with_strict_read(mut self, strict_read: bool) -> Self {
+        self.strict_read = strict_read;
+        self
+    }
+
+    /// Whether the output protocol writes the version in the message headers.
+    /// Old servers only understand the headers without it. Defaults to `true`.
+    #[inline]
+    pub fn with_strict_write(mut self, strict_write: bool) -> Self {
+        self.strict_write = strict_write;
+        self
+    }
 }
 
 #[inline]
@@ -52,7 +82,11 @@ fn field_type_from_u8(ttype: u8) -> Result<TType, ProtocolException> {
 impl<T> TLengthProtocol for TBinaryProtocol<T> {
     #[inline]
     fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
-        self.i32_len(0) + self.faststr_len(&identifier.name) + self.i32_len(0)
+        if self.strict_write {
+            self.i32_len(0) + self.faststr_len(&identifier.name) + self.i32_len(0)
+        } else {
+            self.faststr_len(&identifier.name) + self.byte_len(0) + self.i32_len(0)
+        }
     }
 
     #[inline]
@@ -200,9 +234,14 @@ impl TOutputProtocol for TBinaryProtocol<&mut BytesMut> {
         identifier: &TMessageIdentifier,
     ) -> Result<(), ThriftException> {
         let msg_type_u8: u8 = identifier.message_type.into();
-        let version = (VERSION_LE | msg_type_u8 as u32) as i32;
-        self.write_i32(version)?;
-        self.write_faststr(identifier.name.clone())?;
+        if self.strict_write {
+            let version = (VERSION_LE | msg_type_u8 as u32) as i32;
+            self.write_i32(version)?;
+            self.write_faststr(identifier.name.clone())?;
+        } else {
+            self.write_faststr(identifier.name.clone())?;
+            self.write_byte(msg_type_u8)?;
+        }
         self.write_i32(identifier.sequence_number)?;
         Ok(())
     }
@@ -383,9 +422,14 @@ impl TOutputProtocol for TBinaryProtocol<&mut LinkedBytes> {
         identifier: &TMessageIdentifier,
     ) -> Result<(), ThriftException> {
         let msg_type_u8: u8 = identifier.message_type.into();
-        let version = (VERSION_LE | msg_type_u8 as u32) as i32;
-        self.write_i32(version)?;
-        self.write_faststr(identifier.name.clone())?;
+        if self.strict_write {
+            let version = (VERSION_LE | msg_type_u8 as u32) as i32;
+            self.write_i32(version)?;
+            self.write_faststr(identifier.name.clone())?;
+        } else {
+            self.write_faststr(identifier.name.clone())?;
+            self.write_byte(msg_type_u8)?;
+        }
         self.write_i32(identifier.sequence_number)?;
         Ok(())
     }
@@ -565,7 +609,9 @@ impl TOutputProtocol for TBinaryProtocol<&mut LinkedBytes> {
 }
 
 pub struct TAsyncBinaryProtocol<R> {
-    reader: R,
+    reader: CountingReader<R>,
+    limiter: Limiter,
+    strict_read: bool,
 }
 
 impl<R> TAsyncInputProtocol for TAsyncBinaryProtocol<R>
@@ -574,15 +620,35 @@ where
 {
     // https://github.com/apache/thrift/blob/master/doc/specs/thrift-binary-protocol.md
     async fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.reader.reset();
         let size = self.reader.read_i32_le().await?;
-        if size > 0 {
+
+        let (name, type_u8) = if size < 0 {
+            let version = size & (VERSION_MASK as i32);
+            if version != (VERSION_LE as i32) {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::BadVersion,
+                    format!("Bad version {version:x} in ReadMessageBegin"),
+                ));
+            }
+            (self.read_faststr().await?, (size & 0xf) as u8)
+        } else if self.strict_read {
             return Err(new_protocol_exception(
                 ProtocolExceptionKind::BadVersion,
                 "Missing version in ReadMessageBegin",
             ));
-        }
-
-        let type_u8 = (size & 0xf) as u8;
+        } else {
+            // the old header starts with the name, followed by the type
+            let len = size as usize;
+            self.limiter.check_bytes_len(len)?;
+            let mut v = vec![0; len];
+            self.reader.read_exact(&mut v).await?;
+            (
+                FastStr::from_string(String::from_utf8(v)?),
+                self.read_byte().await?,
+            )
+        };
 
         let message_type = TMessageType::try_from(type_u8).map_err(|_| {
             new_protocol_exception(
@@ -591,37 +657,34 @@ where
             )
         })?;
 
-        let version = size & (VERSION_MASK as i32);
-        if version != (VERSION_LE as i32) {
-            return Err(new_protocol_exception(
-                ProtocolExceptionKind::BadVersion,
-                format!("Bad version {version:x} in ReadMessageBegin"),
-            ));
-        }
-
-        let name = self.read_faststr().await?;
-
         let sequence_number = self.read_i32().await?;
         Ok(TMessageIdentifier::new(name, message_type, sequence_number))
     }
 
     #[inline]
     async fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
         Ok(())
     }
 
     #[inline]
     async fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.reader.reset();
+        }
+        self.limiter.enter_struct()?;
         Ok(None)
     }
 
     #[inline]
     async fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
         Ok(())
     }
 
     #[inline]
     async fn read_field_begin(&mut self) -> Result<TFieldIdentifier, ThriftException> {
+        self.limiter.check_message_len(self.reader.count())?;
         let field_type_byte = self.read_byte().await?;
         let field_type = field_type_byte.try_into().map_err(|_| {
             new_protocol_exception(
@@ -660,6 +723,9 @@ where
     #[inline]
     async fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
         let len = self.reader.read_i32_le().await? as usize;
+        self.limiter.check_bytes_len(len)?;
+        self.limiter
+            .check_message_len(self.reader.count().saturating_add(len))?;
         // FIXME: use maybe_uninit?
         let mut v = vec![0; len];
         self.reader.read_exact(&mut v).await?;
@@ -676,6 +742,9 @@ where
     #[inline]
     async fn read_string(&mut self) -> Result<String, ThriftException> {
         let len = self.reader.read_i32_le().await? as usize;
+        self.limiter.check_bytes_len(len)?;
+        self.limiter
+            .check_message_len(self.reader.count().saturating_add(len))?;
         // FIXME: use maybe_uninit?
         let mut v = vec![0; len];
         self.reader.read_exact(&mut v).await?;
@@ -724,6 +793,8 @@ where
             .await
             .and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32().await?;
+        self.limiter.check_container_len(size as usize)?;
+        self.limiter.check_message_len(self.reader.count())?;
         Ok(TListIdentifier::new(element_type, size as usize))
     }
 
@@ -739,6 +810,8 @@ where
             .await
             .and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32().await?;
+        self.limiter.check_container_len(size as usize)?;
+        self.limiter.check_message_len(self.reader.count())?;
         Ok(TSetIdentifier::new(element_type, size as usize))
     }
 
@@ -758,6 +831,8 @@ where
             .await
             .and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32().await?;
+        self.limiter.check_container_len(size as usize)?;
+        self.limiter.check_message_len(self.reader.count())?;
         Ok(TMapIdentifier::new(key_type, value_type, size as usize))
     }
 
@@ -772,7 +847,27 @@ where
     R: AsyncRead + Unpin + Send,
 {
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader: CountingReader::new(reader),
+            limiter: Limiter::default(),
+            strict_read: true,
+        }
+    }
+
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self.reader.set_limit(limits.max_message_len);
+        self
+    }
+
+    /// Whether the protocol rejects the message headers without a version,
+    /// which old clients send. Defaults to `true`.
+    #[inline]
+    pub fn with_strict_read(mut self, strict_read: bool) -> Self {
+        self.strict_read = strict_read;
+        self
     }
 }
 
@@ -780,15 +875,34 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     type Buf = Bytes;
 
     fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.limiter.check_message_len(self.trans.len())?;
         let size = self.trans.read_i32_le()?;
 
-        if size > 0 {
+        let (name, type_u8) = if size < 0 {
+            let version = size & (VERSION_MASK as i32);
+            if version != (VERSION_LE as i32) {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::BadVersion,
+                    "Bad version in ReadMessageBegin",
+                ));
+            }
+            (self.read_faststr()?, (size & 0xf) as u8)
+        } else if self.strict_read {
             return Err(new_protocol_exception(
                 ProtocolExceptionKind::BadVersion,
                 "Missing version in ReadMessageBegin".to_string(),
             ));
-        }
-        let type_u8 = (size & 0xf) as u8;
+        } else {
+            // the old header starts with the name, followed by the type
+            self.limiter.check_bytes_len(size as usize)?;
+            let mut v = vec![0; size as usize];
+            self.trans.read_to_slice(&mut v)?;
+            (
+                FastStr::from_string(String::from_utf8(v)?),
+                self.read_byte()?,
+            )
+        };
 
         let message_type = TMessageType::try_from(type_u8).map_err(|_| {
             new_protocol_exception(
@@ -797,32 +911,28 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
             )
         })?;
 
-        let version = size & (VERSION_MASK as i32);
-        if version != (VERSION_LE as i32) {
-            return Err(new_protocol_exception(
-                ProtocolExceptionKind::BadVersion,
-                "Bad version in ReadMessageBegin",
-            ));
-        }
-
-        let name = self.read_faststr()?;
-
         let sequence_number = self.read_i32()?;
         Ok(TMessageIdentifier::new(name, message_type, sequence_number))
     }
 
     #[inline]
     fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
         Ok(())
     }
 
     #[inline]
     fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.limiter.check_message_len(self.trans.len())?;
+        }
+        self.limiter.enter_struct()?;
         Ok(None)
     }
 
     #[inline]
     fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
         Ok(())
     }
 
@@ -861,6 +971,7 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     #[inline]
     fn read_bytes(&mut self) -> Result<Bytes, ThriftException> {
         let len = self.trans.read_i32_le()?;
+        self.limiter.check_bytes_len(len as usize)?;
         // split and freeze it
         Ok(self.trans.split_to(len as usize))
     }
@@ -911,12 +1022,14 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     #[inline]
     fn read_string(&mut self) -> Result<String, ThriftException> {
         let len = self.trans.read_i32_le()?;
+        self.limiter.check_bytes_len(len as usize)?;
         Ok(self.trans.read_to_string(len as usize)?)
     }
 
     #[inline]
     fn read_faststr(&mut self) -> Result<FastStr, ThriftException> {
         let len = self.trans.read_i32_le()? as usize;
+        self.limiter.check_bytes_len(len)?;
         let bytes = self.trans.split_to(len);
         unsafe { Ok(FastStr::from_bytes_unchecked(bytes)) }
     }
@@ -925,6 +1038,7 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     fn read_list_begin(&mut self) -> Result<TListIdentifier, ThriftException> {
         let element_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TListIdentifier::new(element_type, size as usize))
     }
 
@@ -937,6 +1051,7 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     fn read_set_begin(&mut self) -> Result<TSetIdentifier, ThriftException> {
         let element_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TSetIdentifier::new(element_type, size as usize))
     }
 
@@ -950,6 +1065,7 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
         let key_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let value_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TMapIdentifier::new(key_type, value_type, size as usize))
     }
 
@@ -966,6 +1082,7 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
     #[inline]
     fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
         let len = self.trans.read_i32_le()? as usize;
+        self.limiter.check_bytes_len(len)?;
         Ok(self.trans.split_to(len).into())
     }
 
@@ -974,3 +1091,69 @@ impl TInputProtocol for TBinaryProtocol<&mut Bytes> {
         self.trans
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use faststr::FastStr;
+
+    use super::{TAsyncBinaryProtocol, TBinaryProtocol};
+    use crate::thrift::{
+        TAsyncInputProtocol, TInputProtocol, TLengthProtocol, TMessageIdentifier, TMessageType,
+        TOutputProtocol,
+    };
+
+    fn identifier() -> TMessageIdentifier {
+        TMessageIdentifier::new(FastStr::from_static_str("ping"), TMessageType::Call, 7)
+    }
+
+    fn write_header(strict_write: bool) -> Bytes {
+        let mut buf = BytesMut::new();
+        let mut p = TBinaryProtocol::new(&mut buf, false).with_strict_write(strict_write);
+        let len = p.message_begin_len(&identifier());
+        p.write_message_begin(&identifier()).unwrap();
+        p.write_i32(42).unwrap();
+        assert_eq!(buf.len(), len + 4);
+        buf.freeze()
+    }
+
+    #[test]
+    fn test_non_strict_header() {
+        let buf = write_header(false);
+        // the name length comes first, then the name and the type
+        assert_eq!(&buf[..9], b"\x04\0\0\0ping\x01");
+
+        for strict_write in [false, true] {
+            let mut b = write_header(strict_write);
+            let mut p = TBinaryProtocol::new(&mut b, false).with_strict_read(false);
+            assert_eq!(p.read_message_begin().unwrap(), identifier());
+            assert_eq!(p.read_i32().unwrap(), 42);
+        }
+
+        let mut b = buf.clone();
+        assert!(
+            TBinaryProtocol::new(&mut b, false)
+                .read_message_begin()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_async_non_strict_header() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            for strict_write in [false, true] {
+                let buf = write_header(strict_write);
+                let mut p = TAsyncBinaryProtocol::new(&buf[..]).with_strict_read(false);
+                assert_eq!(p.read_message_begin().await.unwrap(), identifier());
+                assert_eq!(p.read_i32().await.unwrap(), 42);
+            }
+
+            let buf = write_header(false);
+            let mut p = TAsyncBinaryProtocol::new(&buf[..]);
+            assert!(p.read_message_begin().await.is_err());
+        });
+    }
+}
@@ -9,7 +9,10 @@ use super::{
     BINARY_BASIC_TYPE_FIXED_SIZE, ProtocolException, TFieldIdentifier, TInputProtocol,
     TLengthProtocol, TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType,
     TOutputProtocol, TSetIdentifier, TStructIdentifier, TType, ThriftException,
-    ZERO_COPY_THRESHOLD, error::ProtocolExceptionKind, new_protocol_exception,
+    ZERO_COPY_THRESHOLD,
+    error::ProtocolExceptionKind,
+    limits::{DecodeLimits, Limiter},
+    new_protocol_exception,
 };
 
 static VERSION_1: u32 = 0x80010000;
@@ -24,6 +27,7 @@ pub struct TBinaryUnsafeOutputProtocol<T> {
 
     zero_copy: bool,
     zero_copy_len: usize,
+    strict_write: bool,
 }
 
 impl<T> TBinaryUnsafeOutputProtocol<T> {
@@ -44,9 +48,18 @@ impl<T> TBinaryUnsafeOutputProtocol<T> {
             index: 0,
             zero_copy,
             zero_copy_len: 0,
+            strict_write: true,
         }
     }
 
+    /// Whether to write the version in the message headers. Old servers only
+    /// understand the headers without it. Defaults to `true`.
+    #[inline]
+    pub fn with_strict_write(mut self, strict_write: bool) -> Self {
+        self.strict_write = strict_write;
+        self
+    }
+
     #[doc(hidden)]
     pub fn index(&self) -> usize {
         self.index
@@ -68,7 +81,11 @@ fn field_type_from_u8(ttype: u8) -> Result<TType, ProtocolException> {
 impl<T> TLengthProtocol for TBinaryUnsafeOutputProtocol<T> {
     #[inline]
     fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
-        self.i32_len(0) + self.faststr_len(&identifier.name) + self.i32_len(0)
+        if self.strict_write {
+            self.i32_len(0) + self.faststr_len(&identifier.name) + self.i32_len(0)
+        } else {
+            self.faststr_len(&identifier.name) + self.byte_len(0) + self.i32_len(0)
+        }
     }
 
     #[inline]
@@ -216,9 +233,14 @@ impl TOutputProtocol for TBinaryUnsafeOutputProtocol<&mut BytesMut> {
         identifier: &TMessageIdentifier,
     ) -> Result<(), ThriftException> {
         let msg_type_u8: u8 = identifier.message_type.into();
-        let version = (VERSION_1 | msg_type_u8 as u32) as i32;
-        self.write_i32(version)?;
-        self.write_faststr(identifier.name.clone())?;
+        if self.strict_write {
+            let version = (VERSION_1 | msg_type_u8 as u32) as i32;
+            self.write_i32(version)?;
+            self.write_faststr(identifier.name.clone())?;
+        } else {
+            self.write_faststr(identifier.name.clone())?;
+            self.write_byte(msg_type_u8)?;
+        }
         self.write_i32(identifier.sequence_number)?;
         Ok(())
     }
@@ -472,9 +494,14 @@ impl TOutputProtocol for TBinaryUnsafeOutputProtocol<&mut LinkedBytes> {
         identifier: &TMessageIdentifier,
     ) -> Result<(), ThriftException> {
         let msg_type_u8: u8 = identifier.message_type.into();
-        let version = (VERSION_1 | msg_type_u8 as u32) as i32;
-        self.write_i32(version)?;
-        self.write_faststr(identifier.name.clone())?;
+        if self.strict_write {
+            let version = (VERSION_1 | msg_type_u8 as u32) as i32;
+            self.write_i32(version)?;
+            self.write_faststr(identifier.name.clone())?;
+        } else {
+            self.write_faststr(identifier.name.clone())?;
+            self.write_byte(msg_type_u8)?;
+        }
         self.write_i32(identifier.sequence_number)?;
         self.advance_mut(self.index);
         Ok(())
@@ -738,6 +765,8 @@ pub struct TBinaryUnsafeInputProtocol<'a> {
     pub(crate) trans: &'a mut Bytes,
     pub(crate) buf: &'a [u8],
     pub(crate) index: usize,
+    limiter: Limiter,
+    strict_read: bool,
 }
 
 impl<'a> TBinaryUnsafeInputProtocol<'a> {
@@ -752,10 +781,27 @@ impl<'a> TBinaryUnsafeInputProtocol<'a> {
                 trans,
                 buf,
                 index: 0,
+                limiter: Limiter::default(),
+                strict_read: true,
             }
         }
     }
 
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self
+    }
+
+    /// Whether to reject the message headers without a version, which old
+    /// clients send. Defaults to `true`.
+    #[inline]
+    pub fn with_strict_read(mut self, strict_read: bool) -> Self {
+        self.strict_read = strict_read;
+        self
+    }
+
     #[doc(hidden)]
     pub fn index(&self) -> usize {
         self.index
@@ -914,15 +960,34 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     type Buf = Bytes;
 
     fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.limiter.check_message_len(self.trans.len())?;
         let size = self.read_i32()?;
 
-        if size > 0 {
+        let (name, type_u8) = if size < 0 {
+            let version = size & (VERSION_MASK as i32);
+            if version != (VERSION_1 as i32) {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::BadVersion,
+                    "Bad version in ReadMessageBegin",
+                ));
+            }
+            (self.read_faststr()?, (size & 0xf) as u8)
+        } else if self.strict_read {
             return Err(new_protocol_exception(
                 ProtocolExceptionKind::BadVersion,
                 "Missing version in ReadMessageBegin".to_string(),
             ));
-        }
-        let type_u8 = (size & 0xf) as u8;
+        } else {
+            // the old header starts with the name, followed by the type
+            let len = size as usize;
+            self.limiter.check_bytes_len(len)?;
+            self.advance(self.index);
+            let bytes = self.trans.split_to(len);
+            self.buf = unsafe { slice::from_raw_parts(self.trans.as_ptr(), self.trans.len()) };
+            let name = unsafe { FastStr::from_bytes_unchecked(bytes) };
+            (name, self.read_byte()?)
+        };
 
         let message_type = TMessageType::try_from(type_u8).map_err(|_| {
             new_protocol_exception(
@@ -931,16 +996,6 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
             )
         })?;
 
-        let version = size & (VERSION_MASK as i32);
-        if version != (VERSION_1 as i32) {
-            return Err(new_protocol_exception(
-                ProtocolExceptionKind::BadVersion,
-                "Bad version in ReadMessageBegin",
-            ));
-        }
-
-        let name = self.read_faststr()?;
-
         let sequence_number = self.read_i32()?;
         self.advance(self.index);
         Ok(TMessageIdentifier::new(name, message_type, sequence_number))
@@ -948,16 +1003,22 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
 
     #[inline]
     fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
         Ok(())
     }
 
     #[inline]
     fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.limiter.check_message_len(self.trans.len())?;
+        }
+        self.limiter.enter_struct()?;
         Ok(None)
     }
 
     #[inline]
     fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
         Ok(())
     }
 
@@ -996,6 +1057,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     #[inline]
     fn read_bytes(&mut self) -> Result<Bytes, ThriftException> {
         let len = self.read_i32()?;
+        self.limiter.check_bytes_len(len as usize)?;
         self.advance(self.index);
         // split and freeze it
         let val = self.trans.split_to(len as usize);
@@ -1084,6 +1146,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     fn read_string(&mut self) -> Result<String, ThriftException> {
         unsafe {
             let len = self.read_i32().unwrap_unchecked();
+            self.limiter.check_bytes_len(len as usize)?;
             let val = str::from_utf8_unchecked(
                 self.buf
                     .get_unchecked(self.index..self.index + len as usize),
@@ -1098,6 +1161,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     fn read_faststr(&mut self) -> Result<FastStr, ThriftException> {
         unsafe {
             let len = self.read_i32().unwrap_unchecked() as usize;
+            self.limiter.check_bytes_len(len)?;
             self.advance(self.index);
             let bytes = self.trans.split_to(len);
             self.buf = slice::from_raw_parts(self.trans.as_ptr(), self.trans.len());
@@ -1109,6 +1173,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     fn read_list_begin(&mut self) -> Result<TListIdentifier, ThriftException> {
         let element_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TListIdentifier::new(element_type, size as usize))
     }
 
@@ -1121,6 +1186,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     fn read_set_begin(&mut self) -> Result<TSetIdentifier, ThriftException> {
         let element_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TSetIdentifier::new(element_type, size as usize))
     }
 
@@ -1134,6 +1200,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
         let key_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let value_type: TType = self.read_byte().and_then(|n| Ok(field_type_from_u8(n)?))?;
         let size = self.read_i32()?;
+        self.limiter.check_container_len(size as usize)?;
         Ok(TMapIdentifier::new(key_type, value_type, size as usize))
     }
 
@@ -1154,6 +1221,7 @@ impl<'a> TInputProtocol for TBinaryUnsafeInputProtocol<'a> {
     #[inline]
     fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
         let len = self.read_i32()? as usize;
+        self.limiter.check_bytes_len(len)?;
         self.advance(self.index);
         let val = self.trans.split_to(len).into();
         self.buf = unsafe { slice::from_raw_parts(self.trans.as_ptr(), self.trans.len()) };
@@ -15,6 +15,7 @@ use super::{
     TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol,
     TSetIdentifier, TStructIdentifier, TType, ThriftException, ZERO_COPY_THRESHOLD,
     error::ProtocolExceptionKind,
+    limits::{CountingReader, DecodeLimits, Limiter},
     new_protocol_exception,
     rw_ext::{ReadExt, WriteExt},
     varint_ext::VarIntProcessor,
@@ -958,11 +959,12 @@ impl TOutputProtocol for TCompactOutputProtocol<&mut LinkedBytes> {
 }
 
 pub struct TAsyncCompactProtocol<R> {
-    reader: R,
+    reader: CountingReader<R>,
 
     last_read_field_id: i16,
     read_field_id_stack: Vec<i16>,
     pending_read_bool_value: Option<bool>,
+    limiter: Limiter,
 }
 
 impl<R> TAsyncInputProtocol for TAsyncCompactProtocol<R>
@@ -970,6 +972,8 @@ where
     R: AsyncRead + Unpin + Send,
 {
     async fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.reader.reset();
         let compact_id = self.read_byte().await?;
         if compact_id != COMPACT_PROTOCOL_ID {
             return Err(new_protocol_exception(
@@ -1005,11 +1009,16 @@ where
 
     #[inline]
     async fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
         Ok(())
     }
 
     #[inline]
     async fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.reader.reset();
+        }
+        self.limiter.enter_struct()?;
         self.read_field_id_stack.push(self.last_read_field_id);
         self.last_read_field_id = 0;
         Ok(None)
@@ -1017,11 +1026,13 @@ where
 
     #[inline]
     async fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
         Ok(())
     }
 
     // #[inline]
     async fn read_field_begin(&mut self) -> Result<TFieldIdentifier, ThriftException> {
+        self.limiter.check_message_len(self.reader.count())?;
         // we can read at least one byte, which is:
         // - the type
         // - the field id delta and the type
@@ -1090,6 +1101,9 @@ where
     #[inline]
     async fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
         let size = self.read_varint_async::<u32>().await? as usize;
+        self.limiter.check_bytes_len(size)?;
+        self.limiter
+            .check_message_len(self.reader.count().saturating_add(size))?;
         // FIXME: use maybe_uninit?
         let mut v = vec![0; size];
         self.reader.read_exact(&mut v).await?;
@@ -1175,6 +1189,8 @@ where
     #[inline]
     async fn read_map_begin(&mut self) -> Result<TMapIdentifier, ThriftException> {
         let element_count = self.read_varint_async::<u32>().await? as i32;
+        self.limiter.check_container_len(element_count as usize)?;
+        self.limiter.check_message_len(self.reader.count())?;
         if element_count == 0 {
             Ok(TMapIdentifier::new(TType::Stop, TType::Stop, 0))
         } else {
@@ -1202,13 +1218,22 @@ where
 {
     pub fn new(reader: R) -> TAsyncCompactProtocol<R> {
         Self {
-            reader,
+            reader: CountingReader::new(reader),
             last_read_field_id: 0,
             read_field_id_stack: Vec::new(),
             pending_read_bool_value: None,
+            limiter: Limiter::default(),
         }
     }
 
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self.reader.set_limit(limits.max_message_len);
+        self
+    }
+
     #[inline]
     async fn read_collection_begin(&mut self) -> Result<(TType, usize), ThriftException> {
         let header = self.read_byte().await?;
@@ -1220,6 +1245,8 @@ where
         } else {
             self.read_varint_async::<u32>().await? as i32
         };
+        self.limiter.check_container_len(element_count as usize)?;
+        self.limiter.check_message_len(self.reader.count())?;
         Ok((element_type, element_count as usize))
     }
 
@@ -1248,6 +1275,7 @@ pub struct TCompactInputProtocol<T> {
     // and reading the field only occurs after the field id is read.
     pending_read_bool_value: Option<bool>,
     pending_read_bool_field_identifier: Option<TFieldIdentifier>,
+    limiter: Limiter,
 }
 
 impl<T> TCompactInputProtocol<T> {
@@ -1258,9 +1286,17 @@ impl<T> TCompactInputProtocol<T> {
             read_field_id_stack: Vec::with_capacity(24),
             pending_read_bool_value: None,
             pending_read_bool_field_identifier: None,
+            limiter: Limiter::default(),
         }
     }
 
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self
+    }
+
     fn assert_no_pending_bool_read(&self) {
         if let Some(ref f) = self.pending_read_bool_field_identifier {
             panic!("pending bool field {f:?} not read");
@@ -1292,6 +1328,7 @@ impl TCompactInputProtocol<&mut Bytes> {
         } else {
             self.read_varint::<u32>()? as i32
         };
+        self.limiter.check_container_len(element_count as usize)?;
         Ok((element_type, element_count as usize))
     }
 }
@@ -1516,6 +1553,8 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
     type Buf = Bytes;
 
     fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.limiter.check_message_len(self.trans.len())?;
         let compact_id = self.read_byte()?;
         if compact_id != COMPACT_PROTOCOL_ID {
             return Err(new_protocol_exception(
@@ -1551,11 +1590,16 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
 
     #[inline]
     fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
         Ok(())
     }
 
     #[inline]
     fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.limiter.check_message_len(self.trans.len())?;
+        }
+        self.limiter.enter_struct()?;
         self.read_field_id_stack.push(self.last_read_field_id);
         self.last_read_field_id = 0;
         Ok(None)
@@ -1563,6 +1607,7 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
 
     #[inline]
     fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
         Ok(())
     }
 
@@ -1631,6 +1676,7 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
     #[inline]
     fn read_bytes(&mut self) -> Result<Bytes, ThriftException> {
         let size = self.read_varint::<u32>()?;
+        self.limiter.check_bytes_len(size as usize)?;
         Ok(self.trans.split_to(size as usize))
     }
 
@@ -1655,12 +1701,14 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
     #[inline]
     fn read_string(&mut self) -> Result<String, ThriftException> {
         let size = self.read_varint::<u32>()? as usize;
+        self.limiter.check_bytes_len(size)?;
         Ok(self.trans.read_to_string(size)?)
     }
 
     #[inline]
     fn read_faststr(&mut self) -> Result<FastStr, ThriftException> {
         let size = self.read_varint::<u32>()? as usize;
+        self.limiter.check_bytes_len(size)?;
         let bytes = self.trans.split_to(size);
         unsafe { Ok(FastStr::from_bytes_unchecked(bytes)) }
     }
@@ -1722,6 +1770,7 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
     // #[inline]
     fn read_map_begin(&mut self) -> Result<TMapIdentifier, ThriftException> {
         let element_count = self.read_varint::<u32>()? as i32;
+        self.limiter.check_container_len(element_count as usize)?;
         if element_count == 0 {
             Ok(TMapIdentifier::new(TType::Stop, TType::Stop, 0))
         } else {
@@ -1745,6 +1794,7 @@ impl TInputProtocol for TCompactInputProtocol<&mut Bytes> {
     #[inline]
     fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
         let size = self.read_varint::<u32>()? as usize;
+        self.limiter.check_bytes_len(size)?;
 
         Ok(self.trans.split_to(size).into())
     }
@@ -54,6 +54,14 @@ impl From<TransportException> for ThriftException {
 
 impl From<std::io::Error> for ThriftException {
     fn from(e: std::io::Error) -> Self {
+        if e.get_ref()
+            .is_some_and(|e| e.is::<super::limits::MessageLenExceeded>())
+        {
+            return ThriftException::Protocol(ProtocolException::new(
+                ProtocolExceptionKind::LimitExceeded,
+                e.to_string(),
+            ));
+        }
         ThriftException::Transport(TransportException::from(e))
     }
 }
@@ -59,6 +59,7 @@ impl Display for ProtocolException {
             ProtocolExceptionKind::BadVersion => "invalid thrift version",
             ProtocolExceptionKind::NotImplemented => "not implemented",
             ProtocolExceptionKind::DepthLimit => "maximum skip depth reached",
+            ProtocolExceptionKind::LimitExceeded => "decode limit exceeded",
         };
 
         write!(f, "{}: {}", error_text, self.message)
@@ -95,4 +96,7 @@ pub enum ProtocolExceptionKind {
     /// Reached the maximum nested depth to which an encoded Thrift field could
     /// be skipped.
     DepthLimit,
+    /// A size or depth read from the wire exceeded the configured
+    /// [`DecodeLimits`](crate::thrift::DecodeLimits).
+    LimitExceeded,
 }
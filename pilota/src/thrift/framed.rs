@@ -0,0 +1,341 @@
+//! The framed transport, where each message is prefixed with its length as a
+//! big-endian `u32`.
+//!
+//! The async readers read a whole frame into [`Bytes`] and decode it with the
+//! buffered protocols, which is much faster than decoding field by field with
+//! the async protocols. For the unframed (buffered) transport, wrap the reader
+//! in a [`tokio::io::BufReader`] and read with the async protocols, since the
+//! end of a message is only known once it is decoded.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    DecodeLimits, Message, ProtocolExceptionKind, TMessageIdentifier, TOutputProtocol,
+    ThriftException, binary::TBinaryProtocol, compact::TCompactOutputProtocol, header::ProtocolId,
+    new_protocol_exception,
+};
+
+/// The default maximum length of a frame, 16 MiB.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+const LEN_SIZE: usize = 4;
+
+fn check_frame_len(
+    len: usize,
+    max_frame_len: usize,
+    kind: ProtocolExceptionKind,
+) -> Result<(), ThriftException> {
+    if len > max_frame_len {
+        return Err(new_protocol_exception(
+            kind,
+            format!("frame length of {len} exceeds the limit of {max_frame_len}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Writes a message in a frame, with the protocol of `protocol_id`.
+///
+/// Fails with [`ProtocolExceptionKind::SizeLimit`] if the frame is longer
+/// than `max_frame_len`, before anything is written.
+pub fn encode_frame<B, M>(
+    buf: &mut B,
+    protocol_id: ProtocolId,
+    identifier: &TMessageIdentifier,
+    msg: &M,
+    max_frame_len: usize,
+) -> Result<(), ThriftException>
+where
+    B: BufMut,
+    M: Message,
+    for<'a> TBinaryProtocol<&'a mut B>: TOutputProtocol,
+    for<'a> TCompactOutputProtocol<&'a mut B>: TOutputProtocol,
+{
+    protocol_id.encode_message(buf, identifier, msg, |buf, len| {
+        check_frame_len(
+            len,
+            max_frame_len.min(u32::MAX as usize),
+            ProtocolExceptionKind::SizeLimit,
+        )?;
+        buf.put_u32(len as u32);
+        Ok(())
+    })
+}
+
+/// Splits the frame at the start of `buf` off it, and returns its payload.
+///
+/// Returns `None` and leaves `buf` as it is if the frame is incomplete, and
+/// fails with [`ProtocolExceptionKind::LimitExceeded`] if the frame is longer
+/// than `max_frame_len`.
+pub fn decode_frame(
+    buf: &mut Bytes,
+    max_frame_len: usize,
+) -> Result<Option<Bytes>, ThriftException> {
+    if buf.len() < LEN_SIZE {
+        return Ok(None);
+    }
+    let len = u32::from_be_bytes(buf[..LEN_SIZE].try_into().unwrap()) as usize;
+    check_frame_len(len, max_frame_len, ProtocolExceptionKind::LimitExceeded)?;
+    if buf.len() - LEN_SIZE < len {
+        return Ok(None);
+    }
+    buf.advance(LEN_SIZE);
+    Ok(Some(buf.split_to(len)))
+}
+
+/// Reads framed messages from an [`AsyncRead`].
+pub struct TFramedReader<R> {
+    reader: R,
+    protocol_id: ProtocolId,
+    max_frame_len: usize,
+    limits: DecodeLimits,
+    buf: BytesMut,
+}
+
+impl<R> TFramedReader<R> {
+    pub fn new(reader: R, protocol_id: ProtocolId) -> Self {
+        Self {
+            reader,
+            protocol_id,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            limits: DecodeLimits::default(),
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Sets the maximum length of a frame, [`DEFAULT_MAX_FRAME_LEN`] by
+    /// default. A longer frame is rejected before it is read.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Sets the limits of the protocol that decodes the frames.
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send> TFramedReader<R> {
+    /// Reads the payload of a frame, or `None` if the reader ends before the
+    /// frame begins.
+    pub async fn read_frame(&mut self) -> Result<Option<Bytes>, ThriftException> {
+        let mut len = [0; LEN_SIZE];
+        let n = self.reader.read(&mut len).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut len[n..]).await?;
+        let len = u32::from_be_bytes(len) as usize;
+        check_frame_len(
+            len,
+            self.max_frame_len,
+            ProtocolExceptionKind::LimitExceeded,
+        )?;
+
+        self.buf.resize(len, 0);
+        self.reader.read_exact(&mut self.buf).await?;
+        Ok(Some(self.buf.split().freeze()))
+    }
+
+    /// Reads a message from a frame, or `None` if the reader ends before the
+    /// frame begins.
+    pub async fn read_message<M: Message>(
+        &mut self,
+    ) -> Result<Option<(TMessageIdentifier, M)>, ThriftException> {
+        let Some(mut frame) = self.read_frame().await? else {
+            return Ok(None);
+        };
+        self.protocol_id
+            .decode_message(&mut frame, self.limits)
+            .map(Some)
+    }
+}
+
+/// Writes framed messages to an [`AsyncWrite`].
+pub struct TFramedWriter<W> {
+    writer: W,
+    protocol_id: ProtocolId,
+    max_frame_len: usize,
+    buf: BytesMut,
+}
+
+impl<W> TFramedWriter<W> {
+    pub fn new(writer: W, protocol_id: ProtocolId) -> Self {
+        Self {
+            writer,
+            protocol_id,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Sets the maximum length of a frame, [`DEFAULT_MAX_FRAME_LEN`] by
+    /// default. A longer message is rejected before it is written.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send> TFramedWriter<W> {
+    /// Writes a message in a frame, and flushes the writer.
+    pub async fn write_message<M: Message>(
+        &mut self,
+        identifier: &TMessageIdentifier,
+        msg: &M,
+    ) -> Result<(), ThriftException> {
+        self.buf.clear();
+        encode_frame(
+            &mut self.buf,
+            self.protocol_id,
+            identifier,
+            msg,
+            self.max_frame_len,
+        )?;
+        self.writer.write_all(&self.buf).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, Bytes, BytesMut};
+    use faststr::FastStr;
+
+    use super::{TFramedReader, TFramedWriter, decode_frame, encode_frame};
+    use crate::{
+        LinkedBytes,
+        thrift::{
+            ApplicationException, ApplicationExceptionKind, DecodeLimits, ProtocolExceptionKind,
+            TMessageIdentifier, TMessageType, ThriftException, header::ProtocolId,
+        },
+    };
+
+    fn message() -> (TMessageIdentifier, ApplicationException) {
+        (
+            TMessageIdentifier::new(FastStr::from_static_str("echo"), TMessageType::Call, 7),
+            ApplicationException::new(ApplicationExceptionKind::UNKNOWN, "hello"),
+        )
+    }
+
+    fn assert_protocol_error<T: std::fmt::Debug>(
+        res: Result<T, ThriftException>,
+        kind: ProtocolExceptionKind,
+    ) {
+        match res {
+            Err(ThriftException::Protocol(e)) => assert_eq!(e.kind(), kind, "{e}"),
+            res => panic!("expected a protocol error, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let (identifier, msg) = message();
+        for protocol_id in [ProtocolId::Binary, ProtocolId::Compact] {
+            let mut buf = BytesMut::new();
+            encode_frame(&mut buf, protocol_id, &identifier, &msg, usize::MAX).unwrap();
+            let mut linked = LinkedBytes::new();
+            encode_frame(&mut linked, protocol_id, &identifier, &msg, usize::MAX).unwrap();
+            assert_eq!(buf, linked.concat());
+            let len = buf.len();
+            assert_eq!(&buf[..4], &(len as u32 - 4).to_be_bytes());
+
+            let mut b = buf.clone().freeze().slice(..len - 1);
+            assert_eq!(decode_frame(&mut b, usize::MAX).unwrap(), None);
+            assert_eq!(b.len(), len - 1);
+
+            let mut b = buf.freeze();
+            let mut frame = decode_frame(&mut b, len - 4).unwrap().unwrap();
+            assert!(b.is_empty());
+            let (id, decoded) = protocol_id
+                .decode_message::<ApplicationException>(&mut frame, DecodeLimits::default())
+                .unwrap();
+            assert_eq!(id, identifier);
+            assert_eq!(decoded, msg);
+
+            assert_protocol_error(
+                encode_frame(
+                    &mut BytesMut::new(),
+                    protocol_id,
+                    &identifier,
+                    &msg,
+                    len - 5,
+                ),
+                ProtocolExceptionKind::SizeLimit,
+            );
+        }
+
+        let mut b = BytesMut::new();
+        b.put_u32(8);
+        assert_protocol_error(
+            decode_frame(&mut b.freeze(), 7),
+            ProtocolExceptionKind::LimitExceeded,
+        );
+    }
+
+    #[test]
+    fn test_async_frames() {
+        let (identifier, msg) = message();
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut writer = TFramedWriter::new(Vec::new(), ProtocolId::Compact);
+            writer.write_message(&identifier, &msg).await.unwrap();
+            writer.write_message(&identifier, &msg).await.unwrap();
+            let buf = Bytes::from(writer.into_inner());
+
+            let mut reader = TFramedReader::new(&buf[..], ProtocolId::Compact);
+            for _ in 0..2 {
+                let (id, decoded) = reader
+                    .read_message::<ApplicationException>()
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(id, identifier);
+                assert_eq!(decoded, msg);
+            }
+            assert!(
+                reader
+                    .read_message::<ApplicationException>()
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+
+            let mut reader = TFramedReader::new(&buf[..], ProtocolId::Compact)
+                .with_max_frame_len(buf.len() / 2 - 5);
+            assert_protocol_error(
+                reader.read_frame().await,
+                ProtocolExceptionKind::LimitExceeded,
+            );
+
+            let limits = DecodeLimits {
+                max_bytes_len: 4,
+                ..Default::default()
+            };
+            let mut reader = TFramedReader::new(&buf[..], ProtocolId::Compact).with_limits(limits);
+            assert_protocol_error(
+                reader.read_message::<ApplicationException>().await,
+                ProtocolExceptionKind::LimitExceeded,
+            );
+
+            // a frame cut short
+            let mut reader = TFramedReader::new(&buf[..buf.len() - 1], ProtocolId::Compact);
+            reader.read_frame().await.unwrap();
+            assert!(reader.read_frame().await.is_err());
+        });
+    }
+}
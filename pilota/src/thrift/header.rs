@@ -0,0 +1,576 @@
+//! THeader framing, in the TTHeader layout used by Kitex.
+//!
+//! A frame carries a thrift message of the binary or compact protocol,
+//! prefixed with its length and a header of the sequence id, the protocol id
+//! and the info maps:
+//!
+//! ```text
+//! LENGTH (u32) | MAGIC 0x1000 (u16) | FLAGS (u16) | SEQUENCE ID (u32)
+//! | HEADER SIZE / 4 (u16) | PROTOCOL ID (u8) | NUM TRANSFORMS (u8)
+//! | INFO ID (u8) | INFO DATA | ... | PADDING | PAYLOAD
+//! ```
+//!
+//! `LENGTH` counts the bytes after it. The string key/value pairs are sent
+//! as [`INFO_KEYVALUE`], and the integer-keyed values as
+//! [`INFO_INTKEYVALUE`]. Transforms, e.g. compression, are not supported.
+//! See <https://www.cloudwego.io/docs/kitex/reference/transport_protocol_ttheader/>.
+
+use bytes::{Buf, BufMut, Bytes};
+use faststr::FastStr;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{
+    DecodeLimits, Message, ProtocolExceptionKind, TInputProtocol, TLengthProtocol,
+    TMessageIdentifier, TOutputProtocol, ThriftException,
+    binary::TBinaryProtocol,
+    compact::{TCompactInputProtocol, TCompactOutputProtocol},
+    new_protocol_exception,
+    rw_ext::ReadExt,
+};
+use crate::AHashMap;
+
+pub const HEADER_MAGIC: u16 = 0x1000;
+
+pub const INFO_PADDING: u8 = 0x00;
+/// The info of string keys and values.
+pub const INFO_KEYVALUE: u8 = 0x01;
+/// The info of integer keys and string values.
+pub const INFO_INTKEYVALUE: u8 = 0x10;
+
+/// The bytes of the length, magic, flags, sequence id and header size.
+const FIXED_LEN: usize = 14;
+const MAX_HEADER_LEN: usize = u16::MAX as usize * 4;
+
+/// The protocol of the payload.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ProtocolId {
+    #[default]
+    Binary = 0x00,
+    Compact = 0x02,
+}
+
+impl TryFrom<u8> for ProtocolId {
+    type Error = ThriftException;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(ProtocolId::Binary),
+            0x02 => Ok(ProtocolId::Compact),
+            _ => Err(new_protocol_exception(
+                ProtocolExceptionKind::NotImplemented,
+                format!("unsupported header protocol id {value}"),
+            )),
+        }
+    }
+}
+
+impl ProtocolId {
+    /// Writes a message with this protocol, after the prefix written by
+    /// `prefix` with the length of the message.
+    pub(crate) fn encode_message<B, M>(
+        self,
+        buf: &mut B,
+        identifier: &TMessageIdentifier,
+        msg: &M,
+        prefix: impl FnOnce(&mut B, usize) -> Result<(), ThriftException>,
+    ) -> Result<(), ThriftException>
+    where
+        B: BufMut,
+        M: Message,
+        for<'a> TBinaryProtocol<&'a mut B>: TOutputProtocol,
+        for<'a> TCompactOutputProtocol<&'a mut B>: TOutputProtocol,
+    {
+        match self {
+            ProtocolId::Binary => {
+                let len = message_len(&mut TBinaryProtocol::new(&mut *buf, true), identifier, msg);
+                prefix(buf, len)?;
+                write_message(&mut TBinaryProtocol::new(buf, true), identifier, msg)
+            }
+            ProtocolId::Compact => {
+                let len = message_len(
+                    &mut TCompactOutputProtocol::new(&mut *buf, true),
+                    identifier,
+                    msg,
+                );
+                prefix(buf, len)?;
+                write_message(&mut TCompactOutputProtocol::new(buf, true), identifier, msg)
+            }
+        }
+    }
+
+    /// Reads a message with this protocol.
+    pub(crate) fn decode_message<M: Message>(
+        self,
+        buf: &mut Bytes,
+        limits: DecodeLimits,
+    ) -> Result<(TMessageIdentifier, M), ThriftException> {
+        match self {
+            ProtocolId::Binary => {
+                read_message(&mut TBinaryProtocol::new(buf, true).with_limits(limits))
+            }
+            ProtocolId::Compact => {
+                read_message(&mut TCompactInputProtocol::new(buf).with_limits(limits))
+            }
+        }
+    }
+}
+
+/// The header of a frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct THeader {
+    pub flags: u16,
+    pub seq_id: i32,
+    pub protocol_id: ProtocolId,
+    /// The string key/value pairs, sent as [`INFO_KEYVALUE`].
+    pub str_kvs: AHashMap<FastStr, FastStr>,
+    /// The integer-keyed values, sent as [`INFO_INTKEYVALUE`].
+    pub int_kvs: AHashMap<u16, FastStr>,
+}
+
+impl THeader {
+    /// Returns whether `buf` starts with a header frame, which needs at least
+    /// the first 6 bytes.
+    pub fn is_header(buf: &[u8]) -> bool {
+        buf.len() >= 6 && u16::from_be_bytes([buf[4], buf[5]]) == HEADER_MAGIC
+    }
+
+    /// The length of the info, without padding.
+    fn info_len(&self) -> usize {
+        let mut len = 2;
+        if !self.str_kvs.is_empty() {
+            len += 3 + self
+                .str_kvs
+                .iter()
+                .map(|(k, v)| 4 + k.len() + v.len())
+                .sum::<usize>();
+        }
+        if !self.int_kvs.is_empty() {
+            len += 3 + self.int_kvs.values().map(|v| 4 + v.len()).sum::<usize>();
+        }
+        len
+    }
+
+    /// The length of the frame before the payload.
+    pub fn header_len(&self) -> usize {
+        FIXED_LEN + self.info_len().next_multiple_of(4)
+    }
+
+    /// Writes the frame before a payload of `payload_len` bytes, which the
+    /// protocol of [`THeader::protocol_id`] writes next.
+    pub fn encode<B: BufMut>(
+        &self,
+        buf: &mut B,
+        payload_len: usize,
+    ) -> Result<(), ThriftException> {
+        let info_len = self.info_len();
+        let header_len = info_len.next_multiple_of(4);
+        if header_len > MAX_HEADER_LEN {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::SizeLimit,
+                format!("header of {header_len} bytes is too long"),
+            ));
+        }
+        let frame_len = self.header_len() - 4 + payload_len;
+        let frame_len = u32::try_from(frame_len).map_err(|_| {
+            new_protocol_exception(
+                ProtocolExceptionKind::SizeLimit,
+                format!("frame of {frame_len} bytes is too long"),
+            )
+        })?;
+
+        buf.put_u32(frame_len);
+        buf.put_u16(HEADER_MAGIC);
+        buf.put_u16(self.flags);
+        buf.put_i32(self.seq_id);
+        buf.put_u16((header_len / 4) as u16);
+        buf.put_u8(self.protocol_id as u8);
+        // no transforms
+        buf.put_u8(0);
+        if !self.str_kvs.is_empty() {
+            buf.put_u8(INFO_KEYVALUE);
+            put_len(buf, self.str_kvs.len())?;
+            for (k, v) in &self.str_kvs {
+                put_str(buf, k)?;
+                put_str(buf, v)?;
+            }
+        }
+        if !self.int_kvs.is_empty() {
+            buf.put_u8(INFO_INTKEYVALUE);
+            put_len(buf, self.int_kvs.len())?;
+            for (k, v) in &self.int_kvs {
+                buf.put_u16(*k);
+                put_str(buf, v)?;
+            }
+        }
+        buf.put_bytes(INFO_PADDING, header_len - info_len);
+        Ok(())
+    }
+
+    /// Writes a message in a frame with this header, with the protocol of
+    /// [`THeader::protocol_id`].
+    pub fn encode_message<B, M>(
+        &self,
+        buf: &mut B,
+        identifier: &TMessageIdentifier,
+        msg: &M,
+    ) -> Result<(), ThriftException>
+    where
+        B: BufMut,
+        M: Message,
+        for<'a> TBinaryProtocol<&'a mut B>: TOutputProtocol,
+        for<'a> TCompactOutputProtocol<&'a mut B>: TOutputProtocol,
+    {
+        self.protocol_id
+            .encode_message(buf, identifier, msg, |buf, payload_len| {
+                self.encode(buf, payload_len)
+            })
+    }
+
+    /// Splits the frame at the start of `buf` off it, and returns its header
+    /// and payload.
+    pub fn decode(buf: &mut Bytes) -> Result<(Self, Bytes), ThriftException> {
+        let frame_len = ReadExt::read_u32(&mut &buf[..])? as usize;
+        if buf.len() - 4 < frame_len {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!(
+                    "frame of {frame_len} bytes, but only {} bytes remain",
+                    buf.len() - 4
+                ),
+            ));
+        }
+        let mut frame = buf.split_to(4 + frame_len);
+        frame.advance(4);
+        Self::decode_frame(frame)
+    }
+
+    /// Reads a frame, and returns its header and payload. The length of the
+    /// frame is checked against [`DecodeLimits::max_message_len`].
+    pub async fn decode_async<R: AsyncRead + Unpin + Send>(
+        reader: &mut R,
+        limits: DecodeLimits,
+    ) -> Result<(Self, Bytes), ThriftException> {
+        let frame_len = reader.read_u32().await? as usize;
+        if frame_len > limits.max_message_len {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::LimitExceeded,
+                format!(
+                    "frame length of {frame_len} exceeds the limit of {}",
+                    limits.max_message_len
+                ),
+            ));
+        }
+        let mut frame = vec![0; frame_len];
+        reader.read_exact(&mut frame).await?;
+        Self::decode_frame(frame.into())
+    }
+
+    /// Decodes the frame after its length.
+    fn decode_frame(mut frame: Bytes) -> Result<(Self, Bytes), ThriftException> {
+        let magic = frame.read_u16()?;
+        if magic != HEADER_MAGIC {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::BadVersion,
+                format!("invalid header magic {magic:#06x}"),
+            ));
+        }
+        let flags = frame.read_u16()?;
+        let seq_id = frame.read_i32()?;
+        let header_len = frame.read_u16()? as usize * 4;
+        if frame.len() < header_len {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("header of {header_len} bytes exceeds the frame"),
+            ));
+        }
+        let mut header = frame.split_to(header_len);
+
+        let protocol_id = ProtocolId::try_from(header.read_u8()?)?;
+        let transforms = header.read_u8()?;
+        if transforms != 0 {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::NotImplemented,
+                "header transforms are not supported",
+            ));
+        }
+
+        let mut str_kvs = AHashMap::default();
+        let mut int_kvs = AHashMap::default();
+        while header.has_remaining() {
+            match header.read_u8()? {
+                INFO_PADDING => {}
+                INFO_KEYVALUE => {
+                    let n = header.read_u16()?;
+                    str_kvs.reserve(n as usize);
+                    for _ in 0..n {
+                        let k = read_str(&mut header)?;
+                        let v = read_str(&mut header)?;
+                        str_kvs.insert(k, v);
+                    }
+                }
+                INFO_INTKEYVALUE => {
+                    let n = header.read_u16()?;
+                    int_kvs.reserve(n as usize);
+                    for _ in 0..n {
+                        let k = header.read_u16()?;
+                        let v = read_str(&mut header)?;
+                        int_kvs.insert(k, v);
+                    }
+                }
+                id => {
+                    return Err(new_protocol_exception(
+                        ProtocolExceptionKind::InvalidData,
+                        format!("unknown header info id {id:#04x}"),
+                    ));
+                }
+            }
+        }
+
+        Ok((
+            Self {
+                flags,
+                seq_id,
+                protocol_id,
+                str_kvs,
+                int_kvs,
+            },
+            frame,
+        ))
+    }
+
+    /// Reads a message from the payload of a frame with this header, with
+    /// the protocol of [`THeader::protocol_id`].
+    pub fn decode_message<M: Message>(
+        &self,
+        payload: &mut Bytes,
+        limits: DecodeLimits,
+    ) -> Result<(TMessageIdentifier, M), ThriftException> {
+        self.protocol_id.decode_message(payload, limits)
+    }
+}
+
+fn message_len<P: TLengthProtocol, M: Message>(
+    p: &mut P,
+    identifier: &TMessageIdentifier,
+    msg: &M,
+) -> usize {
+    p.message_begin_len(identifier) + msg.size(p) + p.message_end_len()
+}
+
+fn write_message<P: TOutputProtocol, M: Message>(
+    p: &mut P,
+    identifier: &TMessageIdentifier,
+    msg: &M,
+) -> Result<(), ThriftException> {
+    p.write_message_begin(identifier)?;
+    msg.encode(p)?;
+    p.write_message_end()
+}
+
+fn read_message<P: TInputProtocol, M: Message>(
+    p: &mut P,
+) -> Result<(TMessageIdentifier, M), ThriftException> {
+    let identifier = p.read_message_begin()?;
+    let msg = M::decode(p)?;
+    p.read_message_end()?;
+    Ok((identifier, msg))
+}
+
+fn put_len<B: BufMut>(buf: &mut B, len: usize) -> Result<(), ThriftException> {
+    let len = u16::try_from(len).map_err(|_| {
+        new_protocol_exception(
+            ProtocolExceptionKind::SizeLimit,
+            format!("{len} exceeds the header limit of {}", u16::MAX),
+        )
+    })?;
+    buf.put_u16(len);
+    Ok(())
+}
+
+fn put_str<B: BufMut>(buf: &mut B, s: &FastStr) -> Result<(), ThriftException> {
+    put_len(buf, s.len())?;
+    buf.put_slice(s.as_bytes());
+    Ok(())
+}
+
+fn read_str(buf: &mut Bytes) -> Result<FastStr, ThriftException> {
+    let len = buf.read_u16()? as usize;
+    if buf.len() < len {
+        return Err(new_protocol_exception(
+            ProtocolExceptionKind::InvalidData,
+            format!("header string of {len} bytes exceeds the header"),
+        ));
+    }
+    FastStr::from_bytes(buf.split_to(len))
+        .map_err(|e| new_protocol_exception(ProtocolExceptionKind::InvalidData, format!("{e:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, BytesMut};
+    use faststr::FastStr;
+
+    use super::{HEADER_MAGIC, ProtocolId, THeader};
+    use crate::{
+        LinkedBytes,
+        thrift::{
+            ApplicationException, ApplicationExceptionKind, DecodeLimits, ProtocolExceptionKind,
+            TMessageIdentifier, TMessageType, ThriftException,
+        },
+    };
+
+    fn header(protocol_id: ProtocolId) -> THeader {
+        let mut header = THeader {
+            flags: 0,
+            seq_id: 7,
+            protocol_id,
+            ..Default::default()
+        };
+        header.str_kvs.insert(
+            FastStr::from_static_str("method"),
+            FastStr::from_static_str("echo"),
+        );
+        header.int_kvs.insert(1, FastStr::from_static_str("client"));
+        header
+    }
+
+    fn message() -> (TMessageIdentifier, ApplicationException) {
+        (
+            TMessageIdentifier::new(FastStr::from_static_str("echo"), TMessageType::Call, 7),
+            ApplicationException::new(ApplicationExceptionKind::UNKNOWN, "hello"),
+        )
+    }
+
+    fn assert_protocol_error<T: std::fmt::Debug>(
+        res: Result<T, ThriftException>,
+        kind: ProtocolExceptionKind,
+    ) {
+        match res {
+            Err(ThriftException::Protocol(e)) => assert_eq!(e.kind(), kind, "{e}"),
+            res => panic!("expected a protocol error, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (identifier, msg) = message();
+        for protocol_id in [ProtocolId::Binary, ProtocolId::Compact] {
+            let header = header(protocol_id);
+
+            let mut buf = BytesMut::new();
+            header.encode_message(&mut buf, &identifier, &msg).unwrap();
+            let mut linked = LinkedBytes::new();
+            header
+                .encode_message(&mut linked, &identifier, &msg)
+                .unwrap();
+            let linked = linked.concat().freeze();
+            assert_eq!(buf, linked);
+            let mut empty = BytesMut::new();
+            header.encode(&mut empty, 0).unwrap();
+            assert_eq!(empty.len(), header.header_len());
+            assert!(THeader::is_header(&buf));
+
+            // two frames in a row
+            buf.extend_from_slice(&linked);
+            let mut buf = buf.freeze();
+            for _ in 0..2 {
+                let (decoded, mut payload) = THeader::decode(&mut buf).unwrap();
+                assert_eq!(decoded, header);
+                let (id, decoded) = decoded
+                    .decode_message::<ApplicationException>(&mut payload, DecodeLimits::default())
+                    .unwrap();
+                assert_eq!(id, identifier);
+                assert_eq!(decoded, msg);
+                assert!(payload.is_empty());
+            }
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_decode_async() {
+        let (identifier, msg) = message();
+        let header = header(ProtocolId::Compact);
+        let mut buf = BytesMut::new();
+        header.encode_message(&mut buf, &identifier, &msg).unwrap();
+        let buf = buf.freeze();
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (decoded, mut payload) =
+                THeader::decode_async(&mut &buf[..], DecodeLimits::default())
+                    .await
+                    .unwrap();
+            assert_eq!(decoded, header);
+            let (_, decoded) = decoded
+                .decode_message::<ApplicationException>(&mut payload, DecodeLimits::default())
+                .unwrap();
+            assert_eq!(decoded, msg);
+
+            let limits = DecodeLimits {
+                max_message_len: buf.len() - 5,
+                ..Default::default()
+            };
+            assert_protocol_error(
+                THeader::decode_async(&mut &buf[..], limits).await,
+                ProtocolExceptionKind::LimitExceeded,
+            );
+        });
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let mut buf = BytesMut::new();
+        header(ProtocolId::Binary).encode(&mut buf, 0).unwrap();
+        let buf = buf.freeze();
+
+        // truncated
+        let mut b = buf.slice(..buf.len() - 1);
+        assert_protocol_error(THeader::decode(&mut b), ProtocolExceptionKind::InvalidData);
+
+        let mut b = BytesMut::from(&buf[..]);
+        b[4..6].copy_from_slice(&(HEADER_MAGIC + 1).to_be_bytes());
+        assert!(!THeader::is_header(&b));
+        assert_protocol_error(
+            THeader::decode(&mut b.freeze()),
+            ProtocolExceptionKind::BadVersion,
+        );
+
+        // a compressed payload
+        let mut b = BytesMut::from(&buf[..]);
+        b[15] = 1;
+        assert_protocol_error(
+            THeader::decode(&mut b.freeze()),
+            ProtocolExceptionKind::NotImplemented,
+        );
+
+        let mut b = BytesMut::new();
+        b.put_u32(12);
+        b.put_u16(HEADER_MAGIC);
+        b.put_u16(0);
+        b.put_i32(0);
+        b.put_u16(1);
+        b.put_slice(&[0, 0, 0x20, 0]);
+        assert_protocol_error(
+            THeader::decode(&mut b.freeze()),
+            ProtocolExceptionKind::InvalidData,
+        );
+
+        let long = THeader {
+            str_kvs: [(
+                FastStr::from_static_str("k"),
+                FastStr::from("v".repeat(1 << 16)),
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        assert_protocol_error(
+            long.encode(&mut BytesMut::new(), 0),
+            ProtocolExceptionKind::SizeLimit,
+        );
+    }
+}
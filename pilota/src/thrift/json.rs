@@ -0,0 +1,2176 @@
+//! Apache Thrift JSON protocol (`TJSONProtocol`).
+//!
+//! Messages are encoded as `[1,"name",type,seq,{...}]`, structs as objects
+//! keyed by field id with `{"<type>":value}` values, containers as arrays
+//! headed by their element types and size, and binary data as unpadded
+//! base64. See
+//! <https://github.com/apache/thrift/blob/master/doc/specs/thrift-json-protocol.md>.
+//!
+//! Unknown fields kept by `keep_unknown_fields` structs are stored in the
+//! binary encoding, which has no JSON form, so they are not written; the
+//! generated `size` still counts their bytes, so it over-reports for such
+//! structs.
+
+use std::{fmt, str};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD_NO_PAD};
+use bytes::{Buf, Bytes, BytesMut};
+use faststr::FastStr;
+use linkedbytes::LinkedBytes;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{
+    MAXIMUM_SKIP_DEPTH, TAsyncInputProtocol, TFieldIdentifier, TInputProtocol, TLengthProtocol,
+    TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol,
+    TSetIdentifier, TStructIdentifier, TType, ThriftException,
+    error::ProtocolExceptionKind,
+    limits::{CountingReader, DecodeLimits, Limiter},
+    new_protocol_exception,
+    rw_ext::IOError,
+};
+
+const VERSION: i64 = 1;
+
+const LBRACE: u8 = b'{';
+const RBRACE: u8 = b'}';
+const LBRACKET: u8 = b'[';
+const RBRACKET: u8 = b']';
+const COMMA: u8 = b',';
+const COLON: u8 = b':';
+pub(super) const QUOTE: u8 = b'"';
+const BACKSLASH: u8 = b'\\';
+
+const NAN: &str = "NaN";
+const INFINITY: &str = "Infinity";
+const NEG_INFINITY: &str = "-Infinity";
+
+#[inline]
+fn type_name(ttype: TType) -> Result<&'static str, ThriftException> {
+    Ok(match ttype {
+        TType::Bool => "tf",
+        TType::I8 => "i8",
+        TType::I16 => "i16",
+        TType::I32 => "i32",
+        TType::I64 => "i64",
+        TType::Double => "dbl",
+        TType::Binary => "str",
+        TType::Struct => "rec",
+        TType::Map => "map",
+        TType::Set => "set",
+        TType::List => "lst",
+        TType::Uuid => "uid",
+        TType::Stop | TType::Void => {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::NotImplemented,
+                format!("ttype {ttype:?} has no JSON representation"),
+            ));
+        }
+    })
+}
+
+#[inline]
+fn type_from_name(name: &[u8]) -> Result<TType, ThriftException> {
+    Ok(match name {
+        b"tf" => TType::Bool,
+        b"i8" => TType::I8,
+        b"i16" => TType::I16,
+        b"i32" => TType::I32,
+        b"i64" => TType::I64,
+        b"dbl" => TType::Double,
+        b"str" => TType::Binary,
+        b"rec" => TType::Struct,
+        b"map" => TType::Map,
+        b"set" => TType::Set,
+        b"lst" => TType::List,
+        b"uid" => TType::Uuid,
+        _ => {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("unrecognized JSON type {}", String::from_utf8_lossy(name)),
+            ));
+        }
+    })
+}
+
+#[derive(Clone, Copy, Debug)]
+enum JsonContext {
+    /// Elements of a JSON array, separated by `,`.
+    List { first: bool },
+    /// Members of a JSON object, alternating between `:` and `,`.
+    Pair { first: bool, colon: bool },
+}
+
+/// Tracks the nesting of JSON arrays and objects so that separators are
+/// written and expected at the right places.
+#[derive(Debug, Default)]
+pub(super) struct JsonContextStack(Vec<JsonContext>);
+
+impl JsonContextStack {
+    #[inline]
+    fn push_list(&mut self) {
+        self.0.push(JsonContext::List { first: true });
+    }
+
+    #[inline]
+    fn push_pair(&mut self) {
+        self.0.push(JsonContext::Pair {
+            first: true,
+            colon: true,
+        });
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        self.0.pop();
+    }
+
+    /// Advances the innermost context and returns the separator that must
+    /// precede the next value, if any.
+    #[inline]
+    pub(super) fn separator(&mut self) -> Option<u8> {
+        match self.0.last_mut() {
+            None => None,
+            Some(JsonContext::List { first }) => {
+                if *first {
+                    *first = false;
+                    None
+                } else {
+                    Some(COMMA)
+                }
+            }
+            Some(JsonContext::Pair { first, colon }) => {
+                if *first {
+                    *first = false;
+                    *colon = true;
+                    None
+                } else {
+                    let sep = if *colon { COLON } else { COMMA };
+                    *colon = !*colon;
+                    Some(sep)
+                }
+            }
+        }
+    }
+
+    /// Whether the value just positioned by [`Self::separator`] is an object
+    /// key, in which case numbers must be quoted.
+    #[inline]
+    pub(super) fn escape_num(&self) -> bool {
+        matches!(self.0.last(), Some(JsonContext::Pair { colon: true, .. }))
+    }
+
+    /// Whether the next value written will be an object key.
+    #[inline]
+    pub(super) fn next_is_key(&self) -> bool {
+        matches!(
+            self.0.last(),
+            Some(JsonContext::Pair { first: true, .. } | JsonContext::Pair { colon: false, .. })
+        )
+    }
+
+    #[inline]
+    pub(super) fn separator_len(&mut self) -> usize {
+        self.separator().map_or(0, |_| 1)
+    }
+
+    #[inline]
+    pub(super) fn string_len(&mut self, s: &[u8]) -> usize {
+        self.separator_len() + 2 + escaped_len(s)
+    }
+
+    #[inline]
+    pub(super) fn base64_len(&mut self, b: &[u8]) -> usize {
+        self.separator_len() + 2 + (b.len() * 4).div_ceil(3)
+    }
+
+    #[inline]
+    pub(super) fn integer_len(&mut self, i: i64) -> usize {
+        let sep = self.separator_len();
+        let quotes = if self.escape_num() { 2 } else { 0 };
+        sep + quotes + display_len(i)
+    }
+
+    #[inline]
+    pub(super) fn double_len(&mut self, d: f64) -> usize {
+        let sep = self.separator_len();
+        match special_double(d) {
+            Some(s) => sep + 2 + s.len(),
+            None => {
+                let quotes = if self.escape_num() { 2 } else { 0 };
+                sep + quotes + display_len(DoubleRepr(d))
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn object_begin_len(&mut self) -> usize {
+        let sep = self.separator_len();
+        self.push_pair();
+        sep + 1
+    }
+
+    #[inline]
+    pub(super) fn array_begin_len(&mut self) -> usize {
+        let sep = self.separator_len();
+        self.push_list();
+        sep + 1
+    }
+
+    #[inline]
+    pub(super) fn end_len(&mut self) -> usize {
+        self.pop();
+        1
+    }
+
+    #[inline]
+    fn type_name_len(&mut self, ttype: TType) -> usize {
+        self.string_len(type_name(ttype).unwrap_or_default().as_bytes())
+    }
+}
+
+/// Formats a double the way it is written on the wire: the shortest
+/// representation that round-trips, always containing a `.` or an exponent.
+struct DoubleRepr(f64);
+
+impl fmt::Display for DoubleRepr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+#[inline]
+fn special_double(d: f64) -> Option<&'static str> {
+    if d.is_nan() {
+        Some(NAN)
+    } else if d == f64::INFINITY {
+        Some(INFINITY)
+    } else if d == f64::NEG_INFINITY {
+        Some(NEG_INFINITY)
+    } else {
+        None
+    }
+}
+
+fn display_len<D: fmt::Display>(d: D) -> usize {
+    struct Counter(usize);
+
+    impl fmt::Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = fmt::write(&mut counter, format_args!("{d}"));
+    counter.0
+}
+
+#[inline]
+fn escape_of(b: u8) -> Option<&'static [u8]> {
+    Some(match b {
+        QUOTE => b"\\\"",
+        BACKSLASH => b"\\\\",
+        b'\x08' => b"\\b",
+        b'\x0c' => b"\\f",
+        b'\n' => b"\\n",
+        b'\r' => b"\\r",
+        b'\t' => b"\\t",
+        0..=0x1f => b"\\u00",
+        _ => return None,
+    })
+}
+
+#[inline]
+fn escaped_len(s: &[u8]) -> usize {
+    s.iter()
+        .map(|b| match escape_of(*b) {
+            // `\u00` is followed by two hex digits
+            Some(e) if e.len() == 4 => 6,
+            Some(e) => e.len(),
+            None => 1,
+        })
+        .sum()
+}
+
+#[inline]
+pub(super) fn uuid_to_string(u: [u8; 16]) -> String {
+    let h = hex_lower(&u);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[0..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..32]
+    )
+}
+
+#[inline]
+fn hex_lower(b: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut s = String::with_capacity(b.len() * 2);
+    for byte in b {
+        s.push(HEX[(byte >> 4) as usize] as char);
+        s.push(HEX[(byte & 0xf) as usize] as char);
+    }
+    s
+}
+
+#[inline]
+fn hex_val(b: u8) -> Result<u8, ThriftException> {
+    match b {
+        b'0'..=b'9' => Ok(b - b'0'),
+        b'a'..=b'f' => Ok(b - b'a' + 10),
+        b'A'..=b'F' => Ok(b - b'A' + 10),
+        _ => Err(new_protocol_exception(
+            ProtocolExceptionKind::InvalidData,
+            format!("invalid hex character {:?}", b as char),
+        )),
+    }
+}
+
+fn uuid_from_str(s: &[u8]) -> Result<[u8; 16], ThriftException> {
+    let digits: Vec<u8> = s.iter().copied().filter(|b| *b != b'-').collect();
+    if s.len() != 36 || digits.len() != 32 {
+        return Err(new_protocol_exception(
+            ProtocolExceptionKind::InvalidData,
+            format!("invalid uuid {}", String::from_utf8_lossy(s)),
+        ));
+    }
+    let mut u = [0; 16];
+    for (i, pair) in digits.chunks_exact(2).enumerate() {
+        u[i] = (hex_val(pair[0])? << 4) | hex_val(pair[1])?;
+    }
+    Ok(u)
+}
+
+fn decode_base64(b: &[u8]) -> Result<Vec<u8>, ThriftException> {
+    // Apache Thrift writes base64 without padding, but some clients pad it.
+    let end = b.iter().rposition(|c| *c != b'=').map_or(0, |i| i + 1);
+    STANDARD_NO_PAD.decode(&b[..end]).map_err(|e| {
+        new_protocol_exception(
+            ProtocolExceptionKind::InvalidData,
+            format!("invalid base64 data: {e}"),
+        )
+    })
+}
+
+#[inline]
+fn parse_integer(s: &[u8]) -> Result<i64, ThriftException> {
+    str::from_utf8(s)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("invalid JSON integer {}", String::from_utf8_lossy(s)),
+            )
+        })
+}
+
+#[inline]
+fn parse_double(s: &[u8]) -> Result<f64, ThriftException> {
+    match s {
+        b"NaN" => Ok(f64::NAN),
+        b"Infinity" => Ok(f64::INFINITY),
+        b"-Infinity" => Ok(f64::NEG_INFINITY),
+        _ => str::from_utf8(s)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| {
+                new_protocol_exception(
+                    ProtocolExceptionKind::InvalidData,
+                    format!("invalid JSON number {}", String::from_utf8_lossy(s)),
+                )
+            }),
+    }
+}
+
+#[inline]
+fn is_json_numeric(b: u8) -> bool {
+    matches!(b, b'+' | b'-' | b'.' | b'0'..=b'9' | b'E' | b'e')
+}
+
+#[inline]
+fn narrow<T: TryFrom<i64>>(i: i64) -> Result<T, ThriftException> {
+    T::try_from(i).map_err(|_| {
+        new_protocol_exception(
+            ProtocolExceptionKind::InvalidData,
+            format!("integer {i} out of range"),
+        )
+    })
+}
+
+#[inline]
+fn container_size(size: i64) -> Result<usize, ThriftException> {
+    if size < 0 {
+        return Err(new_protocol_exception(
+            ProtocolExceptionKind::NegativeSize,
+            format!("negative container size {size}"),
+        ));
+    }
+    narrow(size)
+}
+
+#[inline]
+fn unexpected_char(got: u8, expected: u8) -> ThriftException {
+    new_protocol_exception(
+        ProtocolExceptionKind::InvalidData,
+        format!(
+            "unexpected character {:?}, expected {:?}",
+            got as char, expected as char
+        ),
+    )
+}
+
+fn utf8_string(v: Vec<u8>) -> Result<String, ThriftException> {
+    Ok(String::from_utf8(v)?)
+}
+
+/// Unescapes the content of a JSON string (without the surrounding quotes).
+fn unescape(s: &[u8]) -> Result<Vec<u8>, ThriftException> {
+    let invalid =
+        |msg: &'static str| new_protocol_exception(ProtocolExceptionKind::InvalidData, msg);
+
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        let b = s[i];
+        i += 1;
+        if b != BACKSLASH {
+            out.push(b);
+            continue;
+        }
+        let Some(&e) = s.get(i) else {
+            return Err(invalid("unterminated JSON escape sequence"));
+        };
+        i += 1;
+        match e {
+            b'"' | b'\\' | b'/' => out.push(e),
+            b'b' => out.push(b'\x08'),
+            b'f' => out.push(b'\x0c'),
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b't' => out.push(b'\t'),
+            b'u' => {
+                let read_unit = |i: usize| -> Result<u16, ThriftException> {
+                    let hex = s
+                        .get(i..i + 4)
+                        .ok_or_else(|| invalid("truncated JSON unicode escape"))?;
+                    hex.iter()
+                        .try_fold(0u16, |acc, h| Ok((acc << 4) | hex_val(*h)? as u16))
+                };
+                let unit = read_unit(i)?;
+                i += 4;
+                let c = if (0xd800..0xdc00).contains(&unit) {
+                    if s.get(i..i + 2) != Some(b"\\u") {
+                        return Err(invalid("expected low surrogate in JSON string"));
+                    }
+                    let low = read_unit(i + 2)?;
+                    i += 6;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(invalid("invalid low surrogate in JSON string"));
+                    }
+                    let code = 0x10000 + (((unit as u32) - 0xd800) << 10) + ((low as u32) - 0xdc00);
+                    char::from_u32(code)
+                } else {
+                    char::from_u32(unit as u32)
+                };
+                let c = c.ok_or_else(|| invalid("invalid unicode escape in JSON string"))?;
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::InvalidData,
+                    format!("invalid JSON escape character {:?}", e as char),
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Writes JSON tokens into a [`BytesMut`], inserting separators according to
+/// the current context.
+pub(super) struct JsonWriter<'a> {
+    pub(super) context: &'a mut JsonContextStack,
+    pub(super) buf: &'a mut BytesMut,
+}
+
+impl JsonWriter<'_> {
+    #[inline]
+    pub(super) fn write_separator(&mut self) {
+        if let Some(sep) = self.context.separator() {
+            self.buf.extend_from_slice(&[sep]);
+        }
+    }
+
+    pub(super) fn write_string(&mut self, s: &[u8]) {
+        self.write_separator();
+        self.buf.reserve(s.len() + 2);
+        self.buf.extend_from_slice(&[QUOTE]);
+        let mut start = 0;
+        for (i, b) in s.iter().enumerate() {
+            if let Some(escape) = escape_of(*b) {
+                self.buf.extend_from_slice(&s[start..i]);
+                self.buf.extend_from_slice(escape);
+                if escape.len() == 4 {
+                    self.buf.extend_from_slice(hex_lower(&[*b]).as_bytes());
+                }
+                start = i + 1;
+            }
+        }
+        self.buf.extend_from_slice(&s[start..]);
+        self.buf.extend_from_slice(&[QUOTE]);
+    }
+
+    #[inline]
+    pub(super) fn write_base64(&mut self, b: &[u8]) {
+        self.write_separator();
+        self.buf.extend_from_slice(&[QUOTE]);
+        self.buf
+            .extend_from_slice(STANDARD_NO_PAD.encode(b).as_bytes());
+        self.buf.extend_from_slice(&[QUOTE]);
+    }
+
+    #[inline]
+    pub(super) fn write_integer(&mut self, i: i64) {
+        self.write_separator();
+        let escape = self.context.escape_num();
+        if escape {
+            self.buf.extend_from_slice(&[QUOTE]);
+        }
+        self.buf.extend_from_slice(i.to_string().as_bytes());
+        if escape {
+            self.buf.extend_from_slice(&[QUOTE]);
+        }
+    }
+
+    #[inline]
+    pub(super) fn write_double(&mut self, d: f64) {
+        self.write_separator();
+        match special_double(d) {
+            Some(s) => {
+                self.buf.extend_from_slice(&[QUOTE]);
+                self.buf.extend_from_slice(s.as_bytes());
+                self.buf.extend_from_slice(&[QUOTE]);
+            }
+            None => {
+                let escape = self.context.escape_num();
+                if escape {
+                    self.buf.extend_from_slice(&[QUOTE]);
+                }
+                self.buf
+                    .extend_from_slice(DoubleRepr(d).to_string().as_bytes());
+                if escape {
+                    self.buf.extend_from_slice(&[QUOTE]);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn write_object_begin(&mut self) {
+        self.write_separator();
+        self.buf.extend_from_slice(&[LBRACE]);
+        self.context.push_pair();
+    }
+
+    #[inline]
+    pub(super) fn write_object_end(&mut self) {
+        self.context.pop();
+        self.buf.extend_from_slice(&[RBRACE]);
+    }
+
+    #[inline]
+    pub(super) fn write_array_begin(&mut self) {
+        self.write_separator();
+        self.buf.extend_from_slice(&[LBRACKET]);
+        self.context.push_list();
+    }
+
+    #[inline]
+    pub(super) fn write_array_end(&mut self) {
+        self.context.pop();
+        self.buf.extend_from_slice(&[RBRACKET]);
+    }
+
+    #[inline]
+    fn write_type_name(&mut self, ttype: TType) -> Result<(), ThriftException> {
+        self.write_string(type_name(ttype)?.as_bytes());
+        Ok(())
+    }
+}
+
+macro_rules! json_length_impl {
+    ($name:ident) => {
+        impl<T> TLengthProtocol for $name<T> {
+            #[inline]
+            fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
+                let msg_type: u8 = identifier.message_type.into();
+                self.len_context.array_begin_len()
+                    + self.len_context.integer_len(VERSION)
+                    + self.len_context.string_len(identifier.name.as_bytes())
+                    + self.len_context.integer_len(msg_type as i64)
+                    + self
+                        .len_context
+                        .integer_len(identifier.sequence_number as i64)
+            }
+
+            #[inline]
+            fn message_end_len(&mut self) -> usize {
+                self.len_context.end_len()
+            }
+
+            #[inline]
+            fn struct_begin_len(&mut self, _identifier: &TStructIdentifier) -> usize {
+                self.len_context.object_begin_len()
+            }
+
+            #[inline]
+            fn struct_end_len(&mut self) -> usize {
+                self.len_context.end_len()
+            }
+
+            #[inline]
+            fn field_begin_len(&mut self, field_type: TType, id: Option<i16>) -> usize {
+                self.len_context.integer_len(id.unwrap_or_default() as i64)
+                    + self.len_context.object_begin_len()
+                    + self.len_context.type_name_len(field_type)
+            }
+
+            #[inline]
+            fn field_end_len(&mut self) -> usize {
+                self.len_context.end_len()
+            }
+
+            #[inline]
+            fn field_stop_len(&mut self) -> usize {
+                0
+            }
+
+            #[inline]
+            fn bool_len(&mut self, b: bool) -> usize {
+                self.len_context.integer_len(b as i64)
+            }
+
+            #[inline]
+            fn bytes_len(&mut self, b: &[u8]) -> usize {
+                self.len_context.base64_len(b)
+            }
+
+            #[inline]
+            fn bytes_vec_len(&mut self, b: &[u8]) -> usize {
+                self.len_context.base64_len(b)
+            }
+
+            #[inline]
+            fn byte_len(&mut self, b: u8) -> usize {
+                self.len_context.integer_len(b as i8 as i64)
+            }
+
+            #[inline]
+            fn uuid_len(&mut self, _u: [u8; 16]) -> usize {
+                self.len_context.separator_len() + 2 + 36
+            }
+
+            #[inline]
+            fn i8_len(&mut self, i: i8) -> usize {
+                self.len_context.integer_len(i as i64)
+            }
+
+            #[inline]
+            fn i16_len(&mut self, i: i16) -> usize {
+                self.len_context.integer_len(i as i64)
+            }
+
+            #[inline]
+            fn i32_len(&mut self, i: i32) -> usize {
+                self.len_context.integer_len(i as i64)
+            }
+
+            #[inline]
+            fn i64_len(&mut self, i: i64) -> usize {
+                self.len_context.integer_len(i)
+            }
+
+            #[inline]
+            fn double_len(&mut self, d: f64) -> usize {
+                self.len_context.double_len(d)
+            }
+
+            #[inline]
+            fn string_len(&mut self, s: &str) -> usize {
+                self.len_context.string_len(s.as_bytes())
+            }
+
+            #[inline]
+            fn faststr_len(&mut self, s: &FastStr) -> usize {
+                self.len_context.string_len(s.as_bytes())
+            }
+
+            #[inline]
+            fn list_begin_len(&mut self, identifier: TListIdentifier) -> usize {
+                self.len_context.array_begin_len()
+                    + self.len_context.type_name_len(identifier.element_type)
+                    + self.len_context.integer_len(identifier.size as i64)
+            }
+
+            #[inline]
+            fn list_end_len(&mut self) -> usize {
+                self.len_context.end_len()
+            }
+
+            #[inline]
+            fn set_begin_len(&mut self, identifier: TSetIdentifier) -> usize {
+                self.len_context.array_begin_len()
+                    + self.len_context.type_name_len(identifier.element_type)
+                    + self.len_context.integer_len(identifier.size as i64)
+            }
+
+            #[inline]
+            fn set_end_len(&mut self) -> usize {
+                self.len_context.end_len()
+            }
+
+            #[inline]
+            fn map_begin_len(&mut self, identifier: TMapIdentifier) -> usize {
+                self.len_context.array_begin_len()
+                    + self.len_context.type_name_len(identifier.key_type)
+                    + self.len_context.type_name_len(identifier.value_type)
+                    + self.len_context.integer_len(identifier.size as i64)
+                    + self.len_context.object_begin_len()
+            }
+
+            #[inline]
+            fn map_end_len(&mut self) -> usize {
+                self.len_context.end_len() + self.len_context.end_len()
+            }
+        }
+    };
+}
+
+pub struct TJSONOutputProtocol<T> {
+    pub(crate) trans: T,
+
+    context: JsonContextStack,
+    // Lengths are calculated in the same order as values are written, so they
+    // need their own context to know where separators go.
+    len_context: JsonContextStack,
+}
+
+impl<T> TJSONOutputProtocol<T> {
+    #[inline]
+    pub fn new(trans: T) -> Self {
+        Self {
+            trans,
+            context: JsonContextStack::default(),
+            len_context: JsonContextStack::default(),
+        }
+    }
+}
+
+json_length_impl!(TJSONOutputProtocol);
+
+impl TJSONOutputProtocol<&mut BytesMut> {
+    #[inline]
+    fn writer(&mut self) -> JsonWriter<'_> {
+        JsonWriter {
+            context: &mut self.context,
+            buf: self.trans,
+        }
+    }
+}
+
+impl TJSONOutputProtocol<&mut LinkedBytes> {
+    #[inline]
+    fn writer(&mut self) -> JsonWriter<'_> {
+        JsonWriter {
+            context: &mut self.context,
+            buf: self.trans.bytes_mut(),
+        }
+    }
+}
+
+macro_rules! json_output_impl {
+    ($buf:ty) => {
+        impl TOutputProtocol for TJSONOutputProtocol<&mut $buf> {
+            type BufMut = $buf;
+
+            #[inline]
+            fn write_message_begin(
+                &mut self,
+                identifier: &TMessageIdentifier,
+            ) -> Result<(), ThriftException> {
+                let msg_type: u8 = identifier.message_type.into();
+                let mut w = self.writer();
+                w.write_array_begin();
+                w.write_integer(VERSION);
+                w.write_string(identifier.name.as_bytes());
+                w.write_integer(msg_type as i64);
+                w.write_integer(identifier.sequence_number as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_message_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_struct_begin(&mut self, _: &TStructIdentifier) -> Result<(), ThriftException> {
+                self.writer().write_object_begin();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_struct_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_object_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_field_begin(
+                &mut self,
+                field_type: TType,
+                id: i16,
+            ) -> Result<(), ThriftException> {
+                let mut w = self.writer();
+                w.write_integer(id as i64);
+                w.write_object_begin();
+                w.write_type_name(field_type)
+            }
+
+            #[inline]
+            fn write_field_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_object_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_field_stop(&mut self) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bool(&mut self, b: bool) -> Result<(), ThriftException> {
+                self.writer().write_integer(b as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bytes(&mut self, b: Bytes) -> Result<(), ThriftException> {
+                self.writer().write_base64(&b);
+                Ok(())
+            }
+
+            /// Unknown fields are kept in their original binary encoding, which
+            /// has no JSON form, so they are left out.
+            #[inline]
+            fn write_bytes_without_len(&mut self, _b: Bytes) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn write_uuid(&mut self, u: [u8; 16]) -> Result<(), ThriftException> {
+                self.writer().write_string(uuid_to_string(u).as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bytes_vec(&mut self, b: &[u8]) -> Result<(), ThriftException> {
+                self.writer().write_base64(b);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_byte(&mut self, b: u8) -> Result<(), ThriftException> {
+                self.writer().write_integer(b as i8 as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i8(&mut self, i: i8) -> Result<(), ThriftException> {
+                self.writer().write_integer(i as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i16(&mut self, i: i16) -> Result<(), ThriftException> {
+                self.writer().write_integer(i as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i32(&mut self, i: i32) -> Result<(), ThriftException> {
+                self.writer().write_integer(i as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i64(&mut self, i: i64) -> Result<(), ThriftException> {
+                self.writer().write_integer(i);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_double(&mut self, d: f64) -> Result<(), ThriftException> {
+                self.writer().write_double(d);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_string(&mut self, s: &str) -> Result<(), ThriftException> {
+                self.writer().write_string(s.as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_faststr(&mut self, s: FastStr) -> Result<(), ThriftException> {
+                self.writer().write_string(s.as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_list_begin(
+                &mut self,
+                identifier: TListIdentifier,
+            ) -> Result<(), ThriftException> {
+                let mut w = self.writer();
+                w.write_array_begin();
+                w.write_type_name(identifier.element_type)?;
+                w.write_integer(identifier.size as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_list_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_set_begin(
+                &mut self,
+                identifier: TSetIdentifier,
+            ) -> Result<(), ThriftException> {
+                let mut w = self.writer();
+                w.write_array_begin();
+                w.write_type_name(identifier.element_type)?;
+                w.write_integer(identifier.size as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_set_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_map_begin(
+                &mut self,
+                identifier: TMapIdentifier,
+            ) -> Result<(), ThriftException> {
+                let mut w = self.writer();
+                w.write_array_begin();
+                w.write_type_name(identifier.key_type)?;
+                w.write_type_name(identifier.value_type)?;
+                w.write_integer(identifier.size as i64);
+                w.write_object_begin();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_map_end(&mut self) -> Result<(), ThriftException> {
+                let mut w = self.writer();
+                w.write_object_end();
+                w.write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn flush(&mut self) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn buf_mut(&mut self) -> &mut Self::BufMut {
+                self.trans
+            }
+        }
+    };
+}
+
+json_output_impl!(BytesMut);
+json_output_impl!(LinkedBytes);
+
+pub struct TJSONInputProtocol<T> {
+    pub(crate) trans: T,
+
+    context: JsonContextStack,
+    len_context: JsonContextStack,
+    limiter: Limiter,
+}
+
+impl<T> TJSONInputProtocol<T> {
+    #[inline]
+    pub fn new(trans: T) -> Self {
+        Self {
+            trans,
+            context: JsonContextStack::default(),
+            len_context: JsonContextStack::default(),
+            limiter: Limiter::default(),
+        }
+    }
+
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self
+    }
+}
+
+json_length_impl!(TJSONInputProtocol);
+
+impl TJSONInputProtocol<&mut Bytes> {
+    #[inline]
+    fn skip_whitespace(&mut self) {
+        let n = self
+            .trans
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count();
+        self.trans.advance(n);
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<u8, ThriftException> {
+        self.skip_whitespace();
+        match self.trans.first() {
+            Some(b) => Ok(*b),
+            None => Err(IOError::NoRemaining("json".to_string()).into()),
+        }
+    }
+
+    #[inline]
+    fn read_syntax_char(&mut self, expected: u8) -> Result<(), ThriftException> {
+        let got = self.peek()?;
+        if got != expected {
+            return Err(unexpected_char(got, expected));
+        }
+        self.trans.advance(1);
+        Ok(())
+    }
+
+    #[inline]
+    fn read_separator(&mut self) -> Result<(), ThriftException> {
+        match self.context.separator() {
+            Some(sep) => self.read_syntax_char(sep),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads a JSON string and returns its content still escaped.
+    fn read_raw_string(&mut self, skip_context: bool) -> Result<(Bytes, bool), ThriftException> {
+        if !skip_context {
+            self.read_separator()?;
+        }
+        self.read_syntax_char(QUOTE)?;
+        let mut escaped = false;
+        let mut i = 0;
+        loop {
+            match self.trans.get(i) {
+                Some(&QUOTE) => break,
+                Some(&BACKSLASH) => {
+                    escaped = true;
+                    i += 2;
+                }
+                Some(_) => i += 1,
+                None => return Err(IOError::NoRemaining("json string".to_string()).into()),
+            }
+        }
+        let s = self.trans.split_to(i);
+        self.trans.advance(1);
+        Ok((s, escaped))
+    }
+
+    #[inline]
+    fn read_string_bytes(&mut self, skip_context: bool) -> Result<Bytes, ThriftException> {
+        let (s, escaped) = self.read_raw_string(skip_context)?;
+        if escaped {
+            Ok(unescape(&s)?.into())
+        } else {
+            Ok(s)
+        }
+    }
+
+    #[inline]
+    fn read_numeric_chars(&mut self) -> Result<Bytes, ThriftException> {
+        self.skip_whitespace();
+        let n = self
+            .trans
+            .iter()
+            .take_while(|b| is_json_numeric(**b))
+            .count();
+        Ok(self.trans.split_to(n))
+    }
+
+    fn read_integer(&mut self) -> Result<i64, ThriftException> {
+        self.read_separator()?;
+        let escape = self.context.escape_num();
+        if escape {
+            self.read_syntax_char(QUOTE)?;
+        }
+        let s = self.read_numeric_chars()?;
+        if escape {
+            self.read_syntax_char(QUOTE)?;
+        }
+        parse_integer(&s)
+    }
+
+    fn read_json_double(&mut self) -> Result<f64, ThriftException> {
+        self.read_separator()?;
+        if self.peek()? == QUOTE {
+            let s = self.read_string_bytes(true)?;
+            let d = parse_double(&s)?;
+            if !self.context.escape_num() && !d.is_nan() && !d.is_infinite() {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::InvalidData,
+                    "numeric data unexpectedly quoted",
+                ));
+            }
+            return Ok(d);
+        }
+        if self.context.escape_num() {
+            // keys must be quoted
+            self.read_syntax_char(QUOTE)?;
+        }
+        let s = self.read_numeric_chars()?;
+        parse_double(&s)
+    }
+
+    #[inline]
+    fn read_object_begin(&mut self) -> Result<(), ThriftException> {
+        self.read_separator()?;
+        self.read_syntax_char(LBRACE)?;
+        self.context.push_pair();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_object_end(&mut self) -> Result<(), ThriftException> {
+        self.read_syntax_char(RBRACE)?;
+        self.context.pop();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_array_begin(&mut self) -> Result<(), ThriftException> {
+        self.read_separator()?;
+        self.read_syntax_char(LBRACKET)?;
+        self.context.push_list();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_array_end(&mut self) -> Result<(), ThriftException> {
+        self.read_syntax_char(RBRACKET)?;
+        self.context.pop();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_type_name(&mut self) -> Result<TType, ThriftException> {
+        let name = self.read_string_bytes(false)?;
+        type_from_name(&name)
+    }
+
+    fn skip_value(&mut self, field_type: TType, depth: i8) -> Result<(), ThriftException> {
+        if depth == 0 {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::DepthLimit,
+                format!("cannot parse past {field_type:?}"),
+            ));
+        }
+
+        match field_type {
+            TType::Bool | TType::I8 | TType::I16 | TType::I32 | TType::I64 => {
+                self.read_integer().map(|_| ())
+            }
+            TType::Double => self.read_json_double().map(|_| ()),
+            TType::Binary | TType::Uuid => self.read_raw_string(false).map(|_| ()),
+            TType::Struct => {
+                self.read_struct_begin()?;
+                loop {
+                    let field_ident = self.read_field_begin()?;
+                    if field_ident.field_type == TType::Stop {
+                        break;
+                    }
+                    self.skip_value(field_ident.field_type, depth - 1)?;
+                    self.read_field_end()?;
+                }
+                self.read_struct_end()
+            }
+            TType::List => {
+                let list_ident = self.read_list_begin()?;
+                for _ in 0..list_ident.size {
+                    self.skip_value(list_ident.element_type, depth - 1)?;
+                }
+                self.read_list_end()
+            }
+            TType::Set => {
+                let set_ident = self.read_set_begin()?;
+                for _ in 0..set_ident.size {
+                    self.skip_value(set_ident.element_type, depth - 1)?;
+                }
+                self.read_set_end()
+            }
+            TType::Map => {
+                let map_ident = self.read_map_begin()?;
+                for _ in 0..map_ident.size {
+                    self.skip_value(map_ident.key_type, depth - 1)?;
+                    self.skip_value(map_ident.value_type, depth - 1)?;
+                }
+                self.read_map_end()
+            }
+            u => Err(new_protocol_exception(
+                ProtocolExceptionKind::DepthLimit,
+                format!("cannot skip field type {:?}", &u),
+            )),
+        }
+    }
+}
+
+impl TInputProtocol for TJSONInputProtocol<&mut Bytes> {
+    type Buf = Bytes;
+
+    fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.limiter.check_message_len(self.trans.len())?;
+        self.read_array_begin()?;
+        if self.read_integer()? != VERSION {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::BadVersion,
+                "Message contained bad version",
+            ));
+        }
+        let name = self.read_faststr()?;
+        let type_u8: u8 = narrow(self.read_integer()?)?;
+        let message_type = TMessageType::try_from(type_u8).map_err(|_| {
+            new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("invalid message type {type_u8}"),
+            )
+        })?;
+        let sequence_number = narrow(self.read_integer()?)?;
+        Ok(TMessageIdentifier::new(name, message_type, sequence_number))
+    }
+
+    #[inline]
+    fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
+        self.read_array_end()
+    }
+
+    #[inline]
+    fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.limiter.check_message_len(self.trans.len())?;
+        }
+        self.limiter.enter_struct()?;
+        self.read_object_begin()?;
+        Ok(None)
+    }
+
+    #[inline]
+    fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
+        self.read_object_end()
+    }
+
+    #[inline]
+    fn read_field_begin(&mut self) -> Result<TFieldIdentifier, ThriftException> {
+        if self.peek()? == RBRACE {
+            return Ok(TFieldIdentifier::new::<Option<&'static str>, i16>(
+                None,
+                TType::Stop,
+                0,
+            ));
+        }
+        let id: i16 = narrow(self.read_integer()?)?;
+        self.read_object_begin()?;
+        let field_type = self.read_type_name()?;
+        Ok(TFieldIdentifier::new::<Option<&'static str>, i16>(
+            None, field_type, id,
+        ))
+    }
+
+    #[inline]
+    fn read_field_end(&mut self) -> Result<(), ThriftException> {
+        self.read_object_end()
+    }
+
+    #[inline]
+    fn read_bool(&mut self) -> Result<bool, ThriftException> {
+        Ok(self.read_integer()? != 0)
+    }
+
+    #[inline]
+    fn read_bytes(&mut self) -> Result<Bytes, ThriftException> {
+        let s = self.read_string_bytes(false)?;
+        let b = decode_base64(&s)?;
+        self.limiter.check_bytes_len(b.len())?;
+        Ok(b.into())
+    }
+
+    #[inline]
+    fn read_uuid(&mut self) -> Result<[u8; 16], ThriftException> {
+        let s = self.read_string_bytes(false)?;
+        uuid_from_str(&s)
+    }
+
+    #[inline]
+    fn read_i8(&mut self) -> Result<i8, ThriftException> {
+        narrow(self.read_integer()?)
+    }
+
+    #[inline]
+    fn read_i16(&mut self) -> Result<i16, ThriftException> {
+        narrow(self.read_integer()?)
+    }
+
+    #[inline]
+    fn read_i32(&mut self) -> Result<i32, ThriftException> {
+        narrow(self.read_integer()?)
+    }
+
+    #[inline]
+    fn read_i64(&mut self) -> Result<i64, ThriftException> {
+        self.read_integer()
+    }
+
+    #[inline]
+    fn read_double(&mut self) -> Result<f64, ThriftException> {
+        self.read_json_double()
+    }
+
+    #[inline]
+    fn read_string(&mut self) -> Result<String, ThriftException> {
+        let s = self.read_string_bytes(false)?;
+        self.limiter.check_bytes_len(s.len())?;
+        utf8_string(s.into())
+    }
+
+    #[inline]
+    fn read_faststr(&mut self) -> Result<FastStr, ThriftException> {
+        let s = self.read_string_bytes(false)?;
+        self.limiter.check_bytes_len(s.len())?;
+        if let Err(e) = str::from_utf8(&s) {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("{e:?}"),
+            ));
+        }
+        unsafe { Ok(FastStr::from_bytes_unchecked(s)) }
+    }
+
+    #[inline]
+    fn read_list_begin(&mut self) -> Result<TListIdentifier, ThriftException> {
+        self.read_array_begin()?;
+        let element_type = self.read_type_name()?;
+        let size = container_size(self.read_integer()?)?;
+        self.limiter.check_container_len(size)?;
+        Ok(TListIdentifier::new(element_type, size))
+    }
+
+    #[inline]
+    fn read_list_end(&mut self) -> Result<(), ThriftException> {
+        self.read_array_end()
+    }
+
+    #[inline]
+    fn read_set_begin(&mut self) -> Result<TSetIdentifier, ThriftException> {
+        self.read_array_begin()?;
+        let element_type = self.read_type_name()?;
+        let size = container_size(self.read_integer()?)?;
+        self.limiter.check_container_len(size)?;
+        Ok(TSetIdentifier::new(element_type, size))
+    }
+
+    #[inline]
+    fn read_set_end(&mut self) -> Result<(), ThriftException> {
+        self.read_array_end()
+    }
+
+    #[inline]
+    fn read_map_begin(&mut self) -> Result<TMapIdentifier, ThriftException> {
+        self.read_array_begin()?;
+        let key_type = self.read_type_name()?;
+        let value_type = self.read_type_name()?;
+        let size = container_size(self.read_integer()?)?;
+        self.limiter.check_container_len(size)?;
+        self.read_object_begin()?;
+        Ok(TMapIdentifier::new(key_type, value_type, size))
+    }
+
+    #[inline]
+    fn read_map_end(&mut self) -> Result<(), ThriftException> {
+        self.read_object_end()?;
+        self.read_array_end()
+    }
+
+    #[inline]
+    fn skip(&mut self, field_type: TType) -> Result<usize, ThriftException> {
+        self.skip_till_depth(field_type, MAXIMUM_SKIP_DEPTH)
+    }
+
+    fn skip_till_depth(&mut self, field_type: TType, depth: i8) -> Result<usize, ThriftException> {
+        let remaining = self.trans.remaining();
+        self.skip_value(field_type, depth)?;
+        Ok(remaining - self.trans.remaining())
+    }
+
+    #[inline]
+    fn read_byte(&mut self) -> Result<u8, ThriftException> {
+        Ok(self.read_i8()? as u8)
+    }
+
+    #[inline]
+    fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
+        let s = self.read_string_bytes(false)?;
+        let b = decode_base64(&s)?;
+        self.limiter.check_bytes_len(b.len())?;
+        Ok(b)
+    }
+
+    #[inline]
+    fn get_bytes(&mut self, ptr: Option<*const u8>, len: usize) -> Result<Bytes, ThriftException> {
+        if let Some(ptr) = ptr {
+            Ok(Bytes::copy_from_slice(unsafe {
+                std::slice::from_raw_parts(ptr, len)
+            }))
+        } else {
+            Ok(self.trans.split_to(len))
+        }
+    }
+
+    #[inline]
+    fn buf(&mut self) -> &mut Self::Buf {
+        self.trans
+    }
+}
+
+pub struct TAsyncJSONProtocol<R> {
+    reader: CountingReader<R>,
+
+    // JSON needs one byte of lookahead to detect the end of a struct.
+    peeked: Option<u8>,
+    context: JsonContextStack,
+    limiter: Limiter,
+}
+
+impl<R> TAsyncJSONProtocol<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: CountingReader::new(reader),
+            peeked: None,
+            context: JsonContextStack::default(),
+            limiter: Limiter::default(),
+        }
+    }
+
+    /// Sets the limits that the protocol checks while decoding.
+    #[inline]
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limiter.limits = limits;
+        self.reader.set_limit(limits.max_message_len);
+        self
+    }
+
+    #[inline]
+    async fn next_byte(&mut self) -> Result<u8, ThriftException> {
+        match self.peeked.take() {
+            Some(b) => Ok(b),
+            None => Ok(self.reader.read_u8().await?),
+        }
+    }
+
+    #[inline]
+    async fn peek_byte(&mut self) -> Result<u8, ThriftException> {
+        let b = self.next_byte().await?;
+        self.peeked = Some(b);
+        Ok(b)
+    }
+
+    #[inline]
+    async fn peek(&mut self) -> Result<u8, ThriftException> {
+        loop {
+            let b = self.peek_byte().await?;
+            if !b.is_ascii_whitespace() {
+                return Ok(b);
+            }
+            self.peeked = None;
+        }
+    }
+
+    #[inline]
+    async fn read_syntax_char(&mut self, expected: u8) -> Result<(), ThriftException> {
+        let got = self.peek().await?;
+        if got != expected {
+            return Err(unexpected_char(got, expected));
+        }
+        self.peeked = None;
+        Ok(())
+    }
+
+    #[inline]
+    async fn read_separator(&mut self) -> Result<(), ThriftException> {
+        match self.context.separator() {
+            Some(sep) => self.read_syntax_char(sep).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn read_string_bytes(&mut self, skip_context: bool) -> Result<Vec<u8>, ThriftException> {
+        if !skip_context {
+            self.read_separator().await?;
+        }
+        self.read_syntax_char(QUOTE).await?;
+        let mut s = Vec::new();
+        let mut escaped = false;
+        loop {
+            // the length of a string is only known at its end
+            self.limiter.check_message_len(self.reader.count())?;
+            match self.next_byte().await? {
+                QUOTE => break,
+                BACKSLASH => {
+                    escaped = true;
+                    s.push(BACKSLASH);
+                    s.push(self.next_byte().await?);
+                }
+                b => s.push(b),
+            }
+        }
+        if escaped { unescape(&s) } else { Ok(s) }
+    }
+
+    async fn read_numeric_chars(&mut self) -> Result<Vec<u8>, ThriftException> {
+        self.peek().await?;
+        let mut s = Vec::new();
+        loop {
+            // the number may be the last token of the stream
+            let b = match self.peek_byte().await {
+                Ok(b) => b,
+                Err(_) if !s.is_empty() => break,
+                Err(e) => return Err(e),
+            };
+            if !is_json_numeric(b) {
+                break;
+            }
+            self.peeked = None;
+            s.push(b);
+        }
+        Ok(s)
+    }
+
+    async fn read_integer(&mut self) -> Result<i64, ThriftException> {
+        self.read_separator().await?;
+        let escape = self.context.escape_num();
+        if escape {
+            self.read_syntax_char(QUOTE).await?;
+        }
+        let s = self.read_numeric_chars().await?;
+        if escape {
+            self.read_syntax_char(QUOTE).await?;
+        }
+        parse_integer(&s)
+    }
+
+    async fn read_json_double(&mut self) -> Result<f64, ThriftException> {
+        self.read_separator().await?;
+        if self.peek().await? == QUOTE {
+            let s = self.read_string_bytes(true).await?;
+            let d = parse_double(&s)?;
+            if !self.context.escape_num() && !d.is_nan() && !d.is_infinite() {
+                return Err(new_protocol_exception(
+                    ProtocolExceptionKind::InvalidData,
+                    "numeric data unexpectedly quoted",
+                ));
+            }
+            return Ok(d);
+        }
+        if self.context.escape_num() {
+            self.read_syntax_char(QUOTE).await?;
+        }
+        let s = self.read_numeric_chars().await?;
+        parse_double(&s)
+    }
+
+    #[inline]
+    async fn read_object_begin(&mut self) -> Result<(), ThriftException> {
+        self.read_separator().await?;
+        self.read_syntax_char(LBRACE).await?;
+        self.context.push_pair();
+        Ok(())
+    }
+
+    #[inline]
+    async fn read_object_end(&mut self) -> Result<(), ThriftException> {
+        self.read_syntax_char(RBRACE).await?;
+        self.context.pop();
+        Ok(())
+    }
+
+    #[inline]
+    async fn read_array_begin(&mut self) -> Result<(), ThriftException> {
+        self.read_separator().await?;
+        self.read_syntax_char(LBRACKET).await?;
+        self.context.push_list();
+        Ok(())
+    }
+
+    #[inline]
+    async fn read_array_end(&mut self) -> Result<(), ThriftException> {
+        self.read_syntax_char(RBRACKET).await?;
+        self.context.pop();
+        Ok(())
+    }
+
+    #[inline]
+    async fn read_type_name(&mut self) -> Result<TType, ThriftException> {
+        let name = self.read_string_bytes(false).await?;
+        type_from_name(&name)
+    }
+}
+
+impl<R> TAsyncInputProtocol for TAsyncJSONProtocol<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    async fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        self.limiter.begin_message();
+        self.reader.reset();
+        self.read_array_begin().await?;
+        if self.read_integer().await? != VERSION {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::BadVersion,
+                "Message contained bad version",
+            ));
+        }
+        let name = self.read_faststr().await?;
+        let type_u8: u8 = narrow(self.read_integer().await?)?;
+        let message_type = TMessageType::try_from(type_u8).map_err(|_| {
+            new_protocol_exception(
+                ProtocolExceptionKind::InvalidData,
+                format!("invalid message type {type_u8}"),
+            )
+        })?;
+        let sequence_number = narrow(self.read_integer().await?)?;
+        Ok(TMessageIdentifier::new(name, message_type, sequence_number))
+    }
+
+    #[inline]
+    async fn read_message_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.end_message();
+        self.read_array_end().await
+    }
+
+    #[inline]
+    async fn read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException> {
+        if self.limiter.starts_value() {
+            self.reader.reset();
+        }
+        self.limiter.enter_struct()?;
+        self.read_object_begin().await?;
+        Ok(None)
+    }
+
+    #[inline]
+    async fn read_struct_end(&mut self) -> Result<(), ThriftException> {
+        self.limiter.exit_struct();
+        self.read_object_end().await
+    }
+
+    #[inline]
+    async fn read_field_begin(&mut self) -> Result<TFieldIdentifier, ThriftException> {
+        self.limiter.check_message_len(self.reader.count())?;
+        if self.peek().await? == RBRACE {
+            return Ok(TFieldIdentifier::new::<Option<&'static str>, i16>(
+                None,
+                TType::Stop,
+                0,
+            ));
+        }
+        let id: i16 = narrow(self.read_integer().await?)?;
+        self.read_object_begin().await?;
+        let field_type = self.read_type_name().await?;
+        Ok(TFieldIdentifier::new::<Option<&'static str>, i16>(
+            None, field_type, id,
+        ))
+    }
+
+    #[inline]
+    async fn read_field_end(&mut self) -> Result<(), ThriftException> {
+        self.read_object_end().await
+    }
+
+    #[inline]
+    async fn read_bool(&mut self) -> Result<bool, ThriftException> {
+        Ok(self.read_integer().await? != 0)
+    }
+
+    #[inline]
+    async fn read_bytes(&mut self) -> Result<Bytes, ThriftException> {
+        self.read_bytes_vec().await.map(Bytes::from)
+    }
+
+    #[inline]
+    async fn read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException> {
+        let s = self.read_string_bytes(false).await?;
+        let b = decode_base64(&s)?;
+        self.limiter.check_bytes_len(b.len())?;
+        Ok(b)
+    }
+
+    #[inline]
+    async fn read_uuid(&mut self) -> Result<[u8; 16], ThriftException> {
+        let s = self.read_string_bytes(false).await?;
+        uuid_from_str(&s)
+    }
+
+    #[inline]
+    async fn read_string(&mut self) -> Result<String, ThriftException> {
+        let s = self.read_string_bytes(false).await?;
+        self.limiter.check_bytes_len(s.len())?;
+        utf8_string(s)
+    }
+
+    #[inline]
+    async fn read_faststr(&mut self) -> Result<FastStr, ThriftException> {
+        self.read_string().await.map(FastStr::from_string)
+    }
+
+    #[inline]
+    async fn read_byte(&mut self) -> Result<u8, ThriftException> {
+        Ok(self.read_i8().await? as u8)
+    }
+
+    #[inline]
+    async fn read_i8(&mut self) -> Result<i8, ThriftException> {
+        narrow(self.read_integer().await?)
+    }
+
+    #[inline]
+    async fn read_i16(&mut self) -> Result<i16, ThriftException> {
+        narrow(self.read_integer().await?)
+    }
+
+    #[inline]
+    async fn read_i32(&mut self) -> Result<i32, ThriftException> {
+        narrow(self.read_integer().await?)
+    }
+
+    #[inline]
+    async fn read_i64(&mut self) -> Result<i64, ThriftException> {
+        self.read_integer().await
+    }
+
+    #[inline]
+    async fn read_double(&mut self) -> Result<f64, ThriftException> {
+        self.read_json_double().await
+    }
+
+    #[inline]
+    async fn read_list_begin(&mut self) -> Result<TListIdentifier, ThriftException> {
+        self.read_array_begin().await?;
+        let element_type = self.read_type_name().await?;
+        let size = container_size(self.read_integer().await?)?;
+        self.limiter.check_container_len(size)?;
+        Ok(TListIdentifier::new(element_type, size))
+    }
+
+    #[inline]
+    async fn read_list_end(&mut self) -> Result<(), ThriftException> {
+        self.read_array_end().await
+    }
+
+    #[inline]
+    async fn read_set_begin(&mut self) -> Result<TSetIdentifier, ThriftException> {
+        self.read_array_begin().await?;
+        let element_type = self.read_type_name().await?;
+        let size = container_size(self.read_integer().await?)?;
+        self.limiter.check_container_len(size)?;
+        Ok(TSetIdentifier::new(element_type, size))
+    }
+
+    #[inline]
+    async fn read_set_end(&mut self) -> Result<(), ThriftException> {
+        self.read_array_end().await
+    }
+
+    #[inline]
+    async fn read_map_begin(&mut self) -> Result<TMapIdentifier, ThriftException> {
+        self.read_array_begin().await?;
+        let key_type = self.read_type_name().await?;
+        let value_type = self.read_type_name().await?;
+        let size = container_size(self.read_integer().await?)?;
+        self.limiter.check_container_len(size)?;
+        self.read_object_begin().await?;
+        Ok(TMapIdentifier::new(key_type, value_type, size))
+    }
+
+    #[inline]
+    async fn read_map_end(&mut self) -> Result<(), ThriftException> {
+        self.read_object_end().await?;
+        self.read_array_end().await
+    }
+
+    #[async_recursion::async_recursion]
+    async fn skip_till_depth(
+        &mut self,
+        field_type: TType,
+        depth: i8,
+    ) -> Result<(), ThriftException> {
+        if depth == 0 {
+            return Err(new_protocol_exception(
+                ProtocolExceptionKind::DepthLimit,
+                format!("cannot parse past {field_type:?}"),
+            ));
+        }
+
+        match field_type {
+            TType::Bool | TType::I8 | TType::I16 | TType::I32 | TType::I64 => {
+                self.read_integer().await.map(|_| ())
+            }
+            TType::Double => self.read_json_double().await.map(|_| ()),
+            TType::Binary | TType::Uuid => self.read_string_bytes(false).await.map(|_| ()),
+            TType::Struct => {
+                self.read_struct_begin().await?;
+                loop {
+                    let field_ident = self.read_field_begin().await?;
+                    if field_ident.field_type == TType::Stop {
+                        break;
+                    }
+                    self.skip_till_depth(field_ident.field_type, depth - 1)
+                        .await?;
+                    self.read_field_end().await?;
+                }
+                self.read_struct_end().await
+            }
+            TType::List => {
+                let list_ident = self.read_list_begin().await?;
+                for _ in 0..list_ident.size {
+                    self.skip_till_depth(list_ident.element_type, depth - 1)
+                        .await?;
+                }
+                self.read_list_end().await
+            }
+            TType::Set => {
+                let set_ident = self.read_set_begin().await?;
+                for _ in 0..set_ident.size {
+                    self.skip_till_depth(set_ident.element_type, depth - 1)
+                        .await?;
+                }
+                self.read_set_end().await
+            }
+            TType::Map => {
+                let map_ident = self.read_map_begin().await?;
+                for _ in 0..map_ident.size {
+                    self.skip_till_depth(map_ident.key_type, depth - 1).await?;
+                    self.skip_till_depth(map_ident.value_type, depth - 1)
+                        .await?;
+                }
+                self.read_map_end().await
+            }
+            u => Err(new_protocol_exception(
+                ProtocolExceptionKind::DepthLimit,
+                format!("cannot skip field type {:?}", &u),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use linkedbytes::LinkedBytes;
+
+    use super::{TAsyncJSONProtocol, TJSONInputProtocol, TJSONOutputProtocol};
+    use crate::thrift::{
+        TAsyncInputProtocol, TInputProtocol, TLengthProtocol, TListIdentifier, TMapIdentifier,
+        TMessageIdentifier, TMessageType, TOutputProtocol, TSetIdentifier, TStructIdentifier,
+        TType,
+    };
+
+    fn write_sample<P: TOutputProtocol>(p: &mut P) {
+        p.write_message_begin(&TMessageIdentifier::new(
+            "ping".into(),
+            TMessageType::Call,
+            7,
+        ))
+        .unwrap();
+        p.write_struct_begin(&TStructIdentifier::new("Args"))
+            .unwrap();
+
+        p.write_field_begin(TType::Bool, 1).unwrap();
+        p.write_bool(true).unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::Binary, 2).unwrap();
+        p.write_string("a \"quoted\"\n\u{1}").unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::Binary, 3).unwrap();
+        p.write_bytes(Bytes::from_static(b"hello")).unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::Double, 4).unwrap();
+        p.write_double(f64::NAN).unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::Map, 5).unwrap();
+        p.write_map_begin(TMapIdentifier::new(TType::I32, TType::Double, 2))
+            .unwrap();
+        p.write_i32(1).unwrap();
+        p.write_double(1.5).unwrap();
+        p.write_i32(-2).unwrap();
+        p.write_double(-0.25).unwrap();
+        p.write_map_end().unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::List, 6).unwrap();
+        p.write_list_begin(TListIdentifier::new(TType::I64, 2))
+            .unwrap();
+        p.write_i64(i64::MAX).unwrap();
+        p.write_i64(i64::MIN).unwrap();
+        p.write_list_end().unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_begin(TType::Set, 7).unwrap();
+        p.write_set_begin(TSetIdentifier::new(TType::Struct, 1))
+            .unwrap();
+        p.write_struct_begin(&TStructIdentifier::new("Inner"))
+            .unwrap();
+        p.write_field_begin(TType::Uuid, 1).unwrap();
+        p.write_uuid([0xab; 16]).unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+        p.write_set_end().unwrap();
+        p.write_field_end().unwrap();
+
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+        p.write_message_end().unwrap();
+    }
+
+    const SAMPLE: &str = concat!(
+        r#"[1,"ping",1,7,{"1":{"tf":1},"2":{"str":"a \"quoted\"\n\u0001"},"#,
+        r#""3":{"str":"aGVsbG8"},"4":{"dbl":"NaN"},"#,
+        r#""5":{"map":["i32","dbl",2,{"1":1.5,"-2":-0.25}]},"#,
+        r#""6":{"lst":["i64",2,9223372036854775807,-9223372036854775808]},"#,
+        r#""7":{"set":["rec",1,{"1":{"uid":"abababab-abab-abab-abab-abababababab"}}]}}]"#
+    );
+
+    fn sample_len<P: TLengthProtocol>(p: &mut P) -> usize {
+        let mut len = p.message_begin_len(&TMessageIdentifier::new(
+            "ping".into(),
+            TMessageType::Call,
+            7,
+        ));
+        len += p.struct_begin_len(&TStructIdentifier::new("Args"));
+        len += p.field_begin_len(TType::Bool, Some(1)) + p.bool_len(true) + p.field_end_len();
+        len += p.field_begin_len(TType::Binary, Some(2))
+            + p.string_len("a \"quoted\"\n\u{1}")
+            + p.field_end_len();
+        len +=
+            p.field_begin_len(TType::Binary, Some(3)) + p.bytes_len(b"hello") + p.field_end_len();
+        len +=
+            p.field_begin_len(TType::Double, Some(4)) + p.double_len(f64::NAN) + p.field_end_len();
+        len += p.field_begin_len(TType::Map, Some(5))
+            + p.map_begin_len(TMapIdentifier::new(TType::I32, TType::Double, 2))
+            + p.i32_len(1)
+            + p.double_len(1.5)
+            + p.i32_len(-2)
+            + p.double_len(-0.25)
+            + p.map_end_len()
+            + p.field_end_len();
+        len += p.field_begin_len(TType::List, Some(6))
+            + p.list_begin_len(TListIdentifier::new(TType::I64, 2))
+            + p.i64_len(i64::MAX)
+            + p.i64_len(i64::MIN)
+            + p.list_end_len()
+            + p.field_end_len();
+        len += p.field_begin_len(TType::Set, Some(7))
+            + p.set_begin_len(TSetIdentifier::new(TType::Struct, 1))
+            + p.struct_begin_len(&TStructIdentifier::new("Inner"))
+            + p.field_begin_len(TType::Uuid, Some(1))
+            + p.uuid_len([0xab; 16])
+            + p.field_end_len()
+            + p.field_stop_len()
+            + p.struct_end_len()
+            + p.set_end_len()
+            + p.field_end_len();
+        len += p.field_stop_len() + p.struct_end_len() + p.message_end_len();
+        len
+    }
+
+    #[test]
+    fn test_write_matches_apache_encoding() {
+        let mut buf = BytesMut::new();
+        let mut p = TJSONOutputProtocol::new(&mut buf);
+        write_sample(&mut p);
+        assert_eq!(std::str::from_utf8(&buf).unwrap(), SAMPLE);
+
+        let mut linked = LinkedBytes::new();
+        let mut p = TJSONOutputProtocol::new(&mut linked);
+        write_sample(&mut p);
+        assert_eq!(&linked.concat()[..], SAMPLE.as_bytes());
+    }
+
+    #[test]
+    fn test_length_matches_written() {
+        let mut buf = BytesMut::new();
+        let mut p = TJSONOutputProtocol::new(&mut buf);
+        assert_eq!(sample_len(&mut p), SAMPLE.len());
+        // the length context is balanced and can be reused
+        assert_eq!(sample_len(&mut p), SAMPLE.len());
+    }
+
+    fn read_sample<P: TInputProtocol>(p: &mut P) {
+        let ident = p.read_message_begin().unwrap();
+        assert_eq!(ident.name, "ping");
+        assert_eq!(ident.message_type, TMessageType::Call);
+        assert_eq!(ident.sequence_number, 7);
+        p.read_struct_begin().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!((f.field_type, f.id), (TType::Bool, Some(1)));
+        assert!(p.read_bool().unwrap());
+        p.read_field_end().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!((f.field_type, f.id), (TType::Binary, Some(2)));
+        assert_eq!(p.read_faststr().unwrap(), "a \"quoted\"\n\u{1}");
+        p.read_field_end().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!((f.field_type, f.id), (TType::Binary, Some(3)));
+        assert_eq!(p.read_bytes().unwrap(), "hello");
+        p.read_field_end().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!(f.field_type, TType::Double);
+        assert!(p.read_double().unwrap().is_nan());
+        p.read_field_end().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!(f.field_type, TType::Map);
+        let m = p.read_map_begin().unwrap();
+        assert_eq!(m, TMapIdentifier::new(TType::I32, TType::Double, 2));
+        assert_eq!(p.read_i32().unwrap(), 1);
+        assert_eq!(p.read_double().unwrap(), 1.5);
+        assert_eq!(p.read_i32().unwrap(), -2);
+        assert_eq!(p.read_double().unwrap(), -0.25);
+        p.read_map_end().unwrap();
+        p.read_field_end().unwrap();
+
+        // skip the list and the set
+        let f = p.read_field_begin().unwrap();
+        assert_eq!(f.field_type, TType::List);
+        p.skip(f.field_type).unwrap();
+        p.read_field_end().unwrap();
+        let f = p.read_field_begin().unwrap();
+        assert_eq!(f.field_type, TType::Set);
+        p.skip(f.field_type).unwrap();
+        p.read_field_end().unwrap();
+
+        let f = p.read_field_begin().unwrap();
+        assert_eq!(f.field_type, TType::Stop);
+        p.read_struct_end().unwrap();
+        p.read_message_end().unwrap();
+    }
+
+    #[test]
+    fn test_read() {
+        let mut buf = Bytes::from_static(SAMPLE.as_bytes());
+        let mut p = TJSONInputProtocol::new(&mut buf);
+        read_sample(&mut p);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_read_with_whitespace_and_padding() {
+        let mut buf =
+            Bytes::from_static(br#" [ 1 , "ping" , 1 , 7 , { "3" : { "str" : "aGVsbG8=" } } ] "#);
+        let mut p = TJSONInputProtocol::new(&mut buf);
+        p.read_message_begin().unwrap();
+        p.read_struct_begin().unwrap();
+        let f = p.read_field_begin().unwrap();
+        assert_eq!((f.field_type, f.id), (TType::Binary, Some(3)));
+        assert_eq!(p.read_bytes_vec().unwrap(), b"hello");
+        p.read_field_end().unwrap();
+        assert_eq!(p.read_field_begin().unwrap().field_type, TType::Stop);
+        p.read_struct_end().unwrap();
+        p.read_message_end().unwrap();
+    }
+
+    #[test]
+    fn test_read_unicode_escape() {
+        let mut buf = Bytes::from_static(br#""\u00e9\ud83d\ude00\/""#);
+        let mut p = TJSONInputProtocol::new(&mut buf);
+        assert_eq!(p.read_string().unwrap(), "\u{e9}\u{1f600}/");
+    }
+
+    #[test]
+    fn test_bad_version() {
+        let mut buf = Bytes::from_static(br#"[2,"ping",1,7,{}]"#);
+        let mut p = TJSONInputProtocol::new(&mut buf);
+        assert!(p.read_message_begin().is_err());
+    }
+
+    #[test]
+    fn test_async_read() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut p = TAsyncJSONProtocol::new(SAMPLE.as_bytes());
+            let ident = p.read_message_begin().await.unwrap();
+            assert_eq!(ident.name, "ping");
+            p.read_struct_begin().await.unwrap();
+            let f = p.read_field_begin().await.unwrap();
+            assert_eq!((f.field_type, f.id), (TType::Bool, Some(1)));
+            assert!(p.read_bool().await.unwrap());
+            p.read_field_end().await.unwrap();
+            let f = p.read_field_begin().await.unwrap();
+            assert_eq!(f.field_type, TType::Binary);
+            assert_eq!(p.read_string().await.unwrap(), "a \"quoted\"\n\u{1}");
+            p.read_field_end().await.unwrap();
+            let f = p.read_field_begin().await.unwrap();
+            assert_eq!(f.field_type, TType::Binary);
+            assert_eq!(p.read_bytes().await.unwrap(), "hello");
+            p.read_field_end().await.unwrap();
+            let f = p.read_field_begin().await.unwrap();
+            assert!(p.read_double().await.unwrap().is_nan());
+            p.read_field_end().await.unwrap();
+            for _ in 0..3 {
+                let f2 = p.read_field_begin().await.unwrap();
+                assert!(f2.id > f.id);
+                p.skip(f2.field_type).await.unwrap();
+                p.read_field_end().await.unwrap();
+            }
+            assert_eq!(p.read_field_begin().await.unwrap().field_type, TType::Stop);
+            p.read_struct_end().await.unwrap();
+            p.read_message_end().await.unwrap();
+        });
+    }
+}
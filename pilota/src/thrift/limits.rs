@@ -0,0 +1,448 @@
+//! Limits on what the input protocols accept from a peer.
+
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{ProtocolExceptionKind, ThriftException, new_protocol_exception};
+
+/// Limits on the sizes that the input protocols accept while decoding.
+///
+/// The sizes of containers, strings and binaries are read from the wire, so
+/// without limits a peer can make the decoder allocate arbitrarily large
+/// buffers or recurse arbitrarily deep. Every input protocol takes the limits
+/// with `with_limits`, and fails with [`ProtocolExceptionKind::LimitExceeded`]
+/// when one is exceeded. Since the generated decode code reads through the
+/// protocol, it is covered as well, whether it decodes a whole message or a
+/// single struct with [`Message::decode`](super::Message::decode).
+///
+/// The default is unlimited.
+///
+/// ```
+/// use bytes::Bytes;
+/// use pilota::thrift::{DecodeLimits, binary::TBinaryProtocol};
+///
+/// let limits = DecodeLimits {
+///     max_container_len: 1 << 16,
+///     max_depth: 64,
+///     ..Default::default()
+/// };
+/// let mut buf = Bytes::new();
+/// let protocol = TBinaryProtocol::new(&mut buf, true).with_limits(limits);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// The maximum number of elements of a list or set, or entries of a map.
+    pub max_container_len: usize,
+    /// The maximum length in bytes of a string or binary.
+    pub max_bytes_len: usize,
+    /// The maximum nesting depth of structs, including the outermost one.
+    pub max_depth: usize,
+    /// The maximum length in bytes of a message, or of a struct decoded on
+    /// its own.
+    ///
+    /// The buffered protocols check the bytes left when the message or struct
+    /// begins, and the async ones stop reading at the limit.
+    pub max_message_len: usize,
+}
+
+impl DecodeLimits {
+    pub const UNLIMITED: Self = Self {
+        max_container_len: usize::MAX,
+        max_bytes_len: usize::MAX,
+        max_depth: usize::MAX,
+        max_message_len: usize::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+#[inline]
+fn limit_exceeded(what: &str, len: usize, limit: usize) -> ThriftException {
+    new_protocol_exception(
+        ProtocolExceptionKind::LimitExceeded,
+        format!("{what} of {len} exceeds the limit of {limit}"),
+    )
+}
+
+/// The limits of a protocol, with the struct depth of the current message.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limiter {
+    pub(crate) limits: DecodeLimits,
+    depth: usize,
+    in_message: bool,
+}
+
+impl Limiter {
+    /// Starts a new message.
+    #[inline]
+    pub(crate) fn begin_message(&mut self) {
+        self.depth = 0;
+        self.in_message = true;
+    }
+
+    #[inline]
+    pub(crate) fn end_message(&mut self) {
+        self.in_message = false;
+    }
+
+    /// Whether a struct that begins now is decoded on its own rather than as
+    /// part of a message or of another struct, and so starts anew like a
+    /// message does.
+    #[inline]
+    pub(crate) fn starts_value(&self) -> bool {
+        !self.in_message && self.depth == 0
+    }
+
+    #[inline]
+    pub(crate) fn enter_struct(&mut self) -> Result<(), ThriftException> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(limit_exceeded(
+                "struct depth",
+                self.depth,
+                self.limits.max_depth,
+            ));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn exit_struct(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    #[inline]
+    pub(crate) fn check_container_len(&self, len: usize) -> Result<(), ThriftException> {
+        if len > self.limits.max_container_len {
+            return Err(limit_exceeded(
+                "container length",
+                len,
+                self.limits.max_container_len,
+            ));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn check_bytes_len(&self, len: usize) -> Result<(), ThriftException> {
+        if len > self.limits.max_bytes_len {
+            return Err(limit_exceeded(
+                "bytes length",
+                len,
+                self.limits.max_bytes_len,
+            ));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn check_message_len(&self, len: usize) -> Result<(), ThriftException> {
+        if len > self.limits.max_message_len {
+            return Err(limit_exceeded(
+                "message length",
+                len,
+                self.limits.max_message_len,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The error of a [`CountingReader`] asked to read past its limit, which
+/// turns into a [`ProtocolExceptionKind::LimitExceeded`] exception.
+#[derive(Debug)]
+pub(crate) struct MessageLenExceeded {
+    limit: usize,
+}
+
+impl fmt::Display for MessageLenExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message length exceeds the limit of {}", self.limit)
+    }
+}
+
+impl std::error::Error for MessageLenExceeded {}
+
+/// A reader that counts the bytes read and fails to read past the message
+/// limit of the async protocols.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    count: usize,
+    limit: usize,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            count: 0,
+            limit: usize::MAX,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// The bytes read since the last reset.
+    #[inline]
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    pub(crate) fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let left = this.limit.saturating_sub(this.count);
+        if buf.remaining() <= left {
+            let filled = buf.filled().len();
+            let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+            this.count += buf.filled().len() - filled;
+            return poll;
+        }
+        if left == 0 {
+            return Poll::Ready(Err(io::Error::other(MessageLenExceeded {
+                limit: this.limit,
+            })));
+        }
+
+        // read up to the limit, the next read fails
+        let mut limited = buf.take(left);
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        // SAFETY: the inner reader initialized the `n` bytes it filled
+        unsafe { buf.assume_init(n) };
+        buf.advance(n);
+        this.count += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use faststr::FastStr;
+
+    use super::DecodeLimits;
+    use crate::thrift::{
+        ProtocolExceptionKind, TAsyncBinaryProtocol, TAsyncInputProtocol, TInputProtocol,
+        TListIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol, TStructIdentifier,
+        TType, ThriftException, binary::TBinaryProtocol, compact::TCompactInputProtocol,
+        compact::TCompactOutputProtocol,
+    };
+
+    fn assert_limit_exceeded<T: std::fmt::Debug>(res: Result<T, ThriftException>) {
+        match res {
+            Err(ThriftException::Protocol(e)) => {
+                assert_eq!(e.kind(), ProtocolExceptionKind::LimitExceeded, "{e}")
+            }
+            res => panic!("expected a limit error, got {res:?}"),
+        }
+    }
+
+    /// A message with a struct holding a list of two strings.
+    fn write_message<P: TOutputProtocol>(p: &mut P) {
+        p.write_message_begin(&TMessageIdentifier::new(
+            FastStr::from_static_str("m"),
+            TMessageType::Call,
+            1,
+        ))
+        .unwrap();
+        p.write_struct_begin(&TStructIdentifier::new("s")).unwrap();
+        p.write_field_begin(TType::List, 1).unwrap();
+        p.write_list_begin(TListIdentifier::new(TType::Binary, 2))
+            .unwrap();
+        p.write_faststr(FastStr::from_static_str("hello")).unwrap();
+        p.write_faststr(FastStr::from_static_str("world")).unwrap();
+        p.write_list_end().unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+        p.write_message_end().unwrap();
+    }
+
+    /// Reads the message as the generated code does.
+    fn read_message<P: TInputProtocol>(p: &mut P) -> Result<(), ThriftException> {
+        p.read_message_begin()?;
+        p.read_struct_begin()?;
+        p.read_field_begin()?;
+        let list = p.read_list_begin()?;
+        for _ in 0..list.size {
+            p.read_faststr()?;
+        }
+        p.read_list_end()?;
+        p.read_field_end()?;
+        assert_eq!(p.read_field_begin()?.field_type, TType::Stop);
+        p.read_struct_end()?;
+        p.read_message_end()
+    }
+
+    fn binary_message() -> Bytes {
+        let mut buf = BytesMut::new();
+        write_message(&mut TBinaryProtocol::new(&mut buf, false));
+        buf.freeze()
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let mut buf = binary_message();
+        read_message(&mut TBinaryProtocol::new(&mut buf, false)).unwrap();
+    }
+
+    #[test]
+    fn test_binary_limits() {
+        let limits = [
+            DecodeLimits {
+                max_container_len: 1,
+                ..Default::default()
+            },
+            DecodeLimits {
+                max_bytes_len: 4,
+                ..Default::default()
+            },
+            DecodeLimits {
+                max_depth: 0,
+                ..Default::default()
+            },
+            DecodeLimits {
+                max_message_len: 16,
+                ..Default::default()
+            },
+        ];
+        for limits in limits {
+            let mut buf = binary_message();
+            let mut p = TBinaryProtocol::new(&mut buf, false).with_limits(limits);
+            assert_limit_exceeded(read_message(&mut p));
+        }
+
+        let mut buf = binary_message();
+        let limits = DecodeLimits {
+            max_container_len: 2,
+            max_bytes_len: 5,
+            max_depth: 1,
+            max_message_len: buf.len(),
+        };
+        read_message(&mut TBinaryProtocol::new(&mut buf, false).with_limits(limits)).unwrap();
+    }
+
+    #[test]
+    fn test_struct_depth() {
+        let mut buf = BytesMut::new();
+        let mut p = TBinaryProtocol::new(&mut buf, false);
+        for _ in 0..3 {
+            p.write_struct_begin(&TStructIdentifier::new("s")).unwrap();
+            p.write_field_begin(TType::Struct, 1).unwrap();
+        }
+        p.write_field_stop().unwrap();
+        for _ in 0..3 {
+            p.write_field_stop().unwrap();
+        }
+        let buf = buf.freeze();
+
+        let limits = DecodeLimits {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let mut b = buf.clone();
+        assert_limit_exceeded(
+            TBinaryProtocol::new(&mut b, false)
+                .with_limits(limits)
+                .skip(TType::Struct),
+        );
+        let limits = DecodeLimits {
+            max_depth: 4,
+            ..Default::default()
+        };
+        let mut b = buf.clone();
+        TBinaryProtocol::new(&mut b, false)
+            .with_limits(limits)
+            .skip(TType::Struct)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_compact_limits() {
+        let limits = DecodeLimits {
+            max_container_len: 1,
+            ..Default::default()
+        };
+
+        let mut buf = BytesMut::new();
+        write_message(&mut TCompactOutputProtocol::new(&mut buf, false));
+        let mut buf = buf.freeze();
+        assert_limit_exceeded(read_message(
+            &mut TCompactInputProtocol::new(&mut buf).with_limits(limits),
+        ));
+    }
+
+    #[cfg(feature = "thrift-json")]
+    #[test]
+    fn test_json_limits() {
+        use crate::thrift::json::{TJSONInputProtocol, TJSONOutputProtocol};
+
+        let limits = DecodeLimits {
+            max_container_len: 1,
+            ..Default::default()
+        };
+
+        let mut buf = BytesMut::new();
+        write_message(&mut TJSONOutputProtocol::new(&mut buf));
+        let mut buf = buf.freeze();
+        assert_limit_exceeded(read_message(
+            &mut TJSONInputProtocol::new(&mut buf).with_limits(limits),
+        ));
+    }
+
+    #[test]
+    fn test_async_limits() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let buf = binary_message();
+            let limits = DecodeLimits {
+                max_bytes_len: 4,
+                ..Default::default()
+            };
+            let mut p = TAsyncBinaryProtocol::new(&buf[..]).with_limits(limits);
+            p.read_message_begin().await.unwrap();
+            assert_limit_exceeded(p.skip(TType::Struct).await);
+
+            let limits = DecodeLimits {
+                max_message_len: 16,
+                ..Default::default()
+            };
+            let mut p = TAsyncBinaryProtocol::new(&buf[..]).with_limits(limits);
+            p.read_message_begin().await.unwrap();
+            assert_limit_exceeded(p.skip(TType::Struct).await);
+
+            let limits = DecodeLimits {
+                max_message_len: buf.len(),
+                ..Default::default()
+            };
+            let mut p = TAsyncBinaryProtocol::new(&buf[..]).with_limits(limits);
+            p.read_message_begin().await.unwrap();
+            p.skip(TType::Struct).await.unwrap();
+        });
+    }
+}
@@ -3,7 +3,15 @@ pub mod binary_le;
 pub mod binary_unsafe;
 pub mod compact;
 pub mod error;
+pub mod framed;
+pub mod header;
+#[cfg(feature = "thrift-json")]
+pub mod json;
+pub mod limits;
+pub mod multiplexed;
 pub mod rw_ext;
+#[cfg(feature = "thrift-json")]
+pub mod simple_json;
 pub mod unknown;
 pub mod varint_ext;
 
@@ -18,7 +26,14 @@ use bytes::{Buf, BufMut, Bytes};
 pub use error::*;
 use faststr::FastStr;
 
-pub use self::{binary::TAsyncBinaryProtocol, compact::TAsyncCompactProtocol};
+pub use self::{
+    binary::TAsyncBinaryProtocol, compact::TAsyncCompactProtocol, limits::DecodeLimits,
+};
+#[cfg(feature = "thrift-json")]
+pub use self::{
+    json::TAsyncJSONProtocol,
+    simple_json::{TSimpleJsonElement, TSimpleJsonNames, TSimpleJsonOutputProtocol},
+};
 use crate::{AHashMap, AHashSet, assert_remaining, thrift::rw_ext::IOError};
 
 const MAXIMUM_SKIP_DEPTH: i8 = 64;
@@ -0,0 +1,318 @@
+//! Multiplexed protocols, to talk to the multiplexed servers of Apache Thrift.
+//!
+//! A multiplexed server serves several services on one endpoint, and tells
+//! them apart by the service name prefixed to the method name of a call, as
+//! in `Calculator:add`. The replies carry the bare method name.
+
+use bytes::Bytes;
+use faststr::FastStr;
+
+use super::{
+    TFieldIdentifier, TInputProtocol, TLengthProtocol, TListIdentifier, TMapIdentifier,
+    TMessageIdentifier, TMessageType, TOutputProtocol, TSetIdentifier, TStructIdentifier, TType,
+    ThriftException,
+};
+
+/// The separator between the service name and the method name.
+pub const MULTIPLEXED_SEPARATOR: &str = ":";
+
+macro_rules! delegate {
+    ($($name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            #[inline]
+            fn $name(&mut self $(, $arg: $ty)*) -> $ret {
+                self.inner.$name($($arg),*)
+            }
+        )*
+    };
+}
+
+macro_rules! delegate_length {
+    () => {
+        delegate! {
+            message_end_len(&mut self) -> usize;
+            struct_begin_len(&mut self, identifier: &TStructIdentifier) -> usize;
+            struct_end_len(&mut self) -> usize;
+            field_begin_len(&mut self, field_type: TType, id: Option<i16>) -> usize;
+            field_end_len(&mut self) -> usize;
+            field_stop_len(&mut self) -> usize;
+            bool_len(&mut self, b: bool) -> usize;
+            bytes_len(&mut self, b: &[u8]) -> usize;
+            bytes_vec_len(&mut self, b: &[u8]) -> usize;
+            byte_len(&mut self, b: u8) -> usize;
+            uuid_len(&mut self, u: [u8; 16]) -> usize;
+            i8_len(&mut self, i: i8) -> usize;
+            i16_len(&mut self, i: i16) -> usize;
+            i32_len(&mut self, i: i32) -> usize;
+            i64_len(&mut self, i: i64) -> usize;
+            double_len(&mut self, d: f64) -> usize;
+            string_len(&mut self, s: &str) -> usize;
+            faststr_len(&mut self, s: &FastStr) -> usize;
+            list_begin_len(&mut self, identifier: TListIdentifier) -> usize;
+            list_end_len(&mut self) -> usize;
+            set_begin_len(&mut self, identifier: TSetIdentifier) -> usize;
+            set_end_len(&mut self) -> usize;
+            map_begin_len(&mut self, identifier: TMapIdentifier) -> usize;
+            map_end_len(&mut self) -> usize;
+            zero_copy_len(&mut self) -> usize;
+            reset(&mut self) -> ();
+        }
+    };
+}
+
+/// Returns the identifier with the service name prefixed to the method name,
+/// if it is a call.
+fn prefixed(service_name: &str, identifier: &TMessageIdentifier) -> Option<TMessageIdentifier> {
+    matches!(
+        identifier.message_type,
+        TMessageType::Call | TMessageType::OneWay
+    )
+    .then(|| {
+        TMessageIdentifier::new(
+            FastStr::from_string(format!(
+                "{service_name}{MULTIPLEXED_SEPARATOR}{}",
+                identifier.name
+            )),
+            identifier.message_type,
+            identifier.sequence_number,
+        )
+    })
+}
+
+/// An output protocol that prefixes the method names of the calls with the
+/// name of a service, for a multiplexed server.
+pub struct TMultiplexedOutputProtocol<P> {
+    inner: P,
+    service_name: FastStr,
+}
+
+impl<P> TMultiplexedOutputProtocol<P> {
+    pub fn new(inner: P, service_name: impl Into<FastStr>) -> Self {
+        Self {
+            inner,
+            service_name: service_name.into(),
+        }
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: TLengthProtocol> TLengthProtocol for TMultiplexedOutputProtocol<P> {
+    #[inline]
+    fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
+        match prefixed(&self.service_name, identifier) {
+            Some(identifier) => self.inner.message_begin_len(&identifier),
+            None => self.inner.message_begin_len(identifier),
+        }
+    }
+
+    delegate_length!();
+}
+
+impl<P: TOutputProtocol> TOutputProtocol for TMultiplexedOutputProtocol<P> {
+    type BufMut = P::BufMut;
+
+    #[inline]
+    fn write_message_begin(
+        &mut self,
+        identifier: &TMessageIdentifier,
+    ) -> Result<(), ThriftException> {
+        match prefixed(&self.service_name, identifier) {
+            Some(identifier) => self.inner.write_message_begin(&identifier),
+            None => self.inner.write_message_begin(identifier),
+        }
+    }
+
+    delegate! {
+        write_message_end(&mut self) -> Result<(), ThriftException>;
+        write_struct_begin(&mut self, identifier: &TStructIdentifier) -> Result<(), ThriftException>;
+        write_struct_end(&mut self) -> Result<(), ThriftException>;
+        write_field_begin(&mut self, field_type: TType, id: i16) -> Result<(), ThriftException>;
+        write_field_end(&mut self) -> Result<(), ThriftException>;
+        write_field_stop(&mut self) -> Result<(), ThriftException>;
+        write_bool(&mut self, b: bool) -> Result<(), ThriftException>;
+        write_bytes(&mut self, b: Bytes) -> Result<(), ThriftException>;
+        write_bytes_without_len(&mut self, b: Bytes) -> Result<(), ThriftException>;
+        write_uuid(&mut self, u: [u8; 16]) -> Result<(), ThriftException>;
+        write_bytes_vec(&mut self, b: &[u8]) -> Result<(), ThriftException>;
+        write_byte(&mut self, b: u8) -> Result<(), ThriftException>;
+        write_i8(&mut self, i: i8) -> Result<(), ThriftException>;
+        write_i16(&mut self, i: i16) -> Result<(), ThriftException>;
+        write_i32(&mut self, i: i32) -> Result<(), ThriftException>;
+        write_i64(&mut self, i: i64) -> Result<(), ThriftException>;
+        write_double(&mut self, d: f64) -> Result<(), ThriftException>;
+        write_string(&mut self, s: &str) -> Result<(), ThriftException>;
+        write_faststr(&mut self, s: FastStr) -> Result<(), ThriftException>;
+        write_list_begin(&mut self, identifier: TListIdentifier) -> Result<(), ThriftException>;
+        write_list_end(&mut self) -> Result<(), ThriftException>;
+        write_set_begin(&mut self, identifier: TSetIdentifier) -> Result<(), ThriftException>;
+        write_set_end(&mut self) -> Result<(), ThriftException>;
+        write_map_begin(&mut self, identifier: TMapIdentifier) -> Result<(), ThriftException>;
+        write_map_end(&mut self) -> Result<(), ThriftException>;
+        flush(&mut self) -> Result<(), ThriftException>;
+        buf_mut(&mut self) -> &mut Self::BufMut;
+    }
+}
+
+/// An input protocol that strips the service name from the method names of
+/// the calls to a multiplexed server.
+///
+/// The service name of the last message read is kept in
+/// [`TMultiplexedInputProtocol::service_name`], to dispatch the call on.
+pub struct TMultiplexedInputProtocol<P> {
+    inner: P,
+    service_name: Option<FastStr>,
+}
+
+impl<P> TMultiplexedInputProtocol<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            service_name: None,
+        }
+    }
+
+    /// The service name of the last message read, or `None` if its method
+    /// name has no prefix.
+    pub fn service_name(&self) -> Option<&FastStr> {
+        self.service_name.as_ref()
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: TLengthProtocol> TLengthProtocol for TMultiplexedInputProtocol<P> {
+    #[inline]
+    fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
+        match self
+            .service_name
+            .as_ref()
+            .and_then(|service_name| prefixed(service_name, identifier))
+        {
+            Some(identifier) => self.inner.message_begin_len(&identifier),
+            None => self.inner.message_begin_len(identifier),
+        }
+    }
+
+    delegate_length!();
+}
+
+impl<P: TInputProtocol> TInputProtocol for TMultiplexedInputProtocol<P> {
+    type Buf = P::Buf;
+
+    fn read_message_begin(&mut self) -> Result<TMessageIdentifier, ThriftException> {
+        let mut identifier = self.inner.read_message_begin()?;
+        self.service_name = None;
+        if matches!(
+            identifier.message_type,
+            TMessageType::Call | TMessageType::OneWay
+        ) {
+            if let Some(i) = identifier.name.find(MULTIPLEXED_SEPARATOR) {
+                self.service_name = Some(identifier.name.slice_ref(&identifier.name[..i]));
+                identifier.name = identifier
+                    .name
+                    .slice_ref(&identifier.name[i + MULTIPLEXED_SEPARATOR.len()..]);
+            }
+        }
+        Ok(identifier)
+    }
+
+    delegate! {
+        read_message_end(&mut self) -> Result<(), ThriftException>;
+        read_struct_begin(&mut self) -> Result<Option<TStructIdentifier>, ThriftException>;
+        read_struct_end(&mut self) -> Result<(), ThriftException>;
+        read_field_begin(&mut self) -> Result<TFieldIdentifier, ThriftException>;
+        read_field_end(&mut self) -> Result<(), ThriftException>;
+        read_bool(&mut self) -> Result<bool, ThriftException>;
+        read_bytes(&mut self) -> Result<Bytes, ThriftException>;
+        read_uuid(&mut self) -> Result<[u8; 16], ThriftException>;
+        read_i8(&mut self) -> Result<i8, ThriftException>;
+        read_i16(&mut self) -> Result<i16, ThriftException>;
+        read_i32(&mut self) -> Result<i32, ThriftException>;
+        read_i64(&mut self) -> Result<i64, ThriftException>;
+        read_double(&mut self) -> Result<f64, ThriftException>;
+        read_string(&mut self) -> Result<String, ThriftException>;
+        read_faststr(&mut self) -> Result<FastStr, ThriftException>;
+        read_list_begin(&mut self) -> Result<TListIdentifier, ThriftException>;
+        read_list_end(&mut self) -> Result<(), ThriftException>;
+        read_set_begin(&mut self) -> Result<TSetIdentifier, ThriftException>;
+        read_set_end(&mut self) -> Result<(), ThriftException>;
+        read_map_begin(&mut self) -> Result<TMapIdentifier, ThriftException>;
+        read_map_end(&mut self) -> Result<(), ThriftException>;
+        skip(&mut self, field_type: TType) -> Result<usize, ThriftException>;
+        skip_till_depth(&mut self, field_type: TType, depth: i8) -> Result<usize, ThriftException>;
+        read_byte(&mut self) -> Result<u8, ThriftException>;
+        read_bytes_vec(&mut self) -> Result<Vec<u8>, ThriftException>;
+        get_bytes(&mut self, ptr: Option<*const u8>, len: usize) -> Result<Bytes, ThriftException>;
+        buf(&mut self) -> &mut Self::Buf;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use faststr::FastStr;
+
+    use super::{TMultiplexedInputProtocol, TMultiplexedOutputProtocol};
+    use crate::thrift::{
+        ApplicationException, ApplicationExceptionKind, Message, TInputProtocol,
+        TMessageIdentifier, TMessageType, TOutputProtocol, binary::TBinaryProtocol,
+        compact::TCompactInputProtocol, compact::TCompactOutputProtocol,
+    };
+
+    fn write<P: TOutputProtocol>(p: &mut P, message_type: TMessageType) -> usize {
+        let identifier = TMessageIdentifier::new(FastStr::from_static_str("add"), message_type, 1);
+        let msg = ApplicationException::new(ApplicationExceptionKind::UNKNOWN, "hello");
+        let len = p.message_begin_len(&identifier) + msg.size(p) + p.message_end_len();
+        p.write_message_begin(&identifier).unwrap();
+        msg.encode(p).unwrap();
+        p.write_message_end().unwrap();
+        len
+    }
+
+    fn read<P: TInputProtocol>(p: &mut P) -> TMessageIdentifier {
+        let identifier = p.read_message_begin().unwrap();
+        let msg = ApplicationException::decode(p).unwrap();
+        p.read_message_end().unwrap();
+        assert_eq!(msg.message(), "hello");
+        identifier
+    }
+
+    #[test]
+    fn test_multiplexed() {
+        let mut buf = BytesMut::new();
+        let len = write(
+            &mut TMultiplexedOutputProtocol::new(TBinaryProtocol::new(&mut buf, true), "Calc"),
+            TMessageType::Call,
+        );
+        assert_eq!(buf.len(), len);
+        let mut b = buf.clone().freeze();
+        let identifier = read(&mut TBinaryProtocol::new(&mut b, true));
+        assert_eq!(identifier.name, "Calc:add");
+
+        let mut b = buf.freeze();
+        let mut p = TMultiplexedInputProtocol::new(TBinaryProtocol::new(&mut b, true));
+        let identifier = read(&mut p);
+        assert_eq!(identifier.name, "add");
+        assert_eq!(p.service_name().unwrap(), "Calc");
+
+        // the replies are not prefixed
+        let mut buf = BytesMut::new();
+        write(
+            &mut TMultiplexedOutputProtocol::new(
+                TCompactOutputProtocol::new(&mut buf, false),
+                "Calc",
+            ),
+            TMessageType::Reply,
+        );
+        let mut b = buf.freeze();
+        let mut p = TMultiplexedInputProtocol::new(TCompactInputProtocol::new(&mut b));
+        let identifier = read(&mut p);
+        assert_eq!(identifier.name, "add");
+        assert!(p.service_name().is_none());
+    }
+}
@@ -0,0 +1,796 @@
+//! Apache Thrift simple JSON protocol (`TSimpleJSONProtocol`).
+//!
+//! A write-only, human-readable encoding meant for logging and debugging:
+//! structs are objects keyed by field name, lists and sets are arrays, maps
+//! are objects with stringified keys and binary data is unpadded base64. The
+//! output carries no type information, so it can't be decoded back.
+//!
+//! [`TOutputProtocol::write_field_begin`] only takes the type and id of a
+//! field, and enums are written with [`TOutputProtocol::write_i32`], so the
+//! field and variant names never reach the protocol through the generated
+//! encode code; only the bare struct name does, in [`TStructIdentifier`].
+//! The names are looked up instead through the [`TSimpleJsonNames`] given to
+//! [`TSimpleJsonOutputProtocol::with_names`]; `SimpleJsonNames` in
+//! `pilota-thrift-reflect` builds one from the idl. Fields it doesn't know are
+//! keyed by their id and enums are written as numbers.
+
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use faststr::FastStr;
+use linkedbytes::LinkedBytes;
+
+use super::{
+    TLengthProtocol, TListIdentifier, TMapIdentifier, TMessageIdentifier, TOutputProtocol,
+    TSetIdentifier, TStructIdentifier, TType, ThriftException,
+    error::ProtocolExceptionKind,
+    json::{JsonContextStack, JsonWriter, QUOTE, uuid_to_string},
+    new_protocol_exception,
+};
+
+/// Resolves the names written by [`TSimpleJsonOutputProtocol`] in place of
+/// field ids and enum values.
+///
+/// The protocol needs this lookup because the generated encode code hands it
+/// only the field ids and the raw enum values; see the [module
+/// documentation](self).
+pub trait TSimpleJsonNames: Send + Sync {
+    /// Returns the name of field `id` of the struct named `struct_name`.
+    fn field_name(&self, struct_name: &str, id: i16) -> Option<&str>;
+
+    /// Returns the name of the variant `value` if the value at `path` in
+    /// field `id` of the struct named `struct_name` is an enum.
+    ///
+    /// `path` is empty for the field itself, and otherwise leads through the
+    /// containers of the field type, from the outermost one in: a
+    /// `map<string, list<Enum>>` field has its enums at `[Value, Element]`.
+    fn enum_name(
+        &self,
+        struct_name: &str,
+        id: i16,
+        path: &[TSimpleJsonElement],
+        value: i32,
+    ) -> Option<&str> {
+        let _ = (struct_name, id, path, value);
+        None
+    }
+}
+
+/// A step from a container to one of its values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TSimpleJsonElement {
+    /// An element of a list or set.
+    Element,
+    /// A key of a map.
+    Key,
+    /// A value of a map.
+    Value,
+}
+
+#[derive(Debug)]
+enum Frame {
+    /// A struct, with the field whose value is being written.
+    Struct(&'static str, Option<i16>),
+    List,
+    Map,
+}
+
+/// Tracks which struct, field and container a value belongs to.
+#[derive(Debug, Default)]
+struct NameState {
+    frames: Vec<Frame>,
+    // reused for the path of each possible enum
+    path: Vec<TSimpleJsonElement>,
+}
+
+impl NameState {
+    #[inline]
+    fn struct_begin(&mut self, name: &'static str) {
+        self.frames.push(Frame::Struct(name, None));
+    }
+
+    #[inline]
+    fn container_begin(&mut self, map: bool) {
+        self.frames.push(if map { Frame::Map } else { Frame::List });
+    }
+
+    /// Leaves the innermost struct or container.
+    #[inline]
+    fn end(&mut self) {
+        self.frames.pop();
+    }
+
+    #[inline]
+    fn field_begin(&mut self, id: i16) {
+        if let Some(Frame::Struct(_, field)) = self.frames.last_mut() {
+            *field = Some(id);
+        }
+    }
+
+    #[inline]
+    fn field_name<'a>(&self, names: Option<&'a dyn TSimpleJsonNames>, id: i16) -> Option<&'a str> {
+        match self.frames.last()? {
+            Frame::Struct(name, _) => names?.field_name(name, id),
+            _ => None,
+        }
+    }
+
+    /// Looks up the variant name of `value`, where `is_key` tells whether it
+    /// is written as a map key.
+    #[inline]
+    fn enum_name<'a>(
+        &mut self,
+        names: Option<&'a dyn TSimpleJsonNames>,
+        is_key: bool,
+        value: i32,
+    ) -> Option<&'a str> {
+        let names = names?;
+        self.path.clear();
+        // containers are never map keys, so only the innermost value can be
+        let mut key = is_key;
+        for frame in self.frames.iter().rev() {
+            let step = match frame {
+                Frame::Struct(name, field) => {
+                    self.path.reverse();
+                    return names.enum_name(name, (*field)?, &self.path, value);
+                }
+                Frame::List => TSimpleJsonElement::Element,
+                Frame::Map if key => TSimpleJsonElement::Key,
+                Frame::Map => TSimpleJsonElement::Value,
+            };
+            key = false;
+            self.path.push(step);
+        }
+        None
+    }
+}
+
+#[inline]
+fn scalar_key_required() -> ThriftException {
+    new_protocol_exception(
+        ProtocolExceptionKind::InvalidData,
+        "simple JSON map keys must be scalar values",
+    )
+}
+
+#[inline]
+fn bool_literal(b: bool) -> &'static [u8] {
+    if b { b"true" } else { b"false" }
+}
+
+/// Writes a bool as a JSON literal, quoted when it is a map key.
+#[inline]
+fn write_bool_literal(w: &mut JsonWriter<'_>, b: bool) {
+    w.write_separator();
+    let escape = w.context.escape_num();
+    if escape {
+        w.buf.extend_from_slice(&[QUOTE]);
+    }
+    w.buf.extend_from_slice(bool_literal(b));
+    if escape {
+        w.buf.extend_from_slice(&[QUOTE]);
+    }
+}
+
+pub struct TSimpleJsonOutputProtocol<T> {
+    pub(crate) trans: T,
+
+    names: Option<Arc<dyn TSimpleJsonNames>>,
+    context: JsonContextStack,
+    state: NameState,
+    // Lengths are calculated in the same order as values are written, so they
+    // need their own state to know where separators and names go.
+    len_context: JsonContextStack,
+    len_state: NameState,
+}
+
+impl<T> TSimpleJsonOutputProtocol<T> {
+    #[inline]
+    pub fn new(trans: T) -> Self {
+        Self {
+            trans,
+            names: None,
+            context: JsonContextStack::default(),
+            state: NameState::default(),
+            len_context: JsonContextStack::default(),
+            len_state: NameState::default(),
+        }
+    }
+
+    /// Uses `names` to key fields by name and to write enums as their
+    /// variant names.
+    #[inline]
+    pub fn with_names(mut self, names: Arc<dyn TSimpleJsonNames>) -> Self {
+        self.names = Some(names);
+        self
+    }
+}
+
+impl<T> TLengthProtocol for TSimpleJsonOutputProtocol<T> {
+    #[inline]
+    fn message_begin_len(&mut self, identifier: &TMessageIdentifier) -> usize {
+        let msg_type: u8 = identifier.message_type.into();
+        self.len_context.array_begin_len()
+            + self.len_context.string_len(identifier.name.as_bytes())
+            + self.len_context.integer_len(msg_type as i64)
+            + self
+                .len_context
+                .integer_len(identifier.sequence_number as i64)
+    }
+
+    #[inline]
+    fn message_end_len(&mut self) -> usize {
+        self.len_context.end_len()
+    }
+
+    #[inline]
+    fn struct_begin_len(&mut self, identifier: &TStructIdentifier) -> usize {
+        self.len_state.struct_begin(identifier.name);
+        self.len_context.object_begin_len()
+    }
+
+    #[inline]
+    fn struct_end_len(&mut self) -> usize {
+        self.len_state.end();
+        self.len_context.end_len()
+    }
+
+    #[inline]
+    fn field_begin_len(&mut self, _field_type: TType, id: Option<i16>) -> usize {
+        let id = id.unwrap_or_default();
+        let len = match self.len_state.field_name(self.names.as_deref(), id) {
+            Some(name) => self.len_context.string_len(name.as_bytes()),
+            None => self.len_context.integer_len(id as i64),
+        };
+        self.len_state.field_begin(id);
+        len
+    }
+
+    #[inline]
+    fn field_end_len(&mut self) -> usize {
+        0
+    }
+
+    #[inline]
+    fn field_stop_len(&mut self) -> usize {
+        0
+    }
+
+    #[inline]
+    fn bool_len(&mut self, b: bool) -> usize {
+        let sep = self.len_context.separator_len();
+        let quotes = if self.len_context.escape_num() { 2 } else { 0 };
+        sep + quotes + bool_literal(b).len()
+    }
+
+    #[inline]
+    fn bytes_len(&mut self, b: &[u8]) -> usize {
+        self.len_context.base64_len(b)
+    }
+
+    #[inline]
+    fn bytes_vec_len(&mut self, b: &[u8]) -> usize {
+        self.len_context.base64_len(b)
+    }
+
+    #[inline]
+    fn byte_len(&mut self, b: u8) -> usize {
+        self.len_context.integer_len(b as i8 as i64)
+    }
+
+    #[inline]
+    fn uuid_len(&mut self, _u: [u8; 16]) -> usize {
+        self.len_context.separator_len() + 2 + 36
+    }
+
+    #[inline]
+    fn i8_len(&mut self, i: i8) -> usize {
+        self.len_context.integer_len(i as i64)
+    }
+
+    #[inline]
+    fn i16_len(&mut self, i: i16) -> usize {
+        self.len_context.integer_len(i as i64)
+    }
+
+    #[inline]
+    fn i32_len(&mut self, i: i32) -> usize {
+        let is_key = self.len_context.next_is_key();
+        match self.len_state.enum_name(self.names.as_deref(), is_key, i) {
+            Some(name) => self.len_context.string_len(name.as_bytes()),
+            None => self.len_context.integer_len(i as i64),
+        }
+    }
+
+    #[inline]
+    fn i64_len(&mut self, i: i64) -> usize {
+        self.len_context.integer_len(i)
+    }
+
+    #[inline]
+    fn double_len(&mut self, d: f64) -> usize {
+        self.len_context.double_len(d)
+    }
+
+    #[inline]
+    fn string_len(&mut self, s: &str) -> usize {
+        self.len_context.string_len(s.as_bytes())
+    }
+
+    #[inline]
+    fn faststr_len(&mut self, s: &FastStr) -> usize {
+        self.len_context.string_len(s.as_bytes())
+    }
+
+    #[inline]
+    fn list_begin_len(&mut self, _identifier: TListIdentifier) -> usize {
+        self.len_state.container_begin(false);
+        self.len_context.array_begin_len()
+    }
+
+    #[inline]
+    fn list_end_len(&mut self) -> usize {
+        self.len_state.end();
+        self.len_context.end_len()
+    }
+
+    #[inline]
+    fn set_begin_len(&mut self, _identifier: TSetIdentifier) -> usize {
+        self.len_state.container_begin(false);
+        self.len_context.array_begin_len()
+    }
+
+    #[inline]
+    fn set_end_len(&mut self) -> usize {
+        self.len_state.end();
+        self.len_context.end_len()
+    }
+
+    #[inline]
+    fn map_begin_len(&mut self, _identifier: TMapIdentifier) -> usize {
+        self.len_state.container_begin(true);
+        self.len_context.object_begin_len()
+    }
+
+    #[inline]
+    fn map_end_len(&mut self) -> usize {
+        self.len_state.end();
+        self.len_context.end_len()
+    }
+}
+
+type Parts<'a> = (
+    JsonWriter<'a>,
+    &'a mut NameState,
+    Option<&'a dyn TSimpleJsonNames>,
+);
+
+impl TSimpleJsonOutputProtocol<&mut BytesMut> {
+    #[inline]
+    fn parts(&mut self) -> Parts<'_> {
+        let w = JsonWriter {
+            context: &mut self.context,
+            buf: self.trans,
+        };
+        (w, &mut self.state, self.names.as_deref())
+    }
+}
+
+impl TSimpleJsonOutputProtocol<&mut LinkedBytes> {
+    #[inline]
+    fn parts(&mut self) -> Parts<'_> {
+        let w = JsonWriter {
+            context: &mut self.context,
+            buf: self.trans.bytes_mut(),
+        };
+        (w, &mut self.state, self.names.as_deref())
+    }
+}
+
+macro_rules! simple_json_output_impl {
+    ($buf:ty) => {
+        impl TSimpleJsonOutputProtocol<&mut $buf> {
+            #[inline]
+            fn writer(&mut self) -> JsonWriter<'_> {
+                self.parts().0
+            }
+
+            /// Enters an object or array, which can't be used as a map key.
+            #[inline]
+            fn container_begin(&mut self, object: bool) -> Result<(), ThriftException> {
+                if self.context.next_is_key() {
+                    return Err(scalar_key_required());
+                }
+                let mut w = self.writer();
+                if object {
+                    w.write_object_begin();
+                } else {
+                    w.write_array_begin();
+                }
+                Ok(())
+            }
+        }
+
+        impl TOutputProtocol for TSimpleJsonOutputProtocol<&mut $buf> {
+            type BufMut = $buf;
+
+            #[inline]
+            fn write_message_begin(
+                &mut self,
+                identifier: &TMessageIdentifier,
+            ) -> Result<(), ThriftException> {
+                let msg_type: u8 = identifier.message_type.into();
+                let mut w = self.writer();
+                w.write_array_begin();
+                w.write_string(identifier.name.as_bytes());
+                w.write_integer(msg_type as i64);
+                w.write_integer(identifier.sequence_number as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_message_end(&mut self) -> Result<(), ThriftException> {
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_struct_begin(
+                &mut self,
+                identifier: &TStructIdentifier,
+            ) -> Result<(), ThriftException> {
+                self.container_begin(true)?;
+                self.state.struct_begin(identifier.name);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_struct_end(&mut self) -> Result<(), ThriftException> {
+                self.state.end();
+                self.writer().write_object_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_field_begin(
+                &mut self,
+                _field_type: TType,
+                id: i16,
+            ) -> Result<(), ThriftException> {
+                let (mut w, state, names) = self.parts();
+                match state.field_name(names, id) {
+                    Some(name) => w.write_string(name.as_bytes()),
+                    None => w.write_integer(id as i64),
+                }
+                state.field_begin(id);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_field_end(&mut self) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn write_field_stop(&mut self) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bool(&mut self, b: bool) -> Result<(), ThriftException> {
+                write_bool_literal(&mut self.writer(), b);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bytes(&mut self, b: Bytes) -> Result<(), ThriftException> {
+                self.writer().write_base64(&b);
+                Ok(())
+            }
+
+            /// Unknown fields are kept in their original binary encoding, which
+            /// has no JSON form, so they are left out.
+            #[inline]
+            fn write_bytes_without_len(&mut self, _b: Bytes) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn write_uuid(&mut self, u: [u8; 16]) -> Result<(), ThriftException> {
+                self.writer().write_string(uuid_to_string(u).as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_bytes_vec(&mut self, b: &[u8]) -> Result<(), ThriftException> {
+                self.writer().write_base64(b);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_byte(&mut self, b: u8) -> Result<(), ThriftException> {
+                self.writer().write_integer(b as i8 as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i8(&mut self, i: i8) -> Result<(), ThriftException> {
+                self.writer().write_integer(i as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i16(&mut self, i: i16) -> Result<(), ThriftException> {
+                self.writer().write_integer(i as i64);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i32(&mut self, i: i32) -> Result<(), ThriftException> {
+                let (mut w, state, names) = self.parts();
+                let is_key = w.context.next_is_key();
+                match state.enum_name(names, is_key, i) {
+                    Some(name) => w.write_string(name.as_bytes()),
+                    None => w.write_integer(i as i64),
+                }
+                Ok(())
+            }
+
+            #[inline]
+            fn write_i64(&mut self, i: i64) -> Result<(), ThriftException> {
+                self.writer().write_integer(i);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_double(&mut self, d: f64) -> Result<(), ThriftException> {
+                self.writer().write_double(d);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_string(&mut self, s: &str) -> Result<(), ThriftException> {
+                self.writer().write_string(s.as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_faststr(&mut self, s: FastStr) -> Result<(), ThriftException> {
+                self.writer().write_string(s.as_bytes());
+                Ok(())
+            }
+
+            #[inline]
+            fn write_list_begin(&mut self, _: TListIdentifier) -> Result<(), ThriftException> {
+                self.container_begin(false)?;
+                self.state.container_begin(false);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_list_end(&mut self) -> Result<(), ThriftException> {
+                self.state.end();
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_set_begin(&mut self, _: TSetIdentifier) -> Result<(), ThriftException> {
+                self.container_begin(false)?;
+                self.state.container_begin(false);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_set_end(&mut self) -> Result<(), ThriftException> {
+                self.state.end();
+                self.writer().write_array_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn write_map_begin(&mut self, _: TMapIdentifier) -> Result<(), ThriftException> {
+                self.container_begin(true)?;
+                self.state.container_begin(true);
+                Ok(())
+            }
+
+            #[inline]
+            fn write_map_end(&mut self) -> Result<(), ThriftException> {
+                self.state.end();
+                self.writer().write_object_end();
+                Ok(())
+            }
+
+            #[inline]
+            fn flush(&mut self) -> Result<(), ThriftException> {
+                Ok(())
+            }
+
+            #[inline]
+            fn buf_mut(&mut self) -> &mut Self::BufMut {
+                self.trans
+            }
+        }
+    };
+}
+
+simple_json_output_impl!(BytesMut);
+simple_json_output_impl!(LinkedBytes);
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::{Bytes, BytesMut};
+    use linkedbytes::LinkedBytes;
+
+    use super::{TSimpleJsonElement, TSimpleJsonNames, TSimpleJsonOutputProtocol};
+    use crate::thrift::{
+        TLengthProtocol, TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType,
+        TOutputProtocol, TStructIdentifier, TType,
+    };
+
+    struct Names;
+
+    impl TSimpleJsonNames for Names {
+        fn field_name(&self, struct_name: &str, id: i16) -> Option<&str> {
+            match (struct_name, id) {
+                ("Item", 1) => Some("id"),
+                ("Item", 2) => Some("status"),
+                ("Item", 3) => Some("tags"),
+                ("Item", 4) => Some("scores"),
+                ("Item", 5) => Some("child"),
+                ("Child", 1) => Some("ok"),
+                _ => None,
+            }
+        }
+
+        fn enum_name(
+            &self,
+            struct_name: &str,
+            id: i16,
+            path: &[TSimpleJsonElement],
+            value: i32,
+        ) -> Option<&str> {
+            use TSimpleJsonElement::*;
+            match (struct_name, id, path, value) {
+                ("Item", 2, [], 1) | ("Item", 3, [Element], 1) | ("Item", 4, [Key], 3) => {
+                    Some("ACTIVE")
+                }
+                _ => None,
+            }
+        }
+    }
+
+    const NAMED: &str = r#"["call",1,7,{"id":-1,"status":"ACTIVE","tags":["ACTIVE",2],"scores":{"ACTIVE":"a\"b","4":"AQI"},"child":{"ok":true,"2":{"2.5":false}},"6":1}]"#;
+    const UNNAMED: &str = r#"["call",1,7,{"1":-1,"2":1,"3":[1,2],"4":{"3":"a\"b","4":"AQI"},"5":{"1":true,"2":{"2.5":false}},"6":1}]"#;
+
+    fn write_sample<P: TOutputProtocol>(p: &mut P) {
+        p.write_message_begin(&TMessageIdentifier::new(
+            "call".into(),
+            TMessageType::Call,
+            7,
+        ))
+        .unwrap();
+        p.write_struct_begin(&TStructIdentifier::new("Item"))
+            .unwrap();
+        p.write_field_begin(TType::I64, 1).unwrap();
+        p.write_i64(-1).unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::I32, 2).unwrap();
+        p.write_i32(1).unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::List, 3).unwrap();
+        p.write_list_begin(TListIdentifier::new(TType::I32, 2))
+            .unwrap();
+        p.write_i32(1).unwrap();
+        p.write_i32(2).unwrap();
+        p.write_list_end().unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::Map, 4).unwrap();
+        p.write_map_begin(TMapIdentifier::new(TType::I32, TType::Binary, 2))
+            .unwrap();
+        p.write_i32(3).unwrap();
+        p.write_string("a\"b").unwrap();
+        p.write_i32(4).unwrap();
+        p.write_bytes(Bytes::from_static(&[1, 2])).unwrap();
+        p.write_map_end().unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::Struct, 5).unwrap();
+        p.write_struct_begin(&TStructIdentifier::new("Child"))
+            .unwrap();
+        p.write_field_begin(TType::Bool, 1).unwrap();
+        p.write_bool(true).unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::Map, 2).unwrap();
+        p.write_map_begin(TMapIdentifier::new(TType::Double, TType::Bool, 1))
+            .unwrap();
+        p.write_double(2.5).unwrap();
+        p.write_bool(false).unwrap();
+        p.write_map_end().unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_begin(TType::I32, 6).unwrap();
+        p.write_i32(1).unwrap();
+        p.write_field_end().unwrap();
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+        p.write_message_end().unwrap();
+    }
+
+    fn sample_len<P: TLengthProtocol>(p: &mut P) -> usize {
+        let mut len = p.message_begin_len(&TMessageIdentifier::new(
+            "call".into(),
+            TMessageType::Call,
+            7,
+        ));
+        len += p.struct_begin_len(&TStructIdentifier::new("Item"));
+        len += p.field_begin_len(TType::I64, Some(1)) + p.i64_len(-1) + p.field_end_len();
+        len += p.field_begin_len(TType::I32, Some(2)) + p.i32_len(1) + p.field_end_len();
+        len += p.field_begin_len(TType::List, Some(3))
+            + p.list_begin_len(TListIdentifier::new(TType::I32, 2))
+            + p.i32_len(1)
+            + p.i32_len(2)
+            + p.list_end_len()
+            + p.field_end_len();
+        len += p.field_begin_len(TType::Map, Some(4))
+            + p.map_begin_len(TMapIdentifier::new(TType::I32, TType::Binary, 2))
+            + p.i32_len(3)
+            + p.string_len("a\"b")
+            + p.i32_len(4)
+            + p.bytes_len(&[1, 2])
+            + p.map_end_len()
+            + p.field_end_len();
+        len += p.field_begin_len(TType::Struct, Some(5))
+            + p.struct_begin_len(&TStructIdentifier::new("Child"))
+            + p.field_begin_len(TType::Bool, Some(1))
+            + p.bool_len(true)
+            + p.field_end_len()
+            + p.field_begin_len(TType::Map, Some(2))
+            + p.map_begin_len(TMapIdentifier::new(TType::Double, TType::Bool, 1))
+            + p.double_len(2.5)
+            + p.bool_len(false)
+            + p.map_end_len()
+            + p.field_end_len()
+            + p.field_stop_len()
+            + p.struct_end_len()
+            + p.field_end_len();
+        len += p.field_begin_len(TType::I32, Some(6)) + p.i32_len(1) + p.field_end_len();
+        len + p.field_stop_len() + p.struct_end_len() + p.message_end_len()
+    }
+
+    #[test]
+    fn test_write_with_names() {
+        let mut buf = BytesMut::new();
+        let mut p = TSimpleJsonOutputProtocol::new(&mut buf).with_names(Arc::new(Names));
+        write_sample(&mut p);
+        let len = sample_len(&mut p);
+        assert_eq!(std::str::from_utf8(&buf).unwrap(), NAMED);
+        assert_eq!(len, NAMED.len());
+    }
+
+    #[test]
+    fn test_write_without_names() {
+        let mut buf = LinkedBytes::new();
+        let mut p = TSimpleJsonOutputProtocol::new(&mut buf);
+        write_sample(&mut p);
+        let len = sample_len(&mut p);
+        assert_eq!(std::str::from_utf8(buf.bytes()).unwrap(), UNNAMED);
+        assert_eq!(len, UNNAMED.len());
+    }
+
+    #[test]
+    fn test_container_map_key() {
+        let mut buf = BytesMut::new();
+        let mut p = TSimpleJsonOutputProtocol::new(&mut buf);
+        p.write_map_begin(TMapIdentifier::new(TType::List, TType::I32, 1))
+            .unwrap();
+        assert!(
+            p.write_list_begin(TListIdentifier::new(TType::I32, 0))
+                .is_err()
+        );
+    }
+}